@@ -0,0 +1,67 @@
+//! Lists the crates the client is built from along with their licenses, which the about page shows.
+
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env,
+    fmt::Write,
+    path::Path,
+    process::Command,
+};
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=../Cargo.lock");
+
+    let output = Command::new(env::var("CARGO").unwrap())
+        .args(["metadata", "--format-version", "1", "--filter-platform"])
+        .arg(env::var("TARGET").unwrap())
+        .arg("--manifest-path")
+        .arg(Path::new(&manifest_dir).join("Cargo.toml"))
+        .output()
+        .expect("Failed to run cargo metadata");
+    assert!(output.status.success(), "cargo metadata failed: {}", String::from_utf8_lossy(&output.stderr));
+    let metadata: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let packages: HashMap<&str, &Value> = metadata["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|it| (it["id"].as_str().unwrap(), it))
+        .collect();
+    let nodes: HashMap<&str, &Value> = metadata["resolve"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|it| (it["id"].as_str().unwrap(), it))
+        .collect();
+
+    // everything linked into the client, leaving out build and dev dependencies
+    let mut stack = vec![metadata["resolve"]["root"].as_str().unwrap()];
+    let mut linked = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if !linked.insert(id) {
+            continue;
+        }
+        for dep in nodes[id]["deps"].as_array().unwrap() {
+            if dep["dep_kinds"].as_array().unwrap().iter().any(|it| it["kind"].is_null()) {
+                stack.push(dep["pkg"].as_str().unwrap());
+            }
+        }
+    }
+
+    // crates of this workspace have no source
+    let mut licenses: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for package in linked.into_iter().map(|id| packages[id]).filter(|it| !it["source"].is_null()) {
+        let license = package["license"].as_str().unwrap_or("see its repository");
+        licenses.entry(package["name"].as_str().unwrap()).or_default().insert(license);
+    }
+    let mut code = String::from("&[\n");
+    for (name, license) in licenses {
+        let license = license.into_iter().collect::<Vec<_>>().join("; ");
+        writeln!(code, "    ({name:?}, {license:?}),").unwrap();
+    }
+    code.push(']');
+    std::fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("licenses.rs"), code).unwrap();
+}
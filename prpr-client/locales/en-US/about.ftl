@@ -12,3 +12,13 @@ about =
   Test Group：660488396
   GitHub: https://github.com/Mivik/prpr
   Support prpr: https://afdian.net/a/mivik
+
+build-info = Build information
+build = Build: { $profile }, { $os } { $arch }
+features = Features: { $features }
+res-pack = Resource pack: { $name }
+res-pack-by = { $name } by { $author }
+res-pack-unknown = Failed to load
+loading = Loading…
+chart-formats = Chart formats: { $formats }
+licenses = Open source licenses
@@ -2,5 +2,6 @@ label = Local
 
 import-failed = Failed to import
 import-success = Imported successfully
+package-empty = None of the charts in the package could be read
 
 not-loaded = Not yet finished loading
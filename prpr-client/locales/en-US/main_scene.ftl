@@ -4,3 +4,8 @@ cannot-find-chart = Beatmap not found
 
 delete-success = Deleted successfully
 delete-failed = Failed to delete
+tutorial = Tutorial
+tutorial-prompt = New to rhythm games like this? The tutorial goes through each kind of note in a minute. It can be opened again from the settings.
+tutorial-load-failed = Failed to load the tutorial
+stats-load-failed = Failed to load the statistics
+guest = Guest
@@ -7,23 +7,10 @@ respack-loaded = Resource pack loaded
 respack-load-failed = Failed to load resource pack
 respack-save-failed = Failed to save resource pack
 
-autoplay = Autoplay
-double-tips = Double tips
-fixed-aspect-ratio = Fixed aspect ratio
-time-adjustment = Automatic time adjustment
-particles = Particles
-aggressive-opt = Aggressive optimization
 low-perf-mode = Low performance mode
-player-rks = Player RKS
-offset = Offset (s)
-speed = Speed
-note-size = Note size
-music-vol = Music volume
-sfx-vol = Sound effects volume
 chal-color = Challenge mode color
 chal-colors = White,Green,Blue,Red,Gold,Rainbow
 chal-level = Challenge mode level
-double-click-pause = Double click to pause
 
 respack = Respack
 reset = Reset
@@ -35,3 +22,13 @@ reset-all = Restore default settings
 confirm-reset = Sure?
 reset-all-done = Settings are restored
 save-failed = Failed to save config
+
+sync-server = Sync server
+sync-now = Sync now
+syncing = Syncing…
+sync-no-server = Fill in the WebDAV folder to sync with first
+sync-done = Synced, { $plays } plays downloaded
+sync-conflicts = Changed both here and on the server since the last sync: { $files }. The server's copies are saved in the sync-conflicts folder
+sync-failed = Failed to sync
+tutorial = Tutorial
+stats = Statistics
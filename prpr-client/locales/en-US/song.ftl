@@ -24,6 +24,8 @@ ldb-load-failed = Failed to load leaderboard
 tools = Tools
 adjust-offset = Adjust offset
 exercise-mode = Exercise mode
+rate-mod = Rate: { $mod }
+rate-mod-none = Off
 
 save-success = Saved successfully
 save-failed = Save failed
@@ -42,7 +44,6 @@ edit-saving = Saving…
 edit-builtin = You cannot change built-in beatmaps
 edit-fix-chart = Fix beatmap
 edit-load-file-failed = Failed to load file
-edit-save-config-failed = Failed to save configuration
 edit-save-failed = Failed to save file
 
 fix-chart-success = Repair successful
@@ -82,3 +83,6 @@ review-exec = Executing
 review-suc = Executed successfully
 review-wait = Please wait until the last task is done
 review-del-confirm = Are you sure?
+
+delete-chart = Delete chart
+delete-chart-confirm = The chart will be deleted from your device. This cannot be undone.
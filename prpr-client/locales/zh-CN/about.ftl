@@ -12,3 +12,13 @@ about =
   测试群：660488396
   GitHub：https://github.com/Mivik/prpr
   欢迎在爱发电上支持 prpr 的开发：https://afdian.net/a/mivik
+
+build-info = 构建信息
+build = 构建：{ $profile }，{ $os } { $arch }
+features = 功能：{ $features }
+res-pack = 资源包：{ $name }
+res-pack-by = { $name }（作者 { $author }）
+res-pack-unknown = 加载失败
+loading = 加载中…
+chart-formats = 谱面格式：{ $formats }
+licenses = 开源许可
@@ -2,5 +2,6 @@ label = 本地
 
 import-failed = 导入失败
 import-success = 导入成功
+package-empty = 数据包中的谱面均无法读取
 
 not-loaded = 尚未加载完成
@@ -4,3 +4,8 @@ cannot-find-chart = 找不到谱面
 
 delete-success = 已删除
 delete-failed = 删除失败
+tutorial = 新手教程
+tutorial-prompt = 第一次玩这类音游？新手教程会用一分钟介绍每种音符，之后也可以在设置中再次打开。
+tutorial-load-failed = 加载新手教程失败
+stats-load-failed = 加载游玩统计失败
+guest = 游客
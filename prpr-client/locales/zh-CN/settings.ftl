@@ -7,23 +7,10 @@ respack-loaded = 资源包加载成功
 respack-load-failed = 加载资源包失败
 respack-save-failed = 保存资源包失败
 
-autoplay = 自动游玩
-double-tips = 双押提示
-fixed-aspect-ratio = 固定宽高比
-time-adjustment = 自动对齐时间
-particles = 粒子效果
-aggressive-opt = 激进优化
 low-perf-mode = 低性能模式
-player-rks = 玩家 RKS
-offset = 偏移(s)
-speed = 速度
-note-size = 音符大小
-music-vol = 音乐音量
-sfx-vol = 音效音量
 chal-color = 挑战模式颜色
 chal-colors = 白,绿,蓝,红,金,彩
 chal-level = 挑战模式等级
-double-click-pause = 双击暂停
 
 respack = 资源包
 reset = 重置
@@ -35,3 +22,13 @@ reset-all = 恢复默认设定
 confirm-reset = 确定？
 reset-all-done = 设定恢复成功
 save-failed = 保存设定失败
+
+sync-server = 同步服务器
+sync-now = 立即同步
+syncing = 同步中…
+sync-no-server = 请先填写用于同步的 WebDAV 文件夹
+sync-done = 同步完成，下载了 { $plays } 条游玩记录
+sync-conflicts = 以下文件自上次同步后在本机和服务器上都有修改：{ $files }。服务器上的版本已保存到 sync-conflicts 文件夹
+sync-failed = 同步失败
+tutorial = 新手教程
+stats = 游玩统计
@@ -24,6 +24,8 @@ ldb-load-failed = 加载排行榜失败
 tools = 功能
 adjust-offset = 调整延迟
 exercise-mode = 分段练习
+rate-mod = 变速：{ $mod }
+rate-mod-none = 关闭
 
 save-success = 保存成功
 save-failed = 保存失败
@@ -42,7 +44,6 @@ edit-saving = 保存中…
 edit-builtin = 不能更改内置谱面
 edit-fix-chart = 自动修复谱面
 edit-load-file-failed = 加载文件失败
-edit-save-config-failed = 写入配置文件失败
 edit-save-failed = 保存文件失败
 
 fix-chart-success = 修复成功
@@ -82,3 +83,6 @@ review-exec = 执行中
 review-suc = 执行成功
 review-wait = 请等待上一次操作完成
 review-del-confirm = 你确定吗？
+
+delete-chart = 删除谱面
+delete-chart-confirm = 谱面将从设备上删除，且无法恢复。
@@ -2,6 +2,7 @@ use crate::{
     cloud::{Pointer, User},
     dir,
     page::ChartItem,
+    sync::{split_credentials, SyncCredentials},
 };
 use anyhow::Result;
 use chrono::{DateTime, Utc};
@@ -81,10 +82,15 @@ pub struct LocalChart {
 pub struct Data {
     pub me: Option<User>,
     pub charts: Vec<LocalChart>,
+    #[serde(deserialize_with = "prpr::config::deserialize_migrated")]
     pub config: Config,
     pub message_check_time: Option<DateTime<Utc>>,
     pub language: Option<String>,
     pub theme: usize,
+    pub sync_server: Option<String>,
+    pub sync_credentials: Option<SyncCredentials>,
+    /// Whether the tutorial was offered on the first run.
+    pub tutorial_shown: bool,
 }
 
 impl Data {
@@ -112,6 +118,14 @@ impl Data {
                 });
             }
         }
+        if let Some(server) = &self.sync_server {
+            // kept in the URL by older versions
+            let (server, credentials) = split_credentials(server);
+            if credentials.is_some() {
+                self.sync_server = Some(server);
+                self.sync_credentials = credentials;
+            }
+        }
         if let Some(res_pack_path) = &mut self.config.res_pack_path {
             if res_pack_path.starts_with('/') {
                 // for compatibility
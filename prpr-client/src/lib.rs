@@ -2,6 +2,7 @@ mod cloud;
 mod data;
 mod page;
 mod scene;
+mod sync;
 
 use anyhow::Result;
 use data::Data;
@@ -9,8 +10,9 @@ use macroquad::prelude::*;
 use prpr::{
     build_conf,
     core::init_assets,
+    scene::take_hud_layout,
     time::TimeManager,
-    ui::{FontArc, TextPainter, Ui},
+    ui::{apply_accessibility, FontArc, TextPainter, Ui},
     Main, l10n::{set_locale_order, LanguageIdentifier, langid},
 };
 use scene::MainScene;
@@ -88,6 +90,14 @@ mod dir {
         ensure("data/charts/custom")
     }
 
+    pub fn replays() -> Result<String> {
+        ensure("data/replays")
+    }
+
+    pub fn exports() -> Result<String> {
+        ensure("data/exports")
+    }
+
     pub fn downloaded_charts() -> Result<String> {
         ensure("data/charts/download")
     }
@@ -139,13 +149,22 @@ async fn the_main() -> Result<()> {
     let mut painter = TextPainter::new(font);
 
     let mut main = Main::new(Box::new(MainScene::new().await?), TimeManager::default(), None).await?;
+    main.transition = get_data().config.transition;
 
     let tm = TimeManager::default();
     let mut fps_time = -1;
     'app: loop {
         let frame_start = tm.real_time();
+        // settings may have been changed in the last frame
+        apply_accessibility(&get_data().config);
         main.update()?;
         main.render(&mut Ui::new(&mut painter))?;
+        if let Some(layout) = take_hud_layout() {
+            get_data_mut().config.hud_layout = layout;
+            if let Err(err) = save_data() {
+                warn!("Failed to save HUD layout: {:?}", err);
+            }
+        }
         if let Ok(paused) = rx.try_recv() {
             if paused {
                 main.pause()?;
@@ -256,6 +275,13 @@ pub unsafe extern "C" fn Java_quad_1native_QuadNative_setInputText(_: *mut std::
     INPUT_TEXT.lock().unwrap().1 = Some(string_from_java(env, text));
 }
 
+#[cfg(target_os = "android")]
+#[no_mangle]
+pub unsafe extern "C" fn Java_quad_1native_QuadNative_setPreeditText(_: *mut std::ffi::c_void, _: *const std::ffi::c_void, text: ndk_sys::jstring) {
+    let env = crate::miniquad::native::attach_jni_env();
+    prpr::ui::set_preedit(&string_from_java(env, text));
+}
+
 #[cfg(target_os = "android")]
 #[no_mangle]
 pub unsafe extern "C" fn Java_quad_1native_QuadNative_setFfmpegPath(_: *mut std::ffi::c_void, _: *const std::ffi::c_void, path: ndk_sys::jstring) {
@@ -1,18 +1,56 @@
 prpr::tl_file!("about");
 
 use super::{Page, SharedState, SIDE_PADDING};
+use crate::{dir, get_data};
 use anyhow::Result;
-use macroquad::prelude::Touch;
-use prpr::ui::{Scroll, Ui};
+use macroquad::prelude::{Color, Touch, WHITE};
+use prpr::{
+    core::{ResPackInfo, ResourcePack},
+    enabled_features,
+    ext::{poll_future, LocalTask},
+    info::ChartFormat,
+    ui::{Scroll, Ui},
+};
 use std::borrow::Cow;
 
+/// Crates the client is built from, along with their licenses, as listed by `cargo metadata` when building.
+const LICENSES: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/licenses.rs"));
+
+/// Required by the license of unRAR, which the `rar` feature builds in.
+const UNRAR_NOTICE: &str = "unRAR source code may be used in any software to handle RAR archives without limitations free of charge, \
+    but cannot be used to develop RAR (WinRAR) compatible archiver and to re-create RAR compression algorithm, which is proprietary.";
+
 pub struct AboutPage {
     scroll: Scroll,
+    /// The name and author of the resource pack in use, once loaded.
+    res_pack: Option<String>,
+    res_pack_task: LocalTask<Result<ResPackInfo>>,
 }
 
 impl AboutPage {
     pub fn new() -> Self {
-        Self { scroll: Scroll::new() }
+        Self {
+            scroll: Scroll::new(),
+            res_pack: None,
+            res_pack_task: None,
+        }
+    }
+
+    fn build_info(&self) -> String {
+        let mut features = enabled_features();
+        if features.is_empty() {
+            features.push("-");
+        }
+        let res_pack = self.res_pack.clone().unwrap_or_else(|| tl!("loading").into_owned());
+        let formats: Vec<_> = ChartFormat::ALL.iter().map(|it| it.name()).collect();
+        let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+        [
+            tl!("build", "profile" => profile, "os" => std::env::consts::OS, "arch" => std::env::consts::ARCH),
+            tl!("features", "features" => features.join(", ")),
+            tl!("res-pack", "name" => res_pack),
+            tl!("chart-formats", "formats" => formats.join(", ")),
+        ]
+        .join("\n")
     }
 }
 
@@ -21,8 +59,22 @@ impl Page for AboutPage {
         tl!("label")
     }
 
-    fn update(&mut self, _focus: bool, state: &mut SharedState) -> Result<()> {
+    fn update(&mut self, focus: bool, state: &mut SharedState) -> Result<()> {
         self.scroll.update(state.t);
+        if focus && self.res_pack.is_none() && self.res_pack_task.is_none() {
+            let root = dir::root()?;
+            let path = get_data().config.res_pack_path.as_ref().map(|it| format!("{root}/{it}"));
+            self.res_pack_task = Some(Box::pin(ResourcePack::info_from_path(path)));
+        }
+        if let Some(task) = &mut self.res_pack_task {
+            if let Some(result) = poll_future(task.as_mut()) {
+                self.res_pack_task = None;
+                self.res_pack = Some(match result {
+                    Ok(info) => tl!("res-pack-by", "name" => info.name, "author" => info.author),
+                    Err(_) => tl!("res-pack-unknown").into_owned(),
+                });
+            }
+        }
         Ok(())
     }
     fn touch(&mut self, touch: &Touch, state: &mut SharedState) -> Result<bool> {
@@ -35,14 +87,29 @@ impl Page for AboutPage {
         ui.dx(0.02);
         ui.dy(0.01);
         self.scroll.size(state.content_size);
+        let width = (1. - SIDE_PADDING) * 2. - 0.02;
+        let build_info = self.build_info();
         self.scroll.render(ui, |ui| {
-            let r = ui
-                .text(tl!("about", "version" => env!("CARGO_PKG_VERSION")))
-                .multiline()
-                .max_width((1. - SIDE_PADDING) * 2. - 0.02)
-                .size(0.5)
-                .draw();
-            (r.w, r.h + 0.02)
+            let mut h = 0.;
+            let mut section = |ui: &mut Ui, text: Cow<str>, size: f32, color: Color| {
+                let r = ui.text(text).pos(0., h).multiline().max_width(width).size(size).color(color).draw();
+                h += r.h + 0.03;
+            };
+            let gray = Color::new(1., 1., 1., 0.7);
+            section(ui, tl!("about", "version" => env!("CARGO_PKG_VERSION")).into(), 0.5, WHITE);
+            section(ui, tl!("build-info"), 0.6, WHITE);
+            section(ui, build_info.into(), 0.45, gray);
+            section(ui, tl!("licenses"), 0.6, WHITE);
+            let licenses = LICENSES
+                .iter()
+                .map(|(name, license)| format!("{name}: {license}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            section(ui, licenses.into(), 0.4, gray);
+            if LICENSES.iter().any(|(name, _)| name.starts_with("unrar")) {
+                section(ui, UNRAR_NOTICE.into(), 0.4, gray);
+            }
+            (width, h)
         });
         Ok(())
     }
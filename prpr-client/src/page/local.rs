@@ -6,7 +6,7 @@ use crate::{
     dir, get_data_mut, save_data,
     scene::{ChartOrderBox, CHARTS_BAR_HEIGHT},
 };
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use macroquad::prelude::*;
 use prpr::{
     ext::SafeTexture,
@@ -24,7 +24,7 @@ pub struct LocalPage {
     order_box: ChartOrderBox,
 
     import_button: RectButton,
-    import_task: Task<Result<LocalChart>>,
+    import_task: Task<Result<Vec<LocalChart>>>,
 }
 
 impl LocalPage {
@@ -55,19 +55,38 @@ impl Page for LocalPage {
         SharedState::update_charts(&mut state.charts_local);
         if let Some((id, file)) = take_file() {
             if id == "chart" || id == "_import" {
-                async fn import(from: String) -> Result<LocalChart> {
-                    let name = uuid7::uuid7().to_string();
-                    let file = Path::new(&dir::custom_charts()?).join(&name);
-                    std::fs::copy(from, &file).context("Failed to save")?;
-                    let mut fs = fs::fs_from_file(std::path::Path::new(&file))?;
-                    let info = fs::load_info(fs.deref_mut()).await?;
-                    Ok(LocalChart {
-                        info: BriefChartInfo {
-                            id: Option::None,
-                            ..info.into()
-                        },
-                        path: format!("custom/{name}"),
-                    })
+                async fn import(from: String) -> Result<Vec<LocalChart>> {
+                    let dir = dir::custom_charts()?;
+                    let mut names = Vec::new();
+                    // the official game's data packages hold many charts, each saved on its own
+                    if fs::is_package(Path::new(&from)) {
+                        fs::import_package(Path::new(&from), |_, chart| {
+                            let name = uuid7::uuid7().to_string();
+                            std::fs::write(Path::new(&dir).join(&name), chart.to_zip()?).context("Failed to save")?;
+                            names.push(name);
+                            Ok(())
+                        })?;
+                        if names.is_empty() {
+                            bail!(tl!("package-empty"));
+                        }
+                    } else {
+                        let name = uuid7::uuid7().to_string();
+                        std::fs::copy(from, Path::new(&dir).join(&name)).context("Failed to save")?;
+                        names.push(name);
+                    }
+                    let mut charts = Vec::new();
+                    for name in names {
+                        let mut fs = fs::fs_from_file(&Path::new(&dir).join(&name))?;
+                        let info = fs::load_info(fs.deref_mut()).await?;
+                        charts.push(LocalChart {
+                            info: BriefChartInfo {
+                                id: Option::None,
+                                ..info.into()
+                            },
+                            path: format!("custom/{name}"),
+                        });
+                    }
+                    Ok(charts)
                 }
                 self.import_task = Task::new(import(file));
             } else {
@@ -79,8 +98,8 @@ impl Page for LocalPage {
                 Err(err) => {
                     show_error(err.context(tl!("import-failed")));
                 }
-                Ok(chart) => {
-                    get_data_mut().charts.push(chart);
+                Ok(charts) => {
+                    get_data_mut().charts.extend(charts);
                     save_data()?;
                     state.charts_local = load_local(&state.tex, self.order_box.to_order());
                     show_message(tl!("import-success"));
@@ -1,21 +1,40 @@
 prpr::tl_file!("settings");
 
 use super::{Page, SharedState};
-use crate::{dir, get_data, get_data_mut, save_data, sync_lang, data::THEMES};
+use crate::{
+    data::THEMES,
+    dir, get_data, get_data_mut, save_data,
+    scene::{SHOW_STATS, START_TUTORIAL},
+    sync::{split_credentials, sync, SyncResult},
+    sync_lang,
+};
 use anyhow::{Context, Result};
 use macroquad::prelude::*;
 use prpr::{
     core::{ParticleEmitter, ResourcePack, JUDGE_LINE_PERFECT_COLOR, NOTE_WIDTH_RATIO_BASE},
     ext::{create_audio_manger, poll_future, LocalTask, RectExt, SafeTexture},
+    config::{Config, FieldCategory, FieldKind, FIELDS},
     scene::{request_file, return_file, show_error, show_message, take_file},
+    task::Task,
     time::TimeManager,
     ui::{RectButton, Ui},
 };
 use sasa::{AudioClip, AudioManager, Music, MusicParams, PlaySfxParams, Sfx};
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::atomic::Ordering};
 
 const RESET_WAIT: f32 = 0.8;
 
+/// Renders the fields of the category one below another, in the order of [`FIELDS`].
+fn render_fields(ui: &mut Ui, config: &mut Config, category: FieldCategory, length: Option<f32>, s: f32) {
+    for field in FIELDS.iter().filter(|it| it.category == category) {
+        let r = match &field.kind {
+            FieldKind::Toggle(value) => ui.checkbox(field.label(), value(config)),
+            FieldKind::Slider { value, range, step } => ui.slider(field.label(), range.clone(), *step, value(config), length),
+        };
+        ui.dy(r.h + s);
+    }
+}
+
 pub struct SettingsPage {
     focus: bool,
 
@@ -31,6 +50,7 @@ pub struct SettingsPage {
     chal_buttons: [RectButton; 6],
 
     load_res_task: LocalTask<Result<(ResourcePack, Option<String>)>>,
+    sync_task: Option<Task<Result<SyncResult>>>,
     reset_time: f32,
 }
 
@@ -73,6 +93,7 @@ impl SettingsPage {
             chal_buttons: [RectButton::new(); 6],
 
             load_res_task: None,
+            sync_task: None,
             reset_time: f32::NEG_INFINITY,
         })
     }
@@ -143,6 +164,32 @@ impl Page for SettingsPage {
                 }
             }
         }
+        if let Some(task) = &mut self.sync_task {
+            if let Some(result) = task.take() {
+                self.sync_task = None;
+                match result {
+                    Err(err) => {
+                        show_error(err.context(tl!("sync-failed")));
+                    }
+                    Ok(result) => {
+                        if let Some(config) = result.config {
+                            let old = &get_data().config;
+                            get_data_mut().config = Config {
+                                res_pack_path: old.res_pack_path.clone(),
+                                audio_buffer_size: old.audio_buffer_size,
+                                ..config
+                            };
+                            save_data()?;
+                        }
+                        if result.conflicts.is_empty() {
+                            show_message(tl!("sync-done", "plays" => result.plays)).ok();
+                        } else {
+                            show_message(tl!("sync-conflicts", "files" => result.conflicts.join(", "))).warn();
+                        }
+                    }
+                }
+            }
+        }
         if let Some((id, file)) = take_file() {
             if id == "res_pack" {
                 self.load_res_task = Self::new_res_task(Some(file));
@@ -175,41 +222,20 @@ impl Page for SettingsPage {
         ui.scope(|ui| {
             ui.dy(0.01);
             ui.dx(0.02);
+            // a column for each category, the display one also holding the performance switch
             ui.scope(|ui| {
                 let s = 0.005;
-                let r = ui.checkbox(tl!("autoplay"), &mut config.autoplay);
-                ui.dy(r.h + s);
-                let r = ui.checkbox(tl!("double-tips"), &mut config.multiple_hint);
-                ui.dy(r.h + s);
-                let r = ui.checkbox(tl!("fixed-aspect-ratio"), &mut config.fix_aspect_ratio);
-                ui.dy(r.h + s);
-                let r = ui.checkbox(tl!("time-adjustment"), &mut config.adjust_time);
-                ui.dy(r.h + s);
-                let r = ui.checkbox(tl!("particles"), &mut config.particle);
-                ui.dy(r.h + s);
-                let r = ui.checkbox(tl!("aggressive-opt"), &mut config.aggressive);
-                ui.dy(r.h + s);
                 let mut low = config.sample_count == 1;
                 let r = ui.checkbox(tl!("low-perf-mode"), &mut low);
                 config.sample_count = if low { 1 } else { 2 };
                 ui.dy(r.h + s);
-                let r = ui.slider(tl!("player-rks"), 1.0..17.0, 0.01, &mut config.player_rks, Some(0.45));
-                ui.dy(r.h + s);
+                render_fields(ui, config, FieldCategory::Display, Some(0.45), s);
+                self.emitter.set_scale(config.note_scale);
             });
             ui.dx(0.62);
 
             ui.scope(|ui| {
-                let r = ui.slider(tl!("offset"), -0.5..0.5, 0.005, &mut config.offset, None);
-                ui.dy(r.h + s);
-                let r = ui.slider(tl!("speed"), 0.5..2.0, 0.005, &mut config.speed, None);
-                ui.dy(r.h + s);
-                let r = ui.slider(tl!("note-size"), 0.8..1.2, 0.005, &mut config.note_scale, None);
-                self.emitter.set_scale(config.note_scale);
-                ui.dy(r.h + s);
-                let r = ui.slider(tl!("music-vol"), 0.0..2.0, 0.05, &mut config.volume_music, None);
-                ui.dy(r.h + s);
-                let r = ui.slider(tl!("sfx-vol"), 0.0..2.0, 0.05, &mut config.volume_sfx, None);
-                ui.dy(r.h + s);
+                render_fields(ui, config, FieldCategory::Gameplay, None, s);
                 let r = ui.text(tl!("chal-color")).size(0.4).draw();
                 let chosen = config.challenge_color.clone() as usize;
                 ui.dy(r.h + s * 2.);
@@ -236,8 +262,7 @@ impl Page for SettingsPage {
 
             ui.scope(|ui| {
                 ui.dx(0.65);
-                let r = ui.checkbox(tl!("double-click-pause"), &mut config.double_click_to_pause);
-                ui.dy(r.h + s);
+                render_fields(ui, config, FieldCategory::Audio, Some(0.45), 0.005);
                 let r = ui.text(tl!("respack")).size(0.4).anchor(1., 0.).draw();
                 let mut r = Rect::new(0.02, r.y - 0.01, 0.3, r.h + 0.02);
                 if ui.button("choose_res_pack", r, &self.res_pack.info.name) {
@@ -268,10 +293,41 @@ impl Page for SettingsPage {
                     }
                 }
                 ui.dy(r.h + s * 2.);
+                let old = get_data().sync_server.clone().unwrap_or_default();
+                let mut server = old.clone();
+                ui.input(tl!("sync-server"), &mut server, 0.3);
+                if server != old {
+                    // credentials typed in the URL are kept apart from it, see `SyncCredentials`
+                    let (server, credentials) = split_credentials(server.trim());
+                    let data = get_data_mut();
+                    data.sync_server = Some(server).filter(|it| !it.is_empty());
+                    data.sync_credentials = credentials;
+                }
+                ui.dy(r.h + s * 2.);
+                let text = if self.sync_task.is_some() { tl!("syncing") } else { tl!("sync-now") };
+                if ui.button("sync_now", r, text) && self.sync_task.is_none() {
+                    match &get_data().sync_server {
+                        Some(server) => {
+                            self.sync_task = Some(Task::new(sync(server.clone(), get_data().sync_credentials.clone(), config.clone())));
+                        }
+                        None => {
+                            show_message(tl!("sync-no-server"));
+                        }
+                    }
+                }
+                ui.dy(r.h + s * 2.);
                 if ui.button("switch_theme", r, tl!("theme-prompt", "name" => THEMES[get_data().theme].0)) {
                     get_data_mut().theme = (get_data().theme + 1) % THEMES.len();
                 }
                 ui.dy(r.h + s * 2.);
+                if ui.button("tutorial", r, tl!("tutorial")) {
+                    START_TUTORIAL.store(true, Ordering::SeqCst);
+                }
+                ui.dy(r.h + s * 2.);
+                if ui.button("stats", r, tl!("stats")) {
+                    SHOW_STATS.store(true, Ordering::SeqCst);
+                }
+                ui.dy(r.h + s * 2.);
                 r.w = r.w * 1.3 / 2. - 0.01;
                 // TODO refine this
                 let text = tl!("switch-language");
@@ -327,9 +383,13 @@ impl Page for SettingsPage {
             } else {
                 if self.cali_last {
                     let g = ui.to_global(ct);
+                    self.emitter.set_emitting(config.hit_particles, config.square_particles);
                     self.emitter.emit_at(vec2(g.0, g.1), 0., JUDGE_LINE_PERFECT_COLOR);
                     if self.focus {
-                        let _ = self.cali_hit.play(PlaySfxParams::default());
+                        let _ = self.cali_hit.play(PlaySfxParams {
+                            amplifier: config.ui_volume(),
+                            ..Default::default()
+                        });
                     }
                 }
                 self.cali_last = false;
@@ -1,5 +1,5 @@
 mod main;
-pub use main::{MainScene, CHARTS_BAR_HEIGHT};
+pub use main::{MainScene, CHARTS_BAR_HEIGHT, SHOW_STATS, START_TUTORIAL};
 
 mod song;
 pub use song::SongScene;
@@ -15,11 +15,12 @@ use lyon::{
 };
 use macroquad::{prelude::*, texture::RenderTarget};
 use prpr::{
-    core::Tweenable,
-    ext::{screen_aspect, SafeTexture, ScaleType},
-    scene::{show_error, show_message, NextScene, Scene},
+    config::Config,
+    core::{ResourcePack, Tweenable},
+    ext::{poll_future, screen_aspect, LocalTask, SafeTexture, ScaleType},
+    scene::{show_error, show_message, NextScene, Scene, StatsScene, TutorialScene},
     time::TimeManager,
-    ui::{RectButton, Scroll, Ui},
+    ui::{Dialog, RectButton, Scroll, Ui},
 };
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -38,6 +39,8 @@ pub static SHOULD_DELETE: AtomicBool = AtomicBool::new(false);
 pub static UPDATE_TEXTURE: Mutex<Option<(SafeTexture, SafeTexture)>> = Mutex::new(None);
 pub static UPDATE_ONLINE_TEXTURE: Mutex<Option<SafeTexture>> = Mutex::new(None);
 pub static UPDATE_INFO: AtomicBool = AtomicBool::new(false);
+pub static START_TUTORIAL: AtomicBool = AtomicBool::new(false);
+pub static SHOW_STATS: AtomicBool = AtomicBool::new(false);
 
 pub struct MainScene {
     target: Option<RenderTarget>,
@@ -49,7 +52,6 @@ pub struct MainScene {
     icon_tool: SafeTexture,
     icon_edit: SafeTexture,
     icon_delete: SafeTexture,
-    icon_question: SafeTexture,
 
     page_scroll: Scroll,
     page_index: usize,
@@ -59,6 +61,7 @@ pub struct MainScene {
 
     shared_state: SharedState,
     pages: [Box<dyn Page>; PAGE_NUM],
+    tutorial_task: LocalTask<Result<TutorialScene>>,
 }
 
 impl MainScene {
@@ -83,7 +86,6 @@ impl MainScene {
             icon_tool: load_tex!("tool.png"),
             icon_edit: load_tex!("edit.png"),
             icon_delete: load_tex!("delete.png"),
-            icon_question: load_tex!("question.png"),
 
             page_scroll: Scroll::new(),
             page_index: 0,
@@ -100,6 +102,7 @@ impl MainScene {
                 Box::new(page::SettingsPage::new().await?),
                 Box::new(page::AboutPage::new()),
             ],
+            tutorial_task: None,
         })
     }
 
@@ -172,7 +175,7 @@ impl MainScene {
             self.icon_back.clone(),
             self.icon_download.clone(),
             self.icon_play.clone(),
-            TrashBin::new(self.icon_delete.clone(), self.icon_question.clone()),
+            TrashBin::new(self.icon_delete.clone()),
             file,
             online,
         ))))
@@ -202,6 +205,11 @@ impl Scene for MainScene {
         } else {
             tm.seek_to(rand::gen_range(1., 10.));
             show_message(tl!("welcome"));
+            if !get_data().tutorial_shown {
+                get_data_mut().tutorial_shown = true;
+                save_data()?;
+                Dialog::confirm(tl!("tutorial"), tl!("tutorial-prompt"), || START_TUTORIAL.store(true, Ordering::SeqCst)).show();
+            }
         }
         if UPDATE_INFO.fetch_and(false, Ordering::SeqCst) {
             if let Some((None, id, ..)) = self.shared_state.transit {
@@ -254,6 +262,31 @@ impl Scene for MainScene {
         for (id, page) in self.pages.iter_mut().enumerate() {
             page.update(id == self.page_index, &mut self.shared_state)?;
         }
+        if START_TUTORIAL.fetch_and(false, Ordering::SeqCst) && self.tutorial_task.is_none() {
+            let root = dir::root()?;
+            let path = get_data().config.res_pack_path.as_ref().map(|it| format!("{root}/{it}"));
+            self.tutorial_task = Some(Box::pin(async move { Ok(TutorialScene::new(ResourcePack::from_path(path).await?)) }));
+        }
+        if let Some(task) = &mut self.tutorial_task {
+            if let Some(result) = poll_future(task.as_mut()) {
+                self.tutorial_task = None;
+                match result {
+                    Ok(scene) => self.next_scene = Some(NextScene::Overlay(Box::new(scene))),
+                    Err(err) => show_error(err.context(tl!("tutorial-load-failed"))),
+                }
+            }
+        }
+        if SHOW_STATS.fetch_and(false, Ordering::SeqCst) {
+            let config = Config {
+                player_name: get_data().me.as_ref().map(|it| it.name.clone()).unwrap_or_else(|| tl!("guest").to_string()),
+                scores_path: Some(format!("{}/scores.db", dir::root()?)),
+                ..get_data().config.clone()
+            };
+            match StatsScene::new(&config) {
+                Ok(scene) => self.next_scene = Some(NextScene::Overlay(Box::new(scene))),
+                Err(err) => show_error(err.context(tl!("stats-load-failed"))),
+            }
+        }
         Ok(())
     }
 
@@ -6,7 +6,7 @@ use crate::{
     data::{BriefChartInfo, LocalChart},
     dir, get_data, get_data_mut,
     page::{illustration_task, ChartItem, SHOULD_UPDATE},
-    save_data,
+    save_data, sync,
 };
 use anyhow::{bail, Context, Result};
 use futures_util::StreamExt;
@@ -14,17 +14,17 @@ use image::DynamicImage;
 use macroquad::prelude::*;
 use pollster::FutureExt;
 use prpr::{
-    config::Config,
+    config::{Config, RateMod},
     core::Tweenable,
     ext::{poll_future, screen_aspect, JoinToString, LocalTask, RectExt, SafeTexture, ScaleType, BLACK_TEXTURE},
-    fs::{self, update_zip, FileSystem, ZipFileSystem},
+    fs::{self, CachedFileSystem, FileSystem, PatchedFileSystem},
     info::ChartInfo,
     scene::{
         request_input, return_input, show_error, show_message, take_input, GameMode, GameScene, LoadingScene, NextScene, RecordUpdateState, Scene,
     },
     task::Task,
     time::TimeManager,
-    ui::{render_chart_info, ChartInfoEdit, Dialog, MessageHandle, RectButton, Scroll, Ui},
+    ui::{render_chart_info, ChartInfoEdit, Dialog, ProgressHandle, RectButton, Scroll, Ui},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -44,6 +44,7 @@ const FADEIN_TIME: f32 = 0.3;
 const EDIT_TRANSIT: f32 = 0.32;
 const IMAGE_LIMIT: usize = 2 * 1024 * 1024;
 const CHART_LIMIT: usize = 10 * 1024 * 1024;
+const CHART_CACHE_SIZE: usize = 64 * 1024 * 1024;
 
 static CONFIRM_UPLOAD: AtomicBool = AtomicBool::new(false);
 static UPLOAD_STATUS: Mutex<Option<Cow<'static, str>>> = Mutex::new(None);
@@ -56,72 +57,43 @@ fn fs_from_path(path: &str) -> Result<Box<dyn FileSystem>> {
     }
 }
 
+/// Deletes the chart once confirmed in a dialog.
 pub struct TrashBin {
     icon_delete: SafeTexture,
-    icon_question: SafeTexture,
     button: RectButton,
+    confirmed: Arc<AtomicBool>,
     pub clicked: bool,
-    height: f32,
-    offset: f32,
-    time: f32,
 }
 
 impl TrashBin {
-    pub const TRANSIT_TIME: f32 = 0.2;
-    pub const WAIT_TIME: f32 = 1.;
-
-    pub fn new(icon_delete: SafeTexture, icon_question: SafeTexture) -> Self {
+    pub fn new(icon_delete: SafeTexture) -> Self {
         Self {
             icon_delete,
-            icon_question,
             button: RectButton::new(),
+            confirmed: Arc::default(),
             clicked: false,
-            height: 0.,
-            offset: 0.,
-            time: f32::INFINITY,
         }
     }
 
-    pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
+    pub fn touch(&mut self, touch: &Touch) -> bool {
         if self.button.touch(touch) {
-            if (0.0..Self::WAIT_TIME).contains(&(t - self.time - Self::TRANSIT_TIME)) {
-                // delete
-                self.clicked = true;
-            } else if self.time.is_infinite() {
-                self.time = t;
-            }
+            let confirmed = Arc::clone(&self.confirmed);
+            Dialog::confirm(tl!("delete-chart"), tl!("delete-chart-confirm"), move || confirmed.store(true, Ordering::SeqCst)).show();
             true
         } else {
             false
         }
     }
 
-    pub fn update(&mut self, t: f32) {
-        if self.time.is_infinite() {
-            self.offset = 0.;
-        } else {
-            let p = ((t - self.time - Self::WAIT_TIME - Self::TRANSIT_TIME) / Self::TRANSIT_TIME).min(1.);
-            if p >= 0. {
-                self.offset = (1. - p.powi(3)) * self.height;
-                if p >= 1. {
-                    self.time = f32::INFINITY;
-                }
-            } else {
-                let p = 1. - (1. - ((t - self.time) / Self::TRANSIT_TIME).min(1.)).powi(3);
-                self.offset = p * self.height;
-            }
+    pub fn update(&mut self) {
+        if self.confirmed.swap(false, Ordering::SeqCst) {
+            self.clicked = true;
         }
     }
 
-    pub fn render(&mut self, ui: &mut Ui, mut rect: Rect, color: Color) {
+    pub fn render(&mut self, ui: &mut Ui, rect: Rect, color: Color) {
         self.button.set(ui, rect);
-        self.height = rect.h;
-        ui.scissor(Some(rect));
-        rect.y -= self.offset;
         ui.fill_rect(rect, (*self.icon_delete, rect, ScaleType::Fit, color));
-        rect.y += rect.h;
-        ui.fill_rect(rect, (*self.icon_question, rect, ScaleType::Fit, color));
-        ui.scissor(None);
     }
 }
 
@@ -155,6 +127,8 @@ pub struct SongScene {
     illustration_task: Option<Task<Result<(DynamicImage, Option<DynamicImage>)>>>,
     online_illustration_task: Option<Task<Result<DynamicImage>>>,
     chart_info: Option<ChartInfo>,
+    /// Shared by every play of the chart, so that retries don't load it from scratch.
+    chart_fs: Option<CachedFileSystem>,
     scene_task: LocalTask<Result<LoadingScene>>,
 
     target: Option<RenderTarget>,
@@ -162,13 +136,16 @@ pub struct SongScene {
 
     next_scene: Option<NextScene>,
     save_task: Option<Task<Result<()>>>,
+    /// Lists the files of the chart for the editor to check the info against.
+    files_task: Option<Task<Result<Vec<String>>>>,
     upload_task: Option<Task<Result<()>>>,
     info_edit: Option<ChartInfoEdit>,
     side_width: f32,
     side_content: SideContent,
     side_enter_time: f32,
 
-    downloading: Option<(String, Arc<Mutex<f32>>, MessageHandle, Task<Result<LocalChart>>)>,
+    /// The progress shown, whether it's been cancelled and the download.
+    downloading: Option<(ProgressHandle, Arc<AtomicBool>, Task<Result<LocalChart>>)>,
     leaderboard_task: Option<Task<Result<QueryResult<LCRecord>>>>,
     leaderboard_scroll: Scroll,
     leaderboards: Option<Vec<LCRecord>>,
@@ -178,6 +155,8 @@ pub struct SongScene {
 
     review_task: Option<Task<Result<()>>>,
     del_confirm: f32,
+
+    rate_mod: Option<RateMod>,
 }
 
 fn create_info_task(path: String, brief: BriefChartInfo) -> Task<ChartInfo> {
@@ -249,6 +228,7 @@ impl SongScene {
             online_illustration_task: lc_file.map(|file| Task::new(async move { Images::load_lc(&file).await })),
 
             chart_info: None,
+            chart_fs: None,
             scene_task: None,
 
             target: None,
@@ -256,6 +236,7 @@ impl SongScene {
 
             next_scene: None,
             save_task: None,
+            files_task: None,
             upload_task: None,
             info_edit: None,
             side_content: SideContent::Edit,
@@ -272,6 +253,8 @@ impl SongScene {
 
             review_task: None,
             del_confirm: f32::INFINITY,
+
+            rate_mod: None,
         }
     }
 
@@ -313,11 +296,6 @@ impl SongScene {
         let s = 0.1;
         let r = Rect::new(-s, -s, s * 2., s * 2.);
         ui.fill_rect(r, (if self.online { *self.icon_download } else { *self.icon_play }, r, ScaleType::Fit, color));
-        if self.online {
-            let p = self.downloading.as_ref().map_or(0., |(_, p, ..)| *p.lock().unwrap());
-            let r = r.feather(0.04);
-            ui.fill_rect(Rect::new(r.x, r.y + r.h * (1. - p), r.w, r.h * p), color);
-        }
         self.center_button.set(ui, r);
 
         ui.scope(|ui| {
@@ -545,6 +523,16 @@ impl SongScene {
                 self.play_chart(GameMode::Exercise).unwrap();
             }
             ui.dy(r.h + 0.01);
+            let rate_mod = self
+                .rate_mod
+                .map_or_else(|| tl!("rate-mod-none").to_string(), |it| format!("{} ×{:.1}", it.label(), it.rate()));
+            if ui.button("rate_mod", r, tl!("rate-mod", "mod" => rate_mod)) {
+                self.rate_mod = match self.rate_mod {
+                    None => Some(RateMod::ALL[0]),
+                    Some(current) => RateMod::ALL.iter().position(|it| *it == current).and_then(|it| RateMod::ALL.get(it + 1)).copied(),
+                };
+            }
+            ui.dy(r.h + 0.01);
         }
         if self.get_id().is_some() {
             if ui.button(
@@ -608,13 +596,8 @@ impl SongScene {
             } else if self.get_id().is_some() {
                 show_message(tl!("upload-downloaded"));
             } else if !CONFIRM_UPLOAD.load(Ordering::SeqCst) {
-                Dialog::plain(tl!("upload-rules"), tl!("upload-rules-content"))
+                Dialog::confirm(tl!("upload-rules"), tl!("upload-rules-content"), || CONFIRM_UPLOAD.store(true, Ordering::SeqCst))
                     .buttons(vec![tl!("upload-cancel").to_string(), tl!("upload-confirm").to_string()])
-                    .listener(|pos| {
-                        if pos == 1 {
-                            CONFIRM_UPLOAD.store(true, Ordering::SeqCst);
-                        }
-                    })
                     .show();
             }
         }
@@ -632,6 +615,8 @@ impl SongScene {
         {
             if self.chart.path.starts_with(':') {
                 show_message(tl!("edit-builtin"));
+            } else if let Some((_, err)) = self.info_edit.as_ref().unwrap().errors().into_iter().next() {
+                show_message(err).error();
             } else {
                 self.save_edit();
             }
@@ -663,14 +648,10 @@ impl SongScene {
             let path = self.chart.path.clone();
             let edit = edit.clone();
             self.save_task = Some(Task::new(async move {
-                let mut fs = fs_from_path(&path)?;
                 let patches = edit.to_patches().await.with_context(|| tl!("edit-load-file-failed"))?;
-                if let Some(zip) = fs.as_any().downcast_mut::<ZipFileSystem>() {
-                    let bytes = update_zip(&mut zip.0.lock().unwrap(), patches).with_context(|| tl!("edit-save-config-failed"))?;
-                    std::fs::write(format!("{}/{}", dir::charts()?, path), bytes).with_context(|| tl!("edit-save-failed"))?;
-                } else {
-                    unreachable!();
-                }
+                PatchedFileSystem(fs_from_path(&path)?, patches)
+                    .save(std::path::Path::new(&format!("{}/{}", dir::charts()?, path)))
+                    .with_context(|| tl!("edit-save-failed"))?;
                 Ok(())
             }));
         }
@@ -694,32 +675,46 @@ impl SongScene {
             show_message(tl!("already-downloaded")); // TODO redirect instead of showing this
             return Ok(());
         }
-        let handle = show_message(tl!("downloading")).handle();
         let url = self.chart.path.clone();
         let chart = LocalChart {
             info: self.chart.info.clone(),
             path,
         };
-        let progress = Arc::new(Mutex::new(0.));
-        let prog_cl = Arc::clone(&progress);
+        let (dialog, progress) = Dialog::progress(tl!("downloading"), &chart.info.name);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        dialog
+            .buttons(vec![tl!("edit-cancel").to_string()])
+            .listener({
+                let cancelled = Arc::clone(&cancelled);
+                move |pos| {
+                    if pos == 0 {
+                        cancelled.store(true, Ordering::SeqCst);
+                    }
+                }
+            })
+            .show();
         self.downloading = Some((
-            chart.info.name.clone(),
-            progress,
-            handle,
+            progress.clone(),
+            Arc::clone(&cancelled),
             Task::new({
                 let path = format!("{}/{}", dir::downloaded_charts()?, id);
                 async move {
-                    let mut file = tokio::fs::File::create(path).await?;
+                    let mut file = tokio::fs::File::create(&path).await?;
                     let res = reqwest::get(url).await.with_context(|| tl!("request-failed"))?;
                     let size = res.content_length();
                     let mut stream = res.bytes_stream();
                     let mut count = 0;
                     while let Some(chunk) = stream.next().await {
+                        if cancelled.load(Ordering::SeqCst) {
+                            drop(file);
+                            tokio::fs::remove_file(&path).await?;
+                            bail!(tl!("download-cancelled"));
+                        }
                         let chunk = chunk?;
                         file.write_all(&chunk).await?;
                         count += chunk.len() as u64;
                         if let Some(size) = size {
-                            *prog_cl.lock().unwrap() = count.min(size) as f32 / size as f32;
+                            progress.set(count.min(size) as f32 / size as f32);
                         }
                     }
                     Ok(chart)
@@ -733,9 +728,16 @@ impl SongScene {
         if self.scene_task.is_some() {
             return Ok(());
         }
-        let fs = fs_from_path(&self.chart.path)?;
+        if self.chart_fs.is_none() {
+            self.chart_fs = Some(CachedFileSystem::new(fs_from_path(&self.chart.path)?, CHART_CACHE_SIZE));
+        }
+        let fs = self.chart_fs.as_ref().unwrap().clone_box();
         let mut info = self.chart_info.clone().unwrap();
         info.id = self.chart.path.strip_prefix("download/").map(str::to_owned);
+        let rate_mod = self.rate_mod;
+        // kept for syncing, named by time so that they sort in the order played
+        sync::prune_replays()?;
+        let record_replay = format!("{}/{}.json", dir::replays()?, chrono::Local::now().format("%Y%m%d-%H%M%S"));
         self.scene_task = Some(Box::pin(async move {
             LoadingScene::new(
                 mode,
@@ -751,6 +753,11 @@ impl SongScene {
                         .res_pack_path
                         .as_ref()
                         .map(|it| format!("{}/{it}", dir::root().unwrap())),
+                    rate_mod,
+                    export_dir: dir::exports().ok(),
+                    record_replay: Some(record_replay),
+                    scores_path: Some(format!("{}/scores.db", dir::root().unwrap())),
+                    leaderboard_cache: Some(format!("{}/leaderboard", dir::root().unwrap())),
                     ..get_data().config.clone()
                 },
                 fs,
@@ -840,11 +847,13 @@ impl Scene for SongScene {
                     return Ok(true);
                 }
                 if loaded && !self.online {
-                    if self.bin.touch(touch, tm.now() as _) {
+                    if self.bin.touch(touch) {
                         return Ok(true);
                     }
                     if self.edit_button.touch(touch) {
                         self.info_edit = Some(ChartInfoEdit::new(self.chart_info.clone().unwrap()));
+                        let path = self.chart.path.clone();
+                        self.files_task = Some(Task::new(async move { fs_from_path(&path)?.list_root() }));
                         self.side_content = SideContent::Edit;
                         self.side_width = 0.8;
                         self.side_enter_time = rt;
@@ -861,10 +870,8 @@ impl Scene for SongScene {
                 }
                 if (loaded || self.online) && self.center_button.touch(touch) {
                     if self.online {
-                        if let Some((.., mut handle, _)) = self.downloading.take() {
-                            handle.cancel();
-                            show_message(tl!("download-cancelled"));
-                        } else {
+                        // cancelled from the dialog showing the progress
+                        if self.downloading.is_none() {
                             self.start_download()?;
                         }
                     } else {
@@ -900,6 +907,7 @@ impl Scene for SongScene {
     }
 
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        self.bin.update();
         if self.bin.clicked {
             self.next_scene = Some(NextScene::Pop);
             super::main::SHOULD_DELETE.store(true, Ordering::SeqCst);
@@ -914,7 +922,6 @@ impl Scene for SongScene {
             self.fetch_leaderboard();
         }
         let t = tm.now() as f32;
-        self.bin.update(t);
         self.scroll.update(t);
         self.edit_scroll.update(t);
         self.leaderboard_scroll.update(t);
@@ -928,6 +935,15 @@ impl Scene for SongScene {
                 self.info_task = None;
             }
         }
+        if let Some(task) = &mut self.files_task {
+            if let Some(result) = task.take() {
+                match result {
+                    Err(err) => warn!("Failed to list the files of the chart: {:?}", err),
+                    Ok(files) => self.info_edit = self.info_edit.take().map(|it| it.with_files(files)),
+                }
+                self.files_task = None;
+            }
+        }
         if let Some(task) = &mut self.save_task {
             if let Some(result) = task.take() {
                 if let Err(err) = result {
@@ -938,6 +954,7 @@ impl Scene for SongScene {
                     }
                     show_message(tl!("save-success"));
                 }
+                self.chart_fs = None;
                 self.save_task = None;
             }
         }
@@ -1050,9 +1067,12 @@ impl Scene for SongScene {
                 Ok(())
             }));
         }
-        if let Some((.., handle, task)) = &mut self.downloading {
-            if let Some(res) = task.take() {
-                handle.cancel();
+        if let Some((progress, cancelled, task)) = &mut self.downloading {
+            if cancelled.load(Ordering::SeqCst) {
+                self.downloading = None;
+                show_message(tl!("download-cancelled"));
+            } else if let Some(res) = task.take() {
+                progress.finish();
                 match res {
                     Err(err) => {
                         show_error(err.context(tl!("download-failed")));
@@ -1060,6 +1080,7 @@ impl Scene for SongScene {
                     Ok(chart) => {
                         self.chart.info = chart.info.clone();
                         self.chart.path = chart.path.clone();
+                        self.chart_fs = None;
                         self.info_task = Some(create_info_task(chart.path.clone(), chart.info.clone()));
                         get_data_mut().charts.push(chart);
                         save_data()?;
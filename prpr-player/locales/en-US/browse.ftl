@@ -0,0 +1,13 @@
+title = Browse charts
+back = Back
+search = Search
+search-failed = Failed to search charts
+no-charts = No charts found
+loading = Loading…
+load-more = Load more
+downloading = Downloading { $progress }%
+downloading-unknown = Downloading…
+in-library = In library
+tap-to-download = Tap to download
+downloaded = Downloaded { $name }
+download-failed = Failed to download { $name }
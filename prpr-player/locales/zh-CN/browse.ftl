@@ -0,0 +1,13 @@
+title = 浏览谱面
+back = 返回
+search = 搜索
+search-failed = 搜索谱面失败
+no-charts = 没有找到谱面
+loading = 加载中…
+load-more = 加载更多
+downloading = 下载中 { $progress }%
+downloading-unknown = 下载中…
+in-library = 已在谱面库中
+tap-to-download = 点击下载
+downloaded = 已下载 { $name }
+download-failed = 下载 { $name } 失败
@@ -0,0 +1,299 @@
+prpr::tl_file!("browse");
+
+use anyhow::{Context, Result};
+use macroquad::prelude::*;
+use prpr::{
+    ext::{screen_aspect, SafeTexture, ScaleType},
+    repository::{ChartRepository, RemoteChart, PAGE_SIZE},
+    scene::{show_error, show_message, NextScene, Scene},
+    task::Task,
+    time::TimeManager,
+    ui::{Grid, Ui},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+const ROW_HEIGHT: f32 = 0.16;
+const JACKET_SIZE: u32 = 256;
+
+struct Download {
+    task: Task<Result<PathBuf>>,
+    progress: Arc<Mutex<(u64, Option<u64>)>>,
+}
+
+/// Searches a chart repository, downloading the charts tapped into the library.
+pub struct BrowseScene {
+    repo: ChartRepository,
+    root: PathBuf,
+    target: Option<RenderTarget>,
+    list: Grid,
+    query: String,
+    charts: Vec<RemoteChart>,
+    /// Ids of the charts listed that are in the library, checked as they're listed rather than every frame.
+    downloaded: HashSet<String>,
+    /// The next page to load, if there may be more.
+    next_page: Option<u32>,
+    search_task: Option<Task<Result<Vec<RemoteChart>>>>,
+    jackets: HashMap<String, Option<SafeTexture>>,
+    jacket_tasks: HashMap<String, Task<Result<Vec<u8>>>>,
+    downloads: HashMap<String, Download>,
+    /// The search box and buttons, in global coordinates.
+    header: Option<Rect>,
+    next_scene: Option<NextScene>,
+}
+
+impl BrowseScene {
+    pub fn new(repo: &str, root: PathBuf) -> Result<Self> {
+        let mut res = Self {
+            repo: ChartRepository::new(repo)?,
+            root,
+            target: None,
+            list: Grid::list(ROW_HEIGHT),
+            query: String::new(),
+            charts: Vec::new(),
+            downloaded: HashSet::new(),
+            next_page: None,
+            search_task: None,
+            jackets: HashMap::new(),
+            jacket_tasks: HashMap::new(),
+            downloads: HashMap::new(),
+            header: None,
+            next_scene: None,
+        };
+        res.search();
+        Ok(res)
+    }
+
+    fn search(&mut self) {
+        self.charts.clear();
+        self.downloaded.clear();
+        self.next_page = Some(0);
+        self.list.scroll_to(0);
+        self.load_more();
+    }
+
+    fn load_more(&mut self) {
+        let Some(page) = self.next_page else {
+            return;
+        };
+        let (repo, query) = (self.repo.clone(), self.query.trim().to_owned());
+        self.search_task = Some(Task::new(async move { repo.search(&query, page).await }));
+    }
+
+    fn download(&mut self, chart: &RemoteChart) {
+        if self.downloads.contains_key(&chart.id) || self.downloaded.contains(&chart.id) {
+            return;
+        }
+        let progress = Arc::new(Mutex::new((0, None)));
+        let task = Task::new({
+            let (repo, chart, root, progress) = (self.repo.clone(), chart.clone(), self.root.clone(), Arc::clone(&progress));
+            async move {
+                repo.download(&chart, &root, move |done, total| {
+                    *progress.lock().unwrap() = (done, total);
+                })
+                .await
+            }
+        });
+        self.downloads.insert(chart.id.clone(), Download { task, progress });
+    }
+
+    fn jacket(&mut self, chart: &RemoteChart) -> Option<SafeTexture> {
+        if let Some(jacket) = self.jackets.get(&chart.id) {
+            return jacket.clone();
+        }
+        if chart.jacket.is_some() && !self.jacket_tasks.contains_key(&chart.id) {
+            let (repo, chart) = (self.repo.clone(), chart.clone());
+            self.jacket_tasks
+                .insert(chart.id.clone(), Task::new(async move { repo.jacket(&chart).await }));
+        }
+        None
+    }
+}
+
+fn decode_jacket(bytes: &[u8]) -> Result<SafeTexture> {
+    let image = image::load_from_memory(bytes)
+        .context("Invalid jacket")?
+        .thumbnail(JACKET_SIZE, JACKET_SIZE)
+        .into_rgba8();
+    Ok(Texture2D::from_rgba8(image.width() as _, image.height() as _, &image).into())
+}
+
+fn format_length(seconds: f32) -> String {
+    let seconds = seconds.max(0.) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Scene for BrowseScene {
+    fn enter(&mut self, _tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        self.target = target;
+        Ok(())
+    }
+
+    fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        if self.header.map_or(false, |it| it.contains(touch.position)) {
+            return Ok(false);
+        }
+        if !self.list.touch(touch, tm.now() as f32) {
+            return Ok(false);
+        }
+        match self.list.tapped() {
+            Some(index) if index < self.charts.len() => {
+                let chart = self.charts[index].clone();
+                self.download(&chart);
+            }
+            Some(_) if self.search_task.is_none() => self.load_more(),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        self.list.update(tm.now() as f32);
+        if let Some(task) = &mut self.search_task {
+            if let Some(result) = task.take() {
+                self.search_task = None;
+                match result {
+                    Ok(charts) => {
+                        self.next_page = self.next_page.filter(|_| charts.len() >= PAGE_SIZE as usize).map(|it| it + 1);
+                        let root = &self.root;
+                        self.downloaded
+                            .extend(charts.iter().filter(|it| root.join(it.file_name()).exists()).map(|it| it.id.clone()));
+                        self.charts.extend(charts);
+                    }
+                    Err(err) => show_error(err.context(tl!("search-failed"))),
+                }
+            }
+        }
+        for (id, task) in &mut self.jacket_tasks {
+            if let Some(result) = task.take() {
+                let jacket = result
+                    .and_then(|bytes| decode_jacket(&bytes))
+                    .map_err(|err| warn!("Failed to load jacket: {:?}", err))
+                    .ok();
+                self.jackets.insert(id.clone(), jacket);
+            }
+        }
+        self.jacket_tasks.retain(|id, _| !self.jackets.contains_key(id));
+        let mut finished = Vec::new();
+        for (id, download) in &mut self.downloads {
+            if let Some(result) = download.task.take() {
+                let name = self.charts.iter().find(|it| it.id == *id).map_or(id.as_str(), |it| it.name.as_str());
+                match result {
+                    Ok(_) => {
+                        self.downloaded.insert(id.clone());
+                        show_message(tl!("downloaded", "name" => name)).ok();
+                    }
+                    Err(err) => show_error(err.context(tl!("download-failed", "name" => name))),
+                }
+                finished.push(id.clone());
+            }
+        }
+        for id in finished {
+            self.downloads.remove(&id);
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, _tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        let top = 1. / screen_aspect();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -screen_aspect()),
+            render_target: self.target,
+            ..Default::default()
+        });
+        clear_background(BLACK);
+        ui.text(tl!("title")).pos(-0.92, -top + 0.04).size(0.8).draw();
+        if ui.button("browse#back", Rect::new(0.72, -top + 0.04, 0.24, 0.08), tl!("back")) {
+            self.next_scene = Some(NextScene::Pop);
+        }
+        let mut search = false;
+        ui.scope(|ui| {
+            ui.dx(-0.72);
+            ui.dy(-top + 0.17);
+            let r = ui.input(tl!("search"), &mut self.query, 1.2);
+            if ui.button("browse#search", Rect::new(r.right() + 0.04, r.y, 0.24, r.h), tl!("search")) {
+                search = true;
+            }
+        });
+        self.header = Some(ui.rect_to_global(Rect::new(-1., -top, 2., 0.26)));
+        if search {
+            self.search();
+        }
+
+        let width = 1.84;
+        if self.charts.is_empty() && self.search_task.is_none() {
+            ui.text(tl!("no-charts")).pos(0., 0.).anchor(0.5, 0.5).size(0.5).color(GRAY).draw();
+        }
+        let len = self.charts.len() + self.next_page.is_some() as usize;
+        let mut list = std::mem::replace(&mut self.list, Grid::list(ROW_HEIGHT));
+        ui.scope(|ui| {
+            ui.dx(-width / 2.);
+            ui.dy(-top + 0.28);
+            list.size((width, top * 2. - 0.3));
+            list.render(ui, len, |ui, index, r| {
+                let r = Rect { h: r.h - 0.01, ..r };
+                ui.fill_rect(r, Color::new(1., 1., 1., 0.08));
+                let Some(chart) = self.charts.get(index).cloned() else {
+                    let text = if self.search_task.is_some() { tl!("loading") } else { tl!("load-more") };
+                    ui.text(text)
+                        .pos(r.center().x, r.center().y)
+                        .anchor(0.5, 0.5)
+                        .size(0.5)
+                        .no_baseline()
+                        .draw();
+                    return;
+                };
+                let jacket = Rect::new(r.x + 0.01, r.y + 0.01, (r.h - 0.02) * 16. / 9., r.h - 0.02);
+                match self.jacket(&chart) {
+                    Some(tex) => ui.fill_rect(jacket, (*tex, jacket, ScaleType::CropCenter)),
+                    None => ui.fill_rect(jacket, Color::new(1., 1., 1., 0.1)),
+                }
+                let x = jacket.right() + 0.03;
+                ui.text(&chart.name)
+                    .pos(x, r.y + r.h * 0.3)
+                    .anchor(0., 0.5)
+                    .size(0.6)
+                    .max_width(r.right() - x - 0.45)
+                    .draw();
+                ui.text(format!("{}  ·  {}", chart.composer, chart.charter))
+                    .pos(x, r.y + r.h * 0.72)
+                    .anchor(0., 0.5)
+                    .size(0.36)
+                    .color(GRAY)
+                    .draw();
+                let level = match chart.length {
+                    Some(length) => format!("{}  {}", chart.level, format_length(length)),
+                    None => chart.level.clone(),
+                };
+                ui.text(level).pos(r.right() - 0.03, r.y + r.h * 0.3).anchor(1., 0.5).size(0.5).draw();
+                let status = if let Some(download) = self.downloads.get(&chart.id) {
+                    match *download.progress.lock().unwrap() {
+                        (done, Some(total)) if total > 0 => {
+                            tl!("downloading", "progress" => format!("{:.0}", done as f32 / total as f32 * 100.)).into()
+                        }
+                        _ => tl!("downloading-unknown"),
+                    }
+                } else if self.downloaded.contains(&chart.id) {
+                    tl!("in-library")
+                } else {
+                    tl!("tap-to-download")
+                };
+                ui.text(status)
+                    .pos(r.right() - 0.03, r.y + r.h * 0.72)
+                    .anchor(1., 0.5)
+                    .size(0.36)
+                    .color(GRAY)
+                    .draw();
+            });
+        });
+        self.list = list;
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        self.next_scene.take().unwrap_or_default()
+    }
+}
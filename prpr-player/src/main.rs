@@ -1,16 +1,38 @@
 use anyhow::{Context, Result};
 use macroquad::prelude::*;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+use prpr::scores::{ChartSettings, ScoreDatabase};
 use prpr::{
     build_conf,
+    config::{Config, ConfigFormat, HudLayout},
     core::init_assets,
     fs,
-    scene::{show_error, GameMode, LoadingScene, NextScene, Scene},
+    l10n::set_language,
+    scene::{show_error, show_message, take_hud_layout, GameMode, LoadingScene, NextScene, Scene},
     time::TimeManager,
-    ui::{FontArc, TextPainter, Ui},
+    ui::{apply_accessibility, FontArc, TextPainter, Ui},
     Main,
 };
 use std::ops::DerefMut;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod browse;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+mod pack;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+mod recent;
+#[cfg(not(target_arch = "wasm32"))]
+mod select;
+
+enum Start {
+    /// A chart, along with where it's stored locally.
+    #[cfg_attr(any(target_os = "android", target_os = "ios"), allow(dead_code))]
+    Chart(Box<dyn fs::FileSystem>, Option<std::path::PathBuf>),
+    /// A library, along with the config before overrides and profiles and the overrides.
+    #[cfg(not(target_arch = "wasm32"))]
+    Library(prpr::library::Library, Config, Vec<String>),
+}
+
 struct BaseScene(Option<NextScene>, bool);
 impl Scene for BaseScene {
     fn on_result(&mut self, _tm: &mut TimeManager, result: Box<dyn std::any::Any>) -> Result<()> {
@@ -35,81 +57,331 @@ impl Scene for BaseScene {
     }
 }
 
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+async fn download(url: String) -> Result<Box<dyn prpr::fs::FileSystem>> {
+    use std::sync::{Arc, Mutex};
+
+    let progress = Arc::new(Mutex::new((0, None)));
+    let mut task = prpr::task::Task::new({
+        let progress = Arc::clone(&progress);
+        async move {
+            fs::fs_from_url(&url, Some(std::env::temp_dir().join("prpr-charts")), move |done, total| {
+                *progress.lock().unwrap() = (done, total);
+            })
+            .await
+        }
+    });
+    loop {
+        if let Some(result) = task.take() {
+            return result.context("Failed to download chart");
+        }
+        let (done, total): (u64, Option<u64>) = *progress.lock().unwrap();
+        let mb = |bytes: u64| bytes as f32 / 1024. / 1024.;
+        let text = match total {
+            Some(total) => format!("Downloading... {:.1} / {:.1} MB", mb(done), mb(total)),
+            None => format!("Downloading... {:.1} MB", mb(done)),
+        };
+        clear_background(BLACK);
+        let size = measure_text(&text, None, 32, 1.);
+        draw_text(&text, (screen_width() - size.width) / 2., screen_height() / 2., 32., WHITE);
+        next_frame().await;
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+fn parse_arg(args: &mut impl Iterator<Item = String>, name: &str) -> Result<f32> {
+    args.next().with_context(|| format!("Missing {name}"))?.parse().with_context(|| format!("Invalid {name}"))
+}
+
+/// Reads the config at `path` if given, with `overrides` from the environment, `--set` and `--profile` applied over it.
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+fn load_config(path: Option<&str>, overrides: &[String]) -> Result<Option<Config>> {
+    let config = match path {
+        Some(path) => Config::load(path)?,
+        None if overrides.is_empty() => return Ok(None),
+        None => Config::default(),
+    };
+    Ok(Some(config.resolve(overrides)?))
+}
+
+/// Remembers `settings` for the chart, keeping the ones not given unless `reset`.
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+async fn remember_settings(fs: &mut dyn fs::FileSystem, config: Option<&Config>, settings: ChartSettings, reset: bool) -> Result<()> {
+    let path = config
+        .and_then(|it| it.scores_path.as_ref())
+        .context("Chart settings are kept in the database at scoresPath, which is not set in the config")?;
+    let mut info = fs::load_info(fs).await?;
+    let hash = prpr::library::ensure_hash(fs, &mut info)
+        .await
+        .context("Cannot identify the chart")?
+        .to_owned();
+    let db = ScoreDatabase::open(path)?;
+    let old = if reset { ChartSettings::default() } else { db.settings(&hash)? };
+    db.save_settings(
+        &hash,
+        &ChartSettings {
+            offset: settings.offset.or(old.offset),
+            speed: settings.speed.or(old.speed),
+            aspect_ratio: settings.aspect_ratio.or(old.aspect_ratio),
+        },
+    )
+}
+
+/// The library opened on phones, in the storage of the app where charts can be copied to with a file manager.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn mobile_library() -> Result<String> {
+    #[cfg(target_os = "android")]
+    let root = unsafe {
+        use std::{ffi::CStr, ptr::null_mut};
+        let env = macroquad::miniquad::native::attach_jni_env();
+        let ctx = ndk_context::android_context().context();
+        let class = (**env).GetObjectClass.unwrap()(env, ctx as _);
+        let signature = b"(Ljava/lang/String;)Ljava/io/File;\0";
+        let method = (**env).GetMethodID.unwrap()(env, class, b"getExternalFilesDir\0".as_ptr() as _, signature.as_ptr() as _);
+        let dir = (**env).CallObjectMethod.unwrap()(env, ctx as _, method, null_mut::<std::ffi::c_void>());
+        anyhow::ensure!(!dir.is_null(), "External storage is not available");
+        let class = (**env).GetObjectClass.unwrap()(env, dir);
+        let method = (**env).GetMethodID.unwrap()(env, class, b"getAbsolutePath\0".as_ptr() as _, b"()Ljava/lang/String;\0".as_ptr() as _);
+        let path = (**env).CallObjectMethod.unwrap()(env, dir, method);
+        let chars = (**env).GetStringUTFChars.unwrap()(env, path, null_mut());
+        let root = CStr::from_ptr(chars).to_string_lossy().into_owned();
+        (**env).ReleaseStringUTFChars.unwrap()(env, path, chars);
+        root
+    };
+    // shown in the Files app, with file sharing enabled
+    #[cfg(target_os = "ios")]
+    let root = format!("{}/Documents", std::env::var("HOME").context("Cannot find the home folder")?);
+    let path = format!("{root}/library");
+    std::fs::create_dir_all(&path).with_context(|| format!("Cannot create library at {path}"))?;
+    Ok(path)
+}
+
+/// Writes the HUD layout edited in game into the config file, leaving the other keys as they are.
+fn save_hud_layout(path: &str, layout: HudLayout) -> Result<()> {
+    let format = ConfigFormat::of(std::path::Path::new(path));
+    let mut config = format.parse(&std::fs::read_to_string(path)?)?;
+    config.as_mapping_mut().context("The config is not a mapping")?.insert("hudLayout".into(), serde_yaml::to_value(layout)?);
+    std::fs::write(path, format.to_string(&config)?)?;
+    Ok(())
+}
+
 #[macroquad::main(build_conf)]
 async fn main() -> Result<()> {
     init_assets();
 
+    let _guard = {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(4)
+                .enable_all()
+                .build()
+                .unwrap();
+            let rt = Box::leak(Box::new(rt));
+            rt.enter()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            ()
+        }
+    };
+
     #[cfg(target_arch = "wasm32")]
-    let (mut fs, config) = {
+    let (start, config, config_path) = {
         fn js_err(err: wasm_bindgen::JsValue) -> anyhow::Error {
             anyhow::Error::msg(format!("{err:?}"))
         }
         let params = web_sys::UrlSearchParams::new_with_str(&web_sys::window().unwrap().location().search().map_err(js_err)?).map_err(js_err)?;
         let name = params.get("chart").unwrap_or_else(|| "nc".to_string());
         (
-            fs::fs_from_assets(format!("charts/{name}/"))?,
-            Some(prpr::config::Config {
+            Start::Chart(fs::fs_from_assets(format!("charts/{name}/"))?, None),
+            Some(Config {
                 autoplay: false,
                 ..Default::default()
             }),
+            None,
         )
     };
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+    let mut overrides = Config::env_overrides();
     #[cfg(any(target_os = "android", target_os = "ios"))]
-    let (mut fs, config) = (fs::fs_from_assets("charts/moment/")?, None);
+    let (start, config, config_path) = (Start::Library(select::open_library(&mobile_library()?).await?, Config::default(), Vec::new()), None, None);
     #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
-    let (mut fs, config) = {
+    let (start, config, config_path) = {
         let mut args = std::env::args();
         let program = args.next().unwrap();
-        let Some(path) = args.next() else {
-            anyhow::bail!("Usage: {program} <chart>");
-        };
-        let mut config = None;
-        if let Some(config_path) = args.next() {
-            config = Some(serde_yaml::from_str(&std::fs::read_to_string(config_path).context("Cannot read from config file")?)?);
+        let mut server = std::env::var("PRPR_SHARE_SERVER").ok();
+        let (mut code, mut share, mut list, mut search, mut pack, mut output) = (None, None, None, None, None, None);
+        let (mut library, mut import) = (None, None);
+        let (mut settings, mut reset_settings) = (ChartSettings::default(), false);
+        let mut paths = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--offset" => settings.offset = Some(parse_arg(&mut args, "offset")?),
+                "--speed" => settings.speed = Some(parse_arg(&mut args, "speed")?),
+                "--aspect-ratio" => settings.aspect_ratio = Some(parse_arg(&mut args, "aspect ratio")?),
+                "--reset-settings" => reset_settings = true,
+                "--set" => overrides.push(args.next().context("Missing config override")?),
+                "--profile" => overrides.push(format!("profile={}", serde_json::to_string(&args.next().context("Missing profile")?)?)),
+                "--server" => server = Some(args.next().context("Missing server")?),
+                "--code" => code = Some(args.next().context("Missing share code")?),
+                "--share" => share = Some(args.next().context("Missing chart to share")?),
+                "--list" => list = Some(args.next().context("Missing library folder")?),
+                "--search" => search = Some(args.next().context("Missing search text")?),
+                "--library" => library = Some(args.next().context("Missing library folder")?),
+                "--pack" => pack = Some(args.next().context("Missing chart folder")?),
+                "--import" => import = Some(args.next().context("Missing data package")?),
+                "-o" | "--output" => output = Some(args.next().context("Missing output path")?),
+                _ => paths.push(arg),
+            }
         }
-        (fs::fs_from_file(std::path::Path::new(&path))?, config)
-    };
-
-    let _guard = {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let rt = tokio::runtime::Builder::new_multi_thread()
-                .worker_threads(4)
-                .enable_all()
-                .build()
-                .unwrap();
-            let rt = Box::leak(Box::new(rt));
-            rt.enter()
+        let mut paths = paths.into_iter();
+        let server = || server.clone().context("No sharing server given, use --server or PRPR_SHARE_SERVER");
+        if let Some(root) = list {
+            let mut library = prpr::library::Library::open(root)?;
+            library.scan().await?;
+            library.save()?;
+            let query = prpr::library::LibraryQuery {
+                title: search,
+                ..Default::default()
+            };
+            for entry in library.query(&query) {
+                println!("{}\t{}\t{}\t{}", entry.info.name, entry.info.level, entry.info.charter, entry.path);
+            }
+            return Ok(());
         }
-        #[cfg(target_arch = "wasm32")]
-        {
-            ()
+        if let Some(folder) = pack {
+            let folder = std::path::PathBuf::from(folder);
+            let output = output.map_or_else(|| folder.with_extension("pez"), std::path::PathBuf::from);
+            pack::pack(&folder, &output).await?;
+            println!("Packed into {}", output.display());
+            return Ok(());
+        }
+        if let Some(package) = import {
+            #[cfg(feature = "unity")]
+            {
+                let folder = std::path::PathBuf::from(output.unwrap_or_else(|| ".".to_owned()));
+                let count = fs::import_package(std::path::Path::new(&package), |name, chart| {
+                    std::fs::write(folder.join(format!("{name}.pez")), chart.to_zip()?).with_context(|| format!("Cannot save {name}"))
+                })?;
+                println!("Imported {count} charts into {}", folder.display());
+                return Ok(());
+            }
+            #[cfg(not(feature = "unity"))]
+            anyhow::bail!("Cannot import {package}, official data packages are not supported in this build");
+        }
+        if let Some(path) = share {
+            let bytes = std::fs::read(&path).with_context(|| format!("Cannot read {path}, only packed charts can be shared"))?;
+            let code = prpr::ext::spawn_task(fs::share_chart(&server()?, bytes)).await??;
+            println!("Shared with code {code}");
+            return Ok(());
+        }
+        if let Some(root) = library {
+            let config_path = paths.next();
+            let base = config_path.as_deref().map(Config::load).transpose()?.unwrap_or_default();
+            let config = base.resolve(&overrides)?;
+            (Start::Library(select::open_library(&root).await?, base, overrides.clone()), Some(config), config_path)
+        } else {
+            let mut recent = recent::RecentCharts::load();
+            let path = match code {
+                Some(code) => fs::shared_chart_url(&server()?, &code)?,
+                None => match paths.next() {
+                    Some(path) if fs::is_url(&path) => path,
+                    Some(path) => std::fs::canonicalize(&path).map_or(path, |it| it.display().to_string()),
+                    None if !recent.0.is_empty() => match recent.choose().await {
+                        Some(path) => path,
+                        None => return Ok(()),
+                    },
+                    None => anyhow::bail!("Usage: {program} <chart | --code <code>> [config]"),
+                },
+            };
+            let config_path = paths.next();
+            let config = load_config(config_path.as_deref(), &overrides)?;
+            let local = (!fs::is_url(&path)).then(|| std::path::PathBuf::from(&path));
+            let mut fs = match &local {
+                Some(local) => fs::fs_from_file(local)?,
+                None => download(path.clone()).await?,
+            };
+            if fs::needs_password(fs.deref_mut()) {
+                eprint!("The chart is encrypted, password: ");
+                let mut password = String::new();
+                std::io::stdin().read_line(&mut password).context("Cannot read password")?;
+                fs::set_password(fs.deref_mut(), password.trim_end_matches(['\r', '\n']).to_owned())?;
+            }
+            if !settings.is_empty() || reset_settings {
+                remember_settings(fs.deref_mut(), config.as_ref(), settings, reset_settings).await?;
+            }
+            // identified by the hash, see `RecentCharts::add`
+            let mut info = fs::load_info(fs.deref_mut()).await?;
+            prpr::library::ensure_hash(fs.deref_mut(), &mut info).await;
+            recent.add(path, &info);
+            if let Err(err) = recent.save() {
+                warn!("Failed to save recently played charts: {:?}", err);
+            }
+            (Start::Chart(fs, local), config, config_path)
         }
     };
 
     let font = FontArc::try_from_vec(load_file("font.ttf").await?)?;
     let mut painter = TextPainter::new(font);
 
-    let info = fs::load_info(fs.deref_mut()).await?;
     let config = config.unwrap_or_default();
+    if let Some(lang) = &config.language {
+        set_language(lang);
+    }
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+    let mut config_watcher = config_path.as_ref().map(|path| prpr::config::ConfigWatcher::new(path, overrides, config.clone()));
 
     let mut fps_time = -1;
 
     let tm = TimeManager::default();
     let ctm = TimeManager::from_config(&config); // strange variable name...
-    let mut main = Main::new(
-        Box::new(BaseScene(
-            Some(NextScene::Overlay(Box::new(LoadingScene::new(GameMode::Normal, info, config, fs, (None, None), None, None).await?))),
-            false,
-        )),
-        ctm,
-        None,
-    )
-    .await?;
+    let transition = config.transition;
+    apply_accessibility(&config);
+    let scene: Box<dyn Scene> = match start {
+        Start::Chart(mut fs, path) => {
+            let info = fs::load_info(fs.deref_mut()).await?;
+            let config = config.for_chart(fs.deref_mut(), path.as_deref()).await?;
+            Box::new(BaseScene(
+                Some(NextScene::Overlay(Box::new(LoadingScene::new(GameMode::Normal, info, config, fs, (None, None), None, None).await?))),
+                false,
+            ))
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Start::Library(library, base, overrides) => Box::new(select::SongSelectScene::new(library, config, (base, overrides))),
+    };
+    let mut main = Main::new(scene, ctm, None).await?;
+    main.transition = transition;
     'app: loop {
         let frame_start = tm.real_time();
         main.update()?;
         main.render(&mut Ui::new(&mut painter))?;
+        if let Some(layout) = take_hud_layout() {
+            match &config_path {
+                Some(path) => {
+                    if let Err(err) = save_hud_layout(path, layout) {
+                        show_error(err.context("Failed to save HUD layout"));
+                    }
+                    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+                    if let Some(watcher) = &mut config_watcher {
+                        watcher.saved();
+                    }
+                }
+                None => {
+                    show_message("No config file was given, so the HUD layout only applies until this chart is closed").warn();
+                }
+            }
+        }
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+        if let Some(config) = config_watcher.as_mut().and_then(|it| it.update()) {
+            prpr::scene::reload_config(config);
+        }
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android"), not(target_os = "ios")))]
+        if let Some((profile, config)) = select::take_switched_profile() {
+            if let Some(watcher) = &mut config_watcher {
+                watcher.set_profile(profile.as_deref(), config);
+            }
+        }
         if main.should_exit() {
             break 'app;
         }
@@ -0,0 +1,170 @@
+use anyhow::{bail, Context, Result};
+use image::{imageops::FilterType, ImageFormat};
+use macroquad::prelude::*;
+use prpr::{
+    fs::{self, ExternalFileSystem, FileSystem},
+    info::ChartInfo,
+    scene::GameScene,
+};
+use std::{
+    io::{Cursor, Write},
+    path::Path,
+};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+/// Left out wherever they are, along with hidden files.
+const JUNK: &[&str] = &["__MACOSX", "Thumbs.db", "desktop.ini"];
+/// Replaced by the normalized `info.yml`.
+const INFO_FILES: &[&str] = &["info.yml", "info.txt", "info.csv"];
+/// Illustrations larger than this on either side are scaled down.
+const MAX_ILLUSTRATION_SIZE: u32 = 2048;
+
+fn is_junk(name: &str) -> bool {
+    name.starts_with('.') || name.ends_with('~') || name.ends_with(".bak") || JUNK.contains(&name)
+}
+
+fn list_files(root: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(root.join(prefix))? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            bail!("Non UTF-8 file name: {:?}", entry.file_name());
+        };
+        if is_junk(&name) {
+            continue;
+        }
+        let path = format!("{prefix}{name}");
+        if entry.file_type()?.is_dir() {
+            list_files(root, &format!("{path}/"), files)?;
+        } else if !(prefix.is_empty() && INFO_FILES.contains(&name.as_str())) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Scales the illustration down if it's oversized, returning the path and content to pack.
+///
+/// JPEG and PNG illustrations keep their format, others are written as PNG. The extension is changed to match the format written.
+fn shrink_illustration(path: &str, bytes: Vec<u8>) -> Result<(String, Vec<u8>)> {
+    let image = image::load_from_memory(&bytes).with_context(|| format!("Invalid illustration {path}"))?;
+    if image.width().max(image.height()) <= MAX_ILLUSTRATION_SIZE {
+        return Ok((path.to_owned(), bytes));
+    }
+    info!("Scaling down illustration of {}x{}", image.width(), image.height());
+    // told by the content, as the extension may not match it
+    let format = match image::guess_format(&bytes) {
+        Ok(format @ (ImageFormat::Jpeg | ImageFormat::Png)) => format,
+        _ => ImageFormat::Png,
+    };
+    let path = if ImageFormat::from_path(path).ok() == Some(format) {
+        path.to_owned()
+    } else {
+        let path = Path::new(path).with_extension(format.extensions_str()[0]);
+        path.to_str().unwrap().to_owned()
+    };
+    let mut res = Vec::new();
+    image
+        .resize(MAX_ILLUSTRATION_SIZE, MAX_ILLUSTRATION_SIZE, FilterType::Lanczos3)
+        .write_to(&mut Cursor::new(&mut res), format)?;
+    Ok((path, res))
+}
+
+/// Checks the chart in `folder` and packs it into a zip archive at `output` for release.
+///
+/// The info file is rewritten as `info.yml`, junk files are left out and oversized illustrations are scaled down.
+pub async fn pack(folder: &Path, output: &Path) -> Result<()> {
+    let root = folder.canonicalize().with_context(|| format!("Cannot open {}", folder.display()))?;
+    if !root.is_dir() {
+        bail!("{} is not a folder", folder.display());
+    }
+    let mut fs = ExternalFileSystem::new(&root);
+    let info = fs::load_info(&mut fs).await.context("Invalid chart info")?;
+    GameScene::load_chart(&mut fs, &info).await.context("Invalid chart")?;
+    for (what, path) in [("music", &info.music), ("illustration", &info.illustration)] {
+        if !fs.exists(path).await? {
+            bail!("Cannot find {what} {path}");
+        }
+    }
+
+    let mut files = Vec::new();
+    list_files(&root, "", &mut files)?;
+    files.sort();
+    let mut buffer = Vec::new();
+    let mut w = ZipWriter::new(Cursor::new(&mut buffer));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut normalized = ChartInfo {
+        id: None,
+        hash: None,
+        ..info.clone()
+    };
+    for file in files {
+        let mut bytes = fs.load_file(&file).await?;
+        let mut name = file;
+        if name == info.illustration {
+            (name, bytes) = shrink_illustration(&name, bytes)?;
+            normalized.illustration = name.clone();
+        }
+        w.start_file(&name, options)?;
+        w.write_all(&bytes)?;
+    }
+    // written last, as the illustration may be renamed
+    w.start_file("info.yml", options)?;
+    w.write_all(serde_yaml::to_string(&normalized)?.as_bytes())?;
+    w.finish()?;
+    drop(w);
+    std::fs::write(output, buffer).with_context(|| format!("Failed to write to {}", output.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let mut res = Vec::new();
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut res), format)
+            .unwrap();
+        res
+    }
+
+    #[test]
+    fn junk_files() {
+        for name in [
+            ".DS_Store",
+            ".git",
+            "__MACOSX",
+            "Thumbs.db",
+            "desktop.ini",
+            "chart.json~",
+            "chart.json.bak",
+        ] {
+            assert!(is_junk(name), "{name}");
+        }
+        for name in ["chart.json", "song.ogg", "bak", "thumbs.db.json", "illustration.png"] {
+            assert!(!is_junk(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn small_illustrations_kept() {
+        let bytes = encode(64, 64, ImageFormat::Bmp);
+        assert_eq!(shrink_illustration("bg.bmp", bytes.clone()).unwrap(), ("bg.bmp".to_owned(), bytes));
+    }
+
+    #[test]
+    fn large_illustrations_shrunk() {
+        let (path, bytes) = shrink_illustration("art/bg.bmp", encode(2560, 64, ImageFormat::Bmp)).unwrap();
+        assert_eq!(path, "art/bg.png");
+        assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::Png);
+        let image = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (2048, 51));
+
+        // JPEG stays JPEG, only the extension is fixed if it doesn't match
+        let (path, bytes) = shrink_illustration("bg.jpeg", encode(2560, 64, ImageFormat::Jpeg)).unwrap();
+        assert_eq!(path, "bg.jpeg");
+        assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::Jpeg);
+        let (path, _) = shrink_illustration("bg.jpg", encode(64, 2560, ImageFormat::Png)).unwrap();
+        assert_eq!(path, "bg.png");
+    }
+}
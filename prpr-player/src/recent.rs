@@ -0,0 +1,105 @@
+use anyhow::Result;
+use macroquad::prelude::*;
+use prpr::info::ChartInfo;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const MAX_RECENT: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentChart {
+    /// Absolute path or URL of the chart.
+    pub path: String,
+    pub name: String,
+    pub level: String,
+    pub hash: Option<String>,
+}
+
+/// Charts opened lately, newest first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct RecentCharts(pub Vec<RecentChart>);
+
+impl RecentCharts {
+    fn file() -> Option<PathBuf> {
+        dirs::data_dir().map(|it| it.join("prpr-player").join("recent.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::file()
+            .and_then(|it| std::fs::read(it).ok())
+            .and_then(|it| serde_json::from_slice(&it).ok())
+            .unwrap_or_default()
+    }
+
+    /// Moves the chart to the top, identified by its hash so that the entry follows the chart when it's moved.
+    pub fn add(&mut self, path: String, info: &ChartInfo) {
+        self.0.retain(|it| it.path != path && (it.hash.is_none() || it.hash != info.hash));
+        self.0.insert(
+            0,
+            RecentChart {
+                path,
+                name: info.name.clone(),
+                level: info.level.clone(),
+                hash: info.hash.clone(),
+            },
+        );
+        self.0.truncate(MAX_RECENT);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(file) = Self::file() else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(file.parent().unwrap())?;
+        std::fs::write(file, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Shows the charts for the user to pick one, returning its path, or `None` if the window is closed with Escape.
+    pub async fn choose(&self) -> Option<String> {
+        let (size, row) = (28., 48.);
+        loop {
+            if is_key_pressed(KeyCode::Escape) {
+                return None;
+            }
+            clear_background(BLACK);
+            draw_text("Recently played", 40., 60., 40., WHITE);
+            let (mx, my) = mouse_position();
+            for (index, chart) in self.0.iter().enumerate() {
+                let y = 100. + row * index as f32;
+                let rect = Rect::new(30., y, screen_width() - 60., row - 6.);
+                let hovered = rect.contains(vec2(mx, my));
+                if hovered {
+                    draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::new(1., 1., 1., 0.15));
+                    if is_mouse_button_pressed(MouseButton::Left) {
+                        return Some(chart.path.clone());
+                    }
+                }
+                let key = if index < 9 { format!("{}. ", index + 1) } else { String::new() };
+                draw_text(&format!("{key}{}  [{}]", chart.name, chart.level), rect.x + 10., y + size, size, WHITE);
+                let path_size = measure_text(&chart.path, None, 20, 1.);
+                draw_text(&chart.path, rect.right() - path_size.width - 10., y + size, 20., GRAY);
+            }
+            const KEYS: [KeyCode; 9] = [
+                KeyCode::Key1,
+                KeyCode::Key2,
+                KeyCode::Key3,
+                KeyCode::Key4,
+                KeyCode::Key5,
+                KeyCode::Key6,
+                KeyCode::Key7,
+                KeyCode::Key8,
+                KeyCode::Key9,
+            ];
+            for (index, key) in KEYS.into_iter().enumerate() {
+                if is_key_pressed(key) {
+                    if let Some(chart) = self.0.get(index) {
+                        return Some(chart.path.clone());
+                    }
+                }
+            }
+            next_frame().await;
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use crate::browse::BrowseScene;
+use anyhow::{Context, Result};
+use macroquad::prelude::*;
+use prpr::{
+    config::Config,
+    ext::{poll_future, screen_aspect, LocalTask, SafeTexture, ScaleType},
+    fs,
+    library::{Library, LibraryEntry, LibraryWatcher},
+    scene::{downcast_result, show_error, show_message, GameMode, LoadingScene, NextScene, Scene, StatsScene},
+    scores::ScoreDatabase,
+    time::TimeManager,
+    ui::{Dialog, Grid, Ui},
+};
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc, sync::Mutex};
+
+const ROW_HEIGHT: f32 = 0.16;
+const SCAN_INTERVAL: f64 = 5.;
+
+static SWITCHED_PROFILE: Mutex<Option<(Option<String>, Config)>> = Mutex::new(None);
+
+/// Takes the profile last switched to along with the config resolved with it, for the config watcher to keep it.
+#[cfg_attr(any(target_os = "android", target_os = "ios"), allow(dead_code))]
+pub fn take_switched_profile() -> Option<(Option<String>, Config)> {
+    SWITCHED_PROFILE.lock().unwrap().take()
+}
+
+/// Lists the charts of a library to play, picking up those dropped into its folder meanwhile.
+pub struct SongSelectScene {
+    watcher: LibraryWatcher,
+    config: Config,
+    /// The config before overrides and profiles, and the overrides, to switch profiles with.
+    base: (Config, Vec<String>),
+    target: Option<RenderTarget>,
+    list: Grid,
+    thumbnails: HashMap<String, Option<SafeTexture>>,
+    /// Best scores by chart hash, cleared when coming back from a play.
+    bests: HashMap<String, Option<(u32, f64)>>,
+    load_task: LocalTask<Result<LoadingScene>>,
+    /// An encrypted chart waiting for its password, which the dialog asking for it fills in.
+    locked: Option<(LibraryEntry, Rc<RefCell<Option<String>>>)>,
+    next_scene: Option<NextScene>,
+}
+
+impl SongSelectScene {
+    pub fn new(library: Library, config: Config, base: (Config, Vec<String>)) -> Self {
+        Self {
+            watcher: LibraryWatcher::new(library, SCAN_INTERVAL),
+            config,
+            base,
+            target: None,
+            list: Grid::list(ROW_HEIGHT),
+            thumbnails: HashMap::new(),
+            bests: HashMap::new(),
+            load_task: None,
+            locked: None,
+            next_scene: None,
+        }
+    }
+
+    fn play(&mut self, entry: &LibraryEntry, password: Option<String>) -> Result<()> {
+        let path = self.watcher.library().root().join(&entry.path);
+        let mut fs = fs::fs_from_file(&path)?;
+        if let Some(password) = password {
+            fs::set_password(fs.as_mut(), password)?;
+        }
+        if fs::needs_password(fs.as_mut()) {
+            let password = Rc::new(RefCell::new(None));
+            self.locked = Some((entry.clone(), Rc::clone(&password)));
+            Dialog::prompt("Encrypted chart", format!("{} is encrypted, enter its password", entry.info.name), "", move |it| {
+                *password.borrow_mut() = Some(it);
+            })
+            .password()
+            .show();
+            return Ok(());
+        }
+        let (info, config) = (entry.info.clone(), self.config.clone());
+        self.load_task = Some(Box::pin(async move {
+            let config = config.for_chart(fs.as_mut(), Some(&path)).await?;
+            LoadingScene::new(GameMode::Normal, info, config, fs, (None, None), None, None).await
+        }));
+        Ok(())
+    }
+}
+
+fn thumbnail(thumbnails: &mut HashMap<String, Option<SafeTexture>>, root: &Path, entry: &LibraryEntry) -> Option<SafeTexture> {
+    thumbnails
+        .entry(entry.path.clone())
+        .or_insert_with(|| {
+            let image = image::open(root.join(entry.thumbnail.as_ref()?)).ok()?.into_rgba8();
+            Some(Texture2D::from_rgba8(image.width() as _, image.height() as _, &image).into())
+        })
+        .clone()
+}
+
+fn best(bests: &mut HashMap<String, Option<(u32, f64)>>, config: &Config, hash: &str) -> Option<(u32, f64)> {
+    let path = config.scores_path.as_ref()?;
+    *bests.entry(hash.to_owned()).or_insert_with(|| {
+        ScoreDatabase::open(path)
+            .and_then(|db| db.best(hash))
+            .map_err(|err| warn!("Failed to query best score: {:?}", err))
+            .ok()
+            .flatten()
+    })
+}
+
+impl Scene for SongSelectScene {
+    fn enter(&mut self, _tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        self.target = target;
+        self.bests.clear();
+        Ok(())
+    }
+
+    fn on_result(&mut self, _tm: &mut TimeManager, result: Box<dyn std::any::Any>) -> Result<()> {
+        if let Ok(error) = downcast_result::<anyhow::Error>(result) {
+            show_error(error.context("Failed to load chart"));
+        }
+        Ok(())
+    }
+
+    fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        if self.load_task.is_some() {
+            return Ok(true);
+        }
+        if !self.list.touch(touch, tm.now() as f32) {
+            return Ok(false);
+        }
+        if let Some(entry) = self.list.tapped().and_then(|index| self.watcher.library().entries.get(index).cloned()) {
+            if let Err(err) = self.play(&entry, None) {
+                show_error(err.context("Failed to load chart"));
+            }
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        let entered = self.locked.as_ref().and_then(|it| it.1.borrow_mut().take());
+        if let Some(password) = entered {
+            let (entry, _) = self.locked.take().unwrap();
+            if let Err(err) = self.play(&entry, Some(password)) {
+                show_error(err.context("Failed to load chart"));
+            }
+        }
+        self.watcher.update();
+        self.list.update(tm.now() as f32);
+        if let Some(task) = &mut self.load_task {
+            if let Some(result) = poll_future(task.as_mut()) {
+                self.load_task = None;
+                match result {
+                    Ok(scene) => self.next_scene = Some(NextScene::Overlay(Box::new(scene))),
+                    Err(err) => show_error(err.context("Failed to load chart")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, _tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        let top = 1. / screen_aspect();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -screen_aspect()),
+            render_target: self.target,
+            ..Default::default()
+        });
+        clear_background(BLACK);
+        ui.text("Charts").pos(-0.92, -top + 0.04).size(0.8).draw();
+        let profiles = self.config.profile_names();
+        if !profiles.is_empty() {
+            let label = format!("Profile: {}", self.config.profile.as_deref().unwrap_or("default"));
+            if ui.button("select#profile", Rect::new(-0.1, -top + 0.04, 0.34, 0.08), label) {
+                // cycles through the profiles and then back to none of them
+                let next = match &self.config.profile {
+                    Some(name) => profiles.iter().position(|it| it == name).and_then(|index| profiles.get(index + 1)),
+                    None => profiles.first(),
+                };
+                match self.base.0.with_profile(next.copied(), &self.base.1) {
+                    Ok(config) => {
+                        show_message(format!("Switched to profile {}", next.copied().unwrap_or("default"))).ok();
+                        *SWITCHED_PROFILE.lock().unwrap() = Some((next.map(|it| it.to_string()), config.clone()));
+                        self.config = config;
+                        self.bests.clear();
+                    }
+                    Err(err) => show_error(err.context("Failed to switch profile")),
+                }
+            }
+        }
+        if self.config.scores_path.is_some() && ui.button("select#stats", Rect::new(0.26, -top + 0.04, 0.34, 0.08), "Statistics") {
+            match StatsScene::new(&self.config) {
+                Ok(scene) => self.next_scene = Some(NextScene::Overlay(Box::new(scene))),
+                Err(err) => show_error(err.context("Failed to load statistics")),
+            }
+        }
+        if let Some(repo) = &self.config.chart_repository {
+            if ui.button("select#browse", Rect::new(0.62, -top + 0.04, 0.34, 0.08), "Browse online") {
+                match BrowseScene::new(repo, self.watcher.library().root().to_owned()) {
+                    Ok(scene) => self.next_scene = Some(NextScene::Overlay(Box::new(scene))),
+                    Err(err) => show_error(err),
+                }
+            }
+        }
+        if self.watcher.library().entries.is_empty() {
+            ui.text("Put charts into the library folder to play them")
+                .pos(0., 0.)
+                .anchor(0.5, 0.5)
+                .size(0.5)
+                .color(GRAY)
+                .draw();
+        }
+        let width = 1.84;
+        ui.scope(|ui| {
+            ui.dx(-width / 2.);
+            ui.dy(-top + 0.18);
+            self.list.size((width, top * 2. - 0.2));
+            let Self {
+                list,
+                watcher,
+                config,
+                thumbnails,
+                bests,
+                ..
+            } = self;
+            let entries = &watcher.library().entries;
+            list.render(ui, entries.len(), |ui, index, r| {
+                let entry = &entries[index];
+                let r = Rect { h: r.h - 0.01, ..r };
+                ui.fill_rect(r, Color::new(1., 1., 1., 0.08));
+                let jacket = Rect::new(r.x + 0.01, r.y + 0.01, (r.h - 0.02) * 16. / 9., r.h - 0.02);
+                match thumbnail(thumbnails, watcher.library().root(), entry) {
+                    Some(tex) => ui.fill_rect(jacket, (*tex, jacket, ScaleType::CropCenter)),
+                    None => ui.fill_rect(jacket, Color::new(1., 1., 1., 0.1)),
+                }
+                let x = jacket.right() + 0.03;
+                ui.text(&entry.info.name)
+                    .pos(x, r.y + r.h * 0.3)
+                    .anchor(0., 0.5)
+                    .size(0.6)
+                    .max_width(r.right() - x - 0.45)
+                    .draw();
+                ui.text(format!("{}  ·  {}", entry.info.composer, entry.info.charter))
+                    .pos(x, r.y + r.h * 0.72)
+                    .anchor(0., 0.5)
+                    .size(0.36)
+                    .color(GRAY)
+                    .draw();
+                ui.text(&entry.info.level)
+                    .pos(r.right() - 0.03, r.y + r.h * 0.3)
+                    .anchor(1., 0.5)
+                    .size(0.5)
+                    .draw();
+                if let Some((score, accuracy)) = entry.hash.as_deref().and_then(|it| best(bests, config, it)) {
+                    ui.text(format!("{score:07}  {:.2}%", accuracy * 100.))
+                        .pos(r.right() - 0.03, r.y + r.h * 0.72)
+                        .anchor(1., 0.5)
+                        .size(0.36)
+                        .color(GRAY)
+                        .draw();
+                }
+            });
+        });
+        if self.load_task.is_some() {
+            ui.fill_rect(ui.screen_rect(), Color::new(0., 0., 0., 0.6));
+            ui.text("Loading...").pos(0., 0.).anchor(0.5, 0.5).size(0.6).draw();
+        }
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        self.next_scene.take().unwrap_or_default()
+    }
+}
+
+/// Opens the library at `root`, indexing it for the first time if needed.
+pub async fn open_library(root: &str) -> Result<Library> {
+    let mut library = Library::open(root).with_context(|| format!("Cannot open library at {root}"))?;
+    if library.entries.is_empty() {
+        library.scan().await?;
+        library.save()?;
+    }
+    Ok(library)
+}
@@ -0,0 +1,3 @@
+encoder-init-failed = Failed to initialize the openh264 encoder
+create-file-failed = Failed to create { $path }
+encode-failed = Failed to encode
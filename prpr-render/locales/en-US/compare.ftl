@@ -0,0 +1,10 @@
+video-only = Compare mode only supports video output
+ffmpeg-required = Compare mode requires ffmpeg
+rendering-left = [Compare 1/3] Rendering the left side…
+rendering-right = [Compare 2/3] Rendering the right side…
+composing = [Compare 3/3] Composing…
+temp-dir-failed = Failed to create the temporary folder
+info-load-failed = Failed to load chart info
+ffmpeg-failed = Failed to run ffmpeg
+compose-failed = Failed to compose the comparison video
+saved = Comparison video saved to { $path }
@@ -0,0 +1 @@
+detail = Composer: { $composer }  Charter: { $charter }  { $level }
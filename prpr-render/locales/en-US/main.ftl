@@ -0,0 +1,108 @@
+format-png-sequence = PNG sequence
+format-exr-sequence = EXR sequence
+audio-codec-unsupported = { $format } does not support { $codec } audio
+
+resolution-invalid = Should be "WIDTHxHEIGHT", or 720p / 1080p / 1440p / 4k / vertical
+resolution-out-of-range = Width and height should be between 16 and 8192
+resolution-adjusted = Resolution { $from } is adjusted to { $to }
+
+error-file = error.txt
+error-occurred = An error occurred: { $error }
+backtrace = Backtrace:
+cancelling = Cancelling…
+ctrlc-failed = Failed to set the Ctrl+C handler
+
+config-load-failed = Failed to load config file
+config-override-invalid = Invalid config override given by `--set` or `--profile`
+render-config-invalid = Failed to load render config file: { $error }
+compare-config-load-failed = Failed to load the config file to compare with
+
+arg-missing-value = Missing value for `{ $arg }`
+unknown-arg = Unknown argument: { $arg }
+no-chart = Please drag a chart file or folder onto this program!
+fps-invalid = Invalid FPS
+fps-out-of-range = FPS should be between 1 and 480
+crf-invalid = Invalid CRF
+unknown-codec = Unknown codec: { $codec }
+unknown-audio-codec = Unknown audio codec: { $codec }
+unknown-format = Unknown output format: { $format }
+ending-length-invalid = Invalid ending length
+sample-rate-invalid = Invalid sample rate
+music-volume-invalid = Invalid music volume
+hitsound-volume-invalid = Invalid hitsound volume
+ending-volume-invalid = Invalid ending music volume
+target-loudness-invalid = Invalid target loudness
+intro-length-invalid = Invalid intro length
+unknown-compare-audio = Unknown audio to compare with: { $value }
+info-duration-invalid = Invalid chart info duration
+start-invalid = Invalid start time
+end-invalid = Invalid end time
+segments-invalid = Invalid number of segments
+preview-interval-invalid = Invalid preview interval
+unknown-watermark-position = Unknown watermark position: { $value }
+watermark-opacity-invalid = Invalid watermark opacity
+bitrate-invalid = Invalid bitrate: { $value }
+
+ffmpeg-fallback = ffmpeg is not found, using the builtin encoder instead
+no-encoder = ffmpeg is not found, and this build does not include the builtin encoder
+ffmpeg-failed = Failed to run ffmpeg
+segmented-single-chart = Only one chart can be rendered in segments
+compare-single-chart = Only one chart can be given in compare mode
+batch-chart-failed = Failed to render { $path }: { $error }
+batch-failed = { $count } charts failed to render
+read-dir-failed = Failed to read folder { $path }
+create-dir-failed = Failed to create folder { $path }
+create-file-failed = Failed to create { $path }
+temp-dir-failed = Failed to create the temporary folder
+
+chart-load-failed = Failed to load chart
+chart-wrong-password = Wrong chart password
+chart-encrypted = The chart is encrypted, please provide the password with --password
+info-load-failed = Failed to load chart info
+chart-config-invalid = Failed to load the config overrides of the chart (prpr.yml)
+chart-content-load-failed = Failed to load chart content
+sfx-load-failed = Failed to load sound effect `{ $path }`
+music-load-failed = Failed to load music
+illustration-read-failed = Failed to read illustration { $path }
+replay-read-failed = Failed to read replay { $path }
+replay-invalid = Invalid replay
+info-save-failed = Failed to save chart info
+info-saved = Chart info saved
+
+render-cancelled = Render cancelled
+estimate-audio-only = Estimation is not supported when exporting audio only
+metadata-comment = Charter: { $charter }  Illustrator: { $illustrator }  Level: { $level }
+transparent-unsupported = Transparent background is only supported by ProRes and image sequences
+builtin-no-flac = The builtin encoder cannot export FLAC
+builtin-format-unsupported = The builtin encoder only supports MP4 and image sequences
+builtin-aac-only = The builtin encoder only supports AAC audio
+end-before-start = End time should be later than start time
+high-fps = { $frames } frames are to be rendered at a high frame rate ({ $fps }fps), taking much longer
+high-fps-crf = With CRF, the file size grows roughly with the frame rate
+high-fps-bitrate = With a fixed bitrate, each frame gets less data. The file is expected to be { $size }MB, consider raising the bitrate accordingly
+
+mixing = [1] Mixing audio…
+unsupported-sample-rate = Unsupported sample rate: { $rate }
+loudness = Loudness { $measured } LUFS, gain { $gain }dB
+loudness-limited = To avoid clipping, loudness is only adjusted to { $loudness } LUFS
+audio-too-quiet = The audio is too quiet, skipping loudness normalization
+audio-done = Exported! Took { $time }s
+
+rendering-video = [2] Rendering video…
+chapter-intro = Intro
+chapter-chart = Chart
+chapter-ending = Result
+chapters-write-failed = Failed to write chapters
+subtitles-write-failed = Failed to write subtitles { $path }
+extra-ffmpeg-args = Extra ffmpeg arguments: input { $input }, filters { $filters }, output { $output }
+ffmpeg-args-input = Extra ffmpeg arguments cannot contain input files
+ffmpeg-args-invalid = Invalid extra ffmpeg arguments, check the quotes
+encoder-unavailable = Encoder { $encoder } is unavailable, skipping
+hwaccel-unsupported = Hardware acceleration for { $codec } is not supported! Tried { $tried }
+encoder-unsupported = ffmpeg does not support { $codec } encoder { $tried }
+using-encoder = Using encoder: { $encoder }
+write-video-failed = Failed to write video: { $error }
+remaining = left
+average-speed = { $fps }fps on average, transferring { $rate }MB/s
+estimate = Estimated from a { $sample }s trial render: about { $time } in total, about { $size }MB in size
+render-done = Rendered! Took { $time }s
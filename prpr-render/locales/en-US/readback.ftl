@@ -0,0 +1 @@
+map-failed = Failed to read the rendered frame back (OpenGL error { $error })
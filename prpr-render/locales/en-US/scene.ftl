@@ -0,0 +1,62 @@
+chart-load-failed = Failed to load chart
+silence-failed = Failed to analyze music
+no-silence = The music has no leading silence
+silence-found = The music has { $silence }s of leading silence. If the chart is made for the music without it, increase the offset by this value
+
+preview = Preview
+save = Save settings
+save-failed = Failed to save render config
+saved = Saved
+render = Render
+save-last-failed = Failed to save the settings of this render: { $error }
+drag-hint = Note: drag the screen with the mouse to see more settings below
+load-last = Load the settings of the last render
+load-last-failed = Failed to load the last settings
+loaded = Loaded
+invalid-input = Invalid input
+
+# a label as long as the longest one below, so that they fit
+label-width = Watermark image
+resolution = Resolution
+high-fps-hint = High frame rates greatly increase render time and file size, consider raising the bitrate accordingly
+constant-quality = Constant quality (CRF)
+bitrate = Bitrate
+preset = Preset
+format = Output format
+transparent = Transparent background
+codec = Codec
+encoder = Encoder
+auto = Auto
+audio-codec = Audio codec
+default = Default
+audio-bitrate = Audio bitrate
+output = Output file
+output-dir = Output folder
+choose = Choose…
+overwrite = Overwrite existing files
+watermark-text = Watermark text
+watermark-image = Watermark image
+watermark-position = Watermark position
+watermark-opacity = Watermark opacity
+ending-length = Ending length
+start = Start time
+end = End time
+music-volume = Music volume
+hitsound-volume = Effect volume
+ending-volume = Ending volume
+hardware-accel = Enable hardware acceleration
+live-preview = Preview while rendering
+lower-third = Show chart info at the start
+subtitles = Export chart info subtitles
+save-info = Save modified chart info to the chart
+analyzing = Analyzing…
+detect-silence = Detect leading silence
+apply-silence = Offset +{ $silence }s
+applied = Applied
+audio-only = Export audio only
+flac = Export as FLAC
+
+output-exists = Output exists
+output-exists-content = { $path } already exists.
+output-keep-both = Keep both
+output-overwrite = Overwrite
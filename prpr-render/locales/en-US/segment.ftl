@@ -0,0 +1,18 @@
+segment-invalid = Should be "INDEX/COUNT"
+segment-out-of-range = Segment index should be between 1 and { $count }
+video-only = Segmented rendering only supports video output
+ffmpeg-required = Segmented rendering requires ffmpeg
+no-estimate = Estimation is not supported in segmented rendering
+info-load-failed = Failed to load chart info
+exe-path-failed = Failed to get the path of the program
+rendering = Rendering in { $count } segments in parallel…
+temp-dir-failed = Failed to create the temporary folder
+spawn-failed = Failed to start the render process
+segment-failed = Segment { $index } failed to render
+cancelled = Render cancelled
+failed = { $count } segments failed to render
+merging = Merging segments…
+list-write-failed = Failed to write the segment list
+ffmpeg-failed = Failed to run ffmpeg
+merge-failed = Failed to merge segments, the segment files are kept
+done = Rendered: { $path }
@@ -0,0 +1,3 @@
+save-failed = Failed to save { $path }
+writer-exited = The image writer thread has exited
+writer-panicked = The image writer thread panicked
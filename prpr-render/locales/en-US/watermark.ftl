@@ -0,0 +1,7 @@
+top-left = Top left
+top-right = Top right
+bottom-left = Bottom left
+bottom-right = Bottom right
+center = Center
+image-read-failed = Failed to read watermark image { $path }
+image-decode-failed = Failed to decode watermark image { $path }
@@ -0,0 +1,3 @@
+encoder-init-failed = 无法初始化 openh264 编码器
+create-file-failed = 无法创建 { $path }
+encode-failed = 编码失败
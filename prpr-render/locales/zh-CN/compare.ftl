@@ -0,0 +1,10 @@
+video-only = 对比模式仅支持输出视频
+ffmpeg-required = 对比模式需要 ffmpeg
+rendering-left = [对比 1/3] 渲染左侧…
+rendering-right = [对比 2/3] 渲染右侧…
+composing = [对比 3/3] 合成中…
+temp-dir-failed = 无法创建临时文件夹
+info-load-failed = 加载谱面信息失败
+ffmpeg-failed = 无法执行 ffmpeg
+compose-failed = 合成对比视频失败
+saved = 对比视频已保存到 { $path }
@@ -0,0 +1 @@
+detail = 曲师：{ $composer }  谱师：{ $charter }  { $level }
@@ -0,0 +1,108 @@
+format-png-sequence = PNG 序列
+format-exr-sequence = EXR 序列
+audio-codec-unsupported = { $format } 不支持 { $codec } 音频
+
+resolution-invalid = 格式应当为 “宽x高”，或 720p / 1080p / 1440p / 4k / vertical
+resolution-out-of-range = 宽高应当在 16 到 8192 之间
+resolution-adjusted = 分辨率 { $from } 已调整为 { $to }
+
+error-file = 错误信息.txt
+error-occurred = 发生错误：{ $error }
+backtrace = 详细堆栈：
+cancelling = 正在取消…
+ctrlc-failed = 无法注册 Ctrl+C 处理
+
+config-load-failed = 无法加载配置文件
+config-override-invalid = `--set` 或 `--profile` 指定的配置项无效
+render-config-invalid = 无法加载渲染配置文件：{ $error }
+compare-config-load-failed = 无法加载对比配置文件
+
+arg-missing-value = 参数 `{ $arg }` 缺少值
+unknown-arg = 未知参数：{ $arg }
+no-chart = 请将谱面文件或文件夹拖动到该软件上！
+fps-invalid = FPS 非法
+fps-out-of-range = FPS 应当在 1 到 480 之间
+crf-invalid = CRF 非法
+unknown-codec = 未知编码格式：{ $codec }
+unknown-audio-codec = 未知音频编码：{ $codec }
+unknown-format = 未知输出格式：{ $format }
+ending-length-invalid = 结算时间非法
+sample-rate-invalid = 采样率非法
+music-volume-invalid = 音乐音量非法
+hitsound-volume-invalid = 打击音效音量非法
+ending-volume-invalid = 结算音乐音量非法
+target-loudness-invalid = 目标响度非法
+intro-length-invalid = 开场时间非法
+unknown-compare-audio = 未知对比音频：{ $value }
+info-duration-invalid = 信息显示时间非法
+start-invalid = 起始时间非法
+end-invalid = 结束时间非法
+segments-invalid = 分段数非法
+preview-interval-invalid = 预览间隔非法
+unknown-watermark-position = 未知水印位置：{ $value }
+watermark-opacity-invalid = 水印不透明度非法
+bitrate-invalid = 码率非法：{ $value }
+
+ffmpeg-fallback = 未找到 ffmpeg，改用内置编码器
+no-encoder = 未找到 ffmpeg，且该版本未包含内置编码器
+ffmpeg-failed = 无法执行 ffmpeg
+segmented-single-chart = 分段渲染只能指定一个谱面
+compare-single-chart = 对比模式只能指定一个谱面
+batch-chart-failed = 渲染 { $path } 失败：{ $error }
+batch-failed = { $count } 个谱面渲染失败
+read-dir-failed = 无法读取文件夹 { $path }
+create-dir-failed = 无法创建文件夹 { $path }
+create-file-failed = 无法创建 { $path }
+temp-dir-failed = 无法创建临时文件夹
+
+chart-load-failed = 加载谱面失败
+chart-wrong-password = 谱面密码错误
+chart-encrypted = 谱面已加密，请使用 --password 提供密码
+info-load-failed = 加载谱面信息失败
+chart-config-invalid = 加载谱面的配置覆盖（prpr.yml）失败
+chart-content-load-failed = 加载谱面内容失败
+sfx-load-failed = 加载音效 `{ $path }` 失败
+music-load-failed = 加载音乐失败
+illustration-read-failed = 无法读取曲绘 { $path }
+replay-read-failed = 无法读取回放 { $path }
+replay-invalid = 回放格式有误
+info-save-failed = 保存谱面信息失败
+info-saved = 谱面信息已保存
+
+render-cancelled = 渲染已取消
+estimate-audio-only = 仅导出音频时不支持估算
+metadata-comment = 谱师：{ $charter }  曲绘：{ $illustrator }  等级：{ $level }
+transparent-unsupported = 透明背景仅支持 ProRes 与图片序列
+builtin-no-flac = 内置编码器不支持导出 FLAC
+builtin-format-unsupported = 内置编码器仅支持 MP4 格式与图片序列
+builtin-aac-only = 内置编码器仅支持 AAC 音频
+end-before-start = 结束时间应当晚于起始时间
+high-fps = 高帧率（{ $fps }fps）下共需渲染 { $frames } 帧，耗时会成倍增加
+high-fps-crf = 使用 CRF 时文件大小大致随帧率增长
+high-fps-bitrate = 码率固定时每帧分到的数据更少，预计文件大小 { $size }MB，建议相应提高码率
+
+mixing = [1] 混音中…
+unsupported-sample-rate = 不支持的采样率：{ $rate }
+loudness = 响度 { $measured } LUFS，增益 { $gain }dB
+loudness-limited = 为避免削波，响度仅调整到 { $loudness } LUFS
+audio-too-quiet = 音频过于安静，跳过响度标准化
+audio-done = 导出完成！耗时：{ $time }s
+
+rendering-video = [2] 渲染视频…
+chapter-intro = 开场
+chapter-chart = 谱面
+chapter-ending = 结算
+chapters-write-failed = 无法写入章节信息
+subtitles-write-failed = 无法写入字幕 { $path }
+extra-ffmpeg-args = 额外 ffmpeg 参数：输入 { $input }，滤镜 { $filters }，输出 { $output }
+ffmpeg-args-input = 额外 ffmpeg 参数中不能包含输入文件
+ffmpeg-args-invalid = 额外 ffmpeg 参数无效，请检查引号
+encoder-unavailable = 编码器 { $encoder } 不可用，跳过
+hwaccel-unsupported = 不支持 { $codec } 硬件加速！已尝试 { $tried }
+encoder-unsupported = ffmpeg 不支持 { $codec } 编码器 { $tried }
+using-encoder = 使用编码器：{ $encoder }
+write-video-failed = 写入视频失败：{ $error }
+remaining = 剩余
+average-speed = 平均 { $fps }fps，传输 { $rate }MB/s
+estimate = 按 { $sample }s 的试渲染估算：总耗时约 { $time }，文件大小约 { $size }MB
+render-done = 渲染完成！耗时：{ $time }s
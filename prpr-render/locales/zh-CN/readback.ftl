@@ -0,0 +1 @@
+map-failed = 无法读回渲染的帧（OpenGL 错误 { $error }）
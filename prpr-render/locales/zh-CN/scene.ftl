@@ -0,0 +1,62 @@
+chart-load-failed = 加载谱面失败
+silence-failed = 分析音乐失败
+no-silence = 音乐没有前导静音
+silence-found = 音乐有 { $silence }s 的前导静音，如谱面按无静音的音乐制作，可将偏移增加该值
+
+preview = 预览
+save = 保存设置
+save-failed = 保存渲染配置失败
+saved = 已保存
+render = 渲染
+save-last-failed = 无法保存本次渲染的设置：{ $error }
+drag-hint = 注：可以通过鼠标拖动屏幕来查看更下面的配置项
+load-last = 载入上次渲染的设置
+load-last-failed = 载入上次设置失败
+loaded = 已载入
+invalid-input = 输入非法
+
+# a label as long as the longest one below, so that they fit
+label-width = 一二三四
+resolution = 分辨率
+high-fps-hint = 高帧率会显著增加渲染时间与文件大小，建议相应提高码率
+constant-quality = 恒定质量（CRF）
+bitrate = 码率
+preset = 预设
+format = 输出格式
+transparent = 透明背景
+codec = 编码格式
+encoder = 编码器
+auto = 自动
+audio-codec = 音频编码
+default = 默认
+audio-bitrate = 音频码率
+output = 输出文件
+output-dir = 输出文件夹
+choose = 选择…
+overwrite = 覆盖已有文件
+watermark-text = 水印文字
+watermark-image = 水印图片
+watermark-position = 水印位置
+watermark-opacity = 水印不透明度
+ending-length = 结算时间
+start = 起始时间
+end = 结束时间
+music-volume = 音乐音量
+hitsound-volume = 音效音量
+ending-volume = 结算音量
+hardware-accel = 启用硬件加速
+live-preview = 渲染时预览画面
+lower-third = 开始时显示谱面信息
+subtitles = 导出谱面信息字幕
+save-info = 将修改的谱面信息保存到谱面
+analyzing = 分析中…
+detect-silence = 检测前导静音
+apply-silence = 偏移 +{ $silence }s
+applied = 已应用
+audio-only = 仅导出音频
+flac = 导出为 FLAC
+
+output-exists = 输出文件已存在
+output-exists-content = { $path } 已存在。
+output-keep-both = 保留两者
+output-overwrite = 覆盖
@@ -0,0 +1,18 @@
+segment-invalid = 格式应当为 “序号/总数”
+segment-out-of-range = 分段序号应当在 1 到 { $count } 之间
+video-only = 分段渲染仅支持输出视频
+ffmpeg-required = 分段渲染需要 ffmpeg
+no-estimate = 分段渲染时不支持估算
+info-load-failed = 加载谱面信息失败
+exe-path-failed = 无法获取程序路径
+rendering = 分 { $count } 段并行渲染…
+temp-dir-failed = 无法创建临时文件夹
+spawn-failed = 无法启动渲染进程
+segment-failed = 第 { $index } 段渲染失败
+cancelled = 渲染已取消
+failed = { $count } 段渲染失败
+merging = 合并分段…
+list-write-failed = 无法写入分段列表
+ffmpeg-failed = 无法执行 ffmpeg
+merge-failed = 合并分段失败，分段文件已保留
+done = 渲染完成：{ $path }
@@ -0,0 +1,3 @@
+save-failed = 无法保存 { $path }
+writer-exited = 图片写入线程已退出
+writer-panicked = 图片写入线程崩溃
@@ -0,0 +1,7 @@
+top-left = 左上
+top-right = 右上
+bottom-left = 左下
+bottom-right = 右下
+center = 居中
+image-read-failed = 无法读取水印图片 { $path }
+image-decode-failed = 无法解码水印图片 { $path }
@@ -0,0 +1,62 @@
+prpr::tl_file!("builtin");
+
+use anyhow::{Context, Result};
+use minimp4::Mp4Muxer;
+use openh264::{
+    encoder::{Encoder, EncoderConfig},
+    formats::YUVBuffer,
+};
+use std::{fs::File, io::BufWriter, path::Path};
+
+/// Encodes H.264 into MP4 without ffmpeg, slower and larger, but needs nothing to be installed.
+pub struct BuiltinEncoder {
+    encoder: Encoder,
+    muxer: Mp4Muxer<BufWriter<File>>,
+    dim: (u32, u32),
+    fps: u32,
+    audio_bitrate: u32,
+    flipped: Vec<u8>,
+    bitstream: Vec<u8>,
+}
+
+impl BuiltinEncoder {
+    pub fn new(path: &Path, dim: (u32, u32), fps: u32, bitrate: u32, audio_bitrate: u32) -> Result<Self> {
+        let config = EncoderConfig::new(dim.0, dim.1).set_bitrate_bps(bitrate).max_frame_rate(fps as f32);
+        let encoder = Encoder::with_config(config).context(tl!("encoder-init-failed"))?;
+        let file = File::create(path).with_context(|| tl!("create-file-failed", "path" => path.display().to_string()))?;
+        let mut muxer = Mp4Muxer::new(BufWriter::new(file));
+        muxer.init_video(dim.0 as _, dim.1 as _, false, "prpr");
+        Ok(Self {
+            encoder,
+            muxer,
+            dim,
+            fps,
+            audio_bitrate,
+            flipped: vec![0; dim.0 as usize * dim.1 as usize * 3],
+            bitstream: Vec::new(),
+        })
+    }
+
+    /// Takes RGB rows read from OpenGL, which are bottom-up.
+    pub fn write_frame(&mut self, frame: &[u8]) -> Result<()> {
+        let row = self.dim.0 as usize * 3;
+        for (dst, src) in self.flipped.chunks_exact_mut(row).zip(frame.chunks_exact(row).rev()) {
+            dst.copy_from_slice(src);
+        }
+        let yuv = YUVBuffer::with_rgb(self.dim.0 as _, self.dim.1 as _, &self.flipped);
+        let stream = self.encoder.encode(&yuv).context(tl!("encode-failed"))?;
+        self.bitstream.clear();
+        stream.write_vec(&mut self.bitstream);
+        self.muxer.write_video_with_fps(&self.bitstream, self.fps);
+        Ok(())
+    }
+
+    /// Muxes the interleaved stereo `audio` as AAC and closes the file.
+    pub fn finish(mut self, audio: &[f32], sample_rate: u32) -> Result<()> {
+        self.muxer.init_audio(self.audio_bitrate, sample_rate, 2);
+        let pcm: Vec<i16> = audio.iter().map(|it| (it.clamp(-1., 1.) * i16::MAX as f32) as i16).collect();
+        self.muxer.write_pcm(&pcm);
+        self.muxer.close();
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use macroquad::prelude::*;
+use prpr::ui::{TextPainter, Ui};
+
+const FADE_TIME: f32 = 0.5;
+
+/// Extra text lines shown on the intro and ending cards of a render.
+pub struct CardLines {
+    pub intro: Vec<String>,
+    pub ending: Vec<String>,
+    /// Time the intro starts to transition away.
+    pub intro_end: f32,
+    pub ending_start: f32,
+}
+
+impl CardLines {
+    pub fn is_empty(&self) -> bool {
+        self.intro.is_empty() && self.ending.is_empty()
+    }
+
+    pub fn render(&self, painter: &mut TextPainter, target: RenderTarget, (vw, vh): (u32, u32), time: f32) {
+        let (lines, alpha, bottom) = if time < self.intro_end {
+            (&self.intro, ((self.intro_end - time) / FADE_TIME).min(1.), false)
+        } else if time >= self.ending_start {
+            (&self.ending, ((time - self.ending_start) / FADE_TIME).min(1.), true)
+        } else {
+            return;
+        };
+        if lines.is_empty() {
+            return;
+        }
+        let aspect = vw as f32 / vh as f32;
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -aspect),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        let mut ui = Ui::new(painter);
+        let top = 1. / aspect;
+        let color = Color::new(1., 1., 1., alpha);
+        // intro lines go to the top-left, ending lines to the bottom-left, where the cards leave space
+        let mut y = if bottom { top * 0.92 } else { -top * 0.9 };
+        let iter: Box<dyn Iterator<Item = &String>> = if bottom { Box::new(lines.iter().rev()) } else { Box::new(lines.iter()) };
+        for line in iter {
+            let r = ui
+                .text(line)
+                .pos(-0.91, y)
+                .anchor(0., if bottom { 1. } else { 0. })
+                .size(0.45)
+                .color(color)
+                .draw();
+            y += if bottom { -r.h } else { r.h } * 1.2;
+        }
+        unsafe { get_internal_gl() }.flush();
+        pop_camera_state();
+    }
+}
@@ -0,0 +1,140 @@
+prpr::tl_file!("compare");
+
+use crate::{open_chart, output_path, render_chart, AudioCodec, OutputFormat, VideoCodec, VideoConfig};
+use anyhow::{bail, Context, Result};
+use macroquad::prelude::*;
+use prpr::{config::Config, fs, ui::TextPainter};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write as _,
+    ops::DerefMut,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Which side is heard in a comparison.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareAudio {
+    Left,
+    Right,
+    Mix,
+}
+
+impl CompareAudio {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "mix" => Self::Mix,
+            _ => return None,
+        })
+    }
+}
+
+/// Video encoding arguments of the composed video, which always uses software encoders.
+fn video_args(v_config: &VideoConfig) -> Result<String> {
+    if let Some(args) = v_config.format.master_args(false) {
+        return Ok(args.to_owned());
+    }
+    let codec = v_config.format.video_codec(v_config.codec);
+    let encoder = match &v_config.encoder {
+        Some(encoder) => encoder.clone(),
+        None => codec.encoders(false)[0].to_owned(),
+    };
+    let mut args = format!("-c:v {encoder} -pix_fmt yuv420p");
+    match v_config.crf {
+        Some(crf) if matches!(encoder.as_str(), "libvpx-vp9" | "libaom-av1") => write!(&mut args, " -crf {crf} -b:v 0")?,
+        Some(crf) => write!(&mut args, " -crf {crf}")?,
+        None => write!(&mut args, " -b:v {}", v_config.bitrate)?,
+    }
+    if let Some(preset) = &v_config.preset {
+        write!(&mut args, " -preset {preset}")?;
+    }
+    if codec == VideoCodec::Hevc {
+        args += " -tag:v hvc1";
+    }
+    Ok(args)
+}
+
+/// Renders `left` and `right` at half width each, then puts them side by side.
+///
+/// Both sides start the chart at the same time, so they stay in sync as long as their offsets match.
+pub async fn render_comparison(
+    ffmpeg: &str,
+    painter: &mut TextPainter,
+    left: &Path,
+    right: &Path,
+    config: Config,
+    right_config: Config,
+    v_config: VideoConfig,
+) -> Result<()> {
+    if v_config.format.is_sequence() || v_config.audio_only {
+        bail!(tl!("video-only"));
+    }
+    if v_config.builtin_encoder {
+        bail!(tl!("ffmpeg-required"));
+    }
+    let (vw, vh) = v_config.resolution;
+    // wherever the output goes, the sides are rendered into a folder of their own
+    let temp_dir = tempfile::tempdir().context(tl!("temp-dir-failed"))?;
+    let side = VideoConfig {
+        resolution: ((vw / 2 + 1) & !1, vh),
+        // kept close to lossless, since the sides are encoded again
+        format: OutputFormat::Prores,
+        audio_codec: Some(AudioCodec::Pcm),
+        transparent: false,
+        overwrite: true,
+        dry_run: false,
+        chapters: false,
+        subtitles: false,
+        output_dir: Some(temp_dir.path().display().to_string()),
+        ..v_config.clone()
+    };
+    let left_output = temp_dir.path().join("left").with_extension(side.extension());
+    let right_output = temp_dir.path().join("right").with_extension(side.extension());
+    let left_side = VideoConfig {
+        output: "left".to_owned(),
+        ..side.clone()
+    };
+    let right_side = VideoConfig {
+        output: "right".to_owned(),
+        ..side
+    };
+
+    info!("{}", tl!("rendering-left"));
+    render_chart(ffmpeg, painter, left, config, left_side, None, None).await?;
+    info!("{}", tl!("rendering-right"));
+    render_chart(ffmpeg, painter, right, right_config, right_side, None, None).await?;
+
+    info!("{}", tl!("composing"));
+    let mut fs = open_chart(left, &v_config)?;
+    let info = fs::load_info(fs.deref_mut()).await.context(tl!("info-load-failed"))?;
+    let output_file = output_path(&v_config, &info, None)?;
+    let (audio_filter, audio_map) = match v_config.compare_audio {
+        CompareAudio::Left => ("", "0:a"),
+        CompareAudio::Right => ("", "1:a"),
+        CompareAudio::Mix => (";[0:a][1:a]amix=inputs=2:duration=longest[a]", "[a]"),
+    };
+    let audio_args = v_config.format.audio_args(v_config.audio_codec, v_config.audio_bitrate.as_deref())?;
+    let status = Command::new(ffmpeg)
+        .args(["-y", "-i"])
+        .arg(&left_output)
+        .arg("-i")
+        .arg(&right_output)
+        .arg("-filter_complex")
+        .arg(format!("[0:v][1:v]hstack=inputs=2,crop={vw}:{vh}[v]{audio_filter}"))
+        .args(["-map", "[v]", "-map", audio_map])
+        .args(video_args(&v_config)?.split_whitespace())
+        .args(audio_args.split_whitespace())
+        .arg(&output_file)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context(tl!("ffmpeg-failed"))?;
+    if !status.success() {
+        bail!(tl!("compose-failed"));
+    }
+    info!("{}", tl!("saved", "path" => output_file.display().to_string()));
+    Ok(())
+}
@@ -0,0 +1,148 @@
+/// Highest sample peak allowed after normalizing, in dBFS.
+const PEAK_LIMIT: f64 = -1.;
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [[f64; 2]; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, state: [[0.; 2]; 2] }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) -> f64 {
+        let s = &mut self.state[channel];
+        let y = self.b[0] * x + s[0];
+        s[0] = self.b[1] * x - self.a[0] * y + s[1];
+        s[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two-stage K-weighting filter of ITU-R BS.1770, derived for any sample rate.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain / 20.);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1. + k / q + k * k;
+    let shelf = Biquad::new(
+        [(vh + vb * k / q + k * k) / a0, 2. * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1. + k / q + k * k;
+    let high_pass = Biquad::new([1., -2., 1.], [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0]);
+
+    [shelf, high_pass]
+}
+
+/// Integrated loudness of interleaved stereo samples in LUFS, as defined by EBU R128. `None` if everything is silent.
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f64> {
+    let mut filters = k_weighting(sample_rate);
+    // blocks of 400ms overlap by 75%, so energy is summed over 100ms steps first
+    let step = (sample_rate / 10) as usize;
+    let mut steps = Vec::with_capacity(samples.len() / 2 / step + 1);
+    let mut sum = 0.;
+    for (i, frame) in samples.chunks_exact(2).enumerate() {
+        for (channel, sample) in frame.iter().enumerate() {
+            let mut y = *sample as f64;
+            for filter in &mut filters {
+                y = filter.process(channel, y);
+            }
+            sum += y * y;
+        }
+        if (i + 1) % step == 0 {
+            steps.push(sum);
+            sum = 0.;
+        }
+    }
+    let blocks: Vec<f64> = steps.windows(4).map(|it| it.iter().sum::<f64>() / (step * 4) as f64).collect();
+    let loudness = |power: f64| -0.691 + 10. * power.log10();
+    let gated_mean = |threshold: f64| {
+        let passed: Vec<f64> = blocks.iter().copied().filter(|it| loudness(*it) > threshold).collect();
+        (!passed.is_empty()).then(|| passed.iter().sum::<f64>() / passed.len() as f64)
+    };
+    let relative = loudness(gated_mean(-70.)?) - 10.;
+    gated_mean(relative.max(-70.)).map(loudness)
+}
+
+/// Brings the samples to `target` LUFS, lowering the gain if the peak would go above -1dBFS. Returns the measured loudness and the applied gain in dB.
+pub fn normalize(samples: &mut [f32], sample_rate: u32, target: f64) -> Option<(f64, f64)> {
+    let measured = integrated_loudness(samples, sample_rate)?;
+    let peak = samples.iter().fold(0f32, |max, it| max.max(it.abs())) as f64;
+    let gain = (target - measured).min(PEAK_LIMIT - 20. * peak.log10());
+    let factor = 10f64.powf(gain / 20.) as f32;
+    for sample in samples.iter_mut() {
+        *sample *= factor;
+    }
+    Some((measured, gain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    /// Interleaved stereo sine of `peak` dBFS in both channels.
+    fn sine(freq: f64, peak: f64, secs: f64) -> Vec<f32> {
+        let amplitude = 10f64.powf(peak / 20.);
+        (0..(RATE as f64 * secs) as usize)
+            .flat_map(|i| {
+                let it = ((i as f64 / RATE as f64 * freq * std::f64::consts::TAU).sin() * amplitude) as f32;
+                [it, it]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loudness_of_silence() {
+        assert_eq!(integrated_loudness(&vec![0.; RATE as usize * 4], RATE), None);
+        // too short for a single block
+        assert_eq!(integrated_loudness(&sine(1000., -20., 0.2), RATE), None);
+    }
+
+    #[test]
+    fn loudness_of_reference_tone() {
+        // a 1kHz sine of -23dBFS in both channels measures -23 LUFS
+        let measured = integrated_loudness(&sine(1000., -23., 5.), RATE).unwrap();
+        assert!((measured + 23.).abs() < 0.1, "{measured}");
+        let measured = integrated_loudness(&sine(1000., -23., 5.), 44100).unwrap();
+        assert!((measured + 23.).abs() < 0.5, "{measured}");
+    }
+
+    #[test]
+    fn loudness_gates_silence() {
+        let mut samples = sine(1000., -23., 5.);
+        samples.extend(vec![0.; RATE as usize * 10]);
+        let measured = integrated_loudness(&samples, RATE).unwrap();
+        assert!((measured + 23.).abs() < 0.2, "{measured}");
+    }
+
+    #[test]
+    fn normalize_reaches_target() {
+        let mut samples = sine(1000., -30., 5.);
+        let (measured, gain) = normalize(&mut samples, RATE, -23.).unwrap();
+        assert!((measured + 30.).abs() < 0.1);
+        assert!((gain - (-23. - measured)).abs() < 1e-9);
+        let measured = integrated_loudness(&samples, RATE).unwrap();
+        assert!((measured + 23.).abs() < 0.1, "{measured}");
+    }
+
+    #[test]
+    fn normalize_limits_peak() {
+        let mut samples = sine(1000., -20., 5.);
+        let (_, gain) = normalize(&mut samples, RATE, 0.).unwrap();
+        assert!((gain - 19.).abs() < 0.01, "{gain}");
+        let peak = samples.iter().fold(0f32, |max, it| max.max(it.abs()));
+        assert!(20. * peak.log10() <= -1. + 1e-3);
+        assert_eq!(normalize(&mut [0.; 1024], RATE, -23.), None);
+    }
+}
@@ -0,0 +1,60 @@
+prpr::tl_file!("lower_third");
+
+use macroquad::prelude::*;
+use prpr::{
+    info::ChartInfo,
+    ui::{TextPainter, Ui},
+};
+
+const FADE_TIME: f32 = 0.4;
+
+/// Chart info shown at the bottom-left when gameplay starts, like the lower third of a broadcast.
+pub struct LowerThird {
+    pub title: String,
+    pub detail: String,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl LowerThird {
+    pub fn new(info: &ChartInfo, start: f32, end: f32) -> Self {
+        Self {
+            title: info.name.clone(),
+            detail: detail_line(info),
+            start,
+            end,
+        }
+    }
+
+    pub fn render(&self, painter: &mut TextPainter, target: RenderTarget, (vw, vh): (u32, u32), time: f32) {
+        if time < self.start || time >= self.end {
+            return;
+        }
+        let alpha = ((time - self.start) / FADE_TIME).min((self.end - time) / FADE_TIME).min(1.);
+        let aspect = vw as f32 / vh as f32;
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -aspect),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        let mut ui = Ui::new(painter);
+        let top = 1. / aspect;
+        let (x, pad) = (-0.92, 0.02);
+        let detail = ui.text(&self.detail).size(0.4).measure();
+        let title = ui.text(&self.title).size(0.65).measure();
+        let (w, h) = (title.w.max(detail.w) + pad * 2., title.h + detail.h + pad * 3.);
+        let y = top * 0.78 - h;
+        ui.fill_rect(Rect::new(x - pad, y, w, h), Color::new(0., 0., 0., 0.5 * alpha));
+        ui.fill_rect(Rect::new(x - pad - 0.008, y, 0.008, h), Color::new(1., 1., 1., alpha));
+        let color = Color::new(1., 1., 1., alpha);
+        ui.text(&self.title).pos(x, y + pad).size(0.65).color(color).draw();
+        ui.text(&self.detail).pos(x, y + title.h + pad * 2.).size(0.4).color(color).draw();
+        unsafe { get_internal_gl() }.flush();
+        pop_camera_state();
+    }
+}
+
+pub fn detail_line(info: &ChartInfo) -> String {
+    tl!("detail", "composer" => info.composer.as_str(), "charter" => info.charter.as_str(), "level" => info.level.as_str())
+}
@@ -1,40 +1,349 @@
+#[cfg(feature = "builtin-encoder")]
+mod builtin;
+mod card;
+mod compare;
+mod loudness;
+mod lower_third;
+mod readback;
+mod resample;
 mod scene;
+mod segment;
+mod sequence;
+mod watermark;
 
-use crate::scene::MainScene;
+prpr::tl_file!("main");
+
+use crate::{
+    card::CardLines,
+    compare::{render_comparison, CompareAudio},
+    lower_third::LowerThird,
+    readback::PixelReader,
+    resample::resample,
+    scene::MainScene,
+    segment::{parse_segment, part_path, render_segmented},
+    sequence::ImageSequence,
+    watermark::{Watermark, WatermarkPosition, WatermarkRenderer},
+};
 use anyhow::{bail, Context, Result};
 use macroquad::{miniquad::TextureFormat, prelude::*};
 use prpr::{
     build_conf,
     config::Config,
     core::{init_assets, MSRenderTarget, NoteKind},
-    fs::{self, PatchedFileSystem},
+    ext::load_audio,
+    fs::{self, FileSystem, PatchedFileSystem},
+    info::ChartInfo,
+    judge::{note_pan, Judgement, Replay, ReplayAction},
+    l10n::set_language,
     scene::{GameMode, GameScene, LoadingScene, BILLBOARD},
     time::TimeManager,
-    ui::{ChartInfoEdit, FontArc, TextPainter, Ui},
+    ui::{apply_accessibility, ChartInfoEdit, FontArc, TextPainter, Ui},
     Main,
 };
 use sasa::AudioClip;
+use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     cell::RefCell,
     io::{BufWriter, Write},
     ops::DerefMut,
-    process::{Command, Stdio},
+    process::{Child, ChildStdin, Command, Stdio},
     rc::Rc,
     sync::{
         atomic::{AtomicBool, Ordering},
         Mutex,
     },
-    time::Instant,
+    time::{Duration, Instant},
+};
+use std::{
+    fmt::Write as _,
+    path::{Component, Path, PathBuf},
 };
-use std::{fmt::Write as _, path::Path};
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum VideoCodec {
+    H264,
+    Hevc,
+    Av1,
+    Vp9,
+}
+
+impl VideoCodec {
+    const ALL: [VideoCodec; 4] = [Self::H264, Self::Hevc, Self::Av1, Self::Vp9];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::H264 => "H.264",
+            Self::Hevc => "HEVC",
+            Self::Av1 => "AV1",
+            Self::Vp9 => "VP9",
+        }
+    }
+
+    /// Candidate ffmpeg encoders, in the order of preference.
+    fn encoders(self, hardware_accel: bool) -> Vec<&'static str> {
+        let candidates: &[&str] = match (self, hardware_accel) {
+            (Self::H264, false) => &["libx264"],
+            (Self::H264, true) => &["h264_videotoolbox", "h264_nvenc", "h264_qsv", "h264_amf", "h264_vaapi"],
+            (Self::Hevc, false) => &["libx265"],
+            (Self::Hevc, true) => &["hevc_videotoolbox", "hevc_nvenc", "hevc_qsv", "hevc_amf", "hevc_vaapi"],
+            (Self::Av1, false) => &["libsvtav1", "libaom-av1"],
+            (Self::Av1, true) => &["av1_nvenc", "av1_qsv", "av1_amf", "av1_vaapi"],
+            (Self::Vp9, false) => &["libvpx-vp9"],
+            (Self::Vp9, true) => &["vp9_qsv", "vp9_vaapi"],
+        };
+        candidates
+            .iter()
+            .copied()
+            .filter(|it| {
+                if it.ends_with("_videotoolbox") {
+                    cfg!(target_os = "macos")
+                } else if it.ends_with("_vaapi") {
+                    cfg!(target_os = "linux")
+                } else if it.ends_with("_amf") {
+                    cfg!(target_os = "windows") || cfg!(target_os = "linux")
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
+}
+
+/// Length of the slice rendered to estimate a full render.
+const DRY_RUN_LENGTH: f64 = 5.;
+
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+/// The global config files looked for without `--config`, the first one found being used.
+const CONFIG_FILES: [&str; 3] = ["conf.yml", "conf.json", "conf.toml"];
+
+/// Encodes a few frames to see whether `encoder` works,
+/// since hardware encoders can be compiled into ffmpeg without the hardware or driver being present.
+fn probe_encoder(ffmpeg: &str, encoder: &str) -> bool {
+    let mut command = Command::new(ffmpeg);
+    command.args("-hide_banner -loglevel error".split_whitespace());
+    if encoder.ends_with("_vaapi") {
+        command.args(["-vaapi_device", VAAPI_DEVICE]);
+    }
+    command.args("-f lavfi -i color=black:size=256x256:rate=30:duration=0.2".split_whitespace());
+    if encoder.ends_with("_vaapi") {
+        command.args(["-vf", "format=nv12,hwupload"]);
+    }
+    command
+        .args(["-c:v", encoder, "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(false, |it| it.success())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum OutputFormat {
+    Mp4,
+    Webm,
+    Prores,
+    Ffv1,
+    Png,
+    Exr,
+}
+
+impl OutputFormat {
+    const ALL: [OutputFormat; 6] = [Self::Mp4, Self::Webm, Self::Prores, Self::Ffv1, Self::Png, Self::Exr];
+
+    fn name(self) -> Cow<'static, str> {
+        match self {
+            Self::Mp4 => "MP4".into(),
+            Self::Webm => "WebM".into(),
+            Self::Prores => "ProRes 422 HQ".into(),
+            Self::Ffv1 => "FFV1".into(),
+            Self::Png => tl!("format-png-sequence"),
+            Self::Exr => tl!("format-exr-sequence"),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+            Self::Prores => "mov",
+            Self::Ffv1 => "mkv",
+            // a folder of frames
+            Self::Png | Self::Exr => "",
+        }
+    }
+
+    /// Fixed video arguments of master formats, which are (visually) lossless.
+    fn master_args(self, alpha: bool) -> Option<&'static str> {
+        match (self, alpha) {
+            (Self::Prores, false) => Some("-c:v prores_ks -profile:v 3 -pix_fmt yuv422p10le"),
+            (Self::Prores, true) => Some("-c:v prores_ks -profile:v 4 -pix_fmt yuva444p10le -alpha_bits 16"),
+            (Self::Ffv1, _) => Some("-c:v ffv1 -level 3 -pix_fmt bgr0"),
+            _ => None,
+        }
+    }
+
+    fn supports_alpha(self) -> bool {
+        matches!(self, Self::Prores | Self::Png | Self::Exr)
+    }
+
+    /// Image sequences are written without ffmpeg, with the audio saved next to the frames.
+    fn is_sequence(self) -> bool {
+        matches!(self, Self::Png | Self::Exr)
+    }
+
+    /// WebM only allows VP8 / VP9 / AV1, falls back to VP9 for others.
+    fn video_codec(self, codec: VideoCodec) -> VideoCodec {
+        match (self, codec) {
+            (Self::Webm, VideoCodec::H264 | VideoCodec::Hevc) => VideoCodec::Vp9,
+            _ => codec,
+        }
+    }
+
+    fn default_audio_codec(self) -> AudioCodec {
+        match self {
+            Self::Mp4 => AudioCodec::Aac,
+            Self::Webm => AudioCodec::Opus,
+            Self::Prores => AudioCodec::Pcm,
+            Self::Ffv1 | Self::Png | Self::Exr => AudioCodec::Flac,
+        }
+    }
+
+    fn supports_audio_codec(self, codec: AudioCodec) -> bool {
+        match self {
+            Self::Mp4 => codec != AudioCodec::Pcm,
+            Self::Webm => codec == AudioCodec::Opus,
+            Self::Prores => matches!(codec, AudioCodec::Aac | AudioCodec::Pcm),
+            Self::Ffv1 | Self::Png | Self::Exr => true,
+        }
+    }
+
+    fn audio_args(self, codec: Option<AudioCodec>, bitrate: Option<&str>) -> Result<String> {
+        if self.is_sequence() {
+            // saved next to the frames instead
+            return Ok("-an".to_owned());
+        }
+        let codec = codec.unwrap_or_else(|| self.default_audio_codec());
+        if !self.supports_audio_codec(codec) {
+            bail!(tl!("audio-codec-unsupported", "format" => self.name(), "codec" => codec.name()));
+        }
+        let mut args = format!("-c:a {}", codec.encoder());
+        if let Some(default) = codec.default_bitrate() {
+            write!(&mut args, " -b:a {}", bitrate.unwrap_or(default))?;
+        }
+        if self == Self::Mp4 && codec == AudioCodec::Flac {
+            // FLAC in MP4 is still considered experimental by ffmpeg
+            args += " -strict experimental";
+        }
+        Ok(args)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AudioCodec {
+    Aac,
+    Opus,
+    Flac,
+    Pcm,
+}
+
+impl AudioCodec {
+    const ALL: [AudioCodec; 4] = [Self::Aac, Self::Opus, Self::Flac, Self::Pcm];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Aac => "AAC",
+            Self::Opus => "Opus",
+            Self::Flac => "FLAC",
+            Self::Pcm => "PCM",
+        }
+    }
+
+    fn encoder(self) -> &'static str {
+        match self {
+            Self::Aac => "aac",
+            Self::Opus => "libopus",
+            Self::Flac => "flac",
+            Self::Pcm => "pcm_s16le",
+        }
+    }
+
+    /// `None` for lossless codecs, which have no bitrate to choose.
+    fn default_bitrate(self) -> Option<&'static str> {
+        match self {
+            Self::Aac => Some("320k"),
+            Self::Opus => Some("192k"),
+            Self::Flac | Self::Pcm => None,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
 struct VideoConfig {
     fps: u32,
     resolution: (u32, u32),
     hardware_accel: bool,
+    builtin_encoder: bool,
+    transparent: bool,
     ending_length: f64,
+    sample_rate: u32,
+    music_volume: Option<f32>,
+    hitsound_volume: Option<f32>,
+    ending_volume: Option<f32>,
+    normalize: bool,
+    target_loudness: f64,
+    intro_length: f64,
+    intro_lines: Vec<String>,
+    ending_lines: Vec<String>,
+    illustration: Option<String>,
+    replay: Option<String>,
+    compare: Option<String>,
+    compare_config: Option<String>,
+    compare_audio: CompareAudio,
+    lower_third: bool,
+    subtitles: bool,
+    info_duration: f64,
+    save_info: bool,
+    start: Option<f64>,
+    end: Option<f64>,
     bitrate: String,
+    crf: Option<u32>,
+    preset: Option<String>,
+    codec: VideoCodec,
+    encoder: Option<String>,
+    audio_codec: Option<AudioCodec>,
+    audio_bitrate: Option<String>,
+    format: OutputFormat,
+    audio_only: bool,
+    flac: bool,
+    metadata: bool,
+    chapters: bool,
+    output: String,
+    output_dir: Option<String>,
+    overwrite: bool,
+    ffmpeg_input_args: Vec<String>,
+    ffmpeg_output_args: Vec<String>,
+    watermark: Watermark,
+    live_preview: bool,
+    preview_interval: u32,
+    #[serde(skip)]
+    dry_run: bool,
+    segments: u32,
+    /// The segment rendered by this process and the number of segments, counting from 1.
+    #[serde(skip)]
+    segment: Option<(u32, u32)>,
+    /// Where intermediate files go, given to each segment process by the one joining them.
+    #[serde(skip)]
+    temp_dir: Option<String>,
+    /// Kept out of saved settings, since they are written in plain text.
+    #[serde(skip)]
+    password: Option<String>,
 }
 
 impl Default for VideoConfig {
@@ -43,14 +352,97 @@ impl Default for VideoConfig {
             fps: 60,
             resolution: (1920, 1080),
             hardware_accel: false,
+            builtin_encoder: false,
+            transparent: false,
             ending_length: 27.5,
+            sample_rate: 44100,
+            music_volume: None,
+            hitsound_volume: None,
+            ending_volume: None,
+            normalize: false,
+            target_loudness: -14.,
+            intro_length: LoadingScene::TOTAL_TIME as f64,
+            intro_lines: Vec::new(),
+            ending_lines: Vec::new(),
+            illustration: None,
+            replay: None,
+            compare: None,
+            compare_config: None,
+            compare_audio: CompareAudio::Mix,
+            lower_third: false,
+            subtitles: false,
+            info_duration: 5.,
+            save_info: false,
+            start: None,
+            end: None,
             bitrate: "7M".to_string(),
+            crf: None,
+            preset: None,
+            codec: VideoCodec::H264,
+            encoder: None,
+            audio_codec: None,
+            audio_bitrate: None,
+            format: OutputFormat::Mp4,
+            audio_only: false,
+            flac: false,
+            metadata: true,
+            chapters: false,
+            output: "out.mp4".to_owned(),
+            output_dir: None,
+            overwrite: false,
+            ffmpeg_input_args: Vec::new(),
+            ffmpeg_output_args: Vec::new(),
+            watermark: Watermark::default(),
+            live_preview: false,
+            preview_interval: 10,
+            dry_run: false,
+            segments: 1,
+            segment: None,
+            temp_dir: None,
+            password: None,
         }
     }
 }
 
+impl VideoConfig {
+    fn extension(&self) -> &'static str {
+        if !self.audio_only {
+            self.format.extension()
+        } else if self.flac {
+            "flac"
+        } else {
+            "wav"
+        }
+    }
+}
+
+const RESOLUTION_PRESETS: [(&str, (u32, u32)); 5] = [
+    ("720p", (1280, 720)),
+    ("1080p", (1920, 1080)),
+    ("1440p", (2560, 1440)),
+    ("4k", (3840, 2160)),
+    // vertical videos, e.g. for shorts
+    ("vertical", (1080, 1920)),
+];
+
+fn parse_resolution(s: &str) -> Result<(u32, u32)> {
+    let s = s.trim();
+    let res = if let Some((_, res)) = RESOLUTION_PRESETS.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+        *res
+    } else if let Some((w, h)) = s.split_once(['x', 'X', '×', '*']) {
+        (w.trim().parse::<u32>()?, h.trim().parse::<u32>()?)
+    } else {
+        bail!(tl!("resolution-invalid"))
+    };
+    if !(16..=8192).contains(&res.0) || !(16..=8192).contains(&res.1) {
+        bail!(tl!("resolution-out-of-range"));
+    }
+    Ok(res)
+}
+
 static INFO_EDIT: Mutex<Option<ChartInfoEdit>> = Mutex::new(None);
 static VIDEO_CONFIG: Mutex<Option<VideoConfig>> = Mutex::new(None);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
 
 #[cfg(target_arch = "wasm32")]
 compile_error!("WASM target is not supported");
@@ -58,7 +450,7 @@ compile_error!("WASM target is not supported");
 async fn the_main() -> Result<()> {
     init_assets();
     set_panic_handler(|msg, backtrace| async move {
-        let _ = std::fs::write("错误信息.txt", format!("发生错误：{msg}\n\n详细堆栈：\n{backtrace}"));
+        let _ = std::fs::write(&*tl!("error-file"), format!("{}\n\n{}\n{backtrace}", tl!("error-occurred", "error" => msg), tl!("backtrace")));
     });
 
     let ffmpeg = if cfg!(target_os = "windows") {
@@ -72,6 +464,15 @@ async fn the_main() -> Result<()> {
         "ffmpeg".to_owned()
     };
 
+    ctrlc::set_handler(|| {
+        if CANCELLED.swap(true, Ordering::SeqCst) {
+            // pressed twice, the graceful path is stuck somewhere
+            std::process::exit(1);
+        }
+        warn!("{}", tl!("cancelling"));
+    })
+    .context(tl!("ctrlc-failed"))?;
+
     let rt = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(4)
         .enable_all()
@@ -82,33 +483,346 @@ async fn the_main() -> Result<()> {
     let font = FontArc::try_from_vec(load_file("font.ttf").await?)?;
     let mut painter = TextPainter::new(font);
 
-    let (path, config) = {
-        let mut args = std::env::args().skip(1);
-        let Some(path) = args.next() else {
-            bail!("请将谱面文件或文件夹拖动到该软件上！");
+    let (paths, config, v_config, headless, render_config_path) = {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let config_path = match args.iter().position(|it| it == "--config").and_then(|index| args.get(index + 1)) {
+            Some(path) => path.clone(),
+            None => CONFIG_FILES.into_iter().find(|it| Path::new(it).exists()).unwrap_or(CONFIG_FILES[0]).to_owned(),
         };
-        let config =
-            match (|| -> Result<Config> { Ok(serde_yaml::from_str(&std::fs::read_to_string("conf.yml").context("无法加载配置文件")?)?) })() {
-                Err(err) => {
-                    warn!("无法加载配置文件：{:?}", err);
-                    Config::default()
+        // loaded first so that the language applies to the messages below
+        let config = if Path::new(&config_path).exists() {
+            Config::load(&config_path).context(tl!("config-load-failed"))
+        } else {
+            Ok(Config::default())
+        };
+        let mut overrides = Config::env_overrides();
+        overrides.extend(args.iter().zip(args.iter().skip(1)).filter_map(|(arg, value)| match arg.as_str() {
+            "--set" => Some(value.clone()),
+            // quoted so that names like `1` stay strings
+            "--profile" => Some(format!("profile={}", serde_json::to_string(value).unwrap())),
+            _ => None,
+        }));
+        let config = config.and_then(|it| it.resolve(&overrides).context(tl!("config-override-invalid")));
+        if let Some(lang) = config.as_ref().ok().and_then(|it| it.language.as_ref()) {
+            set_language(lang);
+        }
+        let config = config?;
+        let render_config_path = Path::new(&config_path).with_file_name("render.yml");
+        let mut v_config = match (|| -> Result<VideoConfig> { Ok(serde_yaml::from_str(&std::fs::read_to_string(&render_config_path)?)?) })() {
+            Err(err) => {
+                if render_config_path.exists() {
+                    warn!("{}", tl!("render-config-invalid", "error" => format!("{err:?}")));
                 }
-                Ok(config) => config,
-            };
-        (path, config)
+                VideoConfig::default()
+            }
+            Ok(v_config) => v_config,
+        };
+        let mut args = args.into_iter();
+        let mut paths = Vec::new();
+        let mut headless = false;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| tl!("arg-missing-value", "arg" => arg.as_str()));
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--config" | "--set" | "--profile" => {
+                    value()?;
+                }
+                "--resolution" => v_config.resolution = parse_resolution(&value()?)?,
+                "--fps" => {
+                    v_config.fps = value()?.parse().context(tl!("fps-invalid"))?;
+                    if !(1..=480).contains(&v_config.fps) {
+                        bail!(tl!("fps-out-of-range"));
+                    }
+                }
+                "--bitrate" => v_config.bitrate = value()?,
+                "--crf" => v_config.crf = Some(value()?.parse().context(tl!("crf-invalid"))?),
+                "--preset" => v_config.preset = Some(value()?),
+                "--codec" => {
+                    v_config.codec = match value()?.to_lowercase().as_str() {
+                        "h264" => VideoCodec::H264,
+                        "hevc" | "h265" => VideoCodec::Hevc,
+                        "av1" => VideoCodec::Av1,
+                        "vp9" => VideoCodec::Vp9,
+                        other => bail!(tl!("unknown-codec", "codec" => other)),
+                    }
+                }
+                "--encoder" => v_config.encoder = Some(value()?),
+                "--audio-codec" => {
+                    v_config.audio_codec = Some(match value()?.to_lowercase().as_str() {
+                        "aac" => AudioCodec::Aac,
+                        "opus" => AudioCodec::Opus,
+                        "flac" => AudioCodec::Flac,
+                        "pcm" => AudioCodec::Pcm,
+                        other => bail!(tl!("unknown-audio-codec", "codec" => other)),
+                    })
+                }
+                "--audio-bitrate" => v_config.audio_bitrate = Some(value()?),
+                "--format" => {
+                    v_config.format = match value()?.to_lowercase().as_str() {
+                        "mp4" => OutputFormat::Mp4,
+                        "webm" => OutputFormat::Webm,
+                        "prores" => OutputFormat::Prores,
+                        "ffv1" => OutputFormat::Ffv1,
+                        "png" => OutputFormat::Png,
+                        "exr" => OutputFormat::Exr,
+                        other => bail!(tl!("unknown-format", "format" => other)),
+                    }
+                }
+                "--ending-length" => v_config.ending_length = value()?.parse().context(tl!("ending-length-invalid"))?,
+                "--sample-rate" => v_config.sample_rate = value()?.parse().context(tl!("sample-rate-invalid"))?,
+                "--music-volume" => v_config.music_volume = Some(value()?.parse().context(tl!("music-volume-invalid"))?),
+                "--hitsound-volume" => v_config.hitsound_volume = Some(value()?.parse().context(tl!("hitsound-volume-invalid"))?),
+                "--ending-volume" => v_config.ending_volume = Some(value()?.parse().context(tl!("ending-volume-invalid"))?),
+                "--normalize" => v_config.normalize = true,
+                "--target-loudness" => v_config.target_loudness = value()?.parse().context(tl!("target-loudness-invalid"))?,
+                "--intro-length" => v_config.intro_length = value()?.parse().context(tl!("intro-length-invalid"))?,
+                "--intro-line" => v_config.intro_lines.push(value()?),
+                "--ending-line" => v_config.ending_lines.push(value()?),
+                "--illustration" => v_config.illustration = Some(value()?),
+                "--password" => v_config.password = Some(value()?),
+                "--replay" => v_config.replay = Some(value()?),
+                "--compare" => v_config.compare = Some(value()?),
+                "--compare-config" => v_config.compare_config = Some(value()?),
+                "--compare-audio" => {
+                    let value = value()?;
+                    v_config.compare_audio = CompareAudio::parse(&value).with_context(|| tl!("unknown-compare-audio", "value" => value.as_str()))?;
+                }
+                "--lower-third" => v_config.lower_third = true,
+                "--subtitles" => v_config.subtitles = true,
+                "--save-info" => v_config.save_info = true,
+                "--info-duration" => v_config.info_duration = value()?.parse().context(tl!("info-duration-invalid"))?,
+                "--start" => v_config.start = Some(value()?.parse().context(tl!("start-invalid"))?),
+                "--end" => v_config.end = Some(value()?.parse().context(tl!("end-invalid"))?),
+                "--hardware-accel" => v_config.hardware_accel = true,
+                "--builtin-encoder" => v_config.builtin_encoder = true,
+                "--transparent" => v_config.transparent = true,
+                "--audio-only" => v_config.audio_only = true,
+                "--flac" => v_config.flac = true,
+                "--no-metadata" => v_config.metadata = false,
+                "--chapters" => v_config.chapters = true,
+                "-o" | "--output" => v_config.output = value()?,
+                "--output-dir" => v_config.output_dir = Some(value()?),
+                "--overwrite" => v_config.overwrite = true,
+                "--dry-run" => v_config.dry_run = true,
+                "--segments" => v_config.segments = value()?.parse().context(tl!("segments-invalid"))?,
+                "--segment" => v_config.segment = Some(parse_segment(&value()?)?),
+                "--temp-dir" => v_config.temp_dir = Some(value()?),
+                "--ffmpeg-input-args" => v_config.ffmpeg_input_args.extend(split_ffmpeg_args(&value()?)?),
+                "--ffmpeg-output-args" => v_config.ffmpeg_output_args.extend(split_ffmpeg_args(&value()?)?),
+                "--live-preview" => v_config.live_preview = true,
+                "--preview-interval" => v_config.preview_interval = value()?.parse().context(tl!("preview-interval-invalid"))?,
+                "--watermark-text" => v_config.watermark.text = Some(value()?),
+                "--watermark-image" => v_config.watermark.image = Some(value()?),
+                "--watermark-position" => {
+                    let value = value()?;
+                    v_config.watermark.position =
+                        WatermarkPosition::parse(&value).with_context(|| tl!("unknown-watermark-position", "value" => value.as_str()))?;
+                }
+                "--watermark-opacity" => v_config.watermark.opacity = value()?.parse().context(tl!("watermark-opacity-invalid"))?,
+                _ if !arg.starts_with("--") => paths.push(arg),
+                _ => bail!(tl!("unknown-arg", "arg" => arg.as_str())),
+            }
+        }
+        if paths.is_empty() {
+            bail!(tl!("no-chart"));
+        }
+        (paths, config, v_config, headless, render_config_path)
     };
 
-    let mut fs = fs::fs_from_file(std::path::Path::new(&path)).context("加载谱面失败")?;
-    let info = fs::load_info(fs.deref_mut()).await.context("加载谱面信息失败")?;
+    let charts = collect_charts(&paths)?;
+    let batch = charts.len() > 1;
+    // settings of the first chart are shared by the rest of the batch
+    let mut v_config = v_config;
+    let ffmpeg_found = Command::new(&ffmpeg)
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(false, |it| it.success());
+    if !ffmpeg_found && !v_config.builtin_encoder {
+        warn!("{}", tl!("ffmpeg-fallback"));
+        v_config.builtin_encoder = true;
+    }
+    if v_config.segments > 1 && v_config.segment.is_none() {
+        let [chart] = charts.as_slice() else {
+            bail!(tl!("segmented-single-chart"));
+        };
+        return render_segmented(&ffmpeg, chart, &v_config).await;
+    }
+    if let Some(right) = v_config.compare.clone() {
+        let [left] = charts.as_slice() else {
+            bail!(tl!("compare-single-chart"));
+        };
+        // the same chart can be compared under two configurations
+        let right_config = match &v_config.compare_config {
+            Some(path) => Config::load(path).context(tl!("compare-config-load-failed"))?,
+            None => config.clone(),
+        };
+        return match render_comparison(&ffmpeg, &mut painter, left, Path::new(&right), config, right_config, v_config).await {
+            Err(err) if CANCELLED.load(Ordering::SeqCst) => {
+                warn!("{:?}", err);
+                Ok(())
+            }
+            result => result,
+        };
+    }
+    let mut failed = 0;
+    for (index, path) in charts.iter().enumerate() {
+        if batch {
+            info!("[{}/{}] {}", index + 1, charts.len(), path.display());
+        }
+        let interactive = (!headless && index == 0).then_some(render_config_path.as_path());
+        match render_chart(&ffmpeg, &mut painter, path, config.clone(), v_config.clone(), interactive, batch.then_some(index)).await {
+            Ok(used) => {
+                v_config = used;
+            }
+            Err(err) if CANCELLED.load(Ordering::SeqCst) => {
+                warn!("{:?}", err);
+                return Ok(());
+            }
+            Err(err) if batch => {
+                warn!("{}", tl!("batch-chart-failed", "path" => path.display().to_string(), "error" => format!("{err:?}")));
+                failed += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    if failed != 0 {
+        bail!(tl!("batch-failed", "count" => failed));
+    }
+    Ok(())
+}
 
-    let (chart, ..) = GameScene::load_chart(fs.deref_mut(), &info).await.context("加载谱面内容失败")?;
+/// Expands directories that are not charts themselves into the charts inside them.
+fn collect_charts(paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut res = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        if path.is_dir() && !["info.yml", "info.txt", "info.csv"].iter().any(|it| path.join(it).exists()) {
+            let mut entries = std::fs::read_dir(path)
+                .with_context(|| tl!("read-dir-failed", "path" => path.display().to_string()))?
+                .map(|it| Ok(it?.path()))
+                .collect::<Result<Vec<_>>>()?;
+            entries.retain(|it| it.is_dir() || it.extension().map_or(false, |ext| ext == "pez" || ext == "zip"));
+            entries.sort();
+            res.extend(entries);
+        } else {
+            res.push(path.to_owned());
+        }
+    }
+    Ok(res)
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Where the output goes as configured, before avoiding existing files.
+///
+/// Placeholders are filled in every component of the output template, e.g. `{charter}/{title}.mp4`, and the values filled in
+/// can't reach out of the component they're in. Outputs of a batch without placeholders get an index suffix.
+fn planned_output_path(v_config: &VideoConfig, info: &ChartInfo, index: Option<usize>) -> PathBuf {
+    let mut template = Path::new(&v_config.output).with_extension(v_config.extension());
+    if let Some(index) = index {
+        if !v_config.output.contains('{') {
+            let stem = template.file_stem().unwrap_or_default().to_string_lossy();
+            template = template
+                .with_file_name(format!("{stem}-{}", index + 1))
+                .with_extension(v_config.extension());
+        }
+    }
+    let (vw, vh) = v_config.resolution;
+    let fill = |component: &str| {
+        let filled = component
+            .replace("{index}", &index.map_or(1, |it| it + 1).to_string())
+            .replace("{name}", &sanitize_file_name(&info.name))
+            .replace("{title}", &sanitize_file_name(&info.name))
+            .replace("{level}", &sanitize_file_name(&info.level))
+            .replace("{difficulty}", &format!("{:.1}", info.difficulty))
+            .replace("{charter}", &sanitize_file_name(&info.charter))
+            .replace("{composer}", &sanitize_file_name(&info.composer))
+            .replace("{fps}", &v_config.fps.to_string())
+            .replace("{width}", &vw.to_string())
+            .replace("{height}", &vh.to_string());
+        // e.g. a chart named `..`
+        if filled == "." || filled == ".." {
+            "_".to_owned()
+        } else {
+            filled
+        }
+    };
+    let mut path: PathBuf = template
+        .components()
+        .map(|it| match it {
+            Component::Normal(name) => PathBuf::from(fill(&name.to_string_lossy())),
+            other => PathBuf::from(other.as_os_str()),
+        })
+        .collect();
+    if let Some(dir) = &v_config.output_dir {
+        path = Path::new(dir).join(path);
+    }
+    path
+}
+
+fn output_path(v_config: &VideoConfig, info: &ChartInfo, index: Option<usize>) -> Result<PathBuf> {
+    let path = planned_output_path(v_config, info, index);
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).with_context(|| tl!("create-dir-failed", "path" => parent.display().to_string()))?;
+        }
+    }
+    if v_config.overwrite || !path.exists() {
+        return Ok(path);
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    Ok((1..)
+        .map(|i| match v_config.extension() {
+            "" => path.with_file_name(format!("{stem} ({i})")),
+            ext => path.with_file_name(format!("{stem} ({i}).{ext}")),
+        })
+        .find(|it| !it.exists())
+        .unwrap())
+}
+
+fn open_chart(path: &Path, v_config: &VideoConfig) -> Result<Box<dyn FileSystem>> {
+    let mut fs = fs::fs_from_file(path).context(tl!("chart-load-failed"))?;
+    if let Some(password) = &v_config.password {
+        fs::set_password(fs.deref_mut(), password.clone()).context(tl!("chart-wrong-password"))?;
+    } else if fs::needs_password(fs.deref_mut()) {
+        bail!(tl!("chart-encrypted"));
+    }
+    Ok(fs)
+}
+
+/// Shows the settings scene if `interactive` is given, which is the path settings are saved to.
+async fn render_chart(
+    ffmpeg: &str,
+    painter: &mut TextPainter,
+    path: &Path,
+    config: Config,
+    v_config: VideoConfig,
+    interactive: Option<&Path>,
+    index: Option<usize>,
+) -> Result<VideoConfig> {
+    let mut fs = open_chart(path, &v_config)?;
+    let config = config.for_chart(fs.deref_mut(), Some(path)).await.context(tl!("chart-config-invalid"))?;
+    let info = fs::load_info(fs.deref_mut()).await.context(tl!("info-load-failed"))?;
+
+    let (mut chart, ..) = GameScene::load_chart(fs.deref_mut(), &info).await.context(tl!("chart-content-load-failed"))?;
     macro_rules! ld {
         ($path:literal) => {
-            AudioClip::new(load_file($path).await?).with_context(|| format!("加载音效 `{}` 失败", $path))?
+            AudioClip::new(load_file($path).await?).with_context(|| tl!("sfx-load-failed", "path" => $path))?
         };
     }
-    let music: Result<_> = async { AudioClip::new(fs.load_file(&info.music).await?) }.await;
-    let music = music.context("加载音乐失败")?;
+    let music = load_audio(fs.deref_mut(), &info.music).await.context(tl!("music-load-failed"))?;
     let ending = ld!("ending.mp3");
     let track_length = music.length() as f64;
     let sfx_click = ld!("click.ogg");
@@ -117,161 +831,363 @@ async fn the_main() -> Result<()> {
 
     let mut gl = unsafe { get_internal_gl() };
 
-    let texture = miniquad::Texture::new_render_texture(
-        gl.quad_context,
-        miniquad::TextureParams {
-            width: 1080,
-            height: 608,
-            format: TextureFormat::RGB8,
-            ..Default::default()
-        },
-    );
-    let target = Some({
-        let render_pass = miniquad::RenderPass::new(gl.quad_context, texture, None);
-        RenderTarget {
-            texture: Texture2D::from_miniquad_texture(texture),
-            render_pass,
-        }
-    });
-    let tex = Texture2D::from_miniquad_texture(texture);
-    let mut main = Main::new(Box::new(MainScene::new(target, info, config.clone(), fs.clone_box())), TimeManager::default(), None).await?;
-    let width = texture.width as f32 / 2.;
-    loop {
-        if main.scenes.len() == 1 {
-            gl.quad_gl.viewport(Some((0, 0, texture.width as _, texture.height as _)));
-            let sw = screen_width();
-            let lf = (sw - width) / 2.;
-            main.update_with_mutate(|touch| {
-                touch.position.x -= lf / texture.width as f32 * 2.;
-            })?;
-            main.show_billboard = false;
-            main.render(&mut Ui::new(&mut painter))?;
-            gl.flush();
-            set_camera(&Camera2D {
-                zoom: vec2(1., -screen_width() / screen_height()),
+    if let Some(render_config_path) = interactive {
+        let texture = miniquad::Texture::new_render_texture(
+            gl.quad_context,
+            miniquad::TextureParams {
+                width: 1080,
+                height: 608,
+                format: TextureFormat::RGB8,
                 ..Default::default()
-            });
-            let mut ui = Ui::new(&mut painter);
-            clear_background(GRAY);
-            draw_texture_ex(
-                tex,
-                -1. + lf / sw * 2.,
-                -ui.top,
-                WHITE,
-                DrawTextureParams {
-                    flip_y: true,
-                    dest_size: Some(vec2(texture.width as f32, texture.height as f32) * (2. / sw)),
+            },
+        );
+        let target = Some({
+            let render_pass = miniquad::RenderPass::new(gl.quad_context, texture, None);
+            RenderTarget {
+                texture: Texture2D::from_miniquad_texture(texture),
+                render_pass,
+            }
+        });
+        let tex = Texture2D::from_miniquad_texture(texture);
+        let mut main = Main::new(Box::new(MainScene::new(target, info, config.clone(), fs.clone_box(), v_config, render_config_path.to_owned())), TimeManager::default(), None).await?;
+        let width = texture.width as f32 / 2.;
+        loop {
+            if main.scenes.len() == 1 {
+                gl.quad_gl.viewport(Some((0, 0, texture.width as _, texture.height as _)));
+                let sw = screen_width();
+                let lf = (sw - width) / 2.;
+                main.update_with_mutate(|touch| {
+                    touch.position.x -= lf / texture.width as f32 * 2.;
+                })?;
+                main.show_billboard = false;
+                main.render(&mut Ui::new(painter))?;
+                gl.flush();
+                set_camera(&Camera2D {
+                    zoom: vec2(1., -screen_width() / screen_height()),
                     ..Default::default()
-                },
-            );
-            BILLBOARD.with(|it| {
-                let mut guard = it.borrow_mut();
-                let t = guard.1.now() as f32;
-                guard.0.render(&mut ui, t);
-            });
-        } else {
-            main.update()?;
-            gl.quad_gl.viewport(None);
-            gl.quad_gl.render_pass(None);
-            main.render(&mut Ui::new(&mut painter))?;
-        }
-        if main.should_exit() {
-            break;
-        }
+                });
+                let mut ui = Ui::new(painter);
+                clear_background(GRAY);
+                draw_texture_ex(
+                    tex,
+                    -1. + lf / sw * 2.,
+                    -ui.top,
+                    WHITE,
+                    DrawTextureParams {
+                        flip_y: true,
+                        dest_size: Some(vec2(texture.width as f32, texture.height as f32) * (2. / sw)),
+                        ..Default::default()
+                    },
+                );
+                BILLBOARD.with(|it| {
+                    let mut guard = it.borrow_mut();
+                    let t = guard.1.now() as f32;
+                    guard.0.render(&mut ui, t);
+                });
+            } else {
+                main.update()?;
+                gl.quad_gl.viewport(None);
+                gl.quad_gl.render_pass(None);
+                main.render(&mut Ui::new(painter))?;
+            }
+            if main.should_exit() {
+                break;
+            }
+            if CANCELLED.load(Ordering::SeqCst) {
+                bail!(tl!("render-cancelled"));
+            }
 
-        next_frame().await;
+            next_frame().await;
+        }
+    } else {
+        *INFO_EDIT.lock().unwrap() = Some(ChartInfoEdit::new(info));
+        *VIDEO_CONFIG.lock().unwrap() = Some(v_config);
     }
     clear_background(BLACK);
     next_frame().await;
 
     let edit = INFO_EDIT.lock().unwrap().take().unwrap();
-    let volume_music = config.volume_music;
-    let volume_sfx = config.volume_sfx;
+    let mut v_config = VIDEO_CONFIG.lock().unwrap().take().unwrap();
+    // also given by the settings file, which skips the check of `--fps`
+    if !(1..=480).contains(&v_config.fps) {
+        bail!(tl!("fps-out-of-range"));
+    }
+    if !v_config.format.is_sequence() && !v_config.audio_only {
+        // yuv420p, which most encoders use, can't represent odd dimensions
+        let (w, h) = v_config.resolution;
+        let even = ((w + 1) & !1, (h + 1) & !1);
+        if even != (w, h) {
+            warn!("{}", tl!("resolution-adjusted", "from" => format!("{w}x{h}"), "to" => format!("{}x{}", even.0, even.1)));
+            v_config.resolution = even;
+        }
+    }
+    if v_config.dry_run && v_config.audio_only {
+        bail!(tl!("estimate-audio-only"));
+    }
+    // intermediate files, removed along with the folder however the render ends
+    let own_temp_dir = tempfile::tempdir().context(tl!("temp-dir-failed"))?;
+    // segment processes use the one given, which is removed by the process joining them even if they crash
+    let temp_dir = v_config.temp_dir.as_ref().map_or_else(|| own_temp_dir.path().to_owned(), PathBuf::from);
+    let audio_file = temp_dir.join("audio.wav");
+    let chapters_file = temp_dir.join("chapters.txt");
+    let output_file = if v_config.dry_run {
+        temp_dir.join("estimate").with_extension(v_config.extension())
+    } else if let Some((segment, _)) = v_config.segment {
+        part_path(&output_path(&v_config, &edit.info, index)?, segment)
+    } else {
+        output_path(&v_config, &edit.info, index)?
+    };
+    let mut metadata_args = Vec::new();
+    if v_config.metadata {
+        let info = &edit.info;
+        let comment = tl!(
+            "metadata-comment",
+            "charter" => info.charter.as_str(),
+            "illustrator" => info.illustrator.as_str(),
+            "level" => info.level.as_str()
+        );
+        for (key, value) in [("title", &info.name), ("artist", &info.composer), ("comment", &comment)] {
+            metadata_args.push("-metadata".to_owned());
+            metadata_args.push(format!("{key}={value}"));
+        }
+    }
+    // volumes of the rendered audio fall back to those used when playing
+    let volume_music = v_config.music_volume.unwrap_or(config.volume_music);
+    let volume_hitsound = v_config.hitsound_volume.unwrap_or_else(|| config.hitsound_volume());
+    let volume_ending = v_config.ending_volume.unwrap_or_else(|| config.ending_volume());
+    let (vw, vh) = v_config.resolution;
+    let transparent = v_config.transparent && !v_config.audio_only;
+    if transparent && !v_config.format.supports_alpha() {
+        bail!(tl!("transparent-unsupported"));
+    }
+    if v_config.builtin_encoder {
+        if v_config.audio_only && v_config.flac {
+            bail!(tl!("builtin-no-flac"));
+        }
+        if !v_config.audio_only && !(v_config.format == OutputFormat::Mp4 || v_config.format.is_sequence()) {
+            bail!(tl!("builtin-format-unsupported"));
+        }
+        if v_config.audio_codec.map_or(false, |it| it != AudioCodec::Aac) && !v_config.audio_only && !v_config.format.is_sequence() {
+            bail!(tl!("builtin-aac-only"));
+        }
+    }
+    let replay: Option<Replay> = match &v_config.replay {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).with_context(|| tl!("replay-read-failed", "path" => path.as_str()))?;
+            Some(serde_json::from_str(&text).context(tl!("replay-invalid"))?)
+        }
+        None => None,
+    };
     let config = Config {
-        autoplay: true,
+        // judgements come from the replay instead
+        autoplay: replay.is_none(),
+        record_replay: None,
+        replay: v_config.replay.clone(),
+        transparent_background: transparent,
         volume_music: 0.,
         volume_sfx: 0.,
         ..config
     };
 
-    let v_config = VIDEO_CONFIG.lock().unwrap().take().unwrap();
-    let (vw, vh) = v_config.resolution;
-
+    let intro_length = v_config.intro_length.max(LoadingScene::MIN_TIME as f64);
+    let o = intro_length + GameScene::BEFORE_TIME as f64;
     let length = track_length - chart.offset.min(0.) as f64 + 1.;
-    let video_length = O + length + A + v_config.ending_length;
+    let video_length = o + length + A + v_config.ending_length;
     let offset = chart.offset.max(0.);
 
+    // only the requested slice is exported, times are given in chart time
+    let range_start = v_config.start.map_or(0., |it| (o + it).clamp(0., video_length));
+    let range_end = v_config.end.map_or(video_length, |it| (o + it).min(video_length));
+    if range_end <= range_start {
+        bail!(tl!("end-before-start"));
+    }
+    let full_length = range_end - range_start;
+    let (range_start, range_end) = if v_config.dry_run {
+        // gameplay is the most expensive part to render, so it's sampled instead of the intro
+        let start = range_start.max(o).min(range_end - DRY_RUN_LENGTH).max(range_start);
+        (start, (start + DRY_RUN_LENGTH).min(range_end))
+    } else {
+        (range_start, range_end)
+    };
+    // chart info is shown from the start of gameplay
+    let lower_third = v_config.lower_third.then(|| LowerThird::new(&edit.info, o as f32, (o + v_config.info_duration) as f32));
+    let first_frame = (range_start * v_config.fps as f64).floor() as u64;
+    let frames = (range_end * v_config.fps as f64).ceil() as u64;
+    // segments are split by frames, so that joining them doesn't lose or repeat any
+    let (first_frame, frames, range_end) = match v_config.segment {
+        Some((segment, count)) => {
+            let total = frames - first_frame;
+            let end = first_frame + total * segment as u64 / count as u64;
+            (first_frame + total * (segment - 1) as u64 / count as u64, end, end as f64 / v_config.fps as f64)
+        }
+        None => (first_frame, frames, range_end),
+    };
+    if v_config.fps > 60 && !v_config.audio_only {
+        let secs = (frames - first_frame) as f64 / v_config.fps as f64;
+        warn!("{}", tl!("high-fps", "fps" => v_config.fps, "frames" => frames - first_frame));
+        if v_config.crf.is_some() {
+            warn!("{}", tl!("high-fps-crf"));
+        } else if let Ok(bitrate) = parse_bitrate(&v_config.bitrate) {
+            warn!("{}", tl!("high-fps-bitrate", "size" => format!("{:.0}", bitrate as f64 * secs / 8e6)));
+        }
+    }
+
     let render_start_time = Instant::now();
 
-    info!("[1] 混音中…");
-    let sample_rate = 44100;
-    assert_eq!(sample_rate, ending.sample_rate());
-    assert_eq!(sample_rate, sfx_click.sample_rate());
-    assert_eq!(sample_rate, sfx_drag.sample_rate());
-    assert_eq!(sample_rate, sfx_flick.sample_rate());
+    info!("{}", tl!("mixing"));
+    let sample_rate = v_config.sample_rate;
+    if !(8000..=192000).contains(&sample_rate) {
+        bail!(tl!("unsupported-sample-rate", "rate" => sample_rate));
+    }
+    // everything is brought to the output rate once, so charts and sound effects of any rate mix correctly
+    let music = resample(&music, sample_rate);
+    let ending = resample(&ending, sample_rate);
+    let sfx_click = resample(&sfx_click, sample_rate);
+    let sfx_drag = resample(&sfx_drag, sample_rate);
+    let sfx_flick = resample(&sfx_flick, sample_rate);
     let mut output = vec![0.0_f32; (video_length * sample_rate as f64).ceil() as usize * 2];
-    {
-        let pos = O - chart.offset.min(0.) as f64;
-        let count = (music.length() as f64 * sample_rate as f64) as usize;
-        let mut it = output[((pos * sample_rate as f64).round() as usize * 2)..].iter_mut();
-        let ratio = 1. / sample_rate as f64;
-        for frame in 0..count {
-            let position = frame as f64 * ratio;
-            let frame = music.sample(position as f32).unwrap_or_default();
-            *it.next().unwrap() += frame.0 * volume_music;
-            *it.next().unwrap() += frame.1 * volume_music;
-        }
-    }
-    let mut place = |pos: f64, clip: &AudioClip, volume: f32| {
+    let mut place = |pos: f64, clip: &[(f32, f32)], volume: f32, pan: f32| {
         let position = (pos * sample_rate as f64).round() as usize * 2;
-        let slice = &mut output[position..];
-        let len = (slice.len() / 2).min(clip.frame_count());
-        let mut it = slice.iter_mut();
-        // TODO optimize?
-        for frame in clip.frames()[..len].iter() {
-            let dst = it.next().unwrap();
-            *dst += frame.0 * volume;
-            let dst = it.next().unwrap();
-            *dst += frame.1 * volume;
+        let (left, right) = (volume * (1. - pan).min(1.), volume * (1. + pan).min(1.));
+        for (dst, frame) in output[position.min(output.len())..].chunks_exact_mut(2).zip(clip) {
+            dst[0] += frame.0 * left;
+            dst[1] += frame.1 * right;
         }
     };
-    for note in chart.lines.iter().flat_map(|it| it.notes.iter()).filter(|it| !it.fake) {
+    place(o - chart.offset.min(0.) as f64, &music, volume_music, 0.);
+    let aspect_ratio = config.aspect_ratio.unwrap_or(edit.info.aspect_ratio);
+    let hitsounds: Vec<_> = if let Some(replay) = &replay {
+        // only notes that were actually hit make sounds, at the time they were hit
+        replay
+            .events
+            .iter()
+            .filter(|event| {
+                let Some(note) = chart.lines.get(event.line as usize).and_then(|it| it.notes.get(event.note as usize)) else { return false; };
+                match event.action {
+                    ReplayAction::HoldStart { .. } => true,
+                    ReplayAction::Judge(Judgement::Perfect | Judgement::Good) => !matches!(note.kind, NoteKind::Hold { .. }),
+                    ReplayAction::Judge(_) => false,
+                }
+            })
+            .map(|event| (event.time, event.line as usize, event.note as usize))
+            .collect()
+    } else {
+        chart
+            .lines
+            .iter()
+            .enumerate()
+            .flat_map(|(line_id, line)| line.notes.iter().enumerate().filter(|(_, it)| !it.fake).map(move |(id, note)| (note.time, line_id, id)))
+            .collect()
+    };
+    for (time, line_id, id) in hitsounds {
+        if config.hitsound_panning > 1e-3 {
+            for line in &mut chart.lines {
+                line.object.set_time(time);
+            }
+            chart.lines[line_id].notes[id].object.set_time(time);
+        }
+        let line = &chart.lines[line_id];
+        let note = &line.notes[id];
         place(
-            O + note.time as f64 + offset as f64,
+            o + time as f64 + offset as f64,
             match note.kind {
                 NoteKind::Click | NoteKind::Hold { .. } => &sfx_click,
                 NoteKind::Drag => &sfx_drag,
                 NoteKind::Flick => &sfx_flick,
             },
-            volume_sfx,
+            volume_hitsound,
+            note_pan(&chart.lines, line, note, aspect_ratio, &config),
         )
     }
-    place(O + length + A, &ending, volume_music);
-    let mut proc = Command::new(&ffmpeg)
-        .args("-y -f f32le -ar 44100 -ac 2 -i - -c:a mp3 t_audio.mp3".split_whitespace())
-        .stdin(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .context("无法执行 ffmpeg")?;
-    let input = proc.stdin.as_mut().unwrap();
-    let mut writer = BufWriter::new(input);
-    for sample in output.into_iter() {
-        writer.write_all(&sample.to_le_bytes())?;
-    }
-    drop(writer);
-    proc.wait()?;
-
-    info!("[2] 渲染视频…");
-    let mst = Rc::new(MSRenderTarget::new((vw, vh), config.sample_count));
+    place(o + length + A, &ending, volume_ending, 0.);
+    let first_sample = (first_frame as f64 / v_config.fps as f64 * sample_rate as f64).round() as usize * 2;
+    let last_sample = ((range_end * sample_rate as f64).ceil() as usize * 2).min(output.len());
+    if v_config.normalize {
+        match loudness::normalize(&mut output[first_sample..last_sample], sample_rate, v_config.target_loudness) {
+            Some((measured, gain)) => {
+                info!("{}", tl!("loudness", "measured" => format!("{measured:.1}"), "gain" => format!("{gain:+.1}")));
+                if measured + gain < v_config.target_loudness - 0.1 {
+                    warn!("{}", tl!("loudness-limited", "loudness" => format!("{:.1}", measured + gain)));
+                }
+            }
+            None => warn!("{}", tl!("audio-too-quiet")),
+        }
+    }
+    let audio = &output[first_sample..last_sample];
+    if v_config.format.is_sequence() && !v_config.audio_only {
+        std::fs::create_dir_all(&output_file).with_context(|| tl!("create-dir-failed", "path" => output_file.display().to_string()))?;
+        write_wav(&output_file.join("audio.wav"), audio, sample_rate)?;
+    } else if v_config.builtin_encoder {
+        // muxed by the encoder itself later
+        if v_config.audio_only {
+            write_wav(&output_file, audio, sample_rate)?;
+        }
+    } else {
+        let (audio_codec, audio_output) = if !v_config.audio_only {
+            ("pcm_f32le", audio_file.clone())
+        } else if v_config.flac {
+            ("flac", output_file.clone())
+        } else {
+            ("pcm_s16le", output_file.clone())
+        };
+        let mut proc = Command::new(ffmpeg)
+            .args("-y -f f32le -ac 2 -ar".split_whitespace())
+            .arg(sample_rate.to_string())
+            .args("-i - -c:a".split_whitespace())
+            .arg(audio_codec)
+            .args(if v_config.audio_only { metadata_args.as_slice() } else { &[] })
+            .arg(&audio_output)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context(tl!("ffmpeg-failed"))?;
+        let input = proc.stdin.as_mut().unwrap();
+        let mut writer = BufWriter::new(input);
+        for sample in audio.iter() {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        drop(writer);
+        proc.wait()?;
+    }
+    if v_config.subtitles && !v_config.audio_only && !v_config.dry_run && v_config.segment.is_none() {
+        let path = if v_config.format.is_sequence() { output_file.join("subtitles.srt") } else { output_file.with_extension("srt") };
+        let offset = first_frame as f64 / v_config.fps as f64;
+        let (start, end) = ((o - offset).max(0.), (o + v_config.info_duration).min(range_end) - offset);
+        if end > start {
+            let text = format!("{}\n{}", edit.info.name, lower_third::detail_line(&edit.info));
+            write_srt(&path, &[(start, end, text)])?;
+        }
+    }
+    if v_config.audio_only {
+        info!("{}", tl!("audio-done", "time" => format!("{:.2}", render_start_time.elapsed().as_secs_f64())));
+        return Ok(v_config);
+    }
+
+    info!("{}", tl!("rendering-video"));
+    let mst = Rc::new(MSRenderTarget::new((vw, vh), config.sample_count, transparent));
     let my_time: Rc<RefCell<f64>> = Rc::new(RefCell::new(0.));
     let tm = TimeManager::manual(Box::new({
         let my_time = Rc::clone(&my_time);
         move || *(*my_time).borrow()
     }));
-    let fs = Box::new(PatchedFileSystem(fs, edit.to_patches().await?));
+    let mut fs = PatchedFileSystem(fs, edit.to_patches().await?);
+    if v_config.save_info && v_config.segment.map_or(true, |it| it.0 == 1) {
+        fs.save(path).context(tl!("info-save-failed"))?;
+        info!("{}", tl!("info-saved"));
+    }
+    if let Some(illustration) = &v_config.illustration {
+        // replaces the one shown on both the intro and ending cards
+        let bytes = std::fs::read(illustration).with_context(|| tl!("illustration-read-failed", "path" => illustration.as_str()))?;
+        fs.1.insert(edit.info.illustration.clone(), bytes);
+    }
+    let fs = Box::new(fs);
     static MSAA: AtomicBool = AtomicBool::new(false);
+    // text isn't scaled in videos, see `textScale`
+    apply_accessibility(&Config { text_scale: 1., ..config.clone() });
+    let mut loading = LoadingScene::new(GameMode::Normal, edit.info, config, fs, (None, None), Some(Rc::new(move || (vw, vh))), None).await?;
+    loading.set_total_time(intro_length as f32);
     let mut main = Main::new(
-        Box::new(LoadingScene::new(GameMode::Normal, edit.info, config, fs, (None, None), Some(Rc::new(move || (vw, vh))), None).await?),
+        Box::new(loading),
         tm,
         {
             let mut cnt = 0;
@@ -291,86 +1207,564 @@ async fn the_main() -> Result<()> {
     .await?;
     main.show_billboard = false;
 
-    const O: f64 = LoadingScene::TOTAL_TIME as f64 + GameScene::BEFORE_TIME as f64;
     const A: f64 = 0.7 + 0.3 + 0.4;
 
     let fps = v_config.fps;
-    let frame_delta = 1. / fps as f32;
-
-    let codecs = String::from_utf8(Command::new(&ffmpeg).arg("-codecs").output().context("无法执行 ffmpeg")?.stdout)?;
-    let use_cuda = v_config.hardware_accel && codecs.contains("h264_nvenc");
-    let has_qsv = v_config.hardware_accel && codecs.contains("h264_qsv");
-
-    let mut args = "-y -f rawvideo -c:v rawvideo".to_owned();
-    if use_cuda {
-        args += " -hwaccel_output_format cuda";
-    }
-    write!(
-        &mut args,
-        " -s {vw}x{vh} -r {fps} -pix_fmt rgb24 -i - -i t_audio.mp3 -c:a copy -c:v {} -map 0:v:0 -map 1:a:0 -qp 0 -vf vflip t_video.mp4",
-        if use_cuda {
-            "h264_nvenc"
-        } else if has_qsv {
-            "h264_qsv"
-        } else if v_config.hardware_accel {
-            bail!("不支持硬件加速！");
+    let frame_delta = 1. / fps as f64;
+
+    let card_lines = CardLines {
+        intro: v_config.intro_lines.clone(),
+        ending: v_config.ending_lines.clone(),
+        intro_end: (intro_length - LoadingScene::MIN_TIME as f64) as f32 + 0.5,
+        ending_start: (o + length + A) as f32,
+    };
+    let watermark = if v_config.watermark.is_empty() {
+        None
+    } else {
+        Some(WatermarkRenderer::new(v_config.watermark.clone())?)
+    };
+
+    let format = v_config.format;
+    // chapters are read by ffmpeg from a metadata file given as the third input
+    let chapters = v_config.chapters && !v_config.builtin_encoder && !format.is_sequence() && v_config.segment.is_none();
+    if chapters {
+        let offset = first_frame as f64 / fps as f64;
+        let ending_start = o + length + A;
+        let titles = [tl!("chapter-intro"), tl!("chapter-chart"), tl!("chapter-ending")];
+        write_chapters(
+            &chapters_file,
+            [(&*titles[0], 0., o), (&*titles[1], o, ending_start), (&*titles[2], ending_start, video_length)]
+                .into_iter()
+                .map(|(title, start, end)| (title, (start - offset).max(0.), end.min(range_end) - offset))
+                .filter(|(_, start, end)| end > start),
+        )?;
+    }
+    let mut sink = if format.is_sequence() {
+        VideoSink::Images(ImageSequence::new(output_file.clone(), (vw, vh), transparent, format == OutputFormat::Exr))
+    } else if v_config.builtin_encoder {
+        builtin_sink(&output_file, (vw, vh), fps, &v_config.bitrate, v_config.audio_bitrate.as_deref().unwrap_or("320k"))?
+    } else {
+        check_ffmpeg_args(&v_config.ffmpeg_input_args)?;
+        check_ffmpeg_args(&v_config.ffmpeg_output_args)?;
+        let mut output_args = v_config.ffmpeg_output_args.clone();
+        let extra_filters = take_filters(&mut output_args)?;
+        if !v_config.ffmpeg_input_args.is_empty() || extra_filters.is_some() || !output_args.is_empty() {
+            info!(
+                "{}",
+                tl!(
+                    "extra-ffmpeg-args",
+                    "input" => format!("{:?}", v_config.ffmpeg_input_args),
+                    "filters" => format!("{:?}", extra_filters.as_deref().unwrap_or_default()),
+                    "output" => format!("{output_args:?}")
+                )
+            );
+        }
+
+        let mut args = "-y -f rawvideo -c:v rawvideo".to_owned();
+        let mut filters = Vec::new();
+        if let Some(video_args) = format.master_args(transparent) {
+            // masters are meant for further editing, quality settings don't apply
+            write!(
+                &mut args,
+                " -s {vw}x{vh} -r {fps} -pix_fmt {} -i - {} {video_args} -map 0:v:0 -map 1:a:0",
+                if transparent { "rgba" } else { "rgb24" },
+                format.audio_args(v_config.audio_codec, v_config.audio_bitrate.as_deref())?,
+            )?;
+            filters.push("vflip");
+            filters.extend(extra_filters.as_deref());
         } else {
-            "libx264 -preset ultrafast"
-        },
-    )?;
+            let codecs = String::from_utf8(Command::new(ffmpeg).arg("-codecs").output().context(tl!("ffmpeg-failed"))?.stdout)?;
+            let codec = format.video_codec(v_config.codec);
+            let encoder = if let Some(encoder) = &v_config.encoder {
+                encoder.as_str()
+            } else {
+                let candidates = codec.encoders(v_config.hardware_accel);
+                let mut found = None;
+                for encoder in &candidates {
+                    if !codecs.contains(encoder) {
+                        continue;
+                    }
+                    // software encoders always work once compiled in
+                    if !v_config.hardware_accel || probe_encoder(ffmpeg, encoder) {
+                        found = Some(*encoder);
+                        break;
+                    }
+                    info!("{}", tl!("encoder-unavailable", "encoder" => *encoder));
+                }
+                match found {
+                    Some(encoder) => encoder,
+                    None if v_config.hardware_accel => bail!(tl!("hwaccel-unsupported", "codec" => codec.name(), "tried" => candidates.join(" / "))),
+                    None => bail!(tl!("encoder-unsupported", "codec" => codec.name(), "tried" => candidates.join(" / "))),
+                }
+            };
+            info!("{}", tl!("using-encoder", "encoder" => encoder));
 
-    let mut proc = Command::new(&ffmpeg)
-        .args(args.split_whitespace())
-        .stdin(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .context("无法执行 ffmpeg")?;
-    let mut input = proc.stdin.take().unwrap();
+            let vaapi = encoder.ends_with("_vaapi");
+            if encoder.contains("nvenc") {
+                args += " -hwaccel_output_format cuda";
+            }
+            if vaapi {
+                write!(&mut args, " -vaapi_device {VAAPI_DEVICE}")?;
+            }
+            // encode the final video in a single pass, the mixed audio is muxed in at the same time
+            write!(
+                &mut args,
+                " -s {vw}x{vh} -r {fps} -pix_fmt rgb24 -i - {} -c:v {encoder} -map 0:v:0 -map 1:a:0",
+                format.audio_args(v_config.audio_codec, v_config.audio_bitrate.as_deref())?,
+            )?;
+            filters.push("vflip");
+            filters.extend(extra_filters.as_deref());
+            if vaapi {
+                // frames have to be uploaded to the GPU first
+                filters.extend(["format=nv12", "hwupload"]);
+            } else {
+                args += " -pix_fmt yuv420p";
+            }
+            if let Some(crf) = v_config.crf {
+                // constant quality, each encoder family names it differently
+                if encoder.contains("nvenc") {
+                    write!(&mut args, " -rc vbr -cq {crf} -b:v 0")?;
+                } else if encoder.contains("qsv") {
+                    write!(&mut args, " -global_quality {crf}")?;
+                } else if encoder.contains("amf") {
+                    write!(&mut args, " -rc cqp -qp_i {crf} -qp_p {crf}")?;
+                } else if vaapi {
+                    write!(&mut args, " -rc_mode CQP -qp {crf}")?;
+                } else if encoder.contains("videotoolbox") {
+                    // quality ranges from 1 to 100 where higher is better
+                    write!(&mut args, " -q:v {}", 100u32.saturating_sub(crf * 2).max(1))?;
+                } else if matches!(encoder, "libvpx-vp9" | "libaom-av1") {
+                    // otherwise the bitrate acts as the upper bound
+                    write!(&mut args, " -crf {crf} -b:v 0")?;
+                } else {
+                    write!(&mut args, " -crf {crf}")?;
+                }
+            } else {
+                write!(&mut args, " -b:v {}", v_config.bitrate)?;
+            }
+            if let Some(preset) = &v_config.preset {
+                write!(&mut args, " -preset {preset}")?;
+            }
+            if codec == VideoCodec::Hevc {
+                // required by QuickTime and most Apple devices
+                args += " -tag:v hvc1";
+            }
+            if encoder == "libvpx-vp9" {
+                args += " -row-mt 1";
+            }
+        }
+        if chapters {
+            args += " -map_chapters 2";
+        }
+        if !filters.is_empty() {
+            write!(&mut args, " -vf {}", filters.join(","))?;
+        }
+
+        let mut args: Vec<String> = args.split_whitespace().map(str::to_owned).collect();
+        // input options have to come before the piped frames they apply to
+        let input_index = args.windows(2).position(|it| it[0] == "-i" && it[1] == "-").unwrap();
+        // the mixed audio and the chapters follow the piped frames, kept out of the split above since their paths may contain spaces
+        let mut inputs = vec!["-i".to_owned(), audio_file.display().to_string()];
+        if chapters {
+            inputs.extend([
+                "-f".to_owned(),
+                "ffmetadata".to_owned(),
+                "-i".to_owned(),
+                chapters_file.display().to_string(),
+            ]);
+        }
+        args.splice(input_index + 2..input_index + 2, inputs);
+        args.splice(input_index..input_index, v_config.ffmpeg_input_args.iter().cloned());
+        let mut proc = Command::new(ffmpeg)
+            .args(&args)
+            .args(&metadata_args)
+            .args(&output_args)
+            .arg(&output_file)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context(tl!("ffmpeg-failed"))?;
+        let input = proc.stdin.take().unwrap();
+        VideoSink::Ffmpeg(proc, input)
+    };
+    prevent_quit();
 
-    let mut bytes = vec![0; vw as usize * vh as usize * 3];
+    let mut reader = PixelReader::new((vw, vh), transparent);
+    let fbo = mst.output().render_pass.gl_internal_id(gl.quad_context);
+    let mut bytes = vec![0; vw as usize * vh as usize * if transparent { 4 } else { 3 }];
 
-    let frames = (video_length / frame_delta as f64).ceil() as u64;
-    let start_time = Instant::now();
+    let mut start_time = Instant::now();
+    let mut last_update = start_time;
+    let mut last_log = start_time;
+    let mut progress = 0.;
+    let mut status = String::new();
+    let preview_interval = v_config.preview_interval.max(1) as u64;
 
     for frame in 0..frames {
-        *my_time.borrow_mut() = (frame as f32 * frame_delta).max(0.) as f64;
+        // computed from the frame index rather than accumulated, so high frame rates don't drift
+        *my_time.borrow_mut() = frame as f64 * frame_delta;
         gl.quad_gl.render_pass(Some(mst.output().render_pass));
-        clear_background(BLACK);
+        clear_background(if transparent { Color::new(0., 0., 0., 0.) } else { BLACK });
         main.update()?;
-        main.render(&mut Ui::new(&mut painter))?;
+        main.render(&mut Ui::new(painter))?;
         // TODO magic. can't remove this line.
         draw_rectangle(0., 0., 0., 0., Color::default());
         gl.flush();
+        if frame < first_frame {
+            // played through so that judgements and scores stay consistent
+            start_time = Instant::now();
+            continue;
+        }
 
         if MSAA.load(Ordering::SeqCst) {
             mst.blit();
         }
-        mst.output().texture.raw_miniquad_texture_handle().read_pixels(&mut bytes);
-        input.write_all(&bytes)?;
-        if frame % 100 == 0 {
-            info!("{frame} / {frames}, {:.2}fps", frame as f64 / start_time.elapsed().as_secs_f64());
+        if !card_lines.is_empty() {
+            card_lines.render(painter, mst.output(), (vw, vh), (frame as f64 * frame_delta) as f32);
+        }
+        if let Some(lower_third) = &lower_third {
+            lower_third.render(painter, mst.output(), (vw, vh), (frame as f64 * frame_delta) as f32);
+        }
+        if let Some(watermark) = &watermark {
+            watermark.render(painter, mst.output(), (vw, vh));
+        }
+        if reader.read(fbo, &mut bytes)? {
+            if let Err(err) = sink.write(&bytes) {
+                CANCELLED.store(true, Ordering::SeqCst);
+                warn!("{}", tl!("write-video-failed", "error" => format!("{err:?}")));
+            }
+        }
+
+        let now = Instant::now();
+        let report = now - last_update >= Duration::from_millis(500) || frame + 1 == frames;
+        if report {
+            last_update = now;
+            let done = frame + 1 - first_frame;
+            let total = frames - first_frame;
+            progress = done as f32 / total as f32;
+            let encode_fps = done as f64 / start_time.elapsed().as_secs_f64();
+            let eta = (total - done) as f64 / encode_fps;
+            status = format!("{:.1}%  {done} / {total}  {encode_fps:.2}fps  {} {}", progress * 100., tl!("remaining"), fmt_duration(eta));
+            if now - last_log >= Duration::from_secs(5) || done == total {
+                last_log = now;
+                info!("{status}");
+            }
+        }
+        // presenting to the window waits for vsync, so previews are throttled
+        let preview = v_config.live_preview && (frame - first_frame) % preview_interval == 0;
+        if report || preview {
+            draw_progress(painter, progress, &status, v_config.live_preview.then(|| (mst.output().texture, (vw, vh))));
+            next_frame().await;
+            if is_quit_requested() {
+                CANCELLED.store(true, Ordering::SeqCst);
+            }
         }
+        if CANCELLED.load(Ordering::SeqCst) {
+            sink.kill();
+            if format.is_sequence() {
+                let _ = std::fs::remove_dir_all(&output_file);
+            } else {
+                let _ = std::fs::remove_file(&output_file);
+            }
+            bail!(tl!("render-cancelled"));
+        }
+    }
+    if reader.finish(&mut bytes)? {
+        sink.write(&bytes)?;
     }
-    drop(input);
-    proc.wait()?;
+    sink.finish(audio, sample_rate)?;
 
-    info!("[3] 合并 & 转码 & 压制");
-    let _ = Command::new(&ffmpeg)
-        .args("-y -i t_video.mp4 -c:a copy -pix_fmt yuv420p -b:v".split_whitespace())
-        .arg(v_config.bitrate)
-        .arg("out.mp4")
-        .stdin(Stdio::null())
-        .stderr(Stdio::inherit())
-        .status()
-        .context("无法执行 ffmpeg")?;
+    let elapsed = start_time.elapsed().as_secs_f64();
+    let transferred = (frames - first_frame) as f64 * bytes.len() as f64;
+    info!(
+        "{}",
+        tl!(
+            "average-speed",
+            "fps" => format!("{:.2}", (frames - first_frame) as f64 / elapsed),
+            "rate" => format!("{:.1}", transferred / elapsed / 1e6)
+        )
+    );
+    if v_config.dry_run {
+        let size = if format.is_sequence() {
+            let size = std::fs::read_dir(&output_file)?.map(|it| Ok(it?.metadata()?.len())).sum::<Result<u64>>();
+            let _ = std::fs::remove_dir_all(&output_file);
+            size?
+        } else {
+            let size = std::fs::metadata(&output_file).map(|it| it.len());
+            let _ = std::fs::remove_file(&output_file);
+            size?
+        };
+        let scale = full_length / (range_end - range_start);
+        info!(
+            "{}",
+            tl!(
+                "estimate",
+                "sample" => format!("{:.1}", range_end - range_start),
+                "time" => fmt_duration(elapsed * scale),
+                "size" => format!("{:.1}", size as f64 * scale / 1e6)
+            )
+        );
+        return Ok(v_config);
+    }
+    info!("{}", tl!("render-done", "time" => format!("{:.2}", render_start_time.elapsed().as_secs_f64())));
+    Ok(v_config)
+}
+
+/// Splits extra ffmpeg arguments like a shell would, so that quoted filters and values can contain spaces.
+fn split_ffmpeg_args(args: &str) -> Result<Vec<String>> {
+    shell_words::split(args).context(tl!("ffmpeg-args-invalid"))
+}
 
-    info!("渲染完成！耗时：{:.2}s", render_start_time.elapsed().as_secs_f64());
+/// Inputs are fixed, since the streams are mapped by their indices.
+fn check_ffmpeg_args(args: &[String]) -> Result<()> {
+    if args.iter().any(|it| it == "-i") {
+        bail!(tl!("ffmpeg-args-input"));
+    }
     Ok(())
 }
 
-#[macroquad::main(build_conf)]
+/// Takes video filters out of the extra arguments, so that they can be chained with the ones used internally.
+fn take_filters(args: &mut Vec<String>) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|it| it == "-vf" || it == "-filter:v") else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        bail!(tl!("arg-missing-value", "arg" => args[index].as_str()));
+    }
+    let filters = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(filters))
+}
+
+fn write_chapters<'a>(path: &Path, chapters: impl Iterator<Item = (&'a str, f64, f64)>) -> Result<()> {
+    let mut content = ";FFMETADATA1\n".to_owned();
+    for (title, start, end) in chapters {
+        write!(&mut content, "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={title}\n", (start * 1000.).round(), (end * 1000.).round())?;
+    }
+    std::fs::write(path, content).context(tl!("chapters-write-failed"))
+}
+
+enum VideoSink {
+    Ffmpeg(Child, ChildStdin),
+    Images(ImageSequence),
+    #[cfg(feature = "builtin-encoder")]
+    Builtin(builtin::BuiltinEncoder),
+}
+
+impl VideoSink {
+    fn write(&mut self, frame: &[u8]) -> Result<()> {
+        match self {
+            Self::Ffmpeg(_, input) => Ok(input.write_all(frame)?),
+            Self::Images(sequence) => sequence.write(frame),
+            #[cfg(feature = "builtin-encoder")]
+            Self::Builtin(encoder) => encoder.write_frame(frame),
+        }
+    }
+
+    fn kill(self) {
+        match self {
+            Self::Ffmpeg(mut proc, input) => {
+                drop(input);
+                let _ = proc.kill();
+                let _ = proc.wait();
+            }
+            Self::Images(sequence) => {
+                let _ = sequence.finish();
+            }
+            #[cfg(feature = "builtin-encoder")]
+            Self::Builtin(_) => {}
+        }
+    }
+
+    #[cfg_attr(not(feature = "builtin-encoder"), allow(unused_variables))]
+    fn finish(self, audio: &[f32], sample_rate: u32) -> Result<()> {
+        match self {
+            Self::Ffmpeg(mut proc, input) => {
+                drop(input);
+                proc.wait()?;
+                Ok(())
+            }
+            Self::Images(sequence) => sequence.finish(),
+            #[cfg(feature = "builtin-encoder")]
+            Self::Builtin(encoder) => encoder.finish(audio, sample_rate),
+        }
+    }
+}
+
+#[cfg(feature = "builtin-encoder")]
+fn builtin_sink(path: &Path, dim: (u32, u32), fps: u32, bitrate: &str, audio_bitrate: &str) -> Result<VideoSink> {
+    Ok(VideoSink::Builtin(builtin::BuiltinEncoder::new(path, dim, fps, parse_bitrate(bitrate)?, parse_bitrate(audio_bitrate)?)?))
+}
+
+#[cfg(not(feature = "builtin-encoder"))]
+fn builtin_sink(_: &Path, _: (u32, u32), _: u32, _: &str, _: &str) -> Result<VideoSink> {
+    bail!(tl!("no-encoder"))
+}
+
+/// Parses bitrates in ffmpeg's notation, e.g. `7M` or `800k`.
+fn parse_bitrate(s: &str) -> Result<u32> {
+    let s = s.trim();
+    let (num, scale) = match s.chars().last() {
+        Some('k' | 'K') => (&s[..s.len() - 1], 1e3),
+        Some('m' | 'M') => (&s[..s.len() - 1], 1e6),
+        Some('g' | 'G') => (&s[..s.len() - 1], 1e9),
+        _ => (s, 1.),
+    };
+    Ok((num.parse::<f64>().with_context(|| tl!("bitrate-invalid", "value" => s))? * scale) as u32)
+}
+
+/// Writes 16-bit PCM WAV, for when ffmpeg isn't available.
+fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let mut writer = BufWriter::new(std::fs::File::create(path).with_context(|| tl!("create-file-failed", "path" => path.display().to_string()))?);
+    let data_len = samples.len() as u32 * 2;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 4).to_le_bytes())?;
+    writer.write_all(&4u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&((sample.clamp(-1., 1.) * i16::MAX as f32) as i16).to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_srt(path: &Path, entries: &[(f64, f64, String)]) -> Result<()> {
+    let time = |secs: f64| {
+        let ms = (secs.max(0.) * 1000.).round() as u64;
+        format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+    };
+    let mut content = String::new();
+    for (index, (start, end, text)) in entries.iter().enumerate() {
+        write!(&mut content, "{}\n{} --> {}\n{text}\n\n", index + 1, time(*start), time(*end))?;
+    }
+    std::fs::write(path, content).with_context(|| tl!("subtitles-write-failed", "path" => path.display().to_string()))
+}
+
+fn fmt_duration(secs: f64) -> String {
+    let secs = secs.max(0.).round() as u64;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// Draws render progress onto the window, leaving the camera of the game untouched.
+fn draw_progress(painter: &mut TextPainter, progress: f32, text: &str, preview: Option<(Texture2D, (u32, u32))>) {
+    let mut gl = unsafe { get_internal_gl() };
+    gl.quad_gl.render_pass(None);
+    gl.quad_gl.viewport(None);
+    push_camera_state();
+    set_camera(&Camera2D {
+        zoom: vec2(1., -screen_width() / screen_height()),
+        ..Default::default()
+    });
+    clear_background(BLACK);
+    let mut ui = Ui::new(painter);
+    if let Some((texture, (vw, vh))) = preview {
+        let bottom = -0.12;
+        let w = 1.6_f32.min((bottom + ui.top - 0.05) * vw as f32 / vh as f32);
+        let h = w * vh as f32 / vw as f32;
+        draw_texture_ex(
+            texture,
+            -w / 2.,
+            bottom - h,
+            WHITE,
+            DrawTextureParams {
+                flip_y: true,
+                dest_size: Some(vec2(w, h)),
+                ..Default::default()
+            },
+        );
+    }
+    ui.text(text).pos(0., -0.05).anchor(0.5, 1.).size(0.5).draw();
+    ui.fill_rect(Rect::new(-0.8, 0., 1.6, 0.02), Color::new(1., 1., 1., 0.3));
+    ui.fill_rect(Rect::new(-0.8, 0., 1.6 * progress, 0.02), WHITE);
+    gl.flush();
+    pop_camera_state();
+}
+
+/// Segment processes only render into textures, so their windows are kept as small as possible.
+fn window_conf() -> macroquad::window::Conf {
+    let conf = build_conf();
+    if std::env::args().any(|it| it == "--segment") {
+        return macroquad::window::Conf {
+            window_width: 1,
+            window_height: 1,
+            ..conf
+        };
+    }
+    conf
+}
+
+#[macroquad::main(window_conf)]
 async fn main() {
     if let Err(err) = the_main().await {
-        let _ = std::fs::write("错误信息.txt", format!("发生错误：{err:?}"));
+        let _ = std::fs::write(&*tl!("error-file"), tl!("error-occurred", "error" => format!("{err:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bitrate_suffixes() {
+        assert_eq!(parse_bitrate("7M").unwrap(), 7_000_000);
+        assert_eq!(parse_bitrate("800k").unwrap(), 800_000);
+        assert_eq!(parse_bitrate("1.5m").unwrap(), 1_500_000);
+        assert_eq!(parse_bitrate(" 128000 ").unwrap(), 128_000);
+        assert!(parse_bitrate("fast").is_err());
+        assert!(parse_bitrate("M").is_err());
+    }
+
+    #[test]
+    fn parse_resolution_presets_and_sizes() {
+        assert_eq!(parse_resolution("1080P").unwrap(), (1920, 1080));
+        assert_eq!(parse_resolution("vertical").unwrap(), (1080, 1920));
+        assert_eq!(parse_resolution("1280x720").unwrap(), (1280, 720));
+        assert_eq!(parse_resolution(" 640 × 360 ").unwrap(), (640, 360));
+        assert_eq!(parse_resolution("800*600").unwrap(), (800, 600));
+        assert!(parse_resolution("8k").is_err());
+        assert!(parse_resolution("1280x").is_err());
+        assert!(parse_resolution("8x8").is_err());
+        assert!(parse_resolution("10000x1080").is_err());
+    }
+
+    #[test]
+    fn write_wav_header_and_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audio.wav");
+        write_wav(&path, &[0., 1., -1., 2.], 44100).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 44 + 8);
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 44);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        // stereo, 44100Hz, 16 bits
+        assert_eq!(u16::from_le_bytes([bytes[22], bytes[23]]), 2);
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 44100);
+        assert_eq!(u16::from_le_bytes([bytes[34], bytes[35]]), 16);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 8);
+        let samples: Vec<i16> = bytes[44..].chunks_exact(2).map(|it| i16::from_le_bytes([it[0], it[1]])).collect();
+        // out of range samples are clipped
+        assert_eq!(samples, [0, i16::MAX, -i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn write_srt_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lyrics.srt");
+        let entries = [(0., 1.5, "first".to_owned()), (3725.0004, 3727.25, "second".to_owned())];
+        write_srt(&path, &entries).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "1\n00:00:00,000 --> 00:00:01,500\nfirst\n\n2\n01:02:05,000 --> 01:02:07,250\nsecond\n\n"
+        );
     }
 }
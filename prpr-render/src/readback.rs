@@ -0,0 +1,88 @@
+prpr::tl_file!("readback");
+
+use anyhow::{bail, Result};
+use macroquad::miniquad::gl::GLuint;
+
+/// Reads frames back through two pixel buffer objects, so that the transfer of one frame overlaps the rendering of the next.
+///
+/// The frame returned by [`PixelReader::read`] is always the one read in the previous call.
+pub struct PixelReader {
+    dim: (u32, u32),
+    rgba: bool,
+    pbos: [GLuint; 2],
+    pending: [bool; 2],
+    index: usize,
+}
+
+impl PixelReader {
+    pub fn new(dim: (u32, u32), rgba: bool) -> Self {
+        let mut pbos = [0; 2];
+        let size = dim.0 as usize * dim.1 as usize * if rgba { 4 } else { 3 };
+        unsafe {
+            use macroquad::miniquad::gl::*;
+            glGenBuffers(2, pbos.as_mut_ptr());
+            for pbo in pbos {
+                glBindBuffer(GL_PIXEL_PACK_BUFFER, pbo);
+                glBufferData(GL_PIXEL_PACK_BUFFER, size as _, std::ptr::null(), GL_STREAM_READ);
+            }
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+        }
+        Self {
+            dim,
+            rgba,
+            pbos,
+            pending: [false; 2],
+            index: 0,
+        }
+    }
+
+    /// Starts reading `fbo`, and copies the previous frame into `out` if there is one.
+    pub fn read(&mut self, fbo: GLuint, out: &mut [u8]) -> Result<bool> {
+        unsafe {
+            use macroquad::miniquad::gl::*;
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, fbo);
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, self.pbos[self.index]);
+            glPixelStorei(GL_PACK_ALIGNMENT, 1);
+            glReadPixels(0, 0, self.dim.0 as _, self.dim.1 as _, if self.rgba { GL_RGBA } else { GL_RGB }, GL_UNSIGNED_BYTE, std::ptr::null_mut());
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+        }
+        self.pending[self.index] = true;
+        self.index ^= 1;
+        self.take(out)
+    }
+
+    /// Copies the last frame into `out`, should be called once after all frames are read.
+    pub fn finish(&mut self, out: &mut [u8]) -> Result<bool> {
+        self.index ^= 1;
+        self.take(out)
+    }
+
+    /// Fails if the buffer can't be mapped, rather than leaving the previous frame in `out` to be written again.
+    fn take(&mut self, out: &mut [u8]) -> Result<bool> {
+        if !std::mem::take(&mut self.pending[self.index]) {
+            return Ok(false);
+        }
+        unsafe {
+            use macroquad::miniquad::gl::*;
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, self.pbos[self.index]);
+            let ptr = glMapBufferRange(GL_PIXEL_PACK_BUFFER, 0, out.len() as _, GL_MAP_READ_BIT) as *const u8;
+            if ptr.is_null() {
+                glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+                bail!(tl!("map-failed", "error" => glGetError()));
+            }
+            std::ptr::copy_nonoverlapping(ptr, out.as_mut_ptr(), out.len());
+            glUnmapBuffer(GL_PIXEL_PACK_BUFFER);
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for PixelReader {
+    fn drop(&mut self) {
+        unsafe {
+            use macroquad::miniquad::gl::*;
+            glDeleteBuffers(2, self.pbos.as_ptr());
+        }
+    }
+}
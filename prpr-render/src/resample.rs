@@ -0,0 +1,120 @@
+use sasa::AudioClip;
+use std::f64::consts::PI;
+
+/// Half width of the windowed sinc kernel, in zero crossings.
+const TAPS: f64 = 16.;
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Resamples the clip to `rate` with a Lanczos-windowed sinc filter, which also low-passes when downsampling.
+pub fn resample(clip: &AudioClip, rate: u32) -> Vec<(f32, f32)> {
+    let frames = clip.frames();
+    let from = clip.sample_rate();
+    if from == rate {
+        return frames.iter().map(|it| (it.0, it.1)).collect();
+    }
+    // output frame `i` lies at input position `i * num / den`, whose fraction only has `den` different values
+    let g = gcd(from as u64, rate as u64);
+    let (num, den) = (from as u64 / g, rate as u64 / g);
+    // keep frequencies below the Nyquist frequency of the lower rate
+    let cutoff = (rate as f64 / from as f64).min(1.);
+    let half = (TAPS / cutoff).ceil() as i64;
+    let width = (half * 2 + 1) as usize;
+    let mut kernels = vec![0.0_f32; den as usize * width];
+    for (phase, kernel) in kernels.chunks_exact_mut(width).enumerate() {
+        let frac = phase as f64 / den as f64;
+        let mut total = 0.;
+        for (k, weight) in kernel.iter_mut().enumerate() {
+            let x = (k as i64 - half) as f64 - frac;
+            let w = sinc(x * cutoff) * sinc(x * cutoff / TAPS);
+            *weight = w as f32;
+            total += w;
+        }
+        for weight in kernel.iter_mut() {
+            *weight /= total as f32;
+        }
+    }
+
+    let count = (frames.len() as u64 * den).div_ceil(num) as usize;
+    let mut output = Vec::with_capacity(count);
+    for i in 0..count as u64 {
+        let base = (i * num / den) as i64;
+        let kernel = &kernels[((i * num % den) as usize * width)..][..width];
+        let (mut left, mut right) = (0., 0.);
+        for (k, weight) in kernel.iter().enumerate() {
+            let j = base + k as i64 - half;
+            if let Some(frame) = usize::try_from(j).ok().and_then(|j| frames.get(j)) {
+                left += frame.0 * weight;
+                right += frame.1 * weight;
+            }
+        }
+        output.push((left, right));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sasa::Frame;
+
+    fn tone(freq: f32, rate: u32, frames: usize) -> AudioClip {
+        let frames = (0..frames)
+            .map(|i| {
+                let it = (i as f32 / rate as f32 * freq * std::f32::consts::TAU).sin() * 0.5;
+                Frame(it, -it)
+            })
+            .collect();
+        AudioClip::from_raw(frames, rate)
+    }
+
+    /// Root mean square of the left channel, leaving out the edges where the kernel runs past the clip.
+    fn rms(frames: &[(f32, f32)]) -> f32 {
+        let middle = &frames[frames.len() / 4..frames.len() * 3 / 4];
+        (middle.iter().map(|it| it.0 * it.0).sum::<f32>() / middle.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn resample_keeps_same_rate() {
+        let clip = tone(440., 44100, 100);
+        let output = resample(&clip, 44100);
+        assert_eq!(output.len(), 100);
+        assert!(output.iter().zip(clip.frames()).all(|(a, b)| a.0 == b.0 && a.1 == b.1));
+    }
+
+    #[test]
+    fn resample_scales_length() {
+        assert_eq!(resample(&tone(440., 22050, 1000), 44100).len(), 2000);
+        assert_eq!(resample(&tone(440., 48000, 480), 44100).len(), 441);
+        // partial frames at the end are kept
+        assert_eq!(resample(&tone(440., 48000, 481), 44100).len(), 442);
+    }
+
+    #[test]
+    fn resample_keeps_audible_tones() {
+        let output = resample(&tone(1000., 48000, 4800), 44100);
+        assert!((rms(&output) - 0.5 / 2f32.sqrt()).abs() < 0.01);
+        // channels are filtered alike
+        assert!(output[1000..3000].iter().all(|it| (it.0 + it.1).abs() < 1e-4));
+    }
+
+    #[test]
+    fn resample_removes_tones_above_nyquist() {
+        let output = resample(&tone(6000., 44100, 4410), 8000);
+        assert!(rms(&output) < 0.01);
+    }
+}
@@ -1,15 +1,30 @@
-use crate::{VideoConfig, INFO_EDIT, VIDEO_CONFIG};
-use anyhow::{bail, Result};
+prpr::tl_file!("scene");
+
+use crate::{
+    parse_resolution, planned_output_path, watermark::WatermarkPosition, AudioCodec, OutputFormat, VideoCodec, VideoConfig, INFO_EDIT,
+    RESOLUTION_PRESETS, VIDEO_CONFIG,
+};
+use anyhow::Result;
 use macroquad::prelude::*;
 use prpr::{
     config::Config,
-    ext::{poll_future, screen_aspect, LocalTask},
+    ext::{leading_silence, load_audio, poll_future, screen_aspect, LocalTask, RectExt},
     fs::{FileSystem, PatchedFileSystem},
     info::ChartInfo,
     scene::{show_error, show_message, GameMode, LoadingScene, NextScene, Scene},
     time::TimeManager,
-    ui::{render_chart_info, ChartInfoEdit, Scroll, Ui},
+    ui::{render_chart_info, ChartInfoEdit, Dialog, Scroll, Ui},
 };
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// Settings of the last render, kept next to `render.yml`.
+fn last_config_path(render_config_path: &Path) -> PathBuf {
+    render_config_path.with_file_name("render.last.yml")
+}
 
 pub struct MainScene {
     target: Option<RenderTarget>,
@@ -20,30 +35,62 @@ pub struct MainScene {
     fs: Box<dyn FileSystem>,
     next_scene: Option<NextScene>,
     v_config: VideoConfig,
+    render_config_path: PathBuf,
 
     loading_scene_task: LocalTask<Result<LoadingScene>>,
+    silence_task: LocalTask<Result<f32>>,
+    silence: Option<f32>,
+    /// Whether to overwrite the existing output, as chosen in the dialog asking so.
+    overwrite_choice: Rc<Cell<Option<bool>>>,
 }
 
 impl MainScene {
-    pub fn new(target: Option<RenderTarget>, info: ChartInfo, config: Config, fs: Box<dyn FileSystem>) -> Self {
+    pub fn new(
+        target: Option<RenderTarget>,
+        info: ChartInfo,
+        config: Config,
+        fs: Box<dyn FileSystem>,
+        v_config: VideoConfig,
+        render_config_path: PathBuf,
+    ) -> Self {
         Self {
             target,
 
             scroll: Scroll::new(),
-            edit: ChartInfoEdit::new(info),
+            edit: ChartInfoEdit::new(info).with_files(fs.list_root().unwrap_or_default()),
             config,
             fs,
             next_scene: None,
-            v_config: VideoConfig::default(),
+            v_config,
+            render_config_path,
 
             loading_scene_task: None,
+            silence_task: None,
+            silence: None,
+            overwrite_choice: Rc::default(),
         }
     }
+
+    fn start_render(&mut self, overwrite: bool) {
+        // remembered separately, so that the saved settings stay as they are
+        if let Err(err) = serde_yaml::to_string(&self.v_config)
+            .map_err(anyhow::Error::from)
+            .and_then(|it| Ok(std::fs::write(last_config_path(&self.render_config_path), it)?))
+        {
+            warn!("{}", tl!("save-last-failed", "error" => format!("{err:?}")));
+        }
+        *INFO_EDIT.lock().unwrap() = Some(self.edit.clone());
+        *VIDEO_CONFIG.lock().unwrap() = Some(VideoConfig {
+            overwrite: self.v_config.overwrite || overwrite,
+            ..self.v_config.clone()
+        });
+        self.next_scene = Some(NextScene::Exit);
+    }
 }
 
 impl Scene for MainScene {
     fn on_result(&mut self, _tm: &mut TimeManager, result: Box<dyn std::any::Any>) -> Result<()> {
-        show_error(result.downcast::<anyhow::Error>().unwrap().context("加载谱面失败"));
+        show_error(result.downcast::<anyhow::Error>().unwrap().context(tl!("chart-load-failed")));
         Ok(())
     }
 
@@ -53,12 +100,33 @@ impl Scene for MainScene {
 
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
         self.scroll.update(tm.now() as _);
+        if let Some(overwrite) = self.overwrite_choice.take() {
+            self.start_render(overwrite);
+        }
         if let Some(future) = &mut self.loading_scene_task {
             if let Some(scene) = poll_future(future.as_mut()) {
                 self.loading_scene_task = None;
                 self.next_scene = Some(NextScene::Overlay(Box::new(scene?)));
             }
         }
+        if let Some(future) = &mut self.silence_task {
+            if let Some(result) = poll_future(future.as_mut()) {
+                self.silence_task = None;
+                match result {
+                    Err(err) => {
+                        show_error(err.context(tl!("silence-failed")));
+                    }
+                    Ok(silence) => {
+                        if silence < 1e-3 {
+                            show_message(tl!("no-silence")).ok();
+                        } else {
+                            show_message(tl!("silence-found", "silence" => format!("{silence:.3}")));
+                            self.silence = Some(silence);
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
@@ -76,9 +144,9 @@ impl Scene for MainScene {
             let h = 0.1;
             let pad = 0.01;
             self.scroll.size((width, ui.top * 2. - h));
-            let dx = width / 2.;
+            let dx = width / 3.;
             let mut r = Rect::new(pad, ui.top * 2. - h + pad, dx - pad * 2., h - pad * 2.);
-            if ui.button("preview", r, "预览") {
+            if ui.button("preview", r, tl!("preview")) {
                 let info = self.edit.info.clone();
                 let config = self.config.clone();
                 let fs = self.fs.clone_box();
@@ -97,33 +165,76 @@ impl Scene for MainScene {
                 }));
             }
             r.x += dx;
-            if ui.button("render", r, "渲染") {
-                *INFO_EDIT.lock().unwrap() = Some(self.edit.clone());
-                *VIDEO_CONFIG.lock().unwrap() = Some(self.v_config.clone());
-                self.next_scene = Some(NextScene::Exit);
+            if ui.button("save", r, tl!("save")) {
+                match serde_yaml::to_string(&self.v_config)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|it| Ok(std::fs::write(&self.render_config_path, it)?))
+                {
+                    Err(err) => {
+                        show_error(err.context(tl!("save-failed")));
+                    }
+                    Ok(_) => {
+                        show_message(tl!("saved")).ok();
+                    }
+                }
+            }
+            r.x += dx;
+            if ui.button("render", r, tl!("render")) {
+                if let Some((_, err)) = self.edit.errors().into_iter().next() {
+                    show_message(err).error();
+                } else {
+                    let path = planned_output_path(&self.v_config, &self.edit.info, None);
+                    if !self.v_config.overwrite && !self.v_config.dry_run && path.exists() {
+                        let choice = Rc::clone(&self.overwrite_choice);
+                        Dialog::plain(tl!("output-exists"), tl!("output-exists-content", "path" => path.display().to_string()))
+                            .buttons(vec![tl!("output-keep-both").to_string(), tl!("output-overwrite").to_string()])
+                            .listener(move |pos| {
+                                if pos >= 0 {
+                                    choice.set(Some(pos == 1));
+                                }
+                            })
+                            .show();
+                    } else {
+                        self.start_render(false);
+                    }
+                }
             }
             self.scroll.render(ui, |ui| {
                 ui.dy(pad);
-                let r = ui.text("注：可以通过鼠标拖动屏幕来查看更下面的配置项").size(0.4).draw();
+                let r = ui.text(tl!("drag-hint")).size(0.4).draw();
                 ui.dy(r.h + pad);
+                let last_config_path = last_config_path(&self.render_config_path);
+                if last_config_path.exists() {
+                    let text = tl!("load-last");
+                    let r = ui.text(&*text).size(0.4).measure().feather(0.01);
+                    if ui.button("load_last", r, text) {
+                        match std::fs::read_to_string(&last_config_path)
+                            .map_err(anyhow::Error::from)
+                            .and_then(|it| Ok(serde_yaml::from_str(&it)?))
+                        {
+                            Err(err) => {
+                                show_error(err.context(tl!("load-last-failed")));
+                            }
+                            Ok(v_config) => {
+                                self.v_config = v_config;
+                                show_message(tl!("loaded")).ok();
+                            }
+                        }
+                    }
+                    ui.dy(r.h + pad);
+                }
                 let (w, mut h) = render_chart_info(ui, &mut self.edit, width);
                 ui.scope(|ui| {
                     ui.dy(h);
                     h += r.h + pad * 2.;
-                    let width = ui.text("一二三四").size(0.4).measure().w;
+                    let width = ui.text(tl!("label-width")).size(0.4).measure().w;
                     ui.dx(width);
                     let res = self.v_config.resolution;
                     let mut string = format!("{}x{}", res.0, res.1);
-                    let r = ui.input("分辨率", &mut string, 0.8);
-                    match || -> Result<(u32, u32)> {
-                        if let Some((w, h)) = string.split_once(['x', 'X', '×', '*']) {
-                            Ok((w.parse::<u32>()?, h.parse::<u32>()?))
-                        } else {
-                            bail!("格式应当为 “宽x高”")
-                        }
-                    }() {
+                    let r = ui.input(tl!("resolution"), &mut string, 0.8);
+                    match parse_resolution(&string) {
                         Err(_) => {
-                            show_message("输入非法");
+                            show_message(tl!("invalid-input"));
                         }
                         Ok(value) => {
                             self.v_config.resolution = value;
@@ -132,37 +243,185 @@ impl Scene for MainScene {
                     ui.dy(r.h + pad);
                     h += r.h + pad;
 
+                    let mut r = Rect::new(0., 0., 0., 0.);
+                    for (name, res) in RESOLUTION_PRESETS {
+                        let size = ui.text(name).size(0.4).measure().feather(0.01);
+                        r = Rect::new(r.x + r.w + if r.w == 0. { 0. } else { pad }, size.y, size.w, size.h);
+                        if ui.button(&format!("res_{name}"), r, name) {
+                            self.v_config.resolution = res;
+                        }
+                    }
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
                     let mut string = self.v_config.fps.to_string();
                     let old = string.clone();
                     let r = ui.input("FPS", &mut string, 0.8);
                     if string != old {
                         match string.parse::<u32>() {
-                            Err(_) => {
-                                show_message("输入非法");
-                            }
-                            Ok(value) => {
+                            Ok(value) if (1..=480).contains(&value) => {
                                 self.v_config.fps = value;
                             }
+                            _ => {
+                                show_message(tl!("invalid-input"));
+                            }
+                        }
+                    }
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let mut r = Rect::new(0., 0., 0., 0.);
+                    for fps in [30, 60, 120, 240] {
+                        let text = format!("{fps}fps");
+                        let size = ui.text(&text).size(0.4).measure().feather(0.01);
+                        r = Rect::new(r.x + r.w + if r.w == 0. { 0. } else { pad }, size.y, size.w, size.h);
+                        if ui.button(&format!("fps_{fps}"), r, &text) {
+                            self.v_config.fps = fps;
+                            if fps > 60 {
+                                show_message(tl!("high-fps-hint")).ok();
+                            }
+                        }
+                    }
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let mut constant_quality = self.v_config.crf.is_some();
+                    let r = ui.toggle(tl!("constant-quality"), &mut constant_quality);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+                    if constant_quality {
+                        // lower is better, 23 is the default of x264
+                        let mut crf = self.v_config.crf.unwrap_or(23) as f32;
+                        let r = ui.slider_with("CRF", 0.0..51.0, 1., &mut crf, None, |it| format!("{it:.0}"));
+                        self.v_config.crf = Some(crf.round() as u32);
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    } else {
+                        self.v_config.crf = None;
+                        let r = ui.input(tl!("bitrate"), &mut self.v_config.bitrate, 0.8);
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
+                    let mut string = self.v_config.preset.clone().unwrap_or_default();
+                    let r = ui.input(tl!("preset"), &mut string, 0.8);
+                    self.v_config.preset = if string.trim().is_empty() { None } else { Some(string) };
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.dropdown(tl!("format"), &mut self.v_config.format, &OutputFormat::ALL, |it| it.name().into_owned(), 0.8);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    if self.v_config.format.supports_alpha() {
+                        let r = ui.toggle(tl!("transparent"), &mut self.v_config.transparent);
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
+                    let r = ui.dropdown(tl!("codec"), &mut self.v_config.codec, &VideoCodec::ALL, |it| it.name().to_owned(), 0.8);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let codec = self.v_config.format.video_codec(self.v_config.codec);
+                    let mut encoders: Vec<Option<String>> = vec![None];
+                    encoders.extend(codec.encoders(false).into_iter().chain(codec.encoders(true)).map(|it| Some(it.to_owned())));
+                    if !encoders.contains(&self.v_config.encoder) {
+                        encoders.push(self.v_config.encoder.clone());
+                    }
+                    let r = ui.dropdown(
+                        tl!("encoder"),
+                        &mut self.v_config.encoder,
+                        &encoders,
+                        |it| it.clone().unwrap_or_else(|| tl!("auto").into_owned()),
+                        0.8,
+                    );
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let codecs: Vec<_> = std::iter::once(None).chain(AudioCodec::ALL.into_iter().map(Some)).collect();
+                    let r = ui.dropdown(
+                        tl!("audio-codec"),
+                        &mut self.v_config.audio_codec,
+                        &codecs,
+                        |it| it.map_or_else(|| tl!("default").into_owned(), |it| it.name().to_owned()),
+                        0.8,
+                    );
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let audio_codec = self.v_config.audio_codec.unwrap_or_else(|| self.v_config.format.default_audio_codec());
+                    if audio_codec.default_bitrate().is_some() {
+                        let mut string = self.v_config.audio_bitrate.clone().unwrap_or_default();
+                        let r = ui.input(tl!("audio-bitrate"), &mut string, 0.8);
+                        self.v_config.audio_bitrate = if string.trim().is_empty() { None } else { Some(string) };
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
+                    let r = ui.input(tl!("output"), &mut self.v_config.output, 0.8);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let mut string = self.v_config.output_dir.clone().unwrap_or_default();
+                    let r = ui.input(tl!("output-dir"), &mut string, 0.8);
+                    let text = tl!("choose");
+                    let b = ui.text(&*text).size(0.4).measure().feather(0.01);
+                    if ui.button("choose_output_dir", Rect::new(r.right() + pad, r.y, b.w, r.h), text) {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            string = dir.display().to_string();
                         }
                     }
+                    self.v_config.output_dir = if string.trim().is_empty() { None } else { Some(string) };
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.toggle(tl!("overwrite"), &mut self.v_config.overwrite);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let watermark = &mut self.v_config.watermark;
+                    let mut string = watermark.text.clone().unwrap_or_default();
+                    let r = ui.input(tl!("watermark-text"), &mut string, 0.8);
+                    watermark.text = if string.trim().is_empty() { None } else { Some(string) };
                     ui.dy(r.h + pad);
                     h += r.h + pad;
 
-                    let r = ui.input("码率", &mut self.v_config.bitrate, 0.8);
+                    let mut string = watermark.image.clone().unwrap_or_default();
+                    let r = ui.input(tl!("watermark-image"), &mut string, 0.8);
+                    watermark.image = if string.trim().is_empty() { None } else { Some(string) };
                     ui.dy(r.h + pad);
                     h += r.h + pad;
 
+                    if !watermark.is_empty() {
+                        let r = ui.dropdown(
+                            tl!("watermark-position"),
+                            &mut watermark.position,
+                            &WatermarkPosition::ALL,
+                            |it| it.name().into_owned(),
+                            0.8,
+                        );
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+
+                        let r = ui.slider_with(tl!("watermark-opacity"), 0.0..1.0, 0.05, &mut watermark.opacity, None, |it| {
+                            format!("{:.0}%", it * 100.)
+                        });
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
                     let mut string = format!("{:.2}", self.v_config.ending_length);
                     let old = string.clone();
-                    let r = ui.input("结算时间", &mut string, 0.8);
+                    let r = ui.input(tl!("ending-length"), &mut string, 0.8);
                     if string != old {
                         match string.parse::<f64>() {
                             Err(_) => {
-                                show_message("输入非法");
+                                show_message(tl!("invalid-input"));
                             }
                             Ok(value) => {
                                 if !value.is_finite() || value < 0. {
-                                    show_message("输入非法");
+                                    show_message(tl!("invalid-input"));
                                 }
                                 self.v_config.ending_length = value;
                             }
@@ -171,9 +430,107 @@ impl Scene for MainScene {
                     ui.dy(r.h + pad);
                     h += r.h + pad;
 
-                    let r = ui.checkbox("启用硬件加速", &mut self.v_config.hardware_accel);
+                    for (label, value) in [(tl!("start"), &mut self.v_config.start), (tl!("end"), &mut self.v_config.end)] {
+                        let mut string = value.map(|it| format!("{it:.2}")).unwrap_or_default();
+                        let old = string.clone();
+                        let r = ui.input(label, &mut string, 0.8);
+                        if string != old {
+                            if string.trim().is_empty() {
+                                *value = None;
+                            } else {
+                                match string.parse::<f64>() {
+                                    Ok(time) if time.is_finite() => {
+                                        *value = Some(time);
+                                    }
+                                    _ => {
+                                        show_message(tl!("invalid-input"));
+                                    }
+                                }
+                            }
+                        }
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
+                    for (label, value) in [
+                        (tl!("music-volume"), &mut self.v_config.music_volume),
+                        (tl!("hitsound-volume"), &mut self.v_config.hitsound_volume),
+                        (tl!("ending-volume"), &mut self.v_config.ending_volume),
+                    ] {
+                        let mut string = value.map(|it| format!("{it:.2}")).unwrap_or_default();
+                        let old = string.clone();
+                        let r = ui.input(label, &mut string, 0.8);
+                        if string != old {
+                            if string.trim().is_empty() {
+                                *value = None;
+                            } else {
+                                match string.parse::<f32>() {
+                                    Ok(volume) if volume.is_finite() && volume >= 0. => {
+                                        *value = Some(volume);
+                                    }
+                                    _ => {
+                                        show_message(tl!("invalid-input"));
+                                    }
+                                }
+                            }
+                        }
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
+
+                    let r = ui.toggle(tl!("hardware-accel"), &mut self.v_config.hardware_accel);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.toggle(tl!("live-preview"), &mut self.v_config.live_preview);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.toggle(tl!("lower-third"), &mut self.v_config.lower_third);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.toggle(tl!("subtitles"), &mut self.v_config.subtitles);
                     ui.dy(r.h + pad);
                     h += r.h + pad;
+
+                    let r = ui.toggle(tl!("save-info"), &mut self.v_config.save_info);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let text = if self.silence_task.is_some() { tl!("analyzing") } else { tl!("detect-silence") };
+                    let mut r = ui.text(&*text).size(0.4).measure().feather(0.01);
+                    if ui.button("detect_silence", r, text) && self.silence_task.is_none() {
+                        let fs = self.fs.clone_box();
+                        let edit = self.edit.clone();
+                        self.silence_task = Some(Box::pin(async move {
+                            let mut fs = PatchedFileSystem(fs, edit.to_patches().await?);
+                            let clip = load_audio(&mut fs, &edit.info.music).await?;
+                            Ok(leading_silence(&clip))
+                        }));
+                    }
+                    if let Some(silence) = self.silence {
+                        r.x += r.w + pad;
+                        let text = tl!("apply-silence", "silence" => format!("{silence:.3}"));
+                        r.w = ui.text(&text).size(0.4).measure().feather(0.01).w;
+                        if ui.button("apply_silence", r, &text) {
+                            self.edit.info.offset += silence;
+                            self.silence = None;
+                            show_message(tl!("applied")).ok();
+                        }
+                    }
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    let r = ui.toggle(tl!("audio-only"), &mut self.v_config.audio_only);
+                    ui.dy(r.h + pad);
+                    h += r.h + pad;
+
+                    if self.v_config.audio_only {
+                        let r = ui.toggle(tl!("flac"), &mut self.v_config.flac);
+                        ui.dy(r.h + pad);
+                        h += r.h + pad;
+                    }
                 });
                 (w, h)
             });
@@ -0,0 +1,118 @@
+prpr::tl_file!("segment");
+
+use crate::{open_chart, output_path, VideoConfig, CANCELLED};
+use anyhow::{bail, Context, Result};
+use macroquad::prelude::*;
+use prpr::fs;
+use std::{
+    fmt::Write as _,
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::Ordering,
+};
+
+pub fn parse_segment(s: &str) -> Result<(u32, u32)> {
+    let (index, count) = s.split_once('/').context(tl!("segment-invalid"))?;
+    let (index, count) = (index.trim().parse::<u32>()?, count.trim().parse::<u32>()?);
+    if index == 0 || index > count {
+        bail!(tl!("segment-out-of-range", "count" => count));
+    }
+    Ok((index, count))
+}
+
+/// Where the `index`-th segment of `output` is written to.
+pub fn part_path(output: &Path, index: u32) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    match output.extension() {
+        Some(ext) => output.with_file_name(format!("{stem}.part{index}.{}", ext.to_string_lossy())),
+        None => output.with_file_name(format!("{stem}.part{index}")),
+    }
+}
+
+/// Renders the chart in `count` segments with one process each, then joins them without encoding again.
+///
+/// Segments can also be rendered on other machines with `--segment`, as long as the parts are put back in place before joining.
+pub async fn render_segmented(ffmpeg: &str, chart: &Path, v_config: &VideoConfig) -> Result<()> {
+    if v_config.format.is_sequence() || v_config.audio_only {
+        bail!(tl!("video-only"));
+    }
+    if v_config.builtin_encoder {
+        bail!(tl!("ffmpeg-required"));
+    }
+    if v_config.dry_run {
+        bail!(tl!("no-estimate"));
+    }
+    let count = v_config.segments;
+    let mut fs = open_chart(chart, v_config)?;
+    let info = fs::load_info(fs.deref_mut()).await.context(tl!("info-load-failed"))?;
+    // segments resolve the same path, since nothing is written to it until they are joined
+    let output_file = output_path(v_config, &info, None)?;
+
+    let exe = std::env::current_exe().context(tl!("exe-path-failed"))?;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(index) = args.iter().position(|it| it == "--segments") {
+        args.drain(index..(index + 2).min(args.len()));
+    }
+    // one for each process as they all name their intermediate files the same, the first one holds the list of segments
+    let temp_dirs = (0..=count)
+        .map(|_| tempfile::tempdir().context(tl!("temp-dir-failed")))
+        .collect::<Result<Vec<_>>>()?;
+    info!("{}", tl!("rendering", "count" => count));
+    let children = (1..=count)
+        .map(|index| {
+            Command::new(&exe)
+                .args(&args)
+                .args(["--headless", "--segment", &format!("{index}/{count}")])
+                .arg("--temp-dir")
+                .arg(temp_dirs[index as usize].path())
+                .stdin(Stdio::null())
+                .spawn()
+                .context(tl!("spawn-failed"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut failed = 0;
+    for (index, mut child) in children.into_iter().enumerate() {
+        if !child.wait().map_or(false, |it| it.success()) {
+            warn!("{}", tl!("segment-failed", "index" => index + 1));
+            failed += 1;
+        }
+    }
+    let parts: Vec<_> = (1..=count).map(|index| part_path(&output_file, index)).collect();
+    let remove_parts = || {
+        for part in &parts {
+            let _ = std::fs::remove_file(part);
+        }
+    };
+    if CANCELLED.load(Ordering::SeqCst) {
+        remove_parts();
+        bail!(tl!("cancelled"));
+    }
+    if failed != 0 {
+        remove_parts();
+        bail!(tl!("failed", "count" => failed));
+    }
+
+    info!("{}", tl!("merging"));
+    let mut list = String::new();
+    for part in &parts {
+        writeln!(&mut list, "file '{}'", part.display().to_string().replace('\'', "'\\''"))?;
+    }
+    let list_file = temp_dirs[0].path().join("concat.txt");
+    std::fs::write(&list_file, list).context(tl!("list-write-failed"))?;
+    let status = Command::new(ffmpeg)
+        .args("-y -f concat -safe 0 -i".split_whitespace())
+        .arg(&list_file)
+        .args("-c copy -map_metadata 0".split_whitespace())
+        .arg(&output_file)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context(tl!("ffmpeg-failed"))?;
+    if !status.success() {
+        bail!(tl!("merge-failed"));
+    }
+    remove_parts();
+    info!("{}", tl!("done", "path" => output_file.display().to_string()));
+    Ok(())
+}
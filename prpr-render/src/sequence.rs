@@ -0,0 +1,71 @@
+prpr::tl_file!("sequence");
+
+use anyhow::{anyhow, Context, Result};
+use image::{ImageBuffer, Rgb, Rgba};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, SyncSender},
+    thread::JoinHandle,
+};
+
+/// Writes numbered image files into a folder, encoding on another thread so that rendering isn't blocked.
+pub struct ImageSequence {
+    sender: SyncSender<Vec<u8>>,
+    handle: JoinHandle<Result<()>>,
+}
+
+impl ImageSequence {
+    pub fn new(dir: PathBuf, dim: (u32, u32), alpha: bool, exr: bool) -> Self {
+        let (sender, receiver) = sync_channel::<Vec<u8>>(4);
+        let handle = std::thread::spawn(move || {
+            for (index, frame) in receiver.into_iter().enumerate() {
+                let path = dir.join(format!("{:06}.{}", index + 1, if exr { "exr" } else { "png" }));
+                save(&path, dim, alpha, exr, frame).with_context(|| tl!("save-failed", "path" => path.display().to_string()))?;
+            }
+            Ok(())
+        });
+        Self { sender, handle }
+    }
+
+    pub fn write(&self, frame: &[u8]) -> Result<()> {
+        // the thread only stops early on errors, which are reported by `finish`
+        self.sender.send(frame.to_vec()).map_err(|_| anyhow!(tl!("writer-exited")))
+    }
+
+    pub fn finish(self) -> Result<()> {
+        drop(self.sender);
+        self.handle.join().map_err(|_| anyhow!(tl!("writer-panicked")))?
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn save(path: &Path, (w, h): (u32, u32), alpha: bool, exr: bool, frame: Vec<u8>) -> Result<()> {
+    let channels = if alpha { 4 } else { 3 };
+    // rows read from OpenGL are bottom-up
+    let row = w as usize * channels;
+    let flipped: Vec<u8> = frame.chunks_exact(row).rev().flatten().copied().collect();
+    match (exr, alpha) {
+        (false, false) => ImageBuffer::<Rgb<u8>, _>::from_raw(w, h, flipped).unwrap().save(path)?,
+        (false, true) => ImageBuffer::<Rgba<u8>, _>::from_raw(w, h, flipped).unwrap().save(path)?,
+        (true, _) => {
+            // EXR stores linear values, as expected by compositing software
+            let data: Vec<f32> = flipped
+                .chunks_exact(channels)
+                .flat_map(|it| {
+                    let a = if alpha { it[3] as f32 / 255. } else { 1. };
+                    [srgb_to_linear(it[0]), srgb_to_linear(it[1]), srgb_to_linear(it[2]), a]
+                })
+                .collect();
+            ImageBuffer::<Rgba<f32>, _>::from_raw(w, h, data).unwrap().save(path)?;
+        }
+    }
+    Ok(())
+}
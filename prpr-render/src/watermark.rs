@@ -0,0 +1,166 @@
+prpr::tl_file!("watermark");
+
+use anyhow::{Context, Result};
+use macroquad::prelude::*;
+use prpr::ui::{TextPainter, Ui};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl WatermarkPosition {
+    pub const ALL: [WatermarkPosition; 5] = [Self::TopLeft, Self::TopRight, Self::BottomLeft, Self::BottomRight, Self::Center];
+
+    pub fn name(self) -> Cow<'static, str> {
+        match self {
+            Self::TopLeft => tl!("top-left"),
+            Self::TopRight => tl!("top-right"),
+            Self::BottomLeft => tl!("bottom-left"),
+            Self::BottomRight => tl!("bottom-right"),
+            Self::Center => tl!("center"),
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "top-left" => Self::TopLeft,
+            "top-right" => Self::TopRight,
+            "bottom-left" => Self::BottomLeft,
+            "bottom-right" => Self::BottomRight,
+            "center" => Self::Center,
+            _ => return None,
+        })
+    }
+
+    fn anchor(self) -> (f32, f32) {
+        match self {
+            Self::TopLeft => (0., 0.),
+            Self::TopRight => (1., 0.),
+            Self::BottomLeft => (0., 1.),
+            Self::BottomRight => (1., 1.),
+            Self::Center => (0.5, 0.5),
+        }
+    }
+}
+
+/// Text and / or image baked into every rendered frame, e.g. charter credits or a channel watermark.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct Watermark {
+    pub text: Option<String>,
+    pub image: Option<String>,
+    pub position: WatermarkPosition,
+    pub opacity: f32,
+    /// Width of the image relative to the video width.
+    pub image_scale: f32,
+    pub text_size: f32,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            text: None,
+            image: None,
+            position: WatermarkPosition::BottomRight,
+            opacity: 0.6,
+            image_scale: 0.12,
+            text_size: 0.5,
+        }
+    }
+}
+
+impl Watermark {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_none() && self.image.is_none()
+    }
+}
+
+pub struct WatermarkRenderer {
+    watermark: Watermark,
+    image: Option<Texture2D>,
+}
+
+impl WatermarkRenderer {
+    pub fn new(watermark: Watermark) -> Result<Self> {
+        let image = match &watermark.image {
+            Some(path) => {
+                let bytes = std::fs::read(path).with_context(|| tl!("image-read-failed", "path" => path.as_str()))?;
+                let image = image::load_from_memory(&bytes).with_context(|| tl!("image-decode-failed", "path" => path.as_str()))?;
+                Some(Texture2D::from_rgba8(image.width() as _, image.height() as _, &image.into_rgba8()))
+            }
+            None => None,
+        };
+        Ok(Self { watermark, image })
+    }
+
+    /// Draws onto `target`, which is expected to be in the orientation of the chart.
+    pub fn render(&self, painter: &mut TextPainter, target: RenderTarget, (vw, vh): (u32, u32)) {
+        let aspect = vw as f32 / vh as f32;
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -aspect),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        let mut ui = Ui::new(painter);
+        let top = 1. / aspect;
+        let margin = 0.04;
+        let gap = 0.01;
+        let color = Color::new(1., 1., 1., self.watermark.opacity);
+
+        let image_size = self.image.map(|tex| {
+            let w = self.watermark.image_scale * 2.;
+            vec2(w, w * tex.height() / tex.width())
+        });
+        let text_size = self
+            .watermark
+            .text
+            .as_ref()
+            .map(|text| ui.text(text).size(self.watermark.text_size).measure())
+            .map(|r| vec2(r.w, r.h));
+        let (w, h) = match (image_size, text_size) {
+            (Some(i), Some(t)) => (i.x.max(t.x), i.y + gap + t.y),
+            (Some(it), None) | (None, Some(it)) => (it.x, it.y),
+            (None, None) => {
+                pop_camera_state();
+                return;
+            }
+        };
+
+        let (ax, ay) = self.watermark.position.anchor();
+        let x = -1. + margin + (2. - margin * 2.) * ax - w * ax;
+        let mut y = -top + margin + (top - margin) * 2. * ay - h * ay;
+        if let (Some(tex), Some(size)) = (self.image, image_size) {
+            draw_texture_ex(
+                tex,
+                x + (w - size.x) * ax,
+                y,
+                color,
+                DrawTextureParams {
+                    dest_size: Some(size),
+                    ..Default::default()
+                },
+            );
+            y += size.y + gap;
+        }
+        if let Some(text) = &self.watermark.text {
+            ui.text(text)
+                .pos(x + w * ax, y)
+                .anchor(ax, 0.)
+                .size(self.watermark.text_size)
+                .color(color)
+                .draw();
+        }
+        unsafe { get_internal_gl() }.flush();
+        pop_camera_state();
+    }
+}
@@ -0,0 +1,89 @@
+//! Renders need ffmpeg and a display to create the OpenGL context on, so these are ignored by default.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const SAMPLE_RATE: u32 = 44100;
+
+/// Writes an empty chart with three seconds of a sine wave as its music.
+fn write_chart(dir: &Path) -> PathBuf {
+    let chart = dir.join("chart");
+    std::fs::create_dir(&chart).unwrap();
+    std::fs::write(chart.join("info.yml"), "name: Segments\nchart: chart.pec\nmusic: song.wav\nillustration: background.png\n").unwrap();
+    std::fs::write(chart.join("chart.pec"), "0\nbp 0.00 120.00\n").unwrap();
+    image::RgbImage::from_pixel(16, 9, image::Rgb([40, 40, 40]))
+        .save(chart.join("background.png"))
+        .unwrap();
+
+    let samples: Vec<i16> = (0..SAMPLE_RATE * 3)
+        .map(|i| ((i as f32 / SAMPLE_RATE as f32 * 440. * std::f32::consts::TAU).sin() * 8000.) as i16)
+        .collect();
+    let mut wav = Vec::new();
+    wav.extend(b"RIFF");
+    wav.extend((36 + samples.len() as u32 * 2).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(16u32.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(SAMPLE_RATE.to_le_bytes());
+    wav.extend((SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend(2u16.to_le_bytes());
+    wav.extend(16u16.to_le_bytes());
+    wav.extend(b"data");
+    wav.extend((samples.len() as u32 * 2).to_le_bytes());
+    for sample in samples {
+        wav.extend(sample.to_le_bytes());
+    }
+    std::fs::write(chart.join("song.wav"), wav).unwrap();
+    chart
+}
+
+fn render(dir: &Path, chart: &Path, output: &Path, extra: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_prpr-render"))
+        .arg(chart)
+        // neither the settings nor the render settings of the user are picked up
+        .arg("--config")
+        .arg(dir.join("conf.yml"))
+        .args("--headless --overwrite --resolution 320x180 --fps 20 --start 0 --end 2".split_whitespace())
+        .arg("-o")
+        .arg(output)
+        .args(extra)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(output.exists(), "nothing is rendered to {}", output.display());
+}
+
+fn probe(path: &Path, stream: &str, entry: &str) -> String {
+    let output = Command::new("ffprobe")
+        .args("-v error -count_frames -of csv=p=0".split_whitespace())
+        .args(["-select_streams", stream, "-show_entries", entry])
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap().trim().to_owned()
+}
+
+#[test]
+#[ignore = "needs ffmpeg and a display"]
+fn segments_join_into_the_whole_render() {
+    let dir = tempfile::tempdir().unwrap();
+    let chart = write_chart(dir.path());
+    let whole = dir.path().join("whole.mp4");
+    let joined = dir.path().join("joined.mp4");
+    render(dir.path(), &chart, &whole, &[]);
+    render(dir.path(), &chart, &joined, &["--segments", "2"]);
+
+    // segments are split by frames, so none is lost or repeated when joining them
+    let frames = probe(&joined, "v:0", "stream=nb_read_frames");
+    assert_eq!(frames, probe(&whole, "v:0", "stream=nb_read_frames"));
+    assert!(frames.parse::<u32>().unwrap() > 0);
+    let duration = |path: &Path| probe(path, "a:0", "stream=duration").parse::<f64>().unwrap();
+    assert!((duration(&joined) - duration(&whole)).abs() < 0.1);
+    for part in ["joined.part1.mp4", "joined.part2.mp4"] {
+        assert!(!dir.path().join(part).exists(), "{part} is left behind");
+    }
+}
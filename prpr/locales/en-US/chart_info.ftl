@@ -20,3 +20,7 @@ tags = Tags
 tag-exists = Tag already exists
 
 illegal-input = Illegal input
+error-name-empty = Name can't be empty
+warn-level-format = Levels usually look like "IN Lv.13"
+error-aspect-ratio = Aspect ratio should be between { $min } and { $max }
+error-file-missing = Cannot find { $file } in the chart
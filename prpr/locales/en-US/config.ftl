@@ -0,0 +1,3 @@
+reloaded = Config reloaded: { $keys }
+needs-restart = Takes effect after restarting: { $keys }
+reload-failed = Failed to reload config: { $error }
@@ -4,3 +4,6 @@ ok = OK
 error = Error
 error-copy = Copy error
 error-copied = Copied
+
+cancel = Cancel
+confirm = Confirm
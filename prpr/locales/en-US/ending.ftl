@@ -5,3 +5,12 @@ upload-cancel = Cancel upload
 upload-retry = Retry
 
 still-uploading = Uploading result, please wait…
+
+export = Export
+exported = Result image saved to { $path }
+export-failed = Failed to export result image
+
+leaderboard = Leaderboard
+
+early = Early
+late = Late
@@ -0,0 +1,25 @@
+autoplay = Autoplay
+adjustTime = Automatic time adjustment
+doubleClickToPause = Double click to pause
+metronome = Metronome in exercise mode
+multipleHint = Double tips
+fixAspectRatio = Fixed aspect ratio
+hitParticles = Hit effects
+squareParticles = Hit particles
+backgroundVideos = Background videos
+shaders = Shader effects
+reduceMotion = Reduce motion
+aggressive = Aggressive optimization
+offset = Offset (s)
+speed = Speed
+speedMultiplier = Note flow speed
+playerRks = Player RKS
+noteScale = Note size
+textScale = Text size
+volumeMusic = Music volume
+volumeSfx = Sound effects volume
+volumeHitsound = Hitsound volume
+volumeUi = UI sound volume
+volumeEnding = Ending theme volume
+volumeMetronome = Metronome volume
+hitsoundPanning = Hitsound panning
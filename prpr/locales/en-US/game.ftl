@@ -7,3 +7,11 @@ offset-save = Save
 ex-time-out-of-range = Time is out of range
 ex-invalid-format = Invalid format
 ex-time-set = Time changed
+
+replay-save-failed = Failed to save replay
+score-save-failed = Failed to save score
+
+offset-hint = Chart offset { $offset }ms
+offset-save-failed = Failed to save chart offset
+chart-reloaded = Chart reloaded
+chart-reload-failed = Failed to reload the chart
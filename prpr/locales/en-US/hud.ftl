@@ -0,0 +1,5 @@
+hint = Drag an element to move it, pinch to scale it
+scale = Scale
+reset = Reset
+cancel = Cancel
+save = Save
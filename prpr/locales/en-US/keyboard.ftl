@@ -0,0 +1,7 @@
+shift = Shift
+backspace = Delete
+letters = ABC
+symbols = ?123
+space = Space
+system = System
+done = Done
@@ -0,0 +1,7 @@
+title = Leaderboard
+back = Back
+loading = Loading…
+load-failed = Failed to load leaderboard
+tap-to-retry = Tap to retry
+empty = No scores yet
+offline = Offline, showing scores fetched earlier
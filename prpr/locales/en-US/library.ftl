@@ -0,0 +1 @@
+chart-added = New chart added: { $name }
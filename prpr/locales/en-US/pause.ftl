@@ -0,0 +1,7 @@
+settings = Settings
+offset = Offset (ms)
+volume-sfx = Effect volume
+double-click-pause = Double click to pause
+done = Done
+edit-hud = Edit HUD layout
+chart-offset = Chart offset
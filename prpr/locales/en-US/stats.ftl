@@ -0,0 +1,10 @@
+rks = RKS { $rks }
+back = Back
+plays = Plays
+play-time = Play time
+play-time-value = { $hours }h { $minutes }m
+notes-hit = Notes hit
+accuracy = Average accuracy
+accuracy-trend = Accuracy by day
+most-played = Most played
+no-plays = No plays yet
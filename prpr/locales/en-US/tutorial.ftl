@@ -0,0 +1,20 @@
+intro-title = Welcome
+intro = Notes fall onto the judge line, hit each of them as it reaches the line. Let's go through the four kinds of notes.
+tap-to-continue = Tap anywhere to continue
+tap-title = Tap
+tap = Tap the screen right when the note reaches the line.
+drag-title = Drag
+drag = Drag notes don't need a tap. Just keep a finger on the screen as they pass, sliding along if needed.
+hold-title = Hold
+hold = Tap when the head of the hold reaches the line, and keep holding until its end passes.
+flick-title = Flick
+flick = Swipe in any direction as the flick note passes the line. No need to tap first, the finger can be down already.
+done-title = All set
+done = That's all you need to know. Tap anywhere to start playing.
+nice = Nice!
+too-early = Too early, try again
+too-late = Too late, try again
+drag-missed = Keep a finger on the screen as it passes
+hold-released = Hold until the end of the note
+flick-missed = Swipe as the note passes the line
+skip = Skip
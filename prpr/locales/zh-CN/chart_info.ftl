@@ -20,3 +20,7 @@ tags = 标签
 tag-exists = 标签已存在
 
 illegal-input = 输入非法
+error-name-empty = 谱面名不能为空
+warn-level-format = 难度通常形如 "IN Lv.13"
+error-aspect-ratio = 宽高比应在 { $min } 到 { $max } 之间
+error-file-missing = 谱面中找不到 { $file }
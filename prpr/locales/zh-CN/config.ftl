@@ -0,0 +1,3 @@
+reloaded = 配置已重新加载：{ $keys }
+needs-restart = 重启后生效：{ $keys }
+reload-failed = 重新加载配置失败：{ $error }
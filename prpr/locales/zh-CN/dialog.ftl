@@ -4,3 +4,6 @@ ok = 确定
 error = 错误
 error-copy = 复制错误详情
 error-copied = 复制成功
+
+cancel = 取消
+confirm = 确认
@@ -5,3 +5,12 @@ upload-cancel = 取消上传
 upload-retry = 重试
 
 still-uploading = 尚在上传成绩
+
+export = 导出
+exported = 成绩图片已保存到 { $path }
+export-failed = 导出成绩图片失败
+
+leaderboard = 排行榜
+
+early = 偏早
+late = 偏晚
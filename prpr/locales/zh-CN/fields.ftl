@@ -0,0 +1,25 @@
+autoplay = 自动游玩
+adjustTime = 自动对齐时间
+doubleClickToPause = 双击暂停
+metronome = 练习模式节拍器
+multipleHint = 双押提示
+fixAspectRatio = 固定宽高比
+hitParticles = 打击特效
+squareParticles = 打击粒子
+backgroundVideos = 背景视频
+shaders = 着色器特效
+reduceMotion = 减少动态效果
+aggressive = 激进优化
+offset = 偏移(s)
+speed = 速度
+speedMultiplier = 流速倍率
+playerRks = 玩家 RKS
+noteScale = 音符大小
+textScale = 文字大小
+volumeMusic = 音乐音量
+volumeSfx = 音效音量
+volumeHitsound = 打击音效音量
+volumeUi = 界面音效音量
+volumeEnding = 结算音乐音量
+volumeMetronome = 节拍器音量
+hitsoundPanning = 打击音效声像
@@ -7,3 +7,11 @@ offset-save = 保存
 ex-time-out-of-range = 时间不在范围内
 ex-invalid-format = 格式有误
 ex-time-set = 设置成功
+
+replay-save-failed = 保存回放失败
+score-save-failed = 保存成绩失败
+
+offset-hint = 谱面延迟 { $offset }ms
+offset-save-failed = 保存谱面延迟失败
+chart-reloaded = 谱面已重新加载
+chart-reload-failed = 重新加载谱面失败
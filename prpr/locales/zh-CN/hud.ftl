@@ -0,0 +1,5 @@
+hint = 拖动元素以移动，双指缩放以调整大小
+scale = 大小
+reset = 重置
+cancel = 取消
+save = 保存
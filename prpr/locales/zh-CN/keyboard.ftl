@@ -0,0 +1,7 @@
+shift = 大写
+backspace = 删除
+letters = ABC
+symbols = ?123
+space = 空格
+system = 系统输入
+done = 完成
@@ -0,0 +1,7 @@
+title = 排行榜
+back = 返回
+loading = 加载中…
+load-failed = 加载排行榜失败
+tap-to-retry = 点击重试
+empty = 暂无成绩
+offline = 离线，显示先前获取的成绩
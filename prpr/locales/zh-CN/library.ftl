@@ -0,0 +1 @@
+chart-added = 新增谱面：{ $name }
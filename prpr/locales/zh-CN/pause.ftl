@@ -0,0 +1,7 @@
+settings = 设置
+offset = 延迟 (ms)
+volume-sfx = 音效音量
+double-click-pause = 双击暂停
+done = 完成
+edit-hud = 编辑界面布局
+chart-offset = 谱面延迟
@@ -0,0 +1,10 @@
+rks = RKS { $rks }
+back = 返回
+plays = 游玩次数
+play-time = 游玩时长
+play-time-value = { $hours } 小时 { $minutes } 分
+notes-hit = 击中音符
+accuracy = 平均准确率
+accuracy-trend = 每日准确率
+most-played = 最常游玩
+no-plays = 暂无游玩记录
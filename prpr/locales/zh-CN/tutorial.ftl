@@ -0,0 +1,20 @@
+intro-title = 欢迎
+intro = 音符会落向判定线，在它到达判定线时击打它。下面来认识四种音符。
+tap-to-continue = 点击任意位置继续
+tap-title = Tap
+tap = 在音符到达判定线的瞬间点击屏幕。
+drag-title = Drag
+drag = Drag 音符不需要点击，只要在它经过时有手指按在屏幕上即可，可以滑动跟随。
+hold-title = Hold
+hold = 在 Hold 的头部到达判定线时按下，并一直按住直到尾部经过。
+flick-title = Flick
+flick = 在 Flick 音符经过判定线时向任意方向划动。不需要先点击，手指可以提前按在屏幕上。
+done-title = 准备就绪
+done = 以上就是全部内容了。点击任意位置开始游玩。
+nice = 不错！
+too-early = 太早了，再试一次
+too-late = 太晚了，再试一次
+drag-missed = 音符经过时要有手指按在屏幕上
+hold-released = 要一直按住直到音符结束
+flick-missed = 在音符经过判定线时划动
+skip = 跳过
@@ -1,5 +1,106 @@
+crate::tl_file!("config");
+
+mod fields;
+pub use fields::{ConfigField, FieldCategory, FieldKind, FIELDS};
+
+use crate::{
+    ext::parse_hex_color,
+    fs::FileSystem,
+    info::{ChartInfo, DifficultyTier},
+};
+use anyhow::{bail, Context, Result};
+use macroquad::prelude::Color;
+use miniquad::{info, warn};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashMap, path::Path};
+
+/// Keys of the fields that can be changed while playing, see [`Config::apply_live`].
+pub const LIVE_KEYS: &[&str] = &[
+    "backgroundDim",
+    "backgroundVideos",
+    "hitParticles",
+    "speed",
+    "speedMultiplier",
+    "squareParticles",
+    "volumeEnding",
+    "volumeHitsound",
+    "volumeMetronome",
+    "volumeMusic",
+    "volumeSfx",
+    "volumeUi",
+];
+
+/// The version of config files written by this build, see [`migrate`].
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Upgrades a config of one version to the next, the first one upgrading files without a version.
+const MIGRATIONS: &[fn(&mut Mapping)] = &[
+    // `particle` split into a toggle for each effect, and `disableEffect` turned into `shaders`
+    |config| {
+        if let Some(particle) = config.remove("particle") {
+            for key in ["hitParticles", "squareParticles"] {
+                config.entry(key.into()).or_insert_with(|| particle.clone());
+            }
+        }
+        if let Some(disable) = config.remove("disableEffect") {
+            let shaders = disable.as_bool().map_or(disable, |it| (!it).into());
+            config.entry("shaders".into()).or_insert(shaders);
+        }
+    },
+    // `backgroundAnimation` renamed to `backgroundVideos`, as videos are all it turns off
+    |config| {
+        if let Some(videos) = config.remove("backgroundAnimation") {
+            config.entry("backgroundVideos".into()).or_insert(videos);
+        }
+    },
+];
+
+/// The prefix of environment variables overriding config fields, see [`Config::env_overrides`].
+pub const ENV_PREFIX: &str = "PRPR_";
+
+/// The name of the file overriding the global config for a chart, put inside the chart or next to it.
+pub const CHART_CONFIG_FILE: &str = "prpr.yml";
+
+/// The only fields a [`CHART_CONFIG_FILE`] may set. Charts come from anywhere, so they can't touch servers, keys or paths.
+pub const CHART_CONFIG_KEYS: &[&str] = &["aspectRatio", "backgroundDim", "offset", "speed"];
+
+/// Formats config files can be written in, told apart by their extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// By the extension of `path`, YAML unless it's `.json` or `.toml`.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|it| it.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("json") => Self::Json,
+            Some("toml") => Self::Toml,
+            _ => Self::Yaml,
+        }
+    }
+
+    /// Parses `text` into the YAML value configs are handled as.
+    pub fn parse(self, text: &str) -> Result<Value> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_str(text)?,
+            Self::Json => serde_json::from_str(text)?,
+            Self::Toml => toml::from_str(text)?,
+        })
+    }
+
+    pub fn to_string(self, value: &Value) -> Result<String> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::to_string(value)?,
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Toml => toml::to_string_pretty(value)?,
+        })
+    }
+}
 
 pub static TIPS: Lazy<Vec<String>> = Lazy::new(|| include_str!("tips.txt").split('\n').map(str::to_owned).collect());
 
@@ -14,6 +115,87 @@ pub enum ChallengeModeColor {
     Rainbow,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RateMod {
+    Daycore,
+    Nightcore,
+    NightcorePlus,
+}
+
+impl RateMod {
+    pub const ALL: [RateMod; 3] = [Self::Daycore, Self::Nightcore, Self::NightcorePlus];
+
+    pub fn rate(&self) -> f32 {
+        match self {
+            Self::Daycore => 0.9,
+            Self::Nightcore => 1.1,
+            Self::NightcorePlus => 1.2,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Daycore => "DC",
+            Self::Nightcore => "NC",
+            Self::NightcorePlus => "NC+",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SceneTransition {
+    None,
+    Fade,
+    Slide,
+    CircleWipe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VisualizerStyle {
+    Bars,
+    Ring,
+}
+
+/// Overrides of how a difficulty tier is shown.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct TierStyle {
+    /// Like `#51af44`.
+    pub color: Option<String>,
+    pub label: Option<String>,
+}
+
+/// How a HUD element is moved from its default place and scaled about it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct HudElement {
+    pub x: f32,
+    pub y: f32,
+    pub scale: f32,
+}
+
+impl Default for HudElement {
+    fn default() -> Self {
+        Self { x: 0., y: 0., scale: 1. }
+    }
+}
+
+/// Where the in-game HUD elements are placed, edited from the pause menu.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct HudLayout {
+    pub combo: HudElement,
+    pub pause: HudElement,
+    pub progress: HudElement,
+    pub score: HudElement,
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
@@ -23,25 +205,68 @@ pub struct Config {
     pub aspect_ratio: Option<f32>,
     pub audio_buffer_size: Option<u32>,
     pub autoplay: bool,
+    /// Overrides the background dim of charts.
+    pub background_dim: Option<f32>,
+    /// Plays the videos of charts behind them.
+    pub background_videos: bool,
     pub challenge_color: ChallengeModeColor,
     pub challenge_rank: u32,
+    pub chart_repository: Option<String>,
     pub debug: bool,
-    pub disable_effect: bool,
     pub double_click_to_pause: bool,
+    pub export_dir: Option<String>,
     pub fix_aspect_ratio: bool,
     pub fxaa: bool,
+    /// The animated effect where notes are hit.
+    pub hit_particles: bool,
+    pub hitsound_panning: f32,
+    pub hud_layout: HudLayout,
     pub interactive: bool,
+    pub language: Option<String>,
+    /// Folder fetched leaderboards are cached in and results not submitted yet are kept in.
+    pub leaderboard_cache: Option<String>,
+    /// The secret results submitted to the leaderboard are signed with.
+    pub leaderboard_key: Option<String>,
+    pub leaderboard_server: Option<String>,
+    pub metronome: bool,
     pub multiple_hint: bool,
     pub note_scale: f32,
     pub offset: f32,
-    pub particle: bool,
     pub player_name: String,
     pub player_rks: f32,
+    /// The profile applied over the rest of the config, see [`Config::resolve`].
+    pub profile: Option<String>,
+    /// Named sets of fields, each written like a config file with only the fields it changes.
+    pub profiles: HashMap<String, Value>,
+    pub rate_mod: Option<RateMod>,
+    pub record_replay: Option<String>,
+    /// Turns off effects, particles and animations that may bother motion-sensitive players.
+    pub reduce_motion: bool,
+    pub replay: Option<String>,
     pub sample_count: u32,
     pub res_pack_path: Option<String>,
+    pub scores_path: Option<String>,
+    /// The shader effects of charts.
+    pub shaders: bool,
     pub speed: f32,
+    /// Scales how fast notes approach the line, on top of the speeds of the chart.
+    pub speed_multiplier: f32,
+    /// The squares scattered where notes are hit.
+    pub square_particles: bool,
+    /// Multiplies the size of all UI text.
+    pub text_scale: f32,
+    pub tier_styles: HashMap<DifficultyTier, TierStyle>,
+    pub transition: SceneTransition,
+    pub transparent_background: bool,
+    /// The version of the file, see [`CONFIG_VERSION`].
+    pub version: u32,
+    pub visualizer: Option<VisualizerStyle>,
+    pub volume_ending: f32,
+    pub volume_hitsound: f32,
+    pub volume_metronome: f32,
     pub volume_music: f32,
     pub volume_sfx: f32,
+    pub volume_ui: f32,
 }
 
 impl Default for Config {
@@ -52,25 +277,657 @@ impl Default for Config {
             aspect_ratio: None,
             audio_buffer_size: None,
             autoplay: false,
+            background_dim: None,
+            background_videos: true,
             challenge_color: ChallengeModeColor::Golden,
             challenge_rank: 45,
+            chart_repository: None,
             debug: false,
-            disable_effect: false,
             double_click_to_pause: true,
+            export_dir: None,
             fix_aspect_ratio: false,
             fxaa: false,
+            hit_particles: true,
+            hitsound_panning: 0.,
+            hud_layout: HudLayout::default(),
             interactive: true,
+            language: None,
+            leaderboard_cache: None,
+            leaderboard_key: None,
+            leaderboard_server: None,
+            metronome: false,
             multiple_hint: true,
             note_scale: 1.0,
             offset: 0.,
             res_pack_path: None,
-            particle: true,
             player_name: "Mivik".to_string(),
             player_rks: 15.,
+            profile: None,
+            profiles: HashMap::new(),
+            rate_mod: None,
+            record_replay: None,
+            reduce_motion: false,
+            replay: None,
             sample_count: 4,
+            scores_path: None,
+            shaders: true,
             speed: 1.,
+            speed_multiplier: 1.,
+            square_particles: true,
+            text_scale: 1.,
+            tier_styles: HashMap::new(),
+            transition: SceneTransition::Fade,
+            transparent_background: false,
+            version: CONFIG_VERSION,
+            visualizer: None,
+            volume_ending: 1.,
+            volume_hitsound: 1.,
+            volume_metronome: 1.,
             volume_music: 1.,
             volume_sfx: 1.,
+            volume_ui: 1.,
+        }
+    }
+}
+
+impl Config {
+    pub fn hitsound_volume(&self) -> f32 {
+        self.volume_sfx * self.volume_hitsound
+    }
+
+    pub fn ui_volume(&self) -> f32 {
+        self.volume_sfx * self.volume_ui
+    }
+
+    pub fn ending_volume(&self) -> f32 {
+        self.volume_music * self.volume_ending
+    }
+
+    /// Copies the fields in [`LIVE_KEYS`] from `other`.
+    pub fn apply_live(&mut self, other: &Config) {
+        self.background_dim = other.background_dim;
+        self.background_videos = other.background_videos;
+        self.hit_particles = other.hit_particles;
+        self.speed = other.speed;
+        self.speed_multiplier = other.speed_multiplier;
+        self.square_particles = other.square_particles;
+        self.volume_ending = other.volume_ending;
+        self.volume_hitsound = other.volume_hitsound;
+        self.volume_metronome = other.volume_metronome;
+        self.volume_music = other.volume_music;
+        self.volume_sfx = other.volume_sfx;
+        self.volume_ui = other.volume_ui;
+    }
+
+    /// The keys of the fields that differ in `other`, named as in config files.
+    pub fn changed_keys(&self, other: &Config) -> Vec<String> {
+        let (Ok(Value::Mapping(this)), Ok(Value::Mapping(other))) = (serde_yaml::to_value(self), serde_yaml::to_value(other)) else {
+            return Vec::new();
+        };
+        this.iter()
+            .filter(|(key, value)| other.get(key) != Some(value))
+            .filter_map(|(key, _)| key.as_str().map(str::to_owned))
+            .collect()
+    }
+
+    /// Parses a config file, upgrading it from older versions.
+    /// Values of the wrong type and values that make no sense are reported by their keys, while unknown keys are warned about and ignored.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        Self::default().merged(yaml)
+    }
+
+    /// Reads the config file at `path`, in the format told by its extension. Checked like [`Self::from_yaml`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).with_context(|| format!("Cannot read from {}", path.display()))?;
+        Self::default()
+            .merged_as(&text, ConfigFormat::of(path))
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// Merges `yaml`, written like a config file with only some of the fields, over this config. Checked like [`Self::from_yaml`].
+    pub fn merged(&self, yaml: &str) -> Result<Self> {
+        self.merged_as(yaml, ConfigFormat::Yaml)
+    }
+
+    fn merged_as(&self, text: &str, format: ConfigFormat) -> Result<Self> {
+        let mut value = format.parse(text)?;
+        migrate(&mut value)?;
+        self.merged_value(value)
+    }
+
+    fn merged_value(&self, other: Value) -> Result<Self> {
+        if other.is_null() {
+            return Ok(self.clone());
+        }
+        let base = serde_yaml::to_value(self)?;
+        check_keys(&base, &other);
+        let mut value = base.clone();
+        merge(&mut value, other.clone());
+        let config: Self = match serde_yaml::from_value(value) {
+            Ok(config) => config,
+            Err(err) => {
+                // the error doesn't tell which field it's about, so they're tried one by one
+                if let Value::Mapping(fields) = other {
+                    for (key, field) in fields {
+                        let mut value = base.clone();
+                        merge(&mut value, Value::Mapping([(key.clone(), field)].into_iter().collect()));
+                        if let Err(err) = serde_yaml::from_value::<Self>(value) {
+                            bail!("Invalid value for `{}`: {err}", key.as_str().unwrap_or_default());
+                        }
+                    }
+                }
+                return Err(err.into());
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that the values make sense, such as speeds being positive. The ranges of the sliders in the settings only limit
+    /// what can be picked there, and aren't enforced here.
+    pub fn validate(&self) -> Result<()> {
+        let positive: fn(f32) -> bool = |it| it > 0.;
+        let non_negative: fn(f32) -> bool = |it| it >= 0.;
+        let fraction: fn(f32) -> bool = |it| (0.0..=1.).contains(&it);
+        let any: fn(f32) -> bool = |_| true;
+        let checks = [
+            ("aspectRatio", self.aspect_ratio, positive, "positive"),
+            ("backgroundDim", self.background_dim, fraction, "between 0 and 1"),
+            ("hitsoundPanning", Some(self.hitsound_panning), fraction, "between 0 and 1"),
+            ("noteScale", Some(self.note_scale), positive, "positive"),
+            ("offset", Some(self.offset), any, "a number"),
+            ("playerRks", Some(self.player_rks), non_negative, "non-negative"),
+            ("speed", Some(self.speed), positive, "positive"),
+            ("speedMultiplier", Some(self.speed_multiplier), positive, "positive"),
+            ("textScale", Some(self.text_scale), positive, "positive"),
+            ("volumeEnding", Some(self.volume_ending), non_negative, "non-negative"),
+            ("volumeHitsound", Some(self.volume_hitsound), non_negative, "non-negative"),
+            ("volumeMetronome", Some(self.volume_metronome), non_negative, "non-negative"),
+            ("volumeMusic", Some(self.volume_music), non_negative, "non-negative"),
+            ("volumeSfx", Some(self.volume_sfx), non_negative, "non-negative"),
+            ("volumeUi", Some(self.volume_ui), non_negative, "non-negative"),
+        ];
+        for (key, value, valid, expected) in checks {
+            if let Some(value) = value.filter(|it| !it.is_finite() || !valid(*it)) {
+                bail!("`{key}` should be {expected}, got {value}");
+            }
+        }
+        if ![1, 2, 4, 8, 16].contains(&self.sample_count) {
+            bail!("`sampleCount` should be one of 1, 2, 4, 8 and 16, got {}", self.sample_count);
+        }
+        Ok(())
+    }
+
+    /// Merges the [`CHART_CONFIG_FILE`] next to the chart at `path`, and then the one inside the chart, over this config.
+    pub async fn for_chart(&self, fs: &mut dyn FileSystem, path: Option<&Path>) -> Result<Self> {
+        let mut config = self.clone();
+        if let Some(path) = path.map(|it| it.with_file_name(CHART_CONFIG_FILE)).filter(|it| it.is_file()) {
+            let yaml = std::fs::read_to_string(&path).with_context(|| format!("Cannot read from {}", path.display()))?;
+            config = config.merged_for_chart(&yaml).with_context(|| format!("Invalid {}", path.display()))?;
+        }
+        if fs.exists(CHART_CONFIG_FILE).await.unwrap_or_default() {
+            let yaml = String::from_utf8(fs.load_file(CHART_CONFIG_FILE).await?).with_context(|| format!("Invalid {CHART_CONFIG_FILE}"))?;
+            config = config
+                .merged_for_chart(&yaml)
+                .with_context(|| format!("Invalid {CHART_CONFIG_FILE} in the chart"))?;
+        }
+        Ok(config)
+    }
+
+    fn merged_for_chart(&self, yaml: &str) -> Result<Self> {
+        let mut value = serde_yaml::from_str(yaml)?;
+        migrate(&mut value)?;
+        if let Value::Mapping(fields) = &mut value {
+            fields.remove("version");
+            if let Some(key) = fields.keys().find(|it| !it.as_str().map_or(false, |it| CHART_CONFIG_KEYS.contains(&it))) {
+                bail!("`{}` can't be set for a chart, only {}", key.as_str().unwrap_or_default(), CHART_CONFIG_KEYS.join(", "));
+            }
+        }
+        self.merged_value(value)
+    }
+
+    /// Applies `overrides` and then the active profile, keeping the overrides over the profile. See [`Self::with_overrides`].
+    pub fn resolve(&self, overrides: &[String]) -> Result<Self> {
+        let mut config = self.with_overrides(overrides)?;
+        if let Some(name) = config.profile.clone() {
+            let Some(profile) = config.profiles.get(&name).cloned() else {
+                bail!("Unknown profile `{name}`, the profiles are: {}", config.profile_names().join(", "));
+            };
+            config = config
+                .merged_value(profile)
+                .with_context(|| format!("Invalid profile `{name}`"))?
+                .with_overrides(overrides)?;
+        }
+        Ok(config)
+    }
+
+    /// Resolves this config like [`Self::resolve`], but switched to the profile `name`, or to none of them.
+    pub fn with_profile(&self, name: Option<&str>, overrides: &[String]) -> Result<Self> {
+        self.resolve(&profile_overrides(overrides, name))
+    }
+
+    /// The names of the profiles, sorted.
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.profiles.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Overrides from environment variables like `PRPR_VOLUME_MUSIC=0.5`, in the form taken by [`Self::with_overrides`].
+    /// Nested keys are separated by double underscores, and variables not naming a field are left out.
+    pub fn env_overrides() -> Vec<String> {
+        let Ok(Value::Mapping(known)) = serde_yaml::to_value(Self::default()) else {
+            return Vec::new();
+        };
+        let mut overrides: Vec<_> = std::env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(ENV_PREFIX)?.split("__").map(env_key).collect::<Vec<_>>();
+                known.contains_key(key[0].as_str()).then(|| format!("{}={value}", key.join(".")))
+            })
+            .collect();
+        overrides.sort();
+        overrides
+    }
+
+    /// Applies overrides like `volumeMusic=0.5` or `hudLayout.combo.scale=1.2` given on the command line.
+    ///
+    /// Keys are named as in config files, with nested ones separated by dots, and values are parsed as YAML.
+    pub fn with_overrides(&self, overrides: &[String]) -> Result<Self> {
+        let base = serde_yaml::to_value(self)?;
+        let mut value = base.clone();
+        for item in overrides {
+            let (key, raw) = item
+                .split_once('=')
+                .with_context(|| format!("Invalid override, expected key=value: {item}"))?;
+            let mut target = &mut value;
+            for part in key.split('.') {
+                if target.is_null() {
+                    *target = Value::Mapping(Default::default());
+                }
+                let map = target
+                    .as_mapping_mut()
+                    .with_context(|| format!("Cannot set {key}, {part} is not inside a map"))?;
+                target = map.entry(part.into()).or_insert(Value::Null);
+            }
+            *target = serde_yaml::from_str(raw).with_context(|| format!("Invalid value for {key}: {raw}"))?;
+        }
+        check_keys(&base, &value);
+        let config: Self = serde_yaml::from_value(value).context("Invalid config overrides")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The label and the color of the badge showing the tier of the chart, or `None` if the tier is unknown.
+    pub fn tier_badge(&self, info: &ChartInfo) -> Option<(String, Color)> {
+        let tier = info.tier()?;
+        let style = self.tier_styles.get(&tier);
+        let label = style.and_then(|it| it.label.clone()).unwrap_or_else(|| tier.label().to_owned());
+        let color = style.and_then(|it| it.color.as_deref()).and_then(|it| {
+            let color = parse_hex_color(it);
+            if color.is_none() {
+                warn!("Invalid color of tier {}: {}", tier.label(), it);
+            }
+            color
+        });
+        Some((label, color.unwrap_or_else(|| tier.default_color())))
+    }
+}
+
+/// Upgrades `value`, a config file or part of one, to [`CONFIG_VERSION`], profiles included.
+/// Files without a version are taken as the oldest ones.
+pub fn migrate(value: &mut Value) -> Result<()> {
+    let Value::Mapping(config) = value else {
+        return Ok(());
+    };
+    let version = match config.get("version") {
+        Some(version) => version.as_u64().context("Invalid value for `version`")? as u32,
+        None => 0,
+    };
+    if version > CONFIG_VERSION {
+        warn!("The config is of version {version}, newer than this build supports ({CONFIG_VERSION})");
+        return Ok(());
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config);
+        if let Some(Value::Mapping(profiles)) = config.get_mut("profiles") {
+            for profile in profiles.values_mut() {
+                if let Value::Mapping(profile) = profile {
+                    migration(profile);
+                }
+            }
+        }
+    }
+    if version < CONFIG_VERSION {
+        info!("Upgraded the config from version {version} to {CONFIG_VERSION}");
+        config.insert("version".into(), CONFIG_VERSION.into());
+    }
+    Ok(())
+}
+
+/// Deserializes a config kept by an older version inside other data, upgrading it with [`migrate`].
+pub fn deserialize_migrated<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Config, D::Error> {
+    use serde::de::Error;
+    let mut value = Value::deserialize(deserializer)?;
+    migrate(&mut value).map_err(D::Error::custom)?;
+    serde_yaml::from_value(value).map_err(D::Error::custom)
+}
+
+/// `VOLUME_MUSIC` to `volumeMusic`.
+fn env_key(name: &str) -> String {
+    let mut words = name.split('_').map(str::to_lowercase);
+    let first = words.next().unwrap_or_default();
+    words.fold(first, |key, word| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(c) => key + &c.to_uppercase().collect::<String>() + chars.as_str(),
+            None => key,
+        }
+    })
+}
+
+fn merge(base: &mut Value, other: Value) {
+    match (base, other) {
+        (Value::Mapping(base), Value::Mapping(other)) => {
+            for (key, value) in other {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other,
+    }
+}
+
+/// The edit distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut last = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(last + (ca != *cb) as usize);
+            last = std::mem::replace(&mut row[j + 1], next);
         }
     }
+    row[b.len()]
+}
+
+/// Warns about the keys in `given` that aren't in `known`, the serialized config, suggesting the closest one for a typo.
+/// They're left to be ignored when deserializing, so that files written by newer versions still load.
+fn check_keys(known: &Value, given: &Value) {
+    for warning in unknown_keys(known, given, "") {
+        warn!("{warning}");
+    }
+}
+
+/// The warnings of [`check_keys`], for the keys nested in `prefix`.
+fn unknown_keys(known: &Value, given: &Value, prefix: &str) -> Vec<String> {
+    let (Value::Mapping(known), Value::Mapping(given)) = (known, given) else {
+        return Vec::new();
+    };
+    // maps taking any keys, like `tierStyles`
+    if known.is_empty() {
+        return Vec::new();
+    }
+    let mut warnings = Vec::new();
+    for (key, value) in given {
+        let name = key.as_str().map_or_else(|| format!("{key:?}"), str::to_owned);
+        if let Some(known) = known.get(key) {
+            warnings.extend(unknown_keys(known, value, &format!("{prefix}{name}.")));
+            continue;
+        }
+        let closest = known
+            .keys()
+            .filter_map(Value::as_str)
+            .map(|it| (distance(&name.to_lowercase(), &it.to_lowercase()), it))
+            .min()
+            .filter(|(distance, _)| *distance <= 2.max(name.len() / 3));
+        warnings.push(match closest {
+            Some((_, it)) => format!("Ignored unknown config key `{prefix}{name}`, did you mean `{prefix}{it}`?"),
+            None => format!("Ignored unknown config key `{prefix}{name}`"),
+        });
+    }
+    warnings
+}
+
+/// `overrides` with the one picking the profile replaced by one picking `name`, or none of them.
+fn profile_overrides(overrides: &[String], name: Option<&str>) -> Vec<String> {
+    let mut overrides: Vec<_> = overrides.iter().filter(|it| !it.starts_with("profile=")).cloned().collect();
+    overrides.push(format!("profile={}", serde_json::to_string(&name).unwrap()));
+    overrides
+}
+
+/// Reads the config file again whenever it's modified, announcing the changes on the billboard.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ConfigWatcher {
+    path: std::path::PathBuf,
+    /// Applied over the file each time it's read, see [`Config::resolve`].
+    overrides: Vec<String>,
+    config: Config,
+    modified: Option<std::time::SystemTime>,
+    last_check: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ConfigWatcher {
+    const INTERVAL: f64 = 1.;
+
+    pub fn new(path: impl Into<std::path::PathBuf>, overrides: Vec<String>, config: Config) -> Self {
+        let mut res = Self {
+            path: path.into(),
+            overrides,
+            config,
+            modified: None,
+            last_check: f64::NEG_INFINITY,
+        };
+        res.modified = res.modified_time();
+        res
+    }
+
+    fn modified_time(&self) -> Option<std::time::SystemTime> {
+        std::fs::metadata(&self.path).and_then(|it| it.modified()).ok()
+    }
+
+    fn read(&self) -> Result<Config> {
+        Config::load(&self.path)?.resolve(&self.overrides)
+    }
+
+    /// Should be called every frame. Returns the config read if the file was modified since the last call.
+    pub fn update(&mut self) -> Option<Config> {
+        let now = macroquad::time::get_time();
+        if now - self.last_check < Self::INTERVAL {
+            return None;
+        }
+        self.last_check = now;
+        let modified = self.modified_time();
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let config = match self.read() {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("Failed to reload config: {:?}", err);
+                crate::scene::show_message(tl!("reload-failed", "error" => format!("{err:#}"))).error();
+                return None;
+            }
+        };
+        let changed = self.config.changed_keys(&config);
+        if changed.is_empty() {
+            return None;
+        }
+        let (live, rest): (Vec<_>, Vec<_>) = changed
+            .into_iter()
+            .filter(|it| it != "profile" && it != "profiles")
+            .partition(|it| LIVE_KEYS.contains(&it.as_str()));
+        if !live.is_empty() {
+            crate::scene::show_message(tl!("reloaded", "keys" => live.join(", "))).ok();
+        }
+        if !rest.is_empty() {
+            crate::scene::show_message(tl!("needs-restart", "keys" => rest.join(", "))).warn();
+        }
+        self.config = config.clone();
+        Some(config)
+    }
+
+    /// Takes in changes written to the file by the app itself, so that they aren't announced.
+    pub fn saved(&mut self) {
+        self.modified = self.modified_time();
+        match self.read() {
+            Ok(config) => self.config = config,
+            Err(err) => warn!("Failed to reload config: {:?}", err),
+        }
+    }
+
+    /// Switches to the profile `name` for the reloads to come, `config` being the config resolved with it.
+    pub fn set_profile(&mut self, name: Option<&str>, config: Config) {
+        self.overrides = profile_overrides(&self.overrides, name);
+        self.config = config;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated(yaml: &str) -> Value {
+        let mut value = serde_yaml::from_str(yaml).unwrap();
+        migrate(&mut value).unwrap();
+        value
+    }
+
+    #[test]
+    fn migrate_stamps_version() {
+        let value = migrated("speed: 1.5");
+        assert_eq!(value["version"], Value::from(CONFIG_VERSION));
+        assert_eq!(value["speed"], Value::from(1.5));
+    }
+
+    #[test]
+    fn migrate_skips_current_and_newer() {
+        let yaml = format!("version: {CONFIG_VERSION}\nparticle: false");
+        assert_eq!(migrated(&yaml), serde_yaml::from_str::<Value>(&yaml).unwrap());
+        let value = migrated("version: 99\nparticle: false");
+        assert_eq!(value["version"], Value::from(99));
+        assert_eq!(value["particle"], Value::from(false));
+    }
+
+    #[test]
+    fn migrate_rejects_invalid_version() {
+        let mut value = serde_yaml::from_str("version: latest").unwrap();
+        assert!(migrate(&mut value).is_err());
+    }
+
+    #[test]
+    fn migrate_splits_particle() {
+        let value = migrated("particle: false");
+        assert!(value.get("particle").is_none());
+        assert_eq!(value["hitParticles"], Value::from(false));
+        assert_eq!(value["squareParticles"], Value::from(false));
+        // keys already written the new way win
+        let value = migrated("particle: false\nsquareParticles: true");
+        assert_eq!(value["hitParticles"], Value::from(false));
+        assert_eq!(value["squareParticles"], Value::from(true));
+    }
+
+    #[test]
+    fn migrate_negates_disable_effect() {
+        let value = migrated("disableEffect: true");
+        assert!(value.get("disableEffect").is_none());
+        assert_eq!(value["shaders"], Value::from(false));
+        assert_eq!(migrated("disableEffect: false")["shaders"], Value::from(true));
+    }
+
+    #[test]
+    fn migrate_renames_background_animation() {
+        let value = migrated("backgroundAnimation: false");
+        assert!(value.get("backgroundAnimation").is_none());
+        assert_eq!(value["backgroundVideos"], Value::from(false));
+        // keys already written the new way win
+        let value = migrated("version: 1\nbackgroundAnimation: false\nbackgroundVideos: true");
+        assert_eq!(value["backgroundVideos"], Value::from(true));
+    }
+
+    #[test]
+    fn migrate_profiles() {
+        let value = migrated("profiles:\n  quiet:\n    particle: false\n    disableEffect: true");
+        let profile = &value["profiles"]["quiet"];
+        assert_eq!(profile["hitParticles"], Value::from(false));
+        assert_eq!(profile["squareParticles"], Value::from(false));
+        assert_eq!(profile["shaders"], Value::from(false));
+    }
+
+    #[test]
+    fn load_unversioned_file() {
+        let config = Config::from_yaml("particle: false\ndisableEffect: true").unwrap();
+        assert!(!config.hit_particles && !config.square_particles && !config.shaders);
+        assert_eq!(config.version, CONFIG_VERSION);
+    }
+    #[test]
+    fn env_key_to_camel_case() {
+        assert_eq!(env_key("VOLUME_MUSIC"), "volumeMusic");
+        assert_eq!(env_key("SPEED"), "speed");
+        assert_eq!(env_key("PLAYER_RKS"), "playerRks");
+        assert_eq!(env_key("VOLUME__MUSIC_"), "volumeMusic");
+        assert_eq!(env_key(""), "");
+    }
+
+    #[test]
+    fn unknown_keys_suggested() {
+        let known = serde_yaml::from_str("volumeMusic: 1\nspeed: 1\nhudLayout:\n  combo:\n    scale: 1\ntierStyles: {}").unwrap();
+        let given = serde_yaml::from_str(
+            "speed: 2\nvolumeMusik: 0.5\nVOLUMEMUSIC: 0.5\nxyz: 1\nhudLayout:\n  combo:\n    scael: 2\n  score: {}\ntierStyles:\n  EZ: {}",
+        )
+        .unwrap();
+        assert_eq!(
+            unknown_keys(&known, &given, ""),
+            [
+                "Ignored unknown config key `volumeMusik`, did you mean `volumeMusic`?",
+                "Ignored unknown config key `VOLUMEMUSIC`, did you mean `volumeMusic`?",
+                "Ignored unknown config key `xyz`",
+                "Ignored unknown config key `hudLayout.combo.scael`, did you mean `hudLayout.combo.scale`?",
+                "Ignored unknown config key `hudLayout.score`",
+            ]
+        );
+        let config = serde_yaml::to_value(Config::default()).unwrap();
+        assert!(unknown_keys(&config, &config, "").is_empty());
+    }
+
+    #[test]
+    fn config_format_of_extension() {
+        assert_eq!(ConfigFormat::of(Path::new("conf.yml")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::of(Path::new("conf.yaml")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::of(Path::new("conf")), ConfigFormat::Yaml);
+        assert_eq!(ConfigFormat::of(Path::new("conf.JSON")), ConfigFormat::Json);
+        assert_eq!(ConfigFormat::of(Path::new("prpr/conf.toml")), ConfigFormat::Toml);
+    }
+
+    #[test]
+    fn config_format_round_trip() {
+        let value: Value =
+            serde_yaml::from_str("speed: 1.5\nautoplay: true\nlanguage: zh-CN\nchallengeRank: 45\nhudLayout:\n  combo:\n    scale: 1.2").unwrap();
+        for format in [ConfigFormat::Yaml, ConfigFormat::Json, ConfigFormat::Toml] {
+            let text = format.to_string(&value).unwrap();
+            assert_eq!(format.parse(&text).unwrap(), value, "{format:?}: {text}");
+        }
+        let json = r#"{"speed": 1.5, "autoplay": true, "language": "zh-CN", "challengeRank": 45, "hudLayout": {"combo": {"scale": 1.2}}}"#;
+        assert_eq!(ConfigFormat::Json.parse(json).unwrap(), value);
+        let toml = "speed = 1.5\nautoplay = true\nlanguage = 'zh-CN'\nchallengeRank = 45\n[hudLayout.combo]\nscale = 1.2\n";
+        assert_eq!(ConfigFormat::Toml.parse(toml).unwrap(), value);
+    }
+
+    #[test]
+    fn load_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("conf.toml");
+        std::fs::write(&path, "speed = 1.5\nautoplay = true\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.speed, 1.5);
+        assert!(config.autoplay);
+    }
 }
@@ -0,0 +1,89 @@
+crate::tl_file!("fields");
+
+use super::Config;
+use std::{borrow::Cow, ops::Range};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FieldCategory {
+    Gameplay,
+    Display,
+    Audio,
+}
+
+/// How a field is edited, with access to it in a config.
+pub enum FieldKind {
+    Toggle(fn(&mut Config) -> &mut bool),
+    Slider {
+        value: fn(&mut Config) -> &mut f32,
+        /// Both what the slider covers and what [`Config::validate`] accepts, ends included.
+        range: Range<f32>,
+        step: f32,
+    },
+}
+
+/// A field of the config shown in settings.
+pub struct ConfigField {
+    /// Named as in config files, which is also the key of its label.
+    pub key: &'static str,
+    pub category: FieldCategory,
+    pub kind: FieldKind,
+}
+
+impl ConfigField {
+    pub fn label(&self) -> Cow<'static, str> {
+        tl!(self.key)
+    }
+}
+
+macro_rules! toggle {
+    ($key:literal, $category:ident, $field:ident) => {
+        ConfigField {
+            key: $key,
+            category: FieldCategory::$category,
+            kind: FieldKind::Toggle(|it| &mut it.$field),
+        }
+    };
+}
+
+macro_rules! slider {
+    ($key:literal, $category:ident, $field:ident, $range:expr, $step:expr) => {
+        ConfigField {
+            key: $key,
+            category: FieldCategory::$category,
+            kind: FieldKind::Slider {
+                value: |it| &mut it.$field,
+                range: $range,
+                step: $step,
+            },
+        }
+    };
+}
+
+/// The fields settings are generated from, shown in columns by category. New options only need an entry here and a label in `fields.ftl`.
+pub static FIELDS: &[ConfigField] = &[
+    toggle!("autoplay", Gameplay, autoplay),
+    toggle!("adjustTime", Gameplay, adjust_time),
+    toggle!("doubleClickToPause", Gameplay, double_click_to_pause),
+    toggle!("metronome", Gameplay, metronome),
+    toggle!("multipleHint", Display, multiple_hint),
+    toggle!("fixAspectRatio", Display, fix_aspect_ratio),
+    toggle!("hitParticles", Display, hit_particles),
+    toggle!("squareParticles", Display, square_particles),
+    toggle!("backgroundVideos", Display, background_videos),
+    toggle!("shaders", Display, shaders),
+    toggle!("reduceMotion", Display, reduce_motion),
+    toggle!("aggressive", Display, aggressive),
+    slider!("offset", Gameplay, offset, -1.0..1.0, 0.005),
+    slider!("speed", Gameplay, speed, 0.5..2.0, 0.005),
+    slider!("speedMultiplier", Gameplay, speed_multiplier, 0.25..4.0, 0.05),
+    slider!("playerRks", Gameplay, player_rks, 0.0..17.0, 0.01),
+    slider!("noteScale", Display, note_scale, 0.5..2.0, 0.005),
+    slider!("textScale", Display, text_scale, 0.5..2.0, 0.05),
+    slider!("volumeMusic", Audio, volume_music, 0.0..2.0, 0.05),
+    slider!("volumeSfx", Audio, volume_sfx, 0.0..2.0, 0.05),
+    slider!("volumeHitsound", Audio, volume_hitsound, 0.0..2.0, 0.05),
+    slider!("volumeUi", Audio, volume_ui, 0.0..2.0, 0.05),
+    slider!("volumeEnding", Audio, volume_ending, 0.0..2.0, 0.05),
+    slider!("volumeMetronome", Audio, volume_metronome, 0.0..2.0, 0.05),
+    slider!("hitsoundPanning", Audio, hitsound_panning, 0.0..1.0, 0.05),
+];
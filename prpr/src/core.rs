@@ -35,7 +35,7 @@ mod render;
 pub use render::{copy_fbo, MSRenderTarget};
 
 mod resource;
-pub use resource::{ParticleEmitter, Resource, ResourcePack, DPI_VALUE};
+pub use resource::{ParticleEmitter, ResPackInfo, Resource, ResourcePack, DPI_VALUE};
 
 mod tween;
 pub use tween::{easing_from, BezierTween, ClampedTween, StaticTween, TweenFunction, TweenId, TweenMajor, TweenMinor, Tweenable, TWEEN_FUNCTIONS};
@@ -43,6 +43,9 @@ pub use tween::{easing_from, BezierTween, ClampedTween, StaticTween, TweenFuncti
 mod video;
 pub use video::Video;
 
+mod visualizer;
+pub use visualizer::Visualizer;
+
 pub fn init_assets() {
     if let Ok(mut exe) = std::env::current_exe() {
         while exe.pop() {
@@ -92,6 +95,10 @@ impl BpmList {
         BpmList { elements, cursor: 0 }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
     pub fn time_beats(&mut self, beats: f32) -> f32 {
         while let Some(kf) = self.elements.get(self.cursor + 1) {
             if kf.0 > beats {
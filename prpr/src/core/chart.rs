@@ -89,16 +89,20 @@ impl Chart {
         for effect in &mut self.extra.effects {
             effect.update(res);
         }
-        for video in &mut self.extra.videos {
-            if let Err(err) = video.update(res.time) {
-                warn!("Video error: {:?}", err);
+        if res.config.background_videos {
+            for video in &mut self.extra.videos {
+                if let Err(err) = video.update(res.time) {
+                    warn!("Video error: {:?}", err);
+                }
             }
         }
     }
 
     pub fn render(&self, ui: &mut Ui, res: &mut Resource) {
-        for video in &self.extra.videos {
-            video.render(res);
+        if res.config.background_videos {
+            for video in &self.extra.videos {
+                video.render(res);
+            }
         }
         res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(1.0, -1.0)), |res| {
             let mut guard = self.bpm_list.borrow_mut();
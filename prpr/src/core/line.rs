@@ -300,7 +300,7 @@ impl JudgeLine {
             }
             for index in &self.cache.above_indices {
                 let speed = self.notes[*index].speed;
-                let limit = height_above / speed;
+                let limit = height_above / (speed * res.config.speed_multiplier);
                 for note in self.notes[*index..].iter() {
                     if !note.above || speed != note.speed {
                         break;
@@ -317,7 +317,7 @@ impl JudgeLine {
                 }
                 for index in &self.cache.below_indices {
                     let speed = self.notes[*index].speed;
-                    let limit = height_below / speed;
+                    let limit = height_below / (speed * res.config.speed_multiplier);
                     for note in self.notes[*index..].iter() {
                         if speed != note.speed {
                             break;
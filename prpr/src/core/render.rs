@@ -6,6 +6,7 @@ use miniquad::{gl::GLuint, RenderPass, Texture, TextureFormat};
 
 pub struct MSRenderTarget {
     dim: (u32, u32),
+    format: TextureFormat,
     fbo: GLuint,
     rbo: GLuint,
     dummy: RenderTarget,
@@ -28,14 +29,16 @@ fn internal_id(target: RenderTarget) -> GLuint {
 }
 
 impl MSRenderTarget {
-    pub fn new(dim: (u32, u32), samples: u32) -> Self {
+    /// Keeps the alpha channel if `alpha` is set, which is only needed for transparent renders.
+    pub fn new(dim: (u32, u32), samples: u32, alpha: bool) -> Self {
+        let format = if alpha { TextureFormat::RGBA8 } else { TextureFormat::RGB8 };
         let mut fbo = 0;
         let mut rbo = 0;
         unsafe {
             use miniquad::gl::*;
             glGenRenderbuffers(1, &mut rbo as *mut _);
             glBindRenderbuffer(GL_RENDERBUFFER, rbo);
-            glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples as _, GL_RGB8, dim.0 as _, dim.1 as _);
+            glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples as _, if alpha { GL_RGBA8 } else { GL_RGB8 }, dim.0 as _, dim.1 as _);
             glGenFramebuffers(1, &mut fbo as *mut _);
             glBindFramebuffer(GL_FRAMEBUFFER, fbo);
             glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_RENDERBUFFER, rbo);
@@ -46,7 +49,7 @@ impl MSRenderTarget {
             miniquad::TextureParams {
                 width: dim.0,
                 height: dim.1,
-                format: TextureFormat::RGB8,
+                format,
                 ..Default::default()
             },
         );
@@ -54,6 +57,7 @@ impl MSRenderTarget {
         let dummy_render_pass = RenderPass::from_raw(gl.quad_context, fbo, texture);
         Self {
             dim,
+            format,
             fbo,
             rbo,
             dummy: RenderTarget {
@@ -83,7 +87,7 @@ impl MSRenderTarget {
                 miniquad::TextureParams {
                     width: self.dim.0,
                     height: self.dim.1,
-                    format: TextureFormat::RGB8,
+                    format: self.format,
                     ..Default::default()
                 },
             );
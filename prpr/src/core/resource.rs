@@ -1,9 +1,9 @@
 use super::{MSRenderTarget, Matrix, Point, JUDGE_LINE_PERFECT_COLOR, NOTE_WIDTH_RATIO_BASE};
 use crate::{
     config::Config,
-    ext::{create_audio_manger, nalgebra_to_glm, SafeTexture},
+    ext::{create_audio_manger, load_audio, nalgebra_to_glm, parse_hex_color, SafeTexture},
     fs::FileSystem,
-    info::ChartInfo,
+    info::{ChartInfo, LoadingStyle},
     particle::{AtlasConfig, ColorCurve, Emitter, EmitterConfig},
 };
 use anyhow::{bail, Context, Result};
@@ -11,11 +11,21 @@ use macroquad::prelude::*;
 use miniquad::{gl::GLuint, Texture, TextureWrap};
 use sasa::{AudioClip, AudioManager, Sfx};
 use serde::Deserialize;
-use std::{cell::RefCell, collections::BTreeMap, ops::DerefMut, path::Path, sync::atomic::AtomicU32};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicU32, Mutex},
+    time::SystemTime,
+};
 
 pub const MAX_SIZE: usize = 64; // needs tweaking
 pub static DPI_VALUE: AtomicU32 = AtomicU32::new(250);
 
+/// The info last read by [`ResourcePack::info_from_path`], with the pack it's from and when that was modified.
+static INFO_CACHE: Mutex<Option<(PathBuf, SystemTime, ResPackInfo)>> = Mutex::new(None);
+
 #[inline]
 fn default_scale() -> f32 {
     1.
@@ -26,8 +36,40 @@ fn default_duration() -> f32 {
     0.5
 }
 
+/// Feedback on combo milestones and breaks, given by `combo` in the `info.yml` of the resource pack.
+///
+/// Sounds are played if the pack has `combo_milestone.ogg` or `combo_break.ogg`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct ComboStyle {
+    /// The combo flashes every this many notes, never if 0.
+    pub milestone: u32,
+    /// Like `#ffca28`.
+    pub milestone_color: Option<String>,
+    /// Whether the combo lost falls and fades out when it breaks.
+    pub break_effect: bool,
+    pub break_color: Option<String>,
+}
+
+impl ComboStyle {
+    pub fn milestone_color(&self) -> Color {
+        self.milestone_color
+            .as_deref()
+            .and_then(parse_hex_color)
+            .unwrap_or(Color::new(1., 0.79, 0.16, 1.))
+    }
+
+    pub fn break_color(&self) -> Color {
+        self.break_color
+            .as_deref()
+            .and_then(parse_hex_color)
+            .unwrap_or(Color::new(0.96, 0.26, 0.21, 1.))
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ResPackInfo {
     pub name: String,
@@ -53,6 +95,11 @@ pub struct ResPackInfo {
     pub hold_repeat: bool,
     #[serde(default)]
     pub hold_compact: bool,
+
+    #[serde(default)]
+    pub loading: LoadingStyle,
+    #[serde(default)]
+    pub combo: ComboStyle,
 }
 
 pub struct NoteStyle {
@@ -106,20 +153,51 @@ pub struct ResourcePack {
     pub sfx_drag: AudioClip,
     pub sfx_flick: AudioClip,
     pub ending: AudioClip,
+    pub combo_milestone: Option<AudioClip>,
+    pub combo_break: Option<AudioClip>,
     pub hit_fx: SafeTexture,
 }
 
 impl ResourcePack {
+    fn open<T: AsRef<Path>>(path: Option<T>) -> Result<Box<dyn FileSystem>> {
+        if let Some(path) = path {
+            crate::fs::fs_from_file(path.as_ref())
+        } else {
+            crate::fs::fs_from_assets("respack/")
+        }
+    }
+
+    async fn load_info(fs: &mut dyn FileSystem) -> Result<ResPackInfo> {
+        Ok(serde_yaml::from_str(&String::from_utf8(fs.load_file("info.yml").await.context("Missing info.yml")?)?)?)
+    }
+
     pub async fn from_path<T: AsRef<Path>>(path: Option<T>) -> Result<Self> {
-        Self::load(
-            if let Some(path) = path {
-                crate::fs::fs_from_file(path.as_ref())?
-            } else {
-                crate::fs::fs_from_assets("respack/")?
-            }
-            .deref_mut(),
-        )
-        .await
+        Self::load(Self::open(path)?.deref_mut()).await
+    }
+
+    /// Reads only the info of the resource pack, without loading the textures and sounds.
+    ///
+    /// Opening a packed resource pack reads the whole archive, so the info is kept until the pack is modified.
+    pub async fn info_from_path<T: AsRef<Path>>(path: Option<T>) -> Result<ResPackInfo> {
+        let Some(path) = path else {
+            return Self::load_info(Self::open(None::<&Path>)?.deref_mut()).await;
+        };
+        let path = path.as_ref();
+        let modified = std::fs::metadata(path).and_then(|it| it.modified()).ok();
+        let cached = INFO_CACHE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|it| it.0 == path && Some(it.1) == modified)
+            .map(|it| it.2.clone());
+        if let Some(info) = cached {
+            return Ok(info);
+        }
+        let info = Self::load_info(Self::open(Some(path))?.deref_mut()).await?;
+        if let Some(modified) = modified {
+            *INFO_CACHE.lock().unwrap() = Some((path.to_owned(), modified, info.clone()));
+        }
+        Ok(info)
     }
 
     pub async fn load(fs: &mut dyn FileSystem) -> Result<Self> {
@@ -128,7 +206,7 @@ impl ResourcePack {
                 image::load_from_memory(&fs.load_file($path).await.with_context(|| format!("Missing {}", $path))?)?.into()
             };
         }
-        let info: ResPackInfo = serde_yaml::from_str(&String::from_utf8(fs.load_file("info.yml").await.context("Missing info.yml")?)?)?;
+        let info = Self::load_info(fs).await?;
         let mut note_style = NoteStyle {
             click: load_tex!("click.png"),
             hold: load_tex!("hold.png"),
@@ -184,6 +262,8 @@ impl ResourcePack {
             sfx_drag: load_clip!("drag.ogg"),
             sfx_flick: load_clip!("flick.ogg"),
             ending: load_clip!("ending.mp3"),
+            combo_milestone: fs.load_file("combo_milestone.ogg").await.ok().map(AudioClip::new).transpose()?,
+            combo_break: fs.load_file("combo_break.ogg").await.ok().map(AudioClip::new).transpose()?,
             hit_fx,
         })
     }
@@ -194,6 +274,8 @@ pub struct ParticleEmitter {
     emitter: Emitter,
     emitter_square: Emitter,
     hide_particles: bool,
+    hit_fx: bool,
+    squares: bool,
 }
 
 impl ParticleEmitter {
@@ -235,16 +317,20 @@ impl ParticleEmitter {
                 ..Default::default()
             }),
             hide_particles,
+            hit_fx: true,
+            squares: true,
         };
         res.set_scale(scale);
         Ok(res)
     }
 
     pub fn emit_at(&mut self, pt: Vec2, rotation: f32, color: Color) {
-        self.emitter.config.initial_rotation = rotation;
-        self.emitter.config.base_color = color;
-        self.emitter.emit(pt, 1);
-        if !self.hide_particles {
+        if self.hit_fx {
+            self.emitter.config.initial_rotation = rotation;
+            self.emitter.config.base_color = color;
+            self.emitter.emit(pt, 1);
+        }
+        if self.squares && !self.hide_particles {
             self.emitter_square.config.base_color = color;
             self.emitter_square.emit(pt, 4);
         }
@@ -255,6 +341,12 @@ impl ParticleEmitter {
         self.emitter_square.draw(vec2(0., 0.), dt);
     }
 
+    /// Whether the hit effect and the squares around it are emitted.
+    pub fn set_emitting(&mut self, hit_fx: bool, squares: bool) {
+        self.hit_fx = hit_fx;
+        self.squares = squares;
+    }
+
     pub fn set_scale(&mut self, scale: f32) {
         self.emitter.config.size = self.scale * scale / 5.;
         self.emitter_square.config.size = self.scale * scale / 44.;
@@ -325,6 +417,8 @@ pub struct Resource {
     pub sfx_click: Sfx,
     pub sfx_drag: Sfx,
     pub sfx_flick: Sfx,
+    pub sfx_combo_milestone: Option<Sfx>,
+    pub sfx_combo_break: Option<Sfx>,
 
     pub chart_target: Option<MSRenderTarget>,
     pub no_effect: bool,
@@ -380,7 +474,7 @@ impl Resource {
     pub async fn new(
         config: Config,
         info: ChartInfo,
-        mut fs: Box<dyn FileSystem>,
+        fs: &mut dyn FileSystem,
         player: Option<SafeTexture>,
         background: SafeTexture,
         illustration: SafeTexture,
@@ -391,7 +485,9 @@ impl Resource {
                 SafeTexture::from(Texture2D::from_image(&load_image($path).await?))
             };
         }
-        let res_pack = ResourcePack::from_path(config.res_pack_path.as_ref()).await.context("Failed to load resource pack")?;
+        let res_pack = ResourcePack::from_path(config.res_pack_path.as_ref())
+            .await
+            .context("Failed to load resource pack")?;
         let camera = Camera2D {
             target: vec2(0., 0.),
             zoom: vec2(1., -config.aspect_ratio.unwrap_or(info.aspect_ratio)),
@@ -399,12 +495,14 @@ impl Resource {
         };
 
         let mut audio = create_audio_manger(&config)?;
-        let music = AudioClip::new(fs.load_file(&info.music).await?)?;
+        let music = load_audio(fs, &info.music).await?;
         let track_length = music.length();
         let buffer_size = Some(1024);
         let sfx_click = audio.create_sfx(res_pack.sfx_click.clone(), buffer_size)?;
         let sfx_drag = audio.create_sfx(res_pack.sfx_drag.clone(), buffer_size)?;
         let sfx_flick = audio.create_sfx(res_pack.sfx_flick.clone(), buffer_size)?;
+        let sfx_combo_milestone = res_pack.combo_milestone.clone().map(|it| audio.create_sfx(it, Some(2))).transpose()?;
+        let sfx_combo_break = res_pack.combo_break.clone().map(|it| audio.create_sfx(it, Some(2))).transpose()?;
 
         let aspect_ratio = config.aspect_ratio.unwrap_or(info.aspect_ratio);
         let note_width = config.note_scale * NOTE_WIDTH_RATIO_BASE;
@@ -412,7 +510,7 @@ impl Resource {
 
         let emitter = ParticleEmitter::new(&res_pack, note_scale, res_pack.info.hide_particles)?;
 
-        let no_effect = config.disable_effect || has_no_effect;
+        let no_effect = !config.shaders || config.reduce_motion || has_no_effect;
 
         macroquad::window::gl_set_drawcall_buffer_capacity(MAX_SIZE * 4, MAX_SIZE * 6);
         Ok(Self {
@@ -450,6 +548,8 @@ impl Resource {
             sfx_click,
             sfx_drag,
             sfx_flick,
+            sfx_combo_milestone,
+            sfx_combo_break,
 
             chart_target: None,
             no_effect,
@@ -461,9 +561,10 @@ impl Resource {
     }
 
     pub fn emit_at_origin(&mut self, rotation: f32, color: Color) {
-        if !self.config.particle {
+        if !(self.config.hit_particles || self.config.square_particles) || self.config.reduce_motion {
             return;
         }
+        self.emitter.set_emitting(self.config.hit_particles, self.config.square_particles);
         let pt = self.world_to_screen(Point::default());
 
         self.emitter
@@ -476,7 +577,7 @@ impl Resource {
         }
         self.last_screen_size = dim;
         if !self.no_effect || self.config.sample_count != 1 {
-            self.chart_target = Some(MSRenderTarget::new(dim, self.config.sample_count));
+            self.chart_target = Some(MSRenderTarget::new(dim, self.config.sample_count, self.config.transparent_background));
         }
         fn viewport(aspect_ratio: f32, (w, h): (u32, u32)) -> (i32, i32, i32, i32) {
             let w = w as f32;
@@ -6,7 +6,7 @@ use macroquad::prelude::*;
 use miniquad::{Texture, TextureFormat, TextureParams, TextureWrap};
 use std::{
     cell::RefCell,
-    io::{BufRead, Read, Write},
+    io::{BufRead, Read},
     path::Path,
     process::{Child, ChildStdout, Command, Stdio},
 };
@@ -50,9 +50,9 @@ fn new_tex(w: u32, h: u32) -> Texture2D {
 }
 
 impl Video {
-    pub fn new(ffmpeg: &Path, data: Vec<u8>, start_time: f32, scale_type: ScaleType, alpha: Anim<f32>, dim: Anim<f32>) -> Result<Self> {
+    pub fn new(ffmpeg: &Path, mut data: impl Read, start_time: f32, scale_type: ScaleType, alpha: Anim<f32>, dim: Anim<f32>) -> Result<Self> {
         let mut video_file = NamedTempFile::new()?;
-        video_file.write_all(&data)?;
+        std::io::copy(&mut data, &mut video_file)?;
         drop(data);
         let (fps, (w, h)) = || -> Result<(f64, (u32, u32))> {
             for line in Command::new(ffmpeg)
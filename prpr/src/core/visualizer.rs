@@ -0,0 +1,100 @@
+use super::Resource;
+use crate::config::VisualizerStyle;
+use macroquad::prelude::*;
+use sasa::AudioClip;
+use std::f32::consts::PI;
+
+const WINDOW: usize = 1024;
+const BANDS: usize = 48;
+const MIN_FREQ: f32 = 40.;
+const MAX_FREQ: f32 = 16000.;
+const DECAY_HALF_LIFE: f32 = 0.08;
+
+pub struct Visualizer {
+    style: VisualizerStyle,
+    window: Vec<f32>,
+    samples: Vec<f32>,
+    levels: [f32; BANDS],
+    last_time: f32,
+}
+
+impl Visualizer {
+    pub fn new(style: VisualizerStyle) -> Self {
+        Self {
+            style,
+            window: (0..WINDOW)
+                .map(|i| 0.5 - 0.5 * (2. * PI * i as f32 / (WINDOW - 1) as f32).cos())
+                .collect(),
+            samples: vec![0.; WINDOW],
+            levels: [0.; BANDS],
+            last_time: f32::NEG_INFINITY,
+        }
+    }
+
+    /// `time` is the position in the music track, in seconds.
+    pub fn update(&mut self, clip: &AudioClip, time: f32) {
+        let dt = time - self.last_time;
+        self.last_time = time;
+        if !(0.0..1.).contains(&dt) {
+            // seeked or restarted
+            self.levels = [0.; BANDS];
+        }
+        let decay = 0.5_f32.powf(dt.max(0.) / DECAY_HALF_LIFE);
+
+        let rate = clip.sample_rate() as f32;
+        let frames = clip.frames();
+        let start = (time * rate) as isize - WINDOW as isize / 2;
+        for (i, (sample, w)) in self.samples.iter_mut().zip(&self.window).enumerate() {
+            let index = start + i as isize;
+            *sample = if index >= 0 && (index as usize) < frames.len() {
+                let frame = &frames[index as usize];
+                (frame.0 + frame.1) / 2. * w
+            } else {
+                0.
+            };
+        }
+
+        for (band, level) in self.levels.iter_mut().enumerate() {
+            let freq = MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(band as f32 / (BANDS - 1) as f32);
+            // Goertzel algorithm, much cheaper than a full FFT for a few bins
+            let coeff = 2. * (2. * PI * freq / rate).cos();
+            let (mut s1, mut s2) = (0., 0.);
+            for &x in &self.samples {
+                let s = x + coeff * s1 - s2;
+                s2 = s1;
+                s1 = s;
+            }
+            let power = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.);
+            let magnitude = power.sqrt() / (WINDOW as f32 / 4.);
+            let db = 20. * (magnitude + 1e-6).log10();
+            let value = ((db + 60.) / 60.).clamp(0., 1.);
+            *level = value.max(*level * decay);
+        }
+    }
+
+    pub fn render(&self, res: &Resource) {
+        let color = Color::new(1., 1., 1., 0.25 * res.alpha);
+        let h = 1. / res.aspect_ratio;
+        match self.style {
+            VisualizerStyle::Bars => {
+                let width = 2. / BANDS as f32;
+                for (i, level) in self.levels.iter().enumerate() {
+                    let height = level * h * 0.6;
+                    draw_rectangle(-1. + width * (i as f32 + 0.1), h - height, width * 0.8, height, color);
+                }
+            }
+            VisualizerStyle::Ring => {
+                let radius = h * 0.35;
+                let count = BANDS * 2;
+                for i in 0..count {
+                    // mirrored so that low frequencies meet at the top
+                    let level = self.levels[if i < BANDS { i } else { count - 1 - i }];
+                    let angle = -PI / 2. + 2. * PI * i as f32 / count as f32;
+                    let (sin, cos) = angle.sin_cos();
+                    let len = radius * 0.05 + level * radius * 0.8;
+                    draw_line(cos * radius, sin * radius, cos * (radius + len), sin * (radius + len), 0.012, color);
+                }
+            }
+        }
+    }
+}
@@ -1,18 +1,20 @@
 use crate::{
     config::Config,
     core::{Matrix, Point, Vector},
+    fs::FileSystem,
     ui::Ui,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use image::DynamicImage;
 use macroquad::prelude::*;
 use miniquad::{BlendFactor, BlendState, BlendValue, CompareFunc, Equation, PrimitiveType, StencilFaceState, StencilOp, StencilState};
 use once_cell::sync::Lazy;
 use ordered_float::{Float, NotNan};
-use sasa::AudioManager;
-use serde::Deserialize;
+use sasa::{AudioClip, AudioManager, Frame};
+use serde::{Deserialize, Serialize};
 use std::{
     future::Future,
+    io::{ErrorKind, Read},
     ops::Deref,
     pin::Pin,
     sync::{Arc, Mutex},
@@ -141,7 +143,21 @@ pub fn draw_text_aligned(ui: &mut Ui, text: &str, x: f32, y: f32, anchor: (f32,
     ui.text(text).pos(x, y).anchor(anchor.0, anchor.1).size(scale).color(color).draw()
 }
 
-#[derive(Default, Clone, Copy, Deserialize)]
+/// Parses colors like `#51af44` or `#51af44cc`, the leading `#` being optional.
+pub fn parse_hex_color(s: &str) -> Option<Color> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if !matches!(s.len(), 6 | 8) {
+        return None;
+    }
+    let value = u32::from_str_radix(s, 16).ok()?;
+    Some(if s.len() == 6 {
+        Color::from_rgba((value >> 16) as u8, (value >> 8) as u8, value as u8, 255)
+    } else {
+        Color::from_rgba((value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8)
+    })
+}
+
+#[derive(Default, Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ScaleType {
     #[default]
@@ -340,6 +356,82 @@ pub fn create_audio_manger(config: &Config) -> Result<AudioManager> {
     }
 }
 
+/// Length of the leading silence (below -50 dBFS) of the clip, in seconds.
+pub fn leading_silence(clip: &AudioClip) -> f32 {
+    const THRESHOLD: f32 = 3.2e-3;
+    let frames = clip.frames();
+    let pos = frames
+        .iter()
+        .position(|it| it.0.abs().max(it.1.abs()) > THRESHOLD)
+        .unwrap_or(frames.len());
+    pos as f32 / clip.sample_rate() as f32
+}
+
+/// Symphonia wants its sources to be `Sync`, which readers from file systems need not be.
+struct SyncReader(Mutex<Box<dyn Read + Send>>);
+
+impl Read for SyncReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.get_mut().unwrap().read(buf)
+    }
+}
+
+/// Loads the audio at `path`, decoding it as it's read so that the encoded file is never kept in memory as a whole.
+pub async fn load_audio(fs: &mut dyn FileSystem, path: &str) -> Result<AudioClip> {
+    use symphonia::core::{
+        audio::SampleBuffer,
+        codecs::DecoderOptions,
+        errors::Error,
+        formats::FormatOptions,
+        io::{MediaSourceStream, ReadOnlySource},
+        meta::MetadataOptions,
+        probe::Hint,
+    };
+
+    let reader = SyncReader(Mutex::new(fs.open_file(path).await?));
+    let source = MediaSourceStream::new(Box::new(ReadOnlySource::new(reader)), Default::default());
+    let mut hint = Hint::new();
+    if let Some((_, ext)) = path.rsplit_once('.') {
+        hint.with_extension(ext);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .context("Unsupported audio format")?
+        .format;
+    let track = format.default_track().context("No audio track")?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+    let mut frames = Vec::new();
+    let mut buffer: Option<(SampleBuffer<f32>, usize)> = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // a corrupted packet is skipped, like players do
+            Err(Error::DecodeError(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let spec = *decoded.spec();
+        sample_rate = Some(spec.rate);
+        if buffer.as_ref().map_or(true, |it| it.1 < decoded.capacity()) {
+            buffer = Some((SampleBuffer::new(decoded.capacity() as u64, spec), decoded.capacity()));
+        }
+        let buffer = &mut buffer.as_mut().unwrap().0;
+        buffer.copy_interleaved_ref(decoded);
+        let channels = spec.channels.count();
+        frames.extend(buffer.samples().chunks_exact(channels).map(|it| Frame(it[0], it[channels.min(2) - 1])));
+    }
+    Ok(AudioClip::from_raw(frames, sample_rate.context("Unknown sample rate")?))
+}
+
 pub fn make_pipeline(write_color: bool, pass_op: StencilOp, test_func: CompareFunc, test_ref: i32) -> GlPipeline {
     let InternalGlContext {
         quad_gl: gl,
@@ -405,3 +497,18 @@ void main() {
     gl_FragColor = color * texture2D(Texture, uv) ;
 }"#;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_colors() {
+        assert_eq!(parse_hex_color("#51af44"), Some(Color::from_rgba(0x51, 0xaf, 0x44, 0xff)));
+        assert_eq!(parse_hex_color("51AF44"), Some(Color::from_rgba(0x51, 0xaf, 0x44, 0xff)));
+        assert_eq!(parse_hex_color("#51af44cc"), Some(Color::from_rgba(0x51, 0xaf, 0x44, 0xcc)));
+        for s in ["", "#", "#fff", "#51af4", "#51af44c", "#51af44cc00", "#51ag44", "##51af44", "+51af44"] {
+            assert_eq!(parse_hex_color(s), None, "{s}");
+        }
+    }
+}
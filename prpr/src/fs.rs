@@ -3,6 +3,8 @@ use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use chardetng::EncodingDetector;
 use concat_string::concat_string;
+use flate2::read::DeflateDecoder;
+use lru::LruCache;
 use macroquad::prelude::load_file;
 use miniquad::warn;
 use serde::Deserialize;
@@ -14,9 +16,22 @@ use std::{
     io::{Cursor, Read, Seek, Write},
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::SystemTime,
 };
 use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
 
+#[cfg(any(feature = "sevenz", feature = "rar"))]
+mod archive;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+mod http;
+#[cfg(feature = "unity")]
+mod unity;
+
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub use http::{fs_from_url, is_url, share_chart, shared_chart_url, HttpFileSystem};
+#[cfg(feature = "unity")]
+pub use unity::{import_package, is_package};
+
 pub fn update_zip<R: Read + Seek>(zip: &mut ZipArchive<R>, patches: HashMap<String, Vec<u8>>) -> Result<Vec<u8>> {
     let mut buffer = Vec::new();
     let mut w = ZipWriter::new(Cursor::new(&mut buffer));
@@ -53,6 +68,16 @@ pub trait FileSystem: Send {
     fn list_root(&self) -> Result<Vec<String>>;
     fn clone_box(&self) -> Box<dyn FileSystem>;
     fn as_any(&mut self) -> &mut dyn Any;
+
+    /// Opens a file to be read gradually, so that large files like videos are not kept in memory as a whole where possible.
+    async fn open_file(&mut self, path: &str) -> Result<Box<dyn Read + Send>> {
+        Ok(Box::new(Cursor::new(self.load_file(path).await?)))
+    }
+
+    /// Files loaded before that have been modified or removed since, each change reported once.
+    fn take_modified(&mut self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 #[derive(Clone)]
@@ -82,8 +107,19 @@ impl FileSystem for AssetsFileSystem {
     }
 }
 
+/// A chart folder on disk, which keeps track of the files loaded so that edits can be picked up.
 #[derive(Clone)]
-pub struct ExternalFileSystem(PathBuf);
+pub struct ExternalFileSystem(PathBuf, HashMap<String, Option<SystemTime>>);
+
+impl ExternalFileSystem {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self(path.into(), HashMap::new())
+    }
+
+    fn modified_time(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(self.0.join(path)).and_then(|it| it.modified()).ok()
+    }
+}
 
 #[async_trait]
 impl FileSystem for ExternalFileSystem {
@@ -94,6 +130,8 @@ impl FileSystem for ExternalFileSystem {
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
+            let time = self.modified_time(path);
+            self.1.insert(path.to_owned(), time);
             let path = self.0.join(path);
             Ok(tokio::spawn(async move { tokio::fs::read(path).await }).await??)
         }
@@ -103,6 +141,12 @@ impl FileSystem for ExternalFileSystem {
         Ok(self.0.join(path).exists())
     }
 
+    async fn open_file(&mut self, path: &str) -> Result<Box<dyn Read + Send>> {
+        let time = self.modified_time(path);
+        self.1.insert(path.to_owned(), time);
+        Ok(Box::new(fs::File::open(self.0.join(path))?))
+    }
+
     fn list_root(&self) -> Result<Vec<String>> {
         Ok(std::fs::read_dir(&self.0)?
             .filter_map(|res| res.ok()?.file_name().into_string().ok())
@@ -116,20 +160,92 @@ impl FileSystem for ExternalFileSystem {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn take_modified(&mut self) -> Vec<String> {
+        let changed: Vec<_> = self
+            .1
+            .iter()
+            .map(|(path, time)| (path, self.modified_time(path), time))
+            .filter(|(_, now, time)| now != *time)
+            .map(|(path, now, _)| (path.clone(), now))
+            .collect();
+        changed
+            .into_iter()
+            .map(|(path, now)| {
+                self.1.insert(path.clone(), now);
+                path
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone)]
-pub struct ZipFileSystem(pub Arc<Mutex<ZipArchive<Cursor<Vec<u8>>>>>, String);
+pub struct ZipFileSystem(
+    pub Arc<Mutex<ZipArchive<Cursor<Arc<[u8]>>>>>,
+    String,
+    Option<String>,
+    Arc<HashMap<String, usize>>,
+    // shared with the archive, for reading entries without holding the lock
+    Arc<[u8]>,
+);
 
 impl ZipFileSystem {
     pub fn new(bytes: Vec<u8>) -> Result<Self> {
-        let zip = ZipArchive::new(Cursor::new(bytes))?;
-        let root_dirs = zip
-            .file_names()
+        let bytes: Arc<[u8]> = bytes.into();
+        let mut zip = ZipArchive::new(Cursor::new(Arc::clone(&bytes)))?;
+        let names = Self::decode_names(&mut zip);
+        let root_dirs = names
+            .keys()
             .filter(|it| it.ends_with('/') && it.find('/') == Some(it.len() - 1))
             .collect::<Vec<_>>();
         let root = if root_dirs.len() == 1 { root_dirs[0].to_owned() } else { String::new() };
-        Ok(Self(Arc::new(Mutex::new(zip)), root))
+        Ok(Self(Arc::new(Mutex::new(zip)), root, None, Arc::new(names), bytes))
+    }
+
+    /// Maps entry names to their indices.
+    ///
+    /// Zips made on Windows often have names in the system's code page (GBK, Shift-JIS, etc.) without saying so, which are
+    /// decoded with a guessed encoding instead of CP437.
+    fn decode_names(zip: &mut ZipArchive<Cursor<Arc<[u8]>>>) -> HashMap<String, usize> {
+        let raw: Vec<Vec<u8>> = (0..zip.len())
+            .map(|i| zip.by_index_raw(i).map(|it| it.name_raw().to_vec()).unwrap_or_default())
+            .collect();
+        let mut det = EncodingDetector::new();
+        for name in raw.iter().filter(|it| std::str::from_utf8(it).is_err()) {
+            det.feed(name, false);
+        }
+        det.feed(&[], true);
+        let encoding = det.guess(None, true);
+        raw.iter()
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty())
+            .map(|(i, name)| {
+                let name = match std::str::from_utf8(name) {
+                    Ok(name) => name.to_owned(),
+                    Err(_) => encoding.decode(name).0.into_owned(),
+                };
+                (name.replace('\\', "/"), i)
+            })
+            .collect()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        let mut zip = self.0.lock().unwrap();
+        (0..zip.len()).any(|i| zip.by_index_raw(i).map_or(false, |it| it.encrypted()))
+    }
+
+    /// Sets the password to decrypt entries with, failing if it doesn't match.
+    pub fn set_password(&mut self, password: String) -> Result<()> {
+        {
+            let mut zip = self.0.lock().unwrap();
+            if let Some(i) = (0..zip.len()).find(|i| zip.by_index_raw(*i).map_or(false, |it| it.encrypted())) {
+                if zip.by_index_decrypt(i, password.as_bytes())?.is_err() {
+                    bail!("Wrong password");
+                }
+            }
+        }
+        self.2 = Some(password);
+        Ok(())
     }
 }
 
@@ -137,10 +253,17 @@ impl ZipFileSystem {
 impl FileSystem for ZipFileSystem {
     async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
         let arc = Arc::clone(&self.0);
-        let path = concat_string!(self.1, path);
+        let index = *self
+            .3
+            .get(&concat_string!(self.1, path))
+            .ok_or_else(|| anyhow!("File not found: {path}"))?;
+        let password = self.2.clone();
         spawn_task(async move {
             let mut zip = arc.lock().unwrap();
-            let mut entry = zip.by_name(&path)?;
+            let mut entry = match &password {
+                Some(password) => zip.by_index_decrypt(index, password.as_bytes())?.map_err(|_| anyhow!("Wrong password"))?,
+                None => zip.by_index(index)?,
+            };
             let mut res = Vec::new();
             entry.read_to_end(&mut res)?;
             Ok(res)
@@ -149,16 +272,108 @@ impl FileSystem for ZipFileSystem {
     }
 
     async fn exists(&mut self, path: &str) -> Result<bool> {
-        Ok(self.0.lock().unwrap().by_name(&concat_string!(self.1, path)).is_ok())
+        Ok(self.3.contains_key(&concat_string!(self.1, path)))
+    }
+
+    async fn open_file(&mut self, path: &str) -> Result<Box<dyn Read + Send>> {
+        let index = *self
+            .3
+            .get(&concat_string!(self.1, path))
+            .ok_or_else(|| anyhow!("File not found: {path}"))?;
+        let raw = {
+            let mut zip = self.0.lock().unwrap();
+            let entry = zip.by_index_raw(index)?;
+            (!entry.encrypted()).then(|| (entry.data_start(), entry.compressed_size(), entry.compression()))
+        };
+        // encrypted entries are rare enough to be read as a whole
+        let Some((start, size, method)) = raw else {
+            return Ok(Box::new(Cursor::new(self.load_file(path).await?)));
+        };
+        let mut data = Cursor::new(Arc::clone(&self.4));
+        data.set_position(start);
+        let data = data.take(size);
+        Ok(match method {
+            CompressionMethod::Stored => Box::new(data),
+            CompressionMethod::Deflated => Box::new(DeflateDecoder::new(data)),
+            _ => Box::new(Cursor::new(self.load_file(path).await?)),
+        })
+    }
+
+    fn list_root(&self) -> Result<Vec<String>> {
+        Ok(self
+            .3
+            .keys()
+            .filter_map(|it| it.strip_prefix(&self.1).filter(|it| !it.is_empty() && !it.contains('/')))
+            .map(str::to_owned)
+            .collect())
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Files kept in memory, for charts that don't come from disk.
+#[derive(Clone)]
+pub struct MemoryFileSystem(Arc<HashMap<String, Vec<u8>>>, String);
+
+impl MemoryFileSystem {
+    pub fn new(files: HashMap<String, Vec<u8>>) -> Self {
+        Self(Arc::new(files), String::new())
+    }
+
+    /// Like [`MemoryFileSystem::new`], but reads from inside the top folder if every file is in the same one, as packed charts often are.
+    pub fn new_unwrapped(files: HashMap<String, Vec<u8>>) -> Self {
+        let mut tops = files.keys().map(|it| it.split_once('/').map(|it| it.0));
+        let root = match tops.next() {
+            Some(Some(first)) if tops.all(|it| it == Some(first)) => format!("{first}/"),
+            _ => String::new(),
+        };
+        Self(Arc::new(files), root)
+    }
+
+    /// Packs the files into a zip, as charts are saved.
+    pub fn to_zip(&self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        let mut w = ZipWriter::new(Cursor::new(&mut buffer));
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(0o755);
+        for (path, data) in self.0.iter() {
+            let Some(path) = path.strip_prefix(&self.1) else {
+                continue;
+            };
+            w.start_file(path, options)?;
+            w.write_all(data)?;
+        }
+        w.finish()?;
+        drop(w);
+        Ok(buffer)
+    }
+}
+
+#[async_trait]
+impl FileSystem for MemoryFileSystem {
+    async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        self.0
+            .get(&concat_string!(self.1, path))
+            .cloned()
+            .ok_or_else(|| anyhow!("File not found: {path}"))
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        Ok(self.0.contains_key(&concat_string!(self.1, path)))
     }
 
     fn list_root(&self) -> Result<Vec<String>> {
         Ok(self
             .0
-            .lock()
-            .unwrap()
-            .file_names()
-            .filter(|it| it.strip_prefix(&self.1).map_or(false, |it| !it.contains('/')))
+            .keys()
+            .filter_map(|it| it.strip_prefix(&self.1).filter(|it| !it.contains('/')))
             .map(str::to_owned)
             .collect())
     }
@@ -172,8 +387,135 @@ impl FileSystem for ZipFileSystem {
     }
 }
 
+/// Files kept by [`CachedFileSystem`], evicting the least recently used ones once their total size exceeds the budget.
+struct FileCache {
+    files: LruCache<String, Vec<u8>>,
+    size: usize,
+    budget: usize,
+}
+
+impl FileCache {
+    fn get(&mut self, path: &str) -> Option<Vec<u8>> {
+        self.files.get(path).cloned()
+    }
+
+    fn put(&mut self, path: String, data: Vec<u8>) {
+        // would only push everything else out
+        if data.len() > self.budget {
+            return;
+        }
+        self.size += data.len();
+        if let Some(old) = self.files.put(path, data) {
+            self.size -= old.len();
+        }
+        while self.size > self.budget {
+            let Some((_, data)) = self.files.pop_lru() else {
+                break;
+            };
+            self.size -= data.len();
+        }
+    }
+
+    fn pop(&mut self, path: &str) {
+        if let Some(data) = self.files.pop(path) {
+            self.size -= data.len();
+        }
+    }
+}
+
+/// Keeps the most recently loaded files of another file system in memory, so that they are not read and decompressed again.
+///
+/// Clones share the same cache.
+pub struct CachedFileSystem(Box<dyn FileSystem>, Arc<Mutex<FileCache>>);
+
+impl CachedFileSystem {
+    /// Keeps at most `budget` bytes of files.
+    pub fn new(fs: Box<dyn FileSystem>, budget: usize) -> Self {
+        Self(
+            fs,
+            Arc::new(Mutex::new(FileCache {
+                files: LruCache::unbounded(),
+                size: 0,
+                budget,
+            })),
+        )
+    }
+}
+
+impl Clone for CachedFileSystem {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box(), Arc::clone(&self.1))
+    }
+}
+
+#[async_trait]
+impl FileSystem for CachedFileSystem {
+    async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        let cached = self.1.lock().unwrap().get(path);
+        if let Some(data) = cached {
+            return Ok(data);
+        }
+        let data = self.0.load_file(path).await?;
+        self.1.lock().unwrap().put(path.to_owned(), data.clone());
+        Ok(data)
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        let cached = self.1.lock().unwrap().files.contains(path);
+        Ok(cached || self.0.exists(path).await?)
+    }
+
+    async fn open_file(&mut self, path: &str) -> Result<Box<dyn Read + Send>> {
+        let cached = self.1.lock().unwrap().get(path);
+        match cached {
+            Some(data) => Ok(Box::new(Cursor::new(data))),
+            None => self.0.open_file(path).await,
+        }
+    }
+
+    fn list_root(&self) -> Result<Vec<String>> {
+        self.0.list_root()
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn take_modified(&mut self) -> Vec<String> {
+        let res = self.0.take_modified();
+        let mut cache = self.1.lock().unwrap();
+        for path in &res {
+            cache.pop(path);
+        }
+        res
+    }
+}
+
 pub struct PatchedFileSystem(pub Box<dyn FileSystem>, pub HashMap<String, Vec<u8>>);
 
+impl PatchedFileSystem {
+    /// Writes the patches to the chart at `path`, which the inner file system is opened from, rewriting the archive if it's a zip.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let inner = self.0.as_any();
+        if let Some(zip) = inner.downcast_mut::<ZipFileSystem>() {
+            let patches = self.1.iter().map(|(name, data)| (concat_string!(zip.1, name), data.clone())).collect();
+            let bytes = update_zip(&mut zip.0.lock().unwrap(), patches)?;
+            fs::write(path, bytes).with_context(|| format!("Failed to write to {}", path.display()))?;
+        } else if inner.is::<ExternalFileSystem>() {
+            for (name, data) in &self.1 {
+                fs::write(path.join(name), data).with_context(|| format!("Failed to write to {name}"))?;
+            }
+        } else {
+            bail!("Only zip archives and folders can be saved to");
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl FileSystem for PatchedFileSystem {
     async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
@@ -188,6 +530,13 @@ impl FileSystem for PatchedFileSystem {
         Ok(self.0.exists(path).await? || self.1.contains_key(path))
     }
 
+    async fn open_file(&mut self, path: &str) -> Result<Box<dyn Read + Send>> {
+        match self.1.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data.clone()))),
+            None => self.0.open_file(path).await,
+        }
+    }
+
     fn list_root(&self) -> Result<Vec<String>> {
         let mut res = self.0.list_root()?;
         res.extend(self.1.keys().cloned());
@@ -202,6 +551,12 @@ impl FileSystem for PatchedFileSystem {
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn take_modified(&mut self) -> Vec<String> {
+        let mut res = self.0.take_modified();
+        res.retain(|it| !self.1.contains_key(it));
+        res
+    }
 }
 
 fn infer_diff(info: &mut ChartInfo, level: &str) {
@@ -272,8 +627,8 @@ fn info_from_txt(text: &str) -> Result<ChartInfo> {
     let kvs = it
         .map(|line| -> Result<(&str, String)> {
             let Some((key, value)) = line.split_once(": ") else {
-            bail!("Expected \"Key: Value\"");
-        };
+                bail!("Expected \"Key: Value\"");
+            };
             Ok((key, value.to_string()))
         })
         .collect::<Result<Vec<_>>>()?;
@@ -393,8 +748,9 @@ fn bytes_to_text_auto(data: &[u8]) -> String {
     s.into_owned()
 }
 
+/// Only reads the info file, the chart isn't hashed here, see [`crate::library::ensure_hash`].
 pub async fn load_info(fs: &mut dyn FileSystem) -> Result<ChartInfo> {
-    let info = if let Ok(bytes) = fs.load_file("info.yml").await {
+    Ok(if let Ok(bytes) = fs.load_file("info.yml").await {
         serde_yaml::from_str(&bytes_to_text_auto(&bytes))?
     } else if let Ok(bytes) = fs.load_file("info.txt").await {
         info_from_txt(&bytes_to_text_auto(&bytes))?
@@ -405,20 +761,170 @@ pub async fn load_info(fs: &mut dyn FileSystem) -> Result<ChartInfo> {
         let mut info = ChartInfo::default();
         fix_info(fs, &mut info).await?;
         info
-    };
-    Ok(info)
+    })
 }
 
 pub fn fs_from_file(path: &Path) -> Result<Box<dyn FileSystem>> {
     let meta = fs::metadata(path)?;
     Ok(if meta.is_file() {
         let bytes = fs::read(path).with_context(|| format!("Failed to read from {}", path.display()))?;
+        if bytes.starts_with(b"7z\xbc\xaf\x27\x1c") {
+            #[cfg(feature = "sevenz")]
+            return Ok(Box::new(archive::fs_from_7z(bytes).with_context(|| format!("Cannot open {} as 7z archive", path.display()))?));
+            #[cfg(not(feature = "sevenz"))]
+            bail!("7z archives are not supported in this build");
+        }
+        if bytes.starts_with(b"Rar!\x1a\x07") {
+            #[cfg(feature = "rar")]
+            return Ok(Box::new(archive::fs_from_rar(path).with_context(|| format!("Cannot open {} as rar archive", path.display()))?));
+            #[cfg(not(feature = "rar"))]
+            bail!("rar archives are not supported in this build");
+        }
         Box::new(ZipFileSystem::new(bytes).with_context(|| format!("Cannot open {} as zip archive", path.display()))?)
     } else {
-        Box::new(ExternalFileSystem(fs::canonicalize(path)?))
+        Box::new(ExternalFileSystem::new(fs::canonicalize(path)?))
     })
 }
 
+/// Whether `fs` is an encrypted zip that needs [`set_password`] before loading.
+pub fn needs_password(fs: &mut dyn FileSystem) -> bool {
+    fs.as_any()
+        .downcast_ref::<ZipFileSystem>()
+        .map_or(false, |it| it.is_encrypted() && it.2.is_none())
+}
+
+pub fn set_password(fs: &mut dyn FileSystem, password: String) -> Result<()> {
+    match fs.as_any().downcast_mut::<ZipFileSystem>() {
+        Some(zip) => zip.set_password(password),
+        None => Ok(()),
+    }
+}
+
 pub fn fs_from_assets(name: impl Into<String>) -> Result<Box<dyn FileSystem>> {
     Ok(Box::new(AssetsFileSystem(name.into())))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pollster::FutureExt;
+
+    fn memory(files: &[(&str, &str)]) -> MemoryFileSystem {
+        MemoryFileSystem::new(files.iter().map(|(path, data)| (path.to_string(), data.as_bytes().to_vec())).collect())
+    }
+
+    fn read(fs: &mut dyn FileSystem, path: &str) -> String {
+        let mut res = String::new();
+        fs.open_file(path).block_on().unwrap().read_to_string(&mut res).unwrap();
+        res
+    }
+
+    /// Zips the files with their names written as the given bytes, like archivers that don't use UTF-8 do.
+    fn raw_zip(files: &[(&[u8], &str)]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let mut w = ZipWriter::new(Cursor::new(&mut buffer));
+        // ASCII placeholders of the same length, so that the names aren't flagged as UTF-8
+        let placeholders: Vec<String> = files
+            .iter()
+            .enumerate()
+            .map(|(i, it)| format!("{i:#<width$}", width = it.0.len()))
+            .collect();
+        for ((_, data), placeholder) in files.iter().zip(&placeholders) {
+            w.start_file(placeholder, FileOptions::default()).unwrap();
+            w.write_all(data.as_bytes()).unwrap();
+        }
+        w.finish().unwrap();
+        drop(w);
+        for ((name, _), placeholder) in files.iter().zip(&placeholders) {
+            while let Some(pos) = buffer.windows(name.len()).position(|it| it == placeholder.as_bytes()) {
+                buffer[pos..][..name.len()].copy_from_slice(name);
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn memory_loads_files() {
+        let mut fs = memory(&[("chart.json", "{}"), ("extra/notes.txt", "hi")]);
+        assert_eq!(fs.load_file("chart.json").block_on().unwrap(), b"{}");
+        assert_eq!(fs.load_file("extra/notes.txt").block_on().unwrap(), b"hi");
+        assert!(fs.load_file("song.mp3").block_on().is_err());
+        assert!(fs.exists("chart.json").block_on().unwrap());
+        assert!(!fs.exists("song.mp3").block_on().unwrap());
+    }
+
+    #[test]
+    fn memory_lists_only_root() {
+        let mut root = memory(&[("chart.json", "{}"), ("song.mp3", ""), ("extra/notes.txt", "hi")])
+            .list_root()
+            .unwrap();
+        root.sort();
+        assert_eq!(root, ["chart.json", "song.mp3"]);
+    }
+
+    #[test]
+    fn memory_clones_share_files() {
+        let fs = memory(&[("chart.json", "{}")]);
+        let mut clone = fs.clone_box();
+        assert_eq!(clone.load_file("chart.json").block_on().unwrap(), b"{}");
+    }
+
+    #[test]
+    fn load_info_from_memory() {
+        let mut fs = memory(&[("info.yml", "name: Memory\nlevel: IN Lv.12\nchart: notes.pec\n"), ("notes.pec", "")]);
+        let info = load_info(&mut fs).block_on().unwrap();
+        assert_eq!(info.name, "Memory");
+        assert_eq!(info.level, "IN Lv.12");
+        assert_eq!(info.chart, "notes.pec");
+    }
+
+    #[test]
+    fn load_info_infers_from_memory() {
+        let mut fs = memory(&[("notes.json", "{}"), ("track.ogg", ""), ("cover.jpg", "")]);
+        let info = load_info(&mut fs).block_on().unwrap();
+        assert_eq!(info.chart, "notes.json");
+        assert_eq!(info.music, "track.ogg");
+        assert_eq!(info.illustration, "cover.jpg");
+        // nothing to play
+        assert!(load_info(&mut memory(&[("track.ogg", "")])).block_on().is_err());
+    }
+
+    #[test]
+    fn memory_unwraps_top_folder() {
+        let files = |paths: &[&str]| paths.iter().map(|it| (it.to_string(), Vec::new())).collect::<HashMap<_, _>>();
+        let mut fs = MemoryFileSystem::new_unwrapped(files(&["chart/info.yml", "chart/chart.json", "chart/extra/notes.txt"]));
+        assert!(fs.exists("chart.json").block_on().unwrap());
+        let mut root = fs.list_root().unwrap();
+        root.sort();
+        assert_eq!(root, ["chart.json", "info.yml"]);
+        // files outside of a common folder are read as they are
+        let mut fs = MemoryFileSystem::new_unwrapped(files(&["chart/chart.json", "song.mp3"]));
+        assert!(fs.exists("chart/chart.json").block_on().unwrap());
+        assert_eq!(fs.list_root().unwrap(), ["song.mp3"]);
+    }
+
+    #[test]
+    fn memory_to_zip() {
+        let files = [("chart/chart.json", "{}"), ("chart/extra/notes.txt", "hi")];
+        let fs = MemoryFileSystem::new_unwrapped(files.iter().map(|(path, data)| (path.to_string(), data.as_bytes().to_vec())).collect());
+        let mut zip = ZipFileSystem::new(fs.to_zip().unwrap()).unwrap();
+        assert_eq!(zip.list_root().unwrap(), ["chart.json"]);
+        assert_eq!(read(&mut zip, "chart.json"), "{}");
+        assert_eq!(read(&mut zip, "extra/notes.txt"), "hi");
+    }
+
+    #[test]
+    fn zip_decodes_names() {
+        let bytes = raw_zip(&[
+            // "测试谱面/谱面.json" and "测试谱面\\音乐.mp3" in GBK
+            (b"\xb2\xe2\xca\xd4\xc6\xd7\xc3\xe6/\xc6\xd7\xc3\xe6.json", "{}"),
+            (b"\xb2\xe2\xca\xd4\xc6\xd7\xc3\xe6\\\xd2\xf4\xc0\xd6.mp3", "music"),
+            (b"info.yml", "name: GBK"),
+        ]);
+        let mut zip = ZipFileSystem::new(bytes).unwrap();
+        assert_eq!(read(&mut zip, "测试谱面/谱面.json"), "{}");
+        // backslashes of Windows paths are read as separators
+        assert_eq!(read(&mut zip, "测试谱面/音乐.mp3"), "music");
+        assert_eq!(read(&mut zip, "info.yml"), "name: GBK");
+    }
+}
@@ -0,0 +1,37 @@
+use super::MemoryFileSystem;
+use anyhow::Result;
+use std::collections::HashMap;
+
+#[cfg(feature = "sevenz")]
+pub fn fs_from_7z(bytes: Vec<u8>) -> Result<MemoryFileSystem> {
+    use std::io::{Cursor, Read};
+
+    let len = bytes.len() as u64;
+    let mut files = HashMap::new();
+    sevenz_rust::SevenZReader::new(Cursor::new(bytes), len, sevenz_rust::Password::empty())?.for_each_entries(|entry, reader| {
+        if !entry.is_directory() {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            files.insert(entry.name().replace('\\', "/"), data);
+        }
+        Ok(true)
+    })?;
+    Ok(MemoryFileSystem::new_unwrapped(files))
+}
+
+#[cfg(feature = "rar")]
+pub fn fs_from_rar(path: &std::path::Path) -> Result<MemoryFileSystem> {
+    let mut files = HashMap::new();
+    let mut archive = unrar::Archive::new(path).open_for_processing()?;
+    while let Some(header) = archive.read_header()? {
+        archive = if header.entry().is_file() {
+            let name = header.entry().filename.to_string_lossy().replace('\\', "/");
+            let (data, rest) = header.read()?;
+            files.insert(name, data);
+            rest
+        } else {
+            header.skip()?
+        };
+    }
+    Ok(MemoryFileSystem::new_unwrapped(files))
+}
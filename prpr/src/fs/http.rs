@@ -0,0 +1,177 @@
+use super::{FileSystem, ZipFileSystem};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use miniquad::warn;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    any::Any,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+pub fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+fn cache_path(cache: &Path, url: &str) -> PathBuf {
+    let mut name = String::new();
+    for byte in Sha256::digest(url.as_bytes()) {
+        write!(&mut name, "{byte:02x}").unwrap();
+    }
+    cache.join(name)
+}
+
+/// Fetches `url`, keeping a copy in `cache` if given.
+///
+/// The copy is checked against the server with the `ETag` or `Last-Modified` it was sent with, so that files changed since
+/// are downloaded again, and is used as it is when the server can't be reached.
+async fn fetch(client: &reqwest::Client, url: &str, cache: Option<&Path>, mut progress: impl FnMut(u64, Option<u64>)) -> Result<Vec<u8>> {
+    let cached = cache.map(|it| cache_path(it, url));
+    let stored = match &cached {
+        Some(path) => tokio::fs::read(path)
+            .await
+            .ok()
+            .zip(tokio::fs::read_to_string(path.with_extension("meta")).await.ok()),
+        None => None,
+    };
+    let mut req = client.get(url);
+    if let Some((_, meta)) = &stored {
+        for (key, value) in meta.lines().filter_map(|it| it.split_once(": ")) {
+            match key {
+                "etag" => req = req.header(reqwest::header::IF_NONE_MATCH, value),
+                "last-modified" => req = req.header(reqwest::header::IF_MODIFIED_SINCE, value),
+                _ => {}
+            }
+        }
+    }
+    let mut res = match req.send().await {
+        Ok(res) => res,
+        Err(err) => {
+            if let Some((bytes, _)) = stored {
+                warn!("Failed to request {url}, using the cached copy: {err:?}");
+                return Ok(bytes);
+            }
+            return Err(err).with_context(|| format!("Failed to request {url}"));
+        }
+    };
+    if res.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some((bytes, _)) = stored {
+            return Ok(bytes);
+        }
+    }
+    res = res.error_for_status()?;
+    let meta: String = [(reqwest::header::ETAG, "etag"), (reqwest::header::LAST_MODIFIED, "last-modified")]
+        .into_iter()
+        .filter_map(|(header, key)| Some(format!("{key}: {}\n", res.headers().get(header)?.to_str().ok()?)))
+        .collect();
+    let total = res.content_length();
+    let mut bytes = Vec::with_capacity(total.unwrap_or_default() as usize);
+    while let Some(chunk) = res.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        progress(bytes.len() as u64, total);
+    }
+    if let Some(path) = cached {
+        // written under another name first, so that interrupted downloads are never taken as complete
+        let temp = path.with_extension("part");
+        let written = async {
+            tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+            tokio::fs::write(&temp, &bytes).await?;
+            tokio::fs::rename(&temp, &path).await?;
+            tokio::fs::write(path.with_extension("meta"), &meta).await
+        };
+        if let Err(err) = written.await {
+            warn!("Failed to cache {url}: {err:?}");
+        }
+    }
+    Ok(bytes)
+}
+
+/// A chart folder served over HTTP, with files fetched on demand.
+///
+/// Files can't be listed, so the chart needs an info file.
+#[derive(Clone)]
+pub struct HttpFileSystem {
+    client: reqwest::Client,
+    base: Url,
+    cache: Option<PathBuf>,
+}
+
+impl HttpFileSystem {
+    pub fn new(base: &str, cache: Option<PathBuf>) -> Result<Self> {
+        let mut base = Url::parse(base).with_context(|| format!("Invalid URL: {base}"))?;
+        // files are resolved against the folder, not its parent
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base,
+            cache,
+        })
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base.join(path).with_context(|| format!("Invalid path: {path}"))
+    }
+}
+
+#[async_trait]
+impl FileSystem for HttpFileSystem {
+    async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        fetch(&self.client, self.url(path)?.as_str(), self.cache.as_deref(), |_, _| {}).await
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        Ok(self.client.head(self.url(path)?).send().await?.status().is_success())
+    }
+
+    fn list_root(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Opens a chart from `url`, which is either an archive or a folder when ending with `/`.
+///
+/// Archives are downloaded as a whole and kept in `cache` if given, reporting the downloaded and total bytes to `progress`.
+pub async fn fs_from_url(url: &str, cache: Option<PathBuf>, progress: impl FnMut(u64, Option<u64>) + Send) -> Result<Box<dyn FileSystem>> {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path.ends_with('/') {
+        return Ok(Box::new(HttpFileSystem::new(url, cache)?));
+    }
+    let bytes = fetch(&reqwest::Client::new(), url, cache.as_deref(), progress).await?;
+    Ok(Box::new(ZipFileSystem::new(bytes).with_context(|| format!("Cannot open {url} as zip archive"))?))
+}
+
+/// Uploads a packed chart to a sharing server, returning the short code it can be downloaded with.
+pub async fn share_chart(server: &str, bytes: Vec<u8>) -> Result<String> {
+    #[derive(Deserialize)]
+    struct Shared {
+        code: String,
+    }
+    let res = reqwest::Client::new()
+        .post(format!("{}/charts", server.trim_end_matches('/')))
+        .body(bytes)
+        .send()
+        .await
+        .with_context(|| format!("Failed to request {server}"))?
+        .error_for_status()?;
+    Ok(serde_json::from_slice::<Shared>(&res.bytes().await?).context("Invalid response")?.code)
+}
+
+/// Where the chart shared with `code` is downloaded from.
+pub fn shared_chart_url(server: &str, code: &str) -> Result<String> {
+    if code.is_empty() || !code.chars().all(|it| it.is_ascii_alphanumeric()) {
+        bail!("Invalid share code: {code}");
+    }
+    Ok(format!("{}/charts/{code}", server.trim_end_matches('/')))
+}
@@ -0,0 +1,932 @@
+//! Charts in the official game's data packages, for playing them offline from an APK or OBB one has a copy of.
+//!
+//! The packages are zips of Unity asset bundles, in which each track is a folder `Assets/Tracks/<name>.<composer>.<n>` with its
+//! charts as `Chart_<level>.json`, its music as `music.wav` and its illustration as `Illustration.png`. Music is read if it's PCM or
+//! MPEG, or stored as is. Vorbis in FMOD sound banks can't be, since the banks leave out the headers needed to decode it. Illustrations
+//! are read if uncompressed or in ETC or ETC2.
+
+use super::MemoryFileSystem;
+use crate::info::{ChartFormat, ChartInfo, DifficultyTier};
+use anyhow::{anyhow, bail, Context, Result};
+use image::{imageops, DynamicImage, ImageOutputFormat, RgbaImage};
+use miniquad::warn;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{Cursor, Read},
+    path::Path,
+};
+use zip::ZipArchive;
+
+const TEXTURE_2D: i32 = 28;
+const TEXT_ASSET: i32 = 49;
+const AUDIO_CLIP: i32 = 83;
+const ASSET_BUNDLE: i32 = 142;
+
+/// Set on type tree nodes whose values are padded to 4 bytes.
+const ALIGN_FLAG: i32 = 0x4000;
+
+/// Names shared by all type trees, which refer to them by their offsets here with the highest bit set.
+const COMMON_STRINGS: &str = concat!(
+    "AABB\0AnimationClip\0AnimationCurve\0AnimationState\0Array\0Base\0BitField\0bitset\0bool\0char\0ColorRGBA\0Component\0data\0",
+    "deque\0double\0dynamic_array\0FastPropertyName\0first\0float\0Font\0GameObject\0Generic Mono\0GradientNEW\0GUID\0GUIStyle\0int\0",
+    "list\0long long\0map\0Matrix4x4f\0MdFour\0MonoBehaviour\0MonoScript\0m_ByteSize\0m_Curve\0m_EditorClassIdentifier\0",
+    "m_EditorHideFlags\0m_Enabled\0m_ExtensionPtr\0m_GameObject\0m_Index\0m_IsArray\0m_IsStatic\0m_MetaFlag\0m_Name\0",
+    "m_ObjectHideFlags\0m_PrefabInternal\0m_PrefabParentObject\0m_Script\0m_StaticEditorFlags\0m_Type\0m_Version\0Object\0pair\0",
+    "PPtr<Component>\0PPtr<GameObject>\0PPtr<Material>\0PPtr<MonoBehaviour>\0PPtr<MonoScript>\0PPtr<Object>\0PPtr<Prefab>\0",
+    "PPtr<Sprite>\0PPtr<TextAsset>\0PPtr<Texture>\0PPtr<Texture2D>\0PPtr<Transform>\0Prefab\0Quaternionf\0Rectf\0RectInt\0",
+    "RectOffset\0second\0set\0short\0size\0SInt16\0SInt32\0SInt64\0SInt8\0staticvector\0string\0TextAsset\0TextMesh\0Texture\0",
+    "Texture2D\0Transform\0TypelessData\0UInt16\0UInt32\0UInt64\0UInt8\0unsigned int\0unsigned long long\0unsigned short\0vector\0",
+    "Vector2f\0Vector3f\0Vector4f\0m_ScriptingClassIdentifier\0Gradient\0Type*\0int2_storage\0int3_storage\0BoundsInt\0",
+    "m_CorrespondingSourceObject\0m_PrefabInstance\0m_PrefabAsset\0FileSize\0Hash128\0",
+);
+
+const ETC1_MODIFIERS: [[i32; 2]; 8] = [[2, 8], [5, 17], [9, 29], [13, 42], [18, 60], [24, 80], [33, 106], [47, 183]];
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Self { data, pos: 0, big_endian }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let (data, pos) = (self.data, self.pos);
+        let res = pos
+            .checked_add(len)
+            .and_then(|end| data.get(pos..end))
+            .ok_or_else(|| anyhow!("Unexpected end of data"))?;
+        self.pos += len;
+        Ok(res)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut res: [u8; N] = self.bytes(N)?.try_into().unwrap();
+        if self.big_endian {
+            res.reverse();
+        }
+        Ok(res)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        self.array().map(i64::from_le_bytes)
+    }
+
+    /// The number of items that follow, which can't be more than the bytes left, so that malformed data doesn't allocate too much.
+    fn count(&mut self) -> Result<usize> {
+        let count = self.i32()?;
+        usize::try_from(count)
+            .ok()
+            .filter(|it| *it <= self.data.len().saturating_sub(self.pos))
+            .ok_or_else(|| anyhow!("Invalid count {count}"))
+    }
+
+    fn cstring(&mut self) -> Result<String> {
+        let rest = self.data.get(self.pos..).unwrap_or_default();
+        let len = rest.iter().position(|it| *it == 0).ok_or_else(|| anyhow!("Unterminated string"))?;
+        self.pos += len + 1;
+        Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+    }
+
+    fn align(&mut self, to: usize) {
+        self.pos += (to - self.pos % to) % to;
+    }
+}
+
+fn decompress(data: &[u8], flags: u32, size: usize) -> Result<Vec<u8>> {
+    match flags & 0x3f {
+        0 => Ok(data.to_vec()),
+        2 | 3 => Ok(lz4_flex::block::decompress(data, size)?),
+        1 => bail!("LZMA compressed bundles are not supported"),
+        other => bail!("Unknown compression {other}"),
+    }
+}
+
+/// The files in a UnityFS bundle by their names.
+fn read_bundle(data: &[u8]) -> Result<HashMap<String, Vec<u8>>> {
+    let mut r = Reader::new(data, true);
+    if r.cstring()? != "UnityFS" {
+        bail!("Not a UnityFS bundle");
+    }
+    let version = r.u32()?;
+    // versions of the engine it's built with, and the size of the bundle
+    r.cstring()?;
+    r.cstring()?;
+    r.i64()?;
+    let info_size = r.u32()? as usize;
+    let info_uncompressed_size = r.u32()? as usize;
+    let flags = r.u32()?;
+    if version >= 7 {
+        r.align(16);
+    }
+    let info = if flags & 0x80 != 0 {
+        data.len()
+            .checked_sub(info_size)
+            .map(|start| &data[start..])
+            .ok_or_else(|| anyhow!("Unexpected end of data"))?
+    } else {
+        r.bytes(info_size)?
+    };
+    let info_bytes = decompress(info, flags, info_uncompressed_size)?;
+    if flags & 0x200 != 0 {
+        r.align(16);
+    }
+    let mut info = Reader::new(&info_bytes, true);
+    // hash of the data
+    info.bytes(16)?;
+    let mut storage = Vec::new();
+    for _ in 0..info.count()? {
+        let (size, compressed_size, flags) = (info.u32()? as usize, info.u32()? as usize, info.u16()?);
+        storage.extend(decompress(r.bytes(compressed_size)?, flags as u32, size)?);
+    }
+    let mut files = HashMap::new();
+    for _ in 0..info.count()? {
+        let (offset, size) = (info.i64()? as usize, info.i64()? as usize);
+        info.u32()?;
+        let name = info.cstring()?;
+        let file = offset
+            .checked_add(size)
+            .and_then(|end| storage.get(offset..end))
+            .ok_or_else(|| anyhow!("{name} is out of the bundle"))?;
+        files.insert(name, file.to_vec());
+    }
+    Ok(files)
+}
+
+struct TypeNode {
+    ty: String,
+    name: String,
+    level: u8,
+    meta_flag: i32,
+}
+
+struct Object {
+    path_id: i64,
+    class_id: i32,
+    type_index: Option<usize>,
+    start: usize,
+    size: usize,
+}
+
+/// A serialized file in a bundle, with its objects and the type trees to read them with.
+struct SerializedFile<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    types: Vec<(i32, Vec<TypeNode>)>,
+    objects: Vec<Object>,
+}
+
+impl<'a> SerializedFile<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut r = Reader::new(data, true);
+        // sizes of the metadata and the file
+        r.u32()?;
+        r.u32()?;
+        let version = r.u32()?;
+        let mut data_offset = r.u32()? as usize;
+        if version < 9 {
+            bail!("Serialized file version {version} is too old");
+        }
+        let big_endian = r.u8()? != 0;
+        r.bytes(3)?;
+        if version >= 22 {
+            r.u32()?;
+            r.i64()?;
+            data_offset = r.i64()? as usize;
+            r.i64()?;
+        }
+        r.big_endian = big_endian;
+        // version of the engine and the platform
+        r.cstring()?;
+        r.i32()?;
+        let type_trees = version < 13 || r.u8()? != 0;
+        let mut types = Vec::new();
+        for _ in 0..r.count()? {
+            let class_id = r.i32()?;
+            if version >= 16 {
+                r.u8()?;
+            }
+            if version >= 17 {
+                r.i16()?;
+            }
+            if version >= 13 {
+                // script id of MonoBehaviours, and the hash of the type
+                if (version < 16 && class_id < 0) || (version >= 16 && class_id == 114) {
+                    r.bytes(16)?;
+                }
+                r.bytes(16)?;
+            }
+            let nodes = if type_trees {
+                if version < 12 && version != 10 {
+                    bail!("Type trees of version {version} are not supported");
+                }
+                let nodes = read_type_tree(&mut r, version)?;
+                if version >= 21 {
+                    // dependencies
+                    let count = r.count()?;
+                    r.bytes(count * 4)?;
+                }
+                nodes
+            } else {
+                Vec::new()
+            };
+            types.push((class_id, nodes));
+        }
+        let big_ids = (7..14).contains(&version) && r.i32()? != 0;
+        let mut objects = Vec::new();
+        for _ in 0..r.count()? {
+            let path_id = if big_ids {
+                r.i64()?
+            } else if version < 14 {
+                r.i32()? as i64
+            } else {
+                r.align(4);
+                r.i64()?
+            };
+            let start = if version >= 22 { r.i64()? as usize } else { r.u32()? as usize };
+            let size = r.u32()? as usize;
+            let type_id = r.i32()?;
+            let (class_id, type_index) = if version < 16 {
+                (r.u16()? as i32, types.iter().position(|it| it.0 == type_id))
+            } else {
+                let index = usize::try_from(type_id).ok().filter(|it| *it < types.len());
+                (index.map_or(-1, |it| types[it].0), index)
+            };
+            if version < 11 {
+                r.u16()?;
+            }
+            if (11..17).contains(&version) {
+                r.i16()?;
+            }
+            if version == 15 || version == 16 {
+                r.u8()?;
+            }
+            objects.push(Object {
+                path_id,
+                class_id,
+                type_index,
+                start: start.saturating_add(data_offset),
+                size,
+            });
+        }
+        Ok(Self {
+            data,
+            big_endian,
+            types,
+            objects,
+        })
+    }
+
+    fn read(&self, object: &Object) -> Result<Value> {
+        let nodes = object
+            .type_index
+            .and_then(|it| self.types.get(it))
+            .map(|it| &it.1)
+            .filter(|it| !it.is_empty())
+            .ok_or_else(|| anyhow!("No type tree for objects of class {}", object.class_id))?;
+        let data = object
+            .start
+            .checked_add(object.size)
+            .and_then(|end| self.data.get(object.start..end))
+            .ok_or_else(|| anyhow!("Object {} is out of the file", object.path_id))?;
+        read_value(&mut Reader::new(data, self.big_endian), nodes, 0)
+    }
+}
+
+fn read_type_tree(r: &mut Reader, version: u32) -> Result<Vec<TypeNode>> {
+    let count = r.count()?;
+    let strings_len = r.count()?;
+    let mut nodes = Reader::new(r.bytes(count * if version >= 19 { 32 } else { 24 })?, r.big_endian);
+    let strings = r.bytes(strings_len)?;
+    let string = |offset: u32| {
+        let (table, offset) = if offset & 0x8000_0000 != 0 {
+            (COMMON_STRINGS.as_bytes(), offset & 0x7fff_ffff)
+        } else {
+            (strings, offset)
+        };
+        let rest = table.get(offset as usize..).unwrap_or_default();
+        String::from_utf8_lossy(rest.split(|it| *it == 0).next().unwrap_or_default()).into_owned()
+    };
+    (0..count)
+        .map(|_| {
+            nodes.u16()?;
+            let level = nodes.u8()?;
+            nodes.u8()?;
+            let ty = string(nodes.u32()?);
+            let name = string(nodes.u32()?);
+            // size and index
+            nodes.bytes(8)?;
+            let meta_flag = nodes.i32()?;
+            if version >= 19 {
+                nodes.bytes(8)?;
+            }
+            Ok(TypeNode { ty, name, level, meta_flag })
+        })
+        .collect()
+}
+
+/// A value read with a type tree.
+enum Value {
+    Int(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|it| it.0 == name).map(|it| &it.1),
+            _ => None,
+        }
+    }
+
+    fn int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            Self::Float(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<String> {
+        self.bytes().map(|it| String::from_utf8_lossy(it).into_owned())
+    }
+
+    fn array(&self) -> &[Value] {
+        match self {
+            Self::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+/// The index after the node at `i` and its children.
+fn subtree_end(nodes: &[TypeNode], i: usize) -> usize {
+    nodes[i + 1..]
+        .iter()
+        .position(|it| it.level <= nodes[i].level)
+        .map_or(nodes.len(), |it| i + 1 + it)
+}
+
+fn read_value(r: &mut Reader, nodes: &[TypeNode], i: usize) -> Result<Value> {
+    let node = &nodes[i];
+    let mut align = node.meta_flag & ALIGN_FLAG != 0;
+    let value = match node.ty.as_str() {
+        "SInt8" => Value::Int(r.u8()? as i8 as i64),
+        "UInt8" | "char" | "bool" => Value::Int(r.u8()? as i64),
+        "SInt16" | "short" => Value::Int(r.i16()? as i64),
+        "UInt16" | "unsigned short" => Value::Int(r.u16()? as i64),
+        "SInt32" | "int" => Value::Int(r.i32()? as i64),
+        "UInt32" | "unsigned int" | "Type*" => Value::Int(r.u32()? as i64),
+        "SInt64" | "long long" => Value::Int(r.i64()?),
+        "UInt64" | "unsigned long long" | "FileSize" => Value::Int(r.u64()? as i64),
+        "float" => Value::Float(f32::from_bits(r.u32()?) as f64),
+        "double" => Value::Float(f64::from_bits(r.u64()?)),
+        "string" | "TypelessData" => {
+            // the array of characters in a string says whether it's padded
+            align |= node.ty == "string" && nodes.get(i + 1).map_or(false, |it| it.meta_flag & ALIGN_FLAG != 0);
+            let len = r.count()?;
+            Value::Bytes(r.bytes(len)?.to_vec())
+        }
+        _ if nodes.get(i + 1).map_or(false, |it| it.ty == "Array") => {
+            align |= nodes[i + 1].meta_flag & ALIGN_FLAG != 0;
+            let len = r.count()?;
+            // the array's children are its size and then the element
+            let element = i + 3;
+            let item = nodes.get(element).ok_or_else(|| anyhow!("Malformed type tree"))?;
+            if matches!(item.ty.as_str(), "UInt8" | "char") && subtree_end(nodes, element) == element + 1 {
+                Value::Bytes(r.bytes(len)?.to_vec())
+            } else {
+                (0..len).map(|_| read_value(r, nodes, element)).collect::<Result<_>>().map(Value::Array)?
+            }
+        }
+        _ => {
+            let end = subtree_end(nodes, i);
+            let mut fields = Vec::new();
+            let mut j = i + 1;
+            while j < end {
+                fields.push((nodes[j].name.clone(), read_value(r, nodes, j)?));
+                j = subtree_end(nodes, j);
+            }
+            Value::Object(fields)
+        }
+    };
+    if align {
+        r.align(4);
+    }
+    Ok(value)
+}
+
+/// Calls `f` with every asset listed by its path in the bundle, along with the file it's in.
+fn for_each_asset(bundle: &HashMap<String, Vec<u8>>, mut f: impl FnMut(&str, &SerializedFile, &Object) -> Result<()>) -> Result<()> {
+    for (name, data) in bundle {
+        // only hold the data of objects elsewhere
+        if name.ends_with(".resS") || name.ends_with(".resource") {
+            continue;
+        }
+        let file = match SerializedFile::parse(data) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("Failed to read {}: {:?}", name, err);
+                continue;
+            }
+        };
+        for object in file.objects.iter().filter(|it| it.class_id == ASSET_BUNDLE) {
+            let container = match file.read(object) {
+                Ok(value) => value,
+                Err(err) => {
+                    warn!("Failed to read the assets listed in {}: {:?}", name, err);
+                    continue;
+                }
+            };
+            for entry in container.get("m_Container").map_or(&[][..], Value::array) {
+                let (Some(path), Some(asset)) = (entry.get("first").and_then(Value::string), entry.get("second").and_then(|it| it.get("asset")))
+                else {
+                    continue;
+                };
+                // assets in other files are listed by their own bundles as well
+                if asset.get("m_FileID").and_then(Value::int) != Some(0) {
+                    continue;
+                }
+                let path_id = asset.get("m_PathID").and_then(Value::int);
+                if let Some(object) = file.objects.iter().find(|it| Some(it.path_id) == path_id) {
+                    f(&path, &file, object)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The data of an asset kept in a resource file of the bundle, which `source` is the path of.
+fn resource<'a>(bundle: &'a HashMap<String, Vec<u8>>, source: &str, offset: i64, size: i64) -> Result<&'a [u8]> {
+    let name = source.rsplit('/').next().unwrap_or(source);
+    let data = bundle.get(name).ok_or_else(|| anyhow!("Missing resource {name}"))?;
+    let (offset, size) = (offset as usize, size as usize);
+    offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| anyhow!("Data out of resource {name}"))
+}
+
+fn wav(samples: &[u8], channels: u16, rate: u32, bits: u16, float: bool) -> Vec<u8> {
+    let block_align = channels * bits / 8;
+    let mut res = Vec::with_capacity(44 + samples.len());
+    res.extend(b"RIFF");
+    res.extend((36 + samples.len() as u32).to_le_bytes());
+    res.extend(b"WAVEfmt ");
+    res.extend(16u32.to_le_bytes());
+    res.extend((if float { 3u16 } else { 1 }).to_le_bytes());
+    res.extend(channels.to_le_bytes());
+    res.extend(rate.to_le_bytes());
+    res.extend((rate * block_align as u32).to_le_bytes());
+    res.extend(block_align.to_le_bytes());
+    res.extend(bits.to_le_bytes());
+    res.extend(b"data");
+    res.extend((samples.len() as u32).to_le_bytes());
+    res.extend(samples);
+    res
+}
+
+/// Takes the sound out of an FMOD sound bank, as audio clips are stored since Unity 5, returning it with its extension.
+fn read_fsb5(data: &[u8]) -> Result<(&'static str, Vec<u8>)> {
+    let mut r = Reader::new(data, false);
+    r.bytes(4)?;
+    let version = r.u32()?;
+    r.u32()?;
+    let headers_size = r.u32()? as usize;
+    let names_size = r.u32()? as usize;
+    let data_size = r.u32()? as usize;
+    let codec = r.u32()?;
+    r.bytes(if version == 0 { 36 } else { 32 })?;
+    let base = r.pos + headers_size + names_size;
+
+    let header = r.u64()?;
+    let mut channels = (header >> 5 & 1) as u16 + 1;
+    let mut rate = match header >> 1 & 15 {
+        1 => 8000,
+        2 => 11000,
+        3 => 11025,
+        4 => 16000,
+        5 => 22050,
+        6 => 24000,
+        7 => 32000,
+        8 => 44100,
+        9 => 48000,
+        _ => 0,
+    };
+    let offset = (header >> 6 & 0x0fff_ffff) as usize * 16;
+    let mut more = header & 1 != 0;
+    while more {
+        let chunk = r.u32()?;
+        more = chunk & 1 != 0;
+        let mut value = Reader::new(r.bytes((chunk >> 1 & 0xff_ffff) as usize)?, false);
+        match chunk >> 25 {
+            1 => channels = value.u8()? as u16,
+            2 => rate = value.u32()?,
+            _ => {}
+        }
+    }
+    if rate == 0 {
+        bail!("Unknown sample rate");
+    }
+    // a clip is a bank of one sound, which lasts till the end
+    let sound = data
+        .get(base + offset..base + data_size)
+        .ok_or_else(|| anyhow!("Sound out of the bank"))?;
+    Ok(match codec {
+        1 => ("wav", wav(&sound.iter().map(|it| it ^ 0x80).collect::<Vec<_>>(), channels, rate, 8, false)),
+        2 => ("wav", wav(sound, channels, rate, 16, false)),
+        3 => ("wav", wav(sound, channels, rate, 24, false)),
+        4 => ("wav", wav(sound, channels, rate, 32, false)),
+        5 => ("wav", wav(sound, channels, rate, 32, true)),
+        11 => ("mp3", sound.to_vec()),
+        15 => bail!("Vorbis sounds in FMOD banks lack the headers needed to decode them"),
+        _ => bail!("Unsupported FMOD codec {codec}"),
+    })
+}
+
+fn read_audio(data: &[u8]) -> Result<(&'static str, Vec<u8>)> {
+    if data.starts_with(b"FSB5") {
+        return read_fsb5(data);
+    }
+    // stored as is before Unity 5
+    let ext = [(&b"OggS"[..], "ogg"), (b"RIFF", "wav"), (b"fLaC", "flac"), (b"ID3", "mp3")]
+        .into_iter()
+        .find(|it| data.starts_with(it.0))
+        .ok_or_else(|| anyhow!("Unknown audio format"))?
+        .1;
+    Ok((ext, data.to_vec()))
+}
+
+/// Widens a color component of `bits` bits to 8.
+fn extend(value: u8, bits: u32) -> i32 {
+    let value = value as i32;
+    value << (8 - bits) | value >> (2 * bits - 8)
+}
+
+/// The colors of a 4x4 block, by `x * 4 + y`.
+fn paint(colors: impl Fn(usize) -> [i32; 3]) -> [[u8; 4]; 16] {
+    let mut res = [[0, 0, 0, 255]; 16];
+    for (i, pixel) in res.iter_mut().enumerate() {
+        let [r, g, b] = colors(i).map(|it| it.clamp(0, 255) as u8);
+        *pixel = [r, g, b, 255];
+    }
+    res
+}
+
+/// Decodes an ETC1 or ETC2 block of RGB.
+fn etc2_block(b: &[u8; 8]) -> [[u8; 4]; 16] {
+    let (msb, lsb) = (u16::from_be_bytes([b[4], b[5]]), u16::from_be_bytes([b[6], b[7]]));
+    let index = |i: usize| ((msb >> i & 1) << 1 | (lsb >> i & 1)) as usize;
+    let subblocks = |c1: [i32; 3], c2: [i32; 3]| {
+        let flip = b[3] & 1 != 0;
+        let tables = [ETC1_MODIFIERS[(b[3] >> 5) as usize], ETC1_MODIFIERS[(b[3] >> 2 & 7) as usize]];
+        paint(|i| {
+            let (x, y) = (i / 4, i % 4);
+            let (base, table) = if (if flip { y } else { x }) >= 2 {
+                (c2, tables[1])
+            } else {
+                (c1, tables[0])
+            };
+            let modifier = match index(i) {
+                0 => table[0],
+                1 => table[1],
+                2 => -table[0],
+                _ => -table[1],
+            };
+            base.map(|it| it + modifier)
+        })
+    };
+    if b[3] & 2 == 0 {
+        // individual mode
+        return subblocks([b[0] >> 4, b[1] >> 4, b[2] >> 4].map(|it| extend(it, 4)), [b[0] & 15, b[1] & 15, b[2] & 15].map(|it| extend(it, 4)));
+    }
+    let base = [b[0] >> 3, b[1] >> 3, b[2] >> 3];
+    let second = [b[0], b[1], b[2]].map(|it| ((it & 7) << 5) as i8 >> 5).map(i32::from);
+    let second = [0, 1, 2].map(|k| base[k] as i32 + second[k]);
+    if !(0..32).contains(&second[0]) {
+        // T mode
+        let c1 = [(b[0] >> 3 & 3) << 2 | (b[0] & 3), b[1] >> 4, b[1] & 15].map(|it| extend(it, 4));
+        let c2 = [b[2] >> 4, b[2] & 15, b[3] >> 4].map(|it| extend(it, 4));
+        let d = ETC2_DISTANCES[((b[3] >> 2 & 3) << 1 | (b[3] & 1)) as usize];
+        let colors = [c1, c2.map(|it| it + d), c2, c2.map(|it| it - d)];
+        paint(|i| colors[index(i)])
+    } else if !(0..32).contains(&second[1]) {
+        // H mode
+        let c1 = [
+            b[0] >> 3 & 15,
+            (b[0] & 7) << 1 | (b[1] >> 4 & 1),
+            (b[1] & 8) | (b[1] & 3) << 1 | b[2] >> 7,
+        ];
+        let c2 = [b[2] >> 3 & 15, (b[2] & 7) << 1 | b[3] >> 7, b[3] >> 3 & 15];
+        let order = |c: [u8; 3]| (c[0] as u32) << 8 | (c[1] as u32) << 4 | c[2] as u32;
+        let d = ETC2_DISTANCES[((b[3] & 4) | (b[3] & 1) << 1 | (order(c1) >= order(c2)) as u8) as usize];
+        let (c1, c2) = (c1.map(|it| extend(it, 4)), c2.map(|it| extend(it, 4)));
+        let colors = [c1.map(|it| it + d), c1.map(|it| it - d), c2.map(|it| it + d), c2.map(|it| it - d)];
+        paint(|i| colors[index(i)])
+    } else if !(0..32).contains(&second[2]) {
+        // planar mode, a gradient from three colors
+        let widen = |c: [u8; 3]| [extend(c[0], 6), extend(c[1], 7), extend(c[2], 6)];
+        let o = widen([
+            b[0] >> 1 & 63,
+            (b[0] & 1) << 6 | (b[1] >> 1 & 63),
+            (b[1] & 1) << 5 | (b[2] & 0x18) | (b[2] & 3) << 1 | b[3] >> 7,
+        ]);
+        let h = widen([(b[3] >> 2 & 31) << 1 | (b[3] & 1), b[4] >> 1, (b[4] & 1) << 5 | b[5] >> 3]);
+        let v = widen([(b[5] & 7) << 3 | b[6] >> 5, (b[6] & 31) << 2 | b[7] >> 6, b[7] & 63]);
+        paint(|i| {
+            let (x, y) = ((i / 4) as i32, (i % 4) as i32);
+            [0, 1, 2].map(|k| (x * (h[k] - o[k]) + y * (v[k] - o[k]) + 4 * o[k] + 2) >> 2)
+        })
+    } else {
+        // differential mode
+        subblocks(base.map(|it| extend(it, 5)), second.map(|it| extend(it as u8, 5)))
+    }
+}
+
+/// The alpha of the pixel `i` (`x * 4 + y`) in an EAC block.
+fn eac_alpha(b: &[u8], i: usize) -> u8 {
+    let bits = u64::from_be_bytes(b.try_into().unwrap());
+    let (base, multiplier, table) = (b[0] as i32, (b[1] >> 4) as i32, EAC_MODIFIERS[(b[1] & 15) as usize]);
+    let index = (bits >> (45 - 3 * i) & 7) as usize;
+    (base + table[index] * multiplier).clamp(0, 255) as u8
+}
+
+/// Decodes ETC1 or ETC2 blocks, each preceded by an EAC block of the alpha if `alpha`.
+fn decode_etc(data: &[u8], width: usize, height: usize, alpha: bool) -> Result<Vec<u8>> {
+    let blocks_wide = (width + 3) / 4;
+    let block_size = if alpha { 16 } else { 8 };
+    let data = data
+        .get(..blocks_wide * ((height + 3) / 4) * block_size)
+        .ok_or_else(|| anyhow!("Not enough image data"))?;
+    let mut pixels = vec![0; width * height * 4];
+    for (i, block) in data.chunks_exact(block_size).enumerate() {
+        let (alphas, color) = block.split_at(block_size - 8);
+        let colors = etc2_block(color.try_into().unwrap());
+        for (j, mut color) in colors.into_iter().enumerate() {
+            let (x, y) = (i % blocks_wide * 4 + j / 4, i / blocks_wide * 4 + j % 4);
+            if x >= width || y >= height {
+                continue;
+            }
+            if alpha {
+                color[3] = eac_alpha(alphas, j);
+            }
+            let at = (y * width + x) * 4;
+            pixels[at..at + 4].copy_from_slice(&color);
+        }
+    }
+    Ok(pixels)
+}
+
+fn decode_pixels(data: &[u8], width: usize, height: usize, format: i64) -> Result<Vec<u8>> {
+    let plain = |size: usize, f: fn(&[u8]) -> [u8; 4]| -> Result<Vec<u8>> {
+        let data = data.get(..width * height * size).ok_or_else(|| anyhow!("Not enough image data"))?;
+        Ok(data.chunks_exact(size).flat_map(f).collect())
+    };
+    match format {
+        1 => plain(1, |it| [255, 255, 255, it[0]]),
+        3 => plain(3, |it| [it[0], it[1], it[2], 255]),
+        4 => plain(4, |it| [it[0], it[1], it[2], it[3]]),
+        5 => plain(4, |it| [it[1], it[2], it[3], it[0]]),
+        7 => plain(2, |it| {
+            let value = u16::from_le_bytes([it[0], it[1]]);
+            let [r, g, b] = [(value >> 11) as u8, (value >> 5 & 63) as u8, (value & 31) as u8];
+            [extend(r, 5) as u8, extend(g, 6) as u8, extend(b, 5) as u8, 255]
+        }),
+        14 => plain(4, |it| [it[2], it[1], it[0], it[3]]),
+        34 | 45 => decode_etc(data, width, height, false),
+        47 => decode_etc(data, width, height, true),
+        _ => bail!("Unsupported texture format {format}"),
+    }
+}
+
+/// Decodes a texture into a PNG.
+fn read_texture(texture: &Value, bundle: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>> {
+    let field = |value: &Value, name: &str| value.get(name).and_then(Value::int).ok_or_else(|| anyhow!("Missing {name}"));
+    let (width, height) = (field(texture, "m_Width")? as usize, field(texture, "m_Height")? as usize);
+    let mut data = texture.get("image data").and_then(Value::bytes).unwrap_or_default();
+    if data.is_empty() {
+        let stream = texture.get("m_StreamData").ok_or_else(|| anyhow!("Missing image data"))?;
+        let path = stream.get("path").and_then(Value::string).unwrap_or_default();
+        data = resource(bundle, &path, field(stream, "offset")?, field(stream, "size")?)?;
+    }
+    let pixels = decode_pixels(data, width, height, field(texture, "m_TextureFormat")?)?;
+    let mut image = RgbaImage::from_raw(width as u32, height as u32, pixels).ok_or_else(|| anyhow!("Invalid texture size"))?;
+    // rows are stored from the bottom up
+    imageops::flip_vertical_in_place(&mut image);
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    Ok(png)
+}
+
+/// The content of an asset, with the extension it's saved with.
+fn read_asset(file: &SerializedFile, object: &Object, bundle: &HashMap<String, Vec<u8>>) -> Result<(&'static str, Vec<u8>)> {
+    let value = file.read(object)?;
+    match object.class_id {
+        TEXT_ASSET => Ok((
+            "json",
+            value
+                .get("m_Script")
+                .and_then(Value::bytes)
+                .ok_or_else(|| anyhow!("Missing text"))?
+                .to_vec(),
+        )),
+        AUDIO_CLIP => {
+            let stream = value.get("m_Resource").ok_or_else(|| anyhow!("Missing audio data"))?;
+            let field = |name: &str| stream.get(name).and_then(Value::int).ok_or_else(|| anyhow!("Missing {name}"));
+            let source = stream.get("m_Source").and_then(Value::string).unwrap_or_default();
+            read_audio(resource(bundle, &source, field("m_Offset")?, field("m_Size")?)?)
+        }
+        TEXTURE_2D => Ok(("png", read_texture(&value, bundle)?)),
+        class => bail!("Unexpected asset of class {class}"),
+    }
+}
+
+/// Reads the zip entry at `index` as a bundle, or `None` if it isn't one.
+fn open_bundle(zip: &mut ZipArchive<File>, index: usize) -> Option<HashMap<String, Vec<u8>>> {
+    let mut entry = zip.by_index(index).ok()?;
+    let mut magic = [0; 8];
+    if entry.read_exact(&mut magic).is_err() || magic != *b"UnityFS\0" {
+        return None;
+    }
+    let mut data = magic.to_vec();
+    let bundle = entry.read_to_end(&mut data).map_err(anyhow::Error::from).and_then(|_| read_bundle(&data));
+    bundle.map_err(|err| warn!("Failed to read bundle {}: {:?}", entry.name(), err)).ok()
+}
+
+/// Splits `Assets/Tracks/<track>/<file>` into the track and the file.
+fn track_file(path: &str) -> Option<(&str, &str)> {
+    const PREFIX: &str = "assets/tracks/";
+    path.get(..PREFIX.len())
+        .filter(|it| it.eq_ignore_ascii_case(PREFIX))
+        .and_then(|_| path[PREFIX.len()..].split_once('/'))
+        .filter(|(_, file)| !file.contains('/'))
+}
+
+/// The name and composer of a track from its folder, `<name>.<composer>.<n>`.
+fn track_name(track: &str) -> Option<(String, String)> {
+    let mut parts = track.rsplitn(3, '.').skip(1);
+    let composer = parts.next()?;
+    Some((parts.next()?.to_owned(), composer.to_owned()))
+}
+
+/// Whether the file at `path` is an official data package, a zip with asset bundles in `assets/aa`.
+pub fn is_package(path: &Path) -> bool {
+    File::open(path)
+        .ok()
+        .and_then(|it| ZipArchive::new(it).ok())
+        .map_or(false, |zip| zip.file_names().any(|it| it.starts_with("assets/aa/") && it.ends_with(".bundle")))
+}
+
+/// Extracts the charts in the package at `path`, one for each level of a track, and passes them to `save` along with names like
+/// `<track>.<level>` as they're read. Returns how many there were.
+///
+/// Tracks whose music can't be read are skipped.
+pub fn import_package(path: &Path, mut save: impl FnMut(&str, MemoryFileSystem) -> Result<()>) -> Result<usize> {
+    let mut zip = ZipArchive::new(File::open(path).with_context(|| format!("Cannot open {}", path.display()))?)?;
+    // bundles are read twice, first to find out which tracks they're of, so that only the files of one track are kept at a time
+    let mut tracks: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for index in 0..zip.len() {
+        let Some(bundle) = open_bundle(&mut zip, index) else {
+            continue;
+        };
+        for_each_asset(&bundle, |path, _, _| {
+            if let Some((track, _)) = track_file(path) {
+                let bundles = tracks.entry(track.to_owned()).or_default();
+                if !bundles.contains(&index) {
+                    bundles.push(index);
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    let mut count = 0;
+    for (track, bundles) in tracks {
+        let mut assets = BTreeMap::new();
+        for index in bundles {
+            let Some(bundle) = open_bundle(&mut zip, index) else {
+                continue;
+            };
+            for_each_asset(&bundle, |path, file, object| {
+                if let Some((_, name)) = track_file(path).filter(|it| it.0 == track) {
+                    assets.insert(name.to_ascii_lowercase(), read_asset(file, object, &bundle));
+                }
+                Ok(())
+            })?;
+        }
+        let (music_ext, music) = match assets.remove("music.wav") {
+            Some(Ok(music)) => music,
+            Some(Err(err)) => {
+                warn!("Skipping {}, failed to read its music: {:?}", track, err);
+                continue;
+            }
+            None => {
+                warn!("Skipping {}, it has no music", track);
+                continue;
+            }
+        };
+        let illustration = match assets.remove("illustration.png") {
+            Some(Ok((_, png))) => Some(png),
+            Some(Err(err)) => {
+                warn!("Failed to read the illustration of {}: {:?}", track, err);
+                None
+            }
+            None => None,
+        };
+        let (name, composer) = track_name(&track).unwrap_or_else(|| (track.clone(), ChartInfo::default().composer));
+        for (file, chart) in assets {
+            let Some(level) = file.strip_prefix("chart_").and_then(|it| it.strip_suffix(".json")) else {
+                continue;
+            };
+            let chart = match chart {
+                Ok((_, chart)) => chart,
+                Err(err) => {
+                    warn!("Failed to read {} of {}: {:?}", file, track, err);
+                    continue;
+                }
+            };
+            let tier = DifficultyTier::from_label(level);
+            let info = ChartInfo {
+                name: name.clone(),
+                // the official levels aren't in the tracks
+                difficulty: 0.,
+                level: tier.map_or_else(|| level.to_ascii_uppercase(), |it| it.label().to_owned()),
+                tier,
+                composer: composer.clone(),
+                chart: "chart.json".to_owned(),
+                format: Some(ChartFormat::Pgr),
+                music: format!("music.{music_ext}"),
+                illustration: "illustration.png".to_owned(),
+                ..Default::default()
+            };
+            let mut files = HashMap::new();
+            files.insert("info.yml".to_owned(), serde_yaml::to_string(&info)?.into_bytes());
+            files.insert(info.chart.clone(), chart);
+            files.insert(info.music.clone(), music.clone());
+            if let Some(png) = &illustration {
+                files.insert(info.illustration.clone(), png.clone());
+            }
+            save(&format!("{track}.{}", info.level), MemoryFileSystem::new(files))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -1,4 +1,9 @@
+use crate::ext::{JoinToString, ScaleType};
+use macroquad::prelude::Color;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
 
 #[derive(Clone, Serialize, Deserialize)]
 #[repr(u8)]
@@ -9,6 +14,95 @@ pub enum ChartFormat {
     Pgr,
 }
 
+impl ChartFormat {
+    pub const ALL: [Self; 3] = [Self::Rpe, Self::Pec, Self::Pgr];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rpe => "RPE",
+            Self::Pec => "PEC",
+            Self::Pgr => "Phigros",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DifficultyTier {
+    Ez,
+    Hd,
+    In,
+    At,
+    Sp,
+}
+
+impl DifficultyTier {
+    pub const ALL: [DifficultyTier; 5] = [Self::Ez, Self::Hd, Self::In, Self::At, Self::Sp];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ez => "EZ",
+            Self::Hd => "HD",
+            Self::In => "IN",
+            Self::At => "AT",
+            Self::Sp => "SP",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|it| it.label().eq_ignore_ascii_case(label))
+    }
+
+    /// Color of the tier unless overridden in the config.
+    pub fn default_color(&self) -> Color {
+        match self {
+            Self::Ez => Color::from_rgba(0x51, 0xaf, 0x44, 0xff),
+            Self::Hd => Color::from_rgba(0x31, 0x73, 0xb3, 0xff),
+            Self::In => Color::from_rgba(0xbe, 0x2d, 0x23, 0xff),
+            Self::At => Color::from_rgba(0x38, 0x38, 0x38, 0xff),
+            Self::Sp => Color::from_rgba(0x8e, 0x44, 0xad, 0xff),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LoadingIndicator {
+    Text,
+    Spinner,
+    None,
+}
+
+/// Overrides of the loading screen, given by the chart or the resource pack, the former taking precedence.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadingStyle {
+    /// How the illustration is fitted into the jacket.
+    pub illustration_scale: Option<ScaleType>,
+    /// Picked at random when the chart has no tip.
+    pub tips: Vec<String>,
+    pub indicator: Option<LoadingIndicator>,
+    /// How long the card lasts after the chart is loaded, see [`crate::scene::LoadingScene::set_total_time`].
+    pub duration: Option<f32>,
+}
+
+impl LoadingStyle {
+    pub fn is_empty(&self) -> bool {
+        self.illustration_scale.is_none() && self.tips.is_empty() && self.indicator.is_none() && self.duration.is_none()
+    }
+
+    /// Fills what's not given with `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            illustration_scale: self.illustration_scale.or(other.illustration_scale),
+            tips: if self.tips.is_empty() { other.tips } else { self.tips },
+            indicator: self.indicator.or(other.indicator),
+            duration: self.duration.or(other.duration),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +112,8 @@ pub struct ChartInfo {
     pub name: String,
     pub difficulty: f32,
     pub level: String,
+    /// Taken from the first word of `level` if not given.
+    pub tier: Option<DifficultyTier>,
     pub charter: String,
     pub composer: String,
     pub illustrator: String,
@@ -38,6 +134,14 @@ pub struct ChartInfo {
     pub tags: Vec<String>,
 
     pub hold_partial_cover: bool,
+
+    #[serde(skip_serializing_if = "LoadingStyle::is_empty")]
+    pub loading: LoadingStyle,
+
+    /// See [`chart_hash`]. Computed only when needed, by [`crate::library::ensure_hash`] or when the chart is played, never read from or
+    /// written to info files.
+    #[serde(skip)]
+    pub hash: Option<String>,
 }
 
 impl Default for ChartInfo {
@@ -48,6 +152,7 @@ impl Default for ChartInfo {
             name: "UK".to_string(),
             difficulty: 10.,
             level: "UK Lv.10".to_string(),
+            tier: None,
             charter: "UK".to_string(),
             composer: "UK".to_string(),
             illustrator: "UK".to_string(),
@@ -68,6 +173,70 @@ impl Default for ChartInfo {
             tags: Vec::new(),
 
             hold_partial_cover: false,
+
+            loading: LoadingStyle::default(),
+
+            hash: None,
+        }
+    }
+}
+
+impl ChartInfo {
+    pub fn tier(&self) -> Option<DifficultyTier> {
+        self.tier.or_else(|| DifficultyTier::from_label(self.level.split_whitespace().next()?))
+    }
+
+    /// The level with the tier in front of it left out, e.g. `Lv.13` for `IN Lv.13`.
+    pub fn level_without_tier(&self) -> &str {
+        match self.level.trim().split_once(char::is_whitespace) {
+            Some((first, rest)) if DifficultyTier::from_label(first).is_some() => rest.trim_start(),
+            _ => &self.level,
         }
     }
 }
+
+/// Hash of a chart's content, identifying the exact version of it. Metadata and formatting don't affect it.
+pub fn chart_hash(text: &str) -> String {
+    let text = text.trim_start_matches('\u{feff}');
+    let canonical = match serde_json::from_str::<Value>(text) {
+        Ok(mut value) => {
+            if let Some(map) = value.as_object_mut() {
+                // RPE charts carry their name, charter, etc. here
+                map.remove("META");
+            }
+            value.to_string()
+        }
+        Err(_) => text.lines().map(str::trim).filter(|it| !it.is_empty()).join("\n"),
+    };
+    let mut res = String::new();
+    for byte in Sha256::digest(canonical.as_bytes()) {
+        write!(&mut res, "{byte:02x}").unwrap();
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chart_hash_is_sha256() {
+        assert_eq!(chart_hash(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn chart_hash_ignores_json_formatting() {
+        let hash = chart_hash(r#"{"offset":0,"judgeLineList":[{"bpm":120}]}"#);
+        assert_eq!(hash, chart_hash("\u{feff}{\n  \"offset\": 0,\n  \"judgeLineList\": [ { \"bpm\": 120 } ]\n}\n"));
+        // metadata of RPE charts is left out
+        assert_eq!(hash, chart_hash(r#"{"META":{"name":"Renamed"},"offset":0,"judgeLineList":[{"bpm":120}]}"#));
+        assert_ne!(hash, chart_hash(r#"{"offset":1,"judgeLineList":[{"bpm":120}]}"#));
+    }
+
+    #[test]
+    fn chart_hash_ignores_blank_lines() {
+        let hash = chart_hash("0\nbp 0.00 120.00\n");
+        assert_eq!(hash, chart_hash("\u{feff}  0\r\n\r\n\tbp 0.00 120.00  \r\n\r\n"));
+        assert_ne!(hash, chart_hash("0\nbp 0.00 121.00\n"));
+    }
+}
@@ -1,15 +1,18 @@
 use crate::{
-    config::Config,
-    core::{BadNote, Chart, NoteKind, Point, Resource, Vector, JUDGE_LINE_GOOD_COLOR, JUDGE_LINE_PERFECT_COLOR},
+    config::{Config, RateMod},
+    core::{BadNote, Chart, JudgeLine, Note, NoteKind, Point, Resource, Vector, JUDGE_LINE_GOOD_COLOR, JUDGE_LINE_PERFECT_COLOR},
     ext::{get_viewport, NotNanExt},
 };
+use anyhow::{bail, Result};
 use macroquad::prelude::{
     utils::{register_input_subscriber, repeat_all_miniquad_input},
     *,
 };
 use miniquad::{EventHandler, MouseButton};
+use nalgebra::Rotation2;
 use once_cell::sync::Lazy;
 use sasa::{PlaySfxParams, Sfx};
+use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
     collections::{HashMap, VecDeque},
@@ -24,12 +27,31 @@ pub const UP_TOLERANCE: f32 = 0.01;
 pub const DIST_FACTOR: f32 = 0.2;
 
 pub fn play_sfx(sfx: &mut Sfx, config: &Config) {
-    if config.volume_sfx <= 1e-2 {
+    let volume = config.hitsound_volume();
+    if volume <= 1e-2 {
         return;
     }
-    let _ = sfx.play(PlaySfxParams {
-        amplifier: config.volume_sfx,
-    });
+    let _ = sfx.play(PlaySfxParams { amplifier: volume });
+}
+
+/// The stereo pan of the hitsound of `note`, derived from its horizontal position. Only applied to rendered videos, as sasa plays
+/// sound effects without panning.
+pub fn note_pan(lines: &[JudgeLine], line: &JudgeLine, note: &Note, aspect_ratio: f32, config: &Config) -> f32 {
+    if config.hitsound_panning <= 1e-3 {
+        return 0.;
+    }
+    // same as `line.now_transform(..) * note.object.now(..)` applied to the origin
+    let fix = |mut v: Vector| {
+        v.y /= aspect_ratio;
+        v
+    };
+    let mut tr = fix(line.object.translation.now());
+    if let Some(parent) = line.parent {
+        let po = &lines[parent].object;
+        tr = Rotation2::new(po.rotation.now().to_radians()) * tr + fix(po.translation.now());
+    }
+    let x = (Rotation2::new(line.object.rotation.now().to_radians()) * fix(note.object.translation.now()) + tr).x;
+    x.clamp(-1., 1.) * config.hitsound_panning
 }
 
 pub struct VelocityTracker {
@@ -137,7 +159,8 @@ pub enum JudgeStatus {
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Judgement {
     Perfect,
     Good,
@@ -145,6 +168,33 @@ pub enum Judgement {
     Miss,
 }
 
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplayAction {
+    HoldStart { perfect: bool },
+    Judge(Judgement),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReplayEvent {
+    pub time: f32,
+    pub line: u32,
+    pub note: u32,
+    pub action: ReplayAction,
+    pub diff: Option<f32>,
+}
+
+/// Judgements of a play in the order they happened, enough to show the play again without its input.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    pub num_of_notes: u32,
+    /// Hash of the chart played, see [`crate::info::chart_hash`].
+    #[serde(default)]
+    pub chart_hash: Option<String>,
+    pub events: Vec<ReplayEvent>,
+}
+
 #[cfg(not(feature = "closed"))]
 #[derive(Default)]
 pub(crate) struct JudgeInner {
@@ -219,6 +269,7 @@ impl JudgeInner {
             counts: self.counts,
             early,
             late: self.diffs.len() as u32 - early,
+            ..Default::default()
         }
     }
 
@@ -246,6 +297,10 @@ pub struct Judge {
 
     key_down_count: u32,
 
+    recorded: Vec<ReplayEvent>,
+    replay: Option<(Replay, usize)>,
+    timings: Vec<NoteTiming>,
+
     pub(crate) inner: JudgeInner,
 }
 
@@ -272,6 +327,10 @@ impl Judge {
 
             key_down_count: 0,
 
+            recorded: Vec::new(),
+            replay: None,
+            timings: Vec::new(),
+
             inner: JudgeInner::new(chart.lines.iter().map(|it| it.notes.iter().filter(|it| !it.fake).count() as u32).sum()),
         }
     }
@@ -279,9 +338,37 @@ impl Judge {
     pub fn reset(&mut self) {
         self.notes.iter_mut().for_each(|it| it.1 = 0);
         self.trackers.clear();
+        self.recorded.clear();
+        self.timings.clear();
+        if let Some((_, cursor)) = &mut self.replay {
+            *cursor = 0;
+        }
         self.inner.reset();
     }
 
+    /// Plays the judgements of `replay` back instead of judging input.
+    pub fn set_replay(&mut self, replay: Replay) -> Result<()> {
+        let num_of_notes = self.notes.iter().map(|it| it.0.len() as u32).sum::<u32>();
+        if replay.num_of_notes != num_of_notes {
+            bail!("Replay is for a chart with {} notes, but this one has {num_of_notes}", replay.num_of_notes);
+        }
+        self.replay = Some((replay, 0));
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// The judgements made so far, replayable by [`Judge::set_replay`].
+    pub fn replay(&self) -> Replay {
+        Replay {
+            num_of_notes: self.notes.iter().map(|it| it.0.len() as u32).sum(),
+            chart_hash: None,
+            events: self.recorded.clone(),
+        }
+    }
+
     pub fn commit(&mut self, what: Judgement, diff: Option<f32>) {
         self.inner.commit(what, diff);
     }
@@ -330,6 +417,10 @@ impl Judge {
     }
 
     pub fn update(&mut self, res: &mut Resource, chart: &mut Chart, bad_notes: &mut Vec<BadNote>) {
+        if self.replay.is_some() {
+            self.replay_update(res, chart, bad_notes);
+            return;
+        }
         if res.config.autoplay {
             self.auto_play_update(res, chart);
             return;
@@ -447,7 +538,9 @@ impl Judge {
             }
             let mut closest = (None, X_DIFF_MAX, LIMIT_BAD);
             for (line_id, ((line, pos), (idx, st))) in chart.lines.iter_mut().zip(pos.iter()).zip(self.notes.iter_mut()).enumerate() {
-                let Some(pos) = pos[id] else { continue; };
+                let Some(pos) = pos[id] else {
+                    continue;
+                };
                 for id in &idx[*st..] {
                     let note = &mut line.notes[*id as usize];
                     if !matches!(note.judge, JudgeStatus::NotJudged | JudgeStatus::PreJudge) {
@@ -507,7 +600,17 @@ impl Judge {
                             }
                             NoteKind::Hold { .. } => {
                                 play_sfx(&mut res.sfx_click, &res.config);
-                                note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, (t - note.time) / spd, false, f32::INFINITY);
+                                let diff = (t - note.time) / spd;
+                                note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, diff, false, f32::INFINITY);
+                                self.recorded.push(ReplayEvent {
+                                    time: t,
+                                    line: line_id as u32,
+                                    note: id,
+                                    action: ReplayAction::HoldStart {
+                                        perfect: dt <= LIMIT_PERFECT,
+                                    },
+                                    diff: Some(diff),
+                                });
                             }
                             _ => unreachable!(),
                         };
@@ -564,7 +667,17 @@ impl Judge {
                         }
                         NoteKind::Hold { .. } => {
                             play_sfx(&mut res.sfx_click, &res.config);
-                            note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, (t - note.time) / spd, false, f32::INFINITY);
+                            let diff = (t - note.time) / spd;
+                            note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, diff, false, f32::INFINITY);
+                            self.recorded.push(ReplayEvent {
+                                time: t,
+                                line: line_id as u32,
+                                note: id,
+                                action: ReplayAction::HoldStart {
+                                    perfect: dt <= LIMIT_PERFECT,
+                                },
+                                diff: Some(diff),
+                            });
                         }
                         _ => unreachable!(),
                     };
@@ -659,6 +772,27 @@ impl Judge {
                 }
             }
         }
+        self.apply_judgements(res, chart, bad_notes, judgements);
+        for (line, (idx, st)) in chart.lines.iter().zip(self.notes.iter_mut()) {
+            while idx
+                .get(*st)
+                .map_or(false, |id| matches!(line.notes[*id as usize].judge, JudgeStatus::Judged))
+            {
+                *st += 1;
+            }
+        }
+        self.last_time = t / spd;
+    }
+
+    fn apply_judgements(
+        &mut self,
+        res: &mut Resource,
+        chart: &mut Chart,
+        bad_notes: &mut Vec<BadNote>,
+        judgements: Vec<(Judgement, usize, u32, Option<f32>)>,
+    ) {
+        let t = res.time;
+        let spd = res.config.speed;
         for (judgement, line_id, id, diff) in judgements.into_iter() {
             let line = &mut chart.lines[line_id];
             let note = &mut line.notes[id as usize];
@@ -667,14 +801,26 @@ impl Judge {
             let line = &chart.lines[line_id];
             let note = &line.notes[id as usize];
             let line_tr = line.now_transform(res, &chart.lines);
-            self.commit(
+            self.timings.push(NoteTiming {
+                time: note.time,
                 judgement,
-                if matches!(judgement, Judgement::Good | Judgement::Bad) {
-                    Some(diff.unwrap_or((t - note.time) / spd))
-                } else {
-                    None
-                },
-            );
+                diff: (!matches!(judgement, Judgement::Miss)).then(|| diff.unwrap_or((t - note.time) / spd)),
+            });
+            let diff = if matches!(judgement, Judgement::Good | Judgement::Bad) {
+                Some(diff.unwrap_or((t - note.time) / spd))
+            } else {
+                None
+            };
+            self.commit(judgement, diff);
+            if self.replay.is_none() {
+                self.recorded.push(ReplayEvent {
+                    time: t,
+                    line: line_id as u32,
+                    note: id,
+                    action: ReplayAction::Judge(judgement),
+                    diff,
+                });
+            }
             if matches!(note.kind, NoteKind::Hold { .. }) {
                 continue;
             }
@@ -722,6 +868,33 @@ impl Judge {
                 }
             }
         }
+    }
+
+    fn replay_update(&mut self, res: &mut Resource, chart: &mut Chart, bad_notes: &mut Vec<BadNote>) {
+        let t = res.time;
+        let mut judgements = Vec::new();
+        let (replay, cursor) = self.replay.as_mut().unwrap();
+        while let Some(event) = replay.events.get(*cursor).filter(|it| it.time <= t) {
+            *cursor += 1;
+            let Some(note) = chart
+                .lines
+                .get_mut(event.line as usize)
+                .and_then(|it| it.notes.get_mut(event.note as usize))
+            else {
+                continue;
+            };
+            match event.action {
+                ReplayAction::HoldStart { perfect } => {
+                    note.judge = JudgeStatus::Hold(perfect, event.time, event.diff.unwrap_or_default(), false, f32::INFINITY);
+                    play_sfx(&mut res.sfx_click, &res.config);
+                }
+                ReplayAction::Judge(judgement) => {
+                    note.judge = JudgeStatus::Judged;
+                    judgements.push((judgement, event.line as usize, event.note, event.diff));
+                }
+            }
+        }
+        self.apply_judgements(res, chart, bad_notes, judgements);
         for (line, (idx, st)) in chart.lines.iter().zip(self.notes.iter_mut()) {
             while idx
                 .get(*st)
@@ -730,7 +903,7 @@ impl Judge {
                 *st += 1;
             }
         }
-        self.last_time = t / spd;
+        self.last_time = t / res.config.speed;
     }
 
     fn auto_play_update(&mut self, res: &mut Resource, chart: &mut Chart) {
@@ -797,7 +970,10 @@ impl Judge {
 
     #[inline]
     pub fn result(&self) -> PlayResult {
-        self.inner.result()
+        PlayResult {
+            timings: self.timings.clone(),
+            ..self.inner.result()
+        }
     }
 
     #[inline]
@@ -873,6 +1049,16 @@ impl EventHandler for Handler {
     }
 }
 
+/// How a note is hit, for drawing the timing graph.
+#[derive(Clone, Copy)]
+pub struct NoteTiming {
+    /// Time of the note in the chart.
+    pub time: f32,
+    pub judgement: Judgement,
+    /// Seconds the note is hit late by, negative if early. `None` for misses.
+    pub diff: Option<f32>,
+}
+
 #[derive(Default)]
 pub struct PlayResult {
     pub score: u32,
@@ -882,4 +1068,6 @@ pub struct PlayResult {
     pub counts: [u32; 4],
     pub early: u32,
     pub late: u32,
+    pub rate_mod: Option<RateMod>,
+    pub timings: Vec<NoteTiming>,
 }
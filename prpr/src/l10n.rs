@@ -91,6 +91,14 @@ pub fn set_locale_order(locales: &[LanguageIdentifier]) {
     GENERATION.fetch_add(1, Ordering::Relaxed);
 }
 
+/// Prefers `lang` over the system language, as set by [`crate::config::Config::language`].
+pub fn set_language(lang: &str) {
+    match lang.parse::<LanguageIdentifier>() {
+        Ok(lang) if GLOBAL.lang_map.contains_key(&lang) => set_locale_order(&[lang, LANG_IDENTS[0].clone()]),
+        _ => warn!("Unsupported language: {}", lang),
+    }
+}
+
 pub fn locale_order() -> Vec<usize> {
     GLOBAL.order.lock().unwrap().clone()
 }
@@ -0,0 +1,256 @@
+use crate::{config::Config, judge::PlayResult};
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use miniquad::warn;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// How many of the top scores are fetched.
+const LIMIT: usize = 100;
+/// How long a request may take before the server is taken as unreachable.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Guards the file of pending submissions, which is never held across an await.
+static PENDING: Mutex<()> = Mutex::new(());
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaderboardEntry {
+    pub player: String,
+    pub score: u32,
+    pub accuracy: f64,
+    pub max_combo: u32,
+    /// Seconds since the epoch.
+    pub time: i64,
+}
+
+/// A finished play as sent to the server.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub chart: String,
+    pub player: String,
+    pub score: u32,
+    pub accuracy: f64,
+    pub max_combo: u32,
+    pub rate_mod: Option<String>,
+    pub speed: f32,
+    pub time: i64,
+}
+
+impl Submission {
+    pub fn new(chart: String, player: String, result: &PlayResult, speed: f32) -> Self {
+        Self {
+            chart,
+            player,
+            score: result.score,
+            accuracy: result.accuracy,
+            max_combo: result.max_combo,
+            rate_mod: result.rate_mod.map(|it| it.label().to_owned()),
+            speed,
+            time: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+pub struct Scores {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Whether the server couldn't be reached and the entries are those fetched last time.
+    pub offline: bool,
+}
+
+enum Sent {
+    Accepted,
+    Rejected,
+}
+
+fn read_json<T: for<'a> Deserialize<'a>>(path: &Path) -> Option<T> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(path, serde_json::to_vec(value)?)?;
+    Ok(())
+}
+
+/// An HTTP service keeping the top scores of each chart by its hash.
+///
+/// With a cache folder, scores are cached so that the last ones fetched can still be shown offline, and submissions that can't be sent
+/// are kept to be retried the next time the service is used.
+#[derive(Clone)]
+pub struct Leaderboard {
+    client: reqwest::Client,
+    server: String,
+    key: Option<String>,
+    cache: Option<PathBuf>,
+}
+
+impl Leaderboard {
+    /// The service set in `config`, if any.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let server = config.leaderboard_server.as_ref().filter(|it| !it.is_empty())?;
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .map_err(|err| warn!("Failed to build leaderboard client: {:?}", err))
+            .ok()?;
+        Some(Self {
+            client,
+            server: server.trim_end_matches('/').to_owned(),
+            key: config.leaderboard_key.clone(),
+            cache: config.leaderboard_cache.as_ref().map(PathBuf::from),
+        })
+    }
+
+    fn scores_url(&self, hash: &str) -> String {
+        format!("{}/charts/{hash}/scores", self.server)
+    }
+
+    fn pending_path(&self) -> Option<PathBuf> {
+        Some(self.cache.as_ref()?.join("pending.json"))
+    }
+
+    /// Fetches the top scores of the chart with `hash`, falling back to the cached ones if the server can't be reached.
+    /// Pending submissions are retried meanwhile, without holding the scores back.
+    pub async fn top(&self, hash: &str) -> Result<Scores> {
+        let leaderboard = self.clone();
+        tokio::spawn(async move { leaderboard.flush().await });
+        let cached = self.cache.as_ref().map(|it| it.join(format!("{hash}.json")));
+        match self.fetch(hash).await {
+            Ok(entries) => {
+                if let Some(Err(err)) = cached.map(|it| write_json(&it, &entries)) {
+                    warn!("Failed to cache leaderboard: {:?}", err);
+                }
+                Ok(Scores { entries, offline: false })
+            }
+            Err(err) => match cached.and_then(|it| read_json(&it)) {
+                Some(entries) => {
+                    warn!("Failed to fetch leaderboard, showing the cached one: {:?}", err);
+                    Ok(Scores { entries, offline: true })
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    async fn fetch(&self, hash: &str) -> Result<Vec<LeaderboardEntry>> {
+        let res = self
+            .client
+            .get(format!("{}?limit={LIMIT}", self.scores_url(hash)))
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", self.server))?
+            .error_for_status()?;
+        serde_json::from_slice(&res.bytes().await?).context("Invalid response")
+    }
+
+    fn sign(&self, body: &[u8]) -> Option<String> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_ref()?.as_bytes()).unwrap();
+        mac.update(body);
+        let mut signature = String::new();
+        for byte in mac.finalize().into_bytes() {
+            write!(&mut signature, "{byte:02x}").unwrap();
+        }
+        Some(signature)
+    }
+
+    async fn send(&self, submission: &Submission) -> Result<Sent> {
+        let body = serde_json::to_vec(submission)?;
+        let mut req = self
+            .client
+            .post(self.scores_url(&submission.chart))
+            .header("Content-Type", "application/json");
+        if let Some(signature) = self.sign(&body) {
+            req = req.header("X-Signature", signature);
+        }
+        let res = req
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", self.server))?;
+        if res.status().is_client_error() {
+            return Ok(Sent::Rejected);
+        }
+        res.error_for_status()?;
+        Ok(Sent::Accepted)
+    }
+
+    /// Submits a result, keeping it to be sent later if the server can't be reached.
+    pub async fn submit(&self, submission: Submission) {
+        let Some(path) = self.pending_path() else {
+            // nowhere to keep it for later
+            match self.send(&submission).await {
+                Ok(Sent::Accepted) => {}
+                Ok(Sent::Rejected) => warn!("Leaderboard rejected the result of {}", submission.chart),
+                Err(err) => warn!("Failed to submit result, which is dropped as leaderboardCache is not set: {:?}", err),
+            }
+            return;
+        };
+        {
+            let _guard = PENDING.lock().unwrap();
+            let mut pending: Vec<Submission> = read_json(&path).unwrap_or_default();
+            pending.push(submission);
+            if let Err(err) = write_json(&path, &pending) {
+                warn!("Failed to save pending submission: {:?}", err);
+            }
+        }
+        self.flush().await;
+    }
+
+    /// Submits a result without waiting for it, see [`Self::submit`].
+    pub fn submit_in_background(&self, submission: Submission) {
+        let leaderboard = self.clone();
+        tokio::spawn(async move { leaderboard.submit(submission).await });
+    }
+
+    /// Sends the pending submissions in order, stopping at the first that fails.
+    async fn flush(&self) {
+        let Some(path) = self.pending_path() else {
+            return;
+        };
+        if FLUSHING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let pending: Vec<Submission> = {
+            let _guard = PENDING.lock().unwrap();
+            let pending = read_json(&path).unwrap_or_default();
+            let _ = std::fs::remove_file(&path);
+            pending
+        };
+        let mut left = Vec::new();
+        for submission in pending {
+            if !left.is_empty() {
+                left.push(submission);
+                continue;
+            }
+            match self.send(&submission).await {
+                Ok(Sent::Accepted) => {}
+                Ok(Sent::Rejected) => warn!("Leaderboard rejected the result of {}", submission.chart),
+                Err(err) => {
+                    warn!("Failed to submit result, will retry later: {:?}", err);
+                    left.push(submission);
+                }
+            }
+        }
+        if !left.is_empty() {
+            let _guard = PENDING.lock().unwrap();
+            // submitted meanwhile
+            left.extend(read_json::<Vec<Submission>>(&path).unwrap_or_default());
+            if let Err(err) = write_json(&path, &left) {
+                warn!("Failed to save pending submissions: {:?}", err);
+            }
+        }
+        FLUSHING.store(false, Ordering::SeqCst);
+    }
+}
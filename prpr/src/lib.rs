@@ -5,9 +5,16 @@ pub mod fs;
 pub mod info;
 pub mod judge;
 pub mod l10n;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub mod leaderboard;
+pub mod library;
 pub mod parse;
 pub mod particle;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub mod repository;
 pub mod scene;
+#[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+pub mod scores;
 pub mod task;
 pub mod time;
 pub mod ui;
@@ -28,3 +35,18 @@ pub fn build_conf() -> macroquad::window::Conf {
         ..Default::default()
     }
 }
+
+/// The optional features prpr is built with.
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("closed", cfg!(feature = "closed")),
+        ("http", cfg!(feature = "http")),
+        ("rar", cfg!(feature = "rar")),
+        ("scores", cfg!(feature = "scores")),
+        ("sevenz", cfg!(feature = "sevenz")),
+    ]
+    .into_iter()
+    .filter(|it| it.1)
+    .map(|it| it.0)
+    .collect()
+}
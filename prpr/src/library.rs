@@ -0,0 +1,333 @@
+crate::tl_file!("library");
+
+use crate::{
+    fs::{self, FileSystem},
+    info::{chart_hash, ChartInfo},
+    scene::GameScene,
+};
+use anyhow::{Context, Result};
+use miniquad::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write,
+    ops::{DerefMut, RangeInclusive},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const INDEX_FILE: &str = "library.json";
+const THUMBNAIL_DIR: &str = ".thumbnails";
+const THUMBNAIL_SIZE: u32 = 256;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryEntry {
+    /// Path of the chart relative to the library.
+    pub path: String,
+    pub info: ChartInfo,
+    pub hash: Option<String>,
+    /// Path of the thumbnail of the illustration relative to the library.
+    pub thumbnail: Option<String>,
+    /// Modification time of the chart when indexed, in seconds since the Unix epoch.
+    pub modified: u64,
+}
+
+#[derive(Default)]
+pub struct LibraryQuery {
+    /// Matched against the name and composer, ignoring case.
+    pub title: Option<String>,
+    pub charter: Option<String>,
+    pub difficulty: Option<RangeInclusive<f32>>,
+}
+
+impl LibraryQuery {
+    fn matches(&self, entry: &LibraryEntry) -> bool {
+        fn contains(s: &str, pattern: &str) -> bool {
+            s.to_lowercase().contains(&pattern.to_lowercase())
+        }
+        let info = &entry.info;
+        self.title
+            .as_ref()
+            .map_or(true, |it| contains(&info.name, it) || contains(&info.composer, it))
+            && self.charter.as_ref().map_or(true, |it| contains(&info.charter, it))
+            && self.difficulty.as_ref().map_or(true, |it| it.contains(&info.difficulty))
+    }
+}
+
+/// Hashes the chart if it isn't yet, see [`chart_hash`]. Only scores and settings kept per chart need it, so it's left to them to ask
+/// for it rather than reading every chart whose info is loaded.
+pub async fn ensure_hash<'a>(fs: &mut dyn FileSystem, info: &'a mut ChartInfo) -> Option<&'a str> {
+    if info.hash.is_none() {
+        match GameScene::load_chart_bytes(fs, info).await {
+            Ok(bytes) => info.hash = Some(chart_hash(&String::from_utf8_lossy(&bytes))),
+            Err(err) => warn!("Failed to hash the chart: {:?}", err),
+        }
+    }
+    info.hash.as_deref()
+}
+
+/// Charts in a folder, indexed into a file in it so that they don't have to be opened every time.
+#[derive(Clone)]
+pub struct Library {
+    root: PathBuf,
+    pub entries: Vec<LibraryEntry>,
+}
+
+impl Library {
+    /// Opens the library at `root` with the index saved before, which may be out of date until [`Library::scan`].
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        let mut entries: Vec<LibraryEntry> = match std::fs::read(root.join(INDEX_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).context("Invalid library index")?,
+            Err(_) => Vec::new(),
+        };
+        for entry in &mut entries {
+            entry.info.hash = entry.hash.clone();
+        }
+        Ok(Self { root, entries })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn modified_time(path: &Path) -> u64 {
+        std::fs::metadata(path)
+            .and_then(|it| it.modified())
+            .ok()
+            .and_then(|it| it.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |it| it.as_secs())
+    }
+
+    /// Brings the index up to date with the folder, indexing new and modified charts and dropping removed ones. Returns the paths newly indexed.
+    pub async fn scan(&mut self) -> Result<Vec<String>> {
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(&self.root).context("Cannot list charts")? {
+            let entry = entry?;
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if name.starts_with('.') || name == INDEX_FILE {
+                continue;
+            }
+            found.push(name);
+        }
+        let count = self.entries.len();
+        self.entries.retain(|it| found.contains(&it.path));
+        let mut changed = self.entries.len() != count;
+        let mut added = Vec::new();
+        for name in found {
+            let modified = Self::modified_time(&self.root.join(&name));
+            let index = self.entries.iter().position(|it| it.path == name);
+            if index.map_or(false, |it| self.entries[it].modified == modified) {
+                continue;
+            }
+            match self.index_chart(&name, modified).await {
+                Ok(entry) => {
+                    changed = true;
+                    if let Some(index) = index {
+                        self.entries[index] = entry;
+                    } else {
+                        self.entries.push(entry);
+                        added.push(name);
+                    }
+                }
+                Err(err) => {
+                    warn!("Failed to index {}: {:?}", name, err);
+                }
+            }
+        }
+        if changed {
+            if let Err(err) = self.remove_stale_thumbnails() {
+                warn!("Failed to remove thumbnails of removed charts: {:?}", err);
+            }
+        }
+        Ok(added)
+    }
+
+    async fn index_chart(&self, name: &str, modified: u64) -> Result<LibraryEntry> {
+        let mut fs = fs::fs_from_file(&self.root.join(name))?;
+        let mut info = fs::load_info(fs.deref_mut()).await?;
+        // kept in the index, so that best scores can be shown without reading every chart
+        ensure_hash(fs.deref_mut(), &mut info).await;
+        let thumbnail = match fs.load_file(&info.illustration).await {
+            Ok(bytes) => self
+                .save_thumbnail(name, &bytes)
+                .map_err(|err| warn!("Failed to make thumbnail of {}: {:?}", name, err))
+                .ok(),
+            Err(_) => None,
+        };
+        Ok(LibraryEntry {
+            path: name.to_owned(),
+            hash: info.hash.clone(),
+            info,
+            thumbnail,
+            modified,
+        })
+    }
+
+    fn save_thumbnail(&self, name: &str, illustration: &[u8]) -> Result<String> {
+        let image = image::load_from_memory(illustration)?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+        // named by the path of the chart, which stays the same when it's replaced, unlike its hash
+        let mut path = format!("{THUMBNAIL_DIR}/");
+        for byte in Sha256::digest(name.as_bytes()) {
+            write!(&mut path, "{byte:02x}").unwrap();
+        }
+        path += ".jpg";
+        std::fs::create_dir_all(self.root.join(THUMBNAIL_DIR))?;
+        image.into_rgb8().save(self.root.join(&path))?;
+        Ok(path)
+    }
+
+    /// Deletes the thumbnails no entry refers to, left behind by charts removed or replaced.
+    fn remove_stale_thumbnails(&self) -> Result<()> {
+        let Ok(files) = std::fs::read_dir(self.root.join(THUMBNAIL_DIR)) else {
+            return Ok(());
+        };
+        for file in files {
+            let path = format!("{THUMBNAIL_DIR}/{}", file?.file_name().to_string_lossy());
+            if !self.entries.iter().any(|it| it.thumbnail.as_ref() == Some(&path)) {
+                std::fs::remove_file(self.root.join(&path))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::write(self.root.join(INDEX_FILE), serde_json::to_vec(&self.entries)?).context("Failed to save library index")
+    }
+
+    pub fn query<'a>(&'a self, query: &'a LibraryQuery) -> impl Iterator<Item = &'a LibraryEntry> + 'a {
+        self.entries.iter().filter(|it| query.matches(it))
+    }
+}
+
+/// Scans a library every few seconds while the game runs, announcing charts dropped into it on the billboard.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct LibraryWatcher {
+    library: Library,
+    interval: f64,
+    last_scan: f64,
+    task: Option<crate::task::Task<(Library, Result<Vec<String>>)>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LibraryWatcher {
+    pub fn new(library: Library, interval: f64) -> Self {
+        Self {
+            library,
+            interval,
+            last_scan: f64::NEG_INFINITY,
+            task: None,
+        }
+    }
+
+    pub fn library(&self) -> &Library {
+        &self.library
+    }
+
+    /// Should be called every frame. Returns the charts added since the last call.
+    pub fn update(&mut self) -> Vec<&LibraryEntry> {
+        let now = macroquad::time::get_time();
+        let Some(task) = &mut self.task else {
+            if now - self.last_scan >= self.interval {
+                let mut library = self.library.clone();
+                self.task = Some(crate::task::Task::new(async move {
+                    let result = library.scan().await;
+                    (library, result)
+                }));
+            }
+            return Vec::new();
+        };
+        let Some((library, result)) = task.take() else {
+            return Vec::new();
+        };
+        self.task = None;
+        self.last_scan = now;
+        self.library = library;
+        let added = match result {
+            Ok(added) => added,
+            Err(err) => {
+                warn!("Failed to scan library: {:?}", err);
+                return Vec::new();
+            }
+        };
+        if let Err(err) = self.library.save() {
+            warn!("{:?}", err);
+        }
+        let added: Vec<_> = self.library.entries.iter().filter(|it| added.contains(&it.path)).collect();
+        for entry in &added {
+            crate::scene::show_message(tl!("chart-added", "name" => entry.info.name.clone()));
+        }
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, composer: &str, charter: &str, difficulty: f32) -> LibraryEntry {
+        LibraryEntry {
+            path: format!("{name}.pez"),
+            info: ChartInfo {
+                name: name.to_owned(),
+                composer: composer.to_owned(),
+                charter: charter.to_owned(),
+                difficulty,
+                ..Default::default()
+            },
+            hash: None,
+            thumbnail: None,
+            modified: 0,
+        }
+    }
+
+    fn library() -> Library {
+        Library {
+            root: PathBuf::new(),
+            entries: vec![
+                entry("Spasmodic", "Sakuzyo", "Ctymax", 15.),
+                entry("Rrhar'il", "Team Grimoire", "Ctymax", 15.8),
+                entry("Glaciaxion", "SunsetRay", "NerSAN", 6.),
+            ],
+        }
+    }
+
+    fn query(query: LibraryQuery) -> Vec<String> {
+        library().query(&query).map(|it| it.info.name.clone()).collect()
+    }
+
+    #[test]
+    fn query_everything() {
+        assert_eq!(query(LibraryQuery::default()), ["Spasmodic", "Rrhar'il", "Glaciaxion"]);
+    }
+
+    #[test]
+    fn query_title_matches_name_or_composer() {
+        let title = |it: &str| LibraryQuery {
+            title: Some(it.to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(query(title("GLACI")), ["Glaciaxion"]);
+        assert_eq!(query(title("grimoire")), ["Rrhar'il"]);
+        assert!(query(title("NerSAN")).is_empty());
+    }
+
+    #[test]
+    fn query_combines_filters() {
+        let result = query(LibraryQuery {
+            charter: Some("ctymax".to_owned()),
+            difficulty: Some(15.5..=16.),
+            ..Default::default()
+        });
+        assert_eq!(result, ["Rrhar'il"]);
+        // the range includes its ends
+        let result = query(LibraryQuery {
+            difficulty: Some(6.0..=15.),
+            ..Default::default()
+        });
+        assert_eq!(result, ["Spasmodic", "Glaciaxion"]);
+    }
+}
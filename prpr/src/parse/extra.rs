@@ -185,7 +185,7 @@ pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem, ffmpeg: Option<&
             videos.push(
                 Video::new(
                     ffmpeg,
-                    fs.load_file(&video.path)
+                    fs.open_file(&video.path)
                         .await
                         .with_context(|| format!("Failed to read video from {}", video.path))?,
                     r.time(&video.time),
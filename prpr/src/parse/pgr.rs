@@ -217,6 +217,11 @@ fn parse_judge_line(pgr: PgrJudgeLine, max_time: f32) -> Result<JudgeLine> {
 
 pub fn parse_phigros(source: &str, extra: ChartExtra) -> Result<Chart> {
     let pgr: PgrChart = serde_json::from_str(source).context("Failed to parse JSON")?;
+    // judge lines may have their own BPM, we take the first one as the chart BPM
+    let bpm_list = pgr
+        .judge_line_list
+        .first()
+        .map_or_else(BpmList::default, |line| BpmList::new(vec![(0., line.bpm)]));
     let max_time = *pgr
         .judge_line_list
         .iter()
@@ -239,5 +244,5 @@ pub fn parse_phigros(source: &str, extra: ChartExtra) -> Result<Chart> {
         .map(|(id, pgr)| parse_judge_line(pgr, max_time).with_context(|| format!("In judge line #{id}")))
         .collect::<Result<Vec<_>>>()?;
     process_lines(&mut lines);
-    Ok(Chart::new(pgr.offset, lines, BpmList::default(), ChartSettings::default(), extra))
+    Ok(Chart::new(pgr.offset, lines, bpm_list, ChartSettings::default(), extra))
 }
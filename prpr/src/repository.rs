@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// How many charts a page of search results has.
+pub const PAGE_SIZE: u32 = 20;
+
+/// A chart listed by a repository. URLs may be relative to the repository.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteChart {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub composer: String,
+    #[serde(default)]
+    pub charter: String,
+    #[serde(default)]
+    pub level: String,
+    /// In seconds.
+    pub length: Option<f32>,
+    pub jacket: Option<String>,
+    pub file: String,
+}
+
+impl RemoteChart {
+    /// The name the chart is saved under in a library.
+    pub fn file_name(&self) -> String {
+        let name: String = self.name.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+        let id: String = self.id.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        format!("{name}-{id}.pez")
+    }
+}
+
+/// A community chart repository, listing charts at `GET /charts?q=<query>&page=<page>`.
+#[derive(Clone)]
+pub struct ChartRepository {
+    client: reqwest::Client,
+    base: Url,
+}
+
+impl ChartRepository {
+    pub fn new(base: &str) -> Result<Self> {
+        let mut base = base.to_owned();
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base: Url::parse(&base).with_context(|| format!("Invalid repository URL: {base}"))?,
+        })
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base.join(path).with_context(|| format!("Invalid URL: {path}"))
+    }
+
+    async fn get(&self, url: Url) -> Result<reqwest::Response> {
+        Ok(self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {url}"))?
+            .error_for_status()?)
+    }
+
+    /// Searches charts by `query`, all of them if empty, with pages starting from 0.
+    pub async fn search(&self, query: &str, page: u32) -> Result<Vec<RemoteChart>> {
+        let mut url = self.url("charts")?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("page", &page.to_string())
+            .append_pair("limit", &PAGE_SIZE.to_string());
+        serde_json::from_slice(&self.get(url).await?.bytes().await?).context("Invalid response")
+    }
+
+    pub async fn jacket(&self, chart: &RemoteChart) -> Result<Vec<u8>> {
+        let url = self.url(chart.jacket.as_deref().context("No jacket")?)?;
+        Ok(self.get(url).await?.bytes().await?.to_vec())
+    }
+
+    /// Downloads the chart into the folder `dir`, reporting the downloaded and total bytes to `progress`. Returns where it's saved.
+    pub async fn download(&self, chart: &RemoteChart, dir: &Path, mut progress: impl FnMut(u64, Option<u64>)) -> Result<PathBuf> {
+        let mut res = self.get(self.url(&chart.file)?).await?;
+        let total = res.content_length();
+        let mut bytes = Vec::with_capacity(total.unwrap_or_default() as usize);
+        while let Some(chunk) = res.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            progress(bytes.len() as u64, total);
+        }
+        let path = dir.join(chart.file_name());
+        // hidden until complete, so that libraries don't pick it up halfway
+        let temp = dir.join(format!(".{}.part", chart.file_name()));
+        tokio::fs::write(&temp, &bytes).await.context("Failed to save chart")?;
+        tokio::fs::rename(&temp, &path).await.context("Failed to save chart")?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(id: &str, name: &str) -> RemoteChart {
+        serde_json::from_value(serde_json::json!({ "id": id, "name": name, "file": "charts/a.pez" })).unwrap()
+    }
+
+    #[test]
+    fn file_name_is_safe() {
+        assert_eq!(chart("a1b2-c3", "Spasmodic (Extended)").file_name(), "Spasmodic__Extended_-a1b2c3.pez");
+        assert_eq!(chart("42", "狂喜乱舞/Ω").file_name(), "狂喜乱舞_Ω-42.pez");
+        // neither part can leave the library
+        assert_eq!(chart("../../etc", "..").file_name(), "__-etc.pez");
+    }
+
+    #[test]
+    fn remote_chart_defaults() {
+        let chart = chart("1", "Name");
+        assert_eq!((chart.composer.as_str(), chart.charter.as_str(), chart.level.as_str()), ("", "", ""));
+        assert!(chart.length.is_none() && chart.jacket.is_none());
+    }
+
+    #[test]
+    fn urls_relative_to_repository() {
+        let repo = ChartRepository::new("https://example.com/repo").unwrap();
+        assert_eq!(repo.url("charts").unwrap().as_str(), "https://example.com/repo/charts");
+        assert_eq!(repo.url("/files/a.pez").unwrap().as_str(), "https://example.com/files/a.pez");
+        assert_eq!(repo.url("https://cdn.example.com/a.png").unwrap().as_str(), "https://cdn.example.com/a.png");
+        assert!(ChartRepository::new("not a url").is_err());
+    }
+}
@@ -4,18 +4,39 @@ mod ending;
 pub use ending::{EndingScene, RecordUpdateState};
 
 mod game;
-pub use game::{GameMode, GameScene, FFMPEG_PATH};
+pub use game::{reload_config, GameMode, GameScene, FFMPEG_PATH};
+
+mod hud;
+pub use hud::take_hud_layout;
+
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+mod leaderboard;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub use leaderboard::LeaderboardScene;
 
 mod loading;
 pub use loading::LoadingScene;
 
+mod pause;
+pub use pause::{PauseAction, PauseMenu};
+
+#[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+mod stats;
+#[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+pub use stats::StatsScene;
+
+mod tutorial;
+pub use tutorial::TutorialScene;
+
 use crate::{
-    ext::{draw_image, screen_aspect, SafeTexture, ScaleType},
+    config::SceneTransition,
+    core::{easing_from, TweenMajor, TweenMinor},
+    ext::{draw_image, screen_aspect, source_of_image, SafeTexture, ScaleType},
     judge::Judge,
     time::TimeManager,
-    ui::{BillBoard, Dialog, Message, MessageHandle, MessageKind, Ui},
+    ui::{BillBoard, Dialog, Eased, Message, MessageHandle, MessageKind, Ui},
 };
-use anyhow::{Error, Result};
+use anyhow::{bail, Error, Result};
 use cfg_if::cfg_if;
 use macroquad::prelude::*;
 use std::{any::Any, cell::RefCell, sync::Mutex};
@@ -32,6 +53,18 @@ pub enum NextScene {
     Replace(Box<dyn Scene>),
 }
 
+impl NextScene {
+    /// Pops the current scene, passing `result` to [`Scene::on_result`] of the one below, which can get it back with [`downcast_result`].
+    pub fn pop_with<T: Any>(result: T) -> Self {
+        Self::PopWithResult(Box::new(result))
+    }
+}
+
+/// Takes the result passed to [`Scene::on_result`] if it's a `T`, or gives it back to try other types.
+pub fn downcast_result<T: Any>(result: Box<dyn Any>) -> Result<T, Box<dyn Any>> {
+    result.downcast().map(|it| *it)
+}
+
 thread_local! {
     pub static BILLBOARD: RefCell<(BillBoard, TimeManager)> = RefCell::new((BillBoard::new(), TimeManager::default()));
     pub static DIALOG: RefCell<Option<Dialog>> = RefCell::new(None);
@@ -84,13 +117,16 @@ impl MessageBuilder {
         self.kind(MessageKind::Error)
     }
 
+    /// Keeps the message until it's tapped or dismissed with its handle.
+    #[inline]
+    pub fn sticky(self) -> Self {
+        self.duration(f32::INFINITY)
+    }
+
     fn show(&mut self) -> MessageHandle {
-        BILLBOARD.with(|it| {
-            let mut guard = it.borrow_mut();
-            let (msg, handle) = Message::new(std::mem::take(&mut self.content), guard.1.now() as _, self.duration, self.kind.clone());
-            guard.0.add(msg);
-            handle
-        })
+        let (msg, handle) = Message::new(std::mem::take(&mut self.content), self.duration, self.kind.clone());
+        msg.show();
+        handle
     }
 
     #[inline]
@@ -304,6 +340,10 @@ pub trait Scene {
     fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
         NextScene::None
     }
+    /// Whether switching to this scene is covered by [`Main::transition`]. Scenes with an entrance of their own, like the game, opt out.
+    fn uses_transition(&self) -> bool {
+        true
+    }
 }
 
 pub trait RenderTargetChooser {
@@ -329,7 +369,11 @@ pub struct Main {
     last_update_time: f64,
     should_exit: bool,
     pub show_billboard: bool,
+    /// How scenes are switched between. None by default, which is what rendered videos use.
+    pub transition: SceneTransition,
     touches: Option<Vec<Touch>>,
+    /// How much of the scene just switched to is revealed, from 0 to 1.
+    reveal: Eased<f32>,
 }
 
 impl Main {
@@ -353,7 +397,9 @@ impl Main {
             last_update_time,
             should_exit: false,
             show_billboard: true,
+            transition: SceneTransition::None,
             touches: None,
+            reveal: Eased::new(1., 0.4, easing_from(TweenMajor::Quad, TweenMinor::Out)),
         })
     }
 
@@ -365,56 +411,40 @@ impl Main {
         if self.paused {
             return Ok(());
         }
-        match self.scenes.last_mut().unwrap().next_scene(&mut self.tm) {
-            NextScene::None => {}
-            NextScene::Pop => {
-                self.scenes.pop();
-                self.tm.seek_to(self.times.pop().unwrap());
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
-            }
-            NextScene::PopN(num) => {
-                for _ in 0..num {
-                    self.scenes.pop();
-                    self.tm.seek_to(self.times.pop().unwrap());
-                }
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
-            }
-            NextScene::PopWithResult(result) => {
-                self.scenes.pop();
-                self.tm.seek_to(self.times.pop().unwrap());
-                self.scenes.last_mut().unwrap().on_result(&mut self.tm, result)?;
-                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
-            }
-            NextScene::Exit => {
-                self.should_exit = true;
-            }
-            NextScene::Overlay(mut scene) => {
-                self.times.push(self.tm.now());
-                scene.enter(&mut self.tm, self.target_chooser.choose())?;
-                self.scenes.push(scene);
-            }
-            NextScene::Replace(mut scene) => {
-                scene.enter(&mut self.tm, self.target_chooser.choose())?;
-                *self.scenes.last_mut().unwrap() = scene;
-            }
-        }
+        let next_scene = self.scenes.last_mut().unwrap().next_scene(&mut self.tm);
+        self.switch(next_scene)?;
         Judge::on_new_frame();
+        crate::ui::update_text_input();
         let mut touches = Judge::get_touches();
         touches.iter_mut().for_each(f);
         if !touches.is_empty() {
             let now = self.tm.now();
             let delta = (now - self.last_update_time) / touches.len() as f64;
             let start_time = self.tm.start_time;
+            let show_billboard = self.show_billboard;
             DIALOG.with(|it| -> Result<()> {
                 let mut index = 1;
                 touches.retain_mut(|touch| {
                     let t = self.last_update_time + (index + 1) as f64 * delta;
                     index += 1;
+                    if crate::ui::touch_keyboard(touch) {
+                        return false;
+                    }
+                    if show_billboard
+                        && BILLBOARD.with(|it| {
+                            let mut guard = it.borrow_mut();
+                            let t = guard.1.now() as f32;
+                            guard.0.touch(touch, t)
+                        })
+                    {
+                        return false;
+                    }
                     let mut guard = it.borrow_mut();
                     if let Some(dialog) = guard.as_mut() {
                         if !dialog.touch(touch, t as _) {
+                            let dialog = guard.take().unwrap();
                             drop(guard);
-                            *it.borrow_mut() = None;
+                            dialog.close();
                         }
                         false
                     } else {
@@ -430,29 +460,156 @@ impl Main {
         self.touches = Some(touches);
         self.last_update_time = self.tm.now();
         DIALOG.with(|it| {
-            if let Some(dialog) = it.borrow_mut().as_mut() {
-                dialog.update(self.last_update_time as _);
+            let mut guard = it.borrow_mut();
+            if guard.as_mut().map_or(false, |dialog| !dialog.update(self.last_update_time as _)) {
+                let dialog = guard.take().unwrap();
+                drop(guard);
+                dialog.close();
             }
         });
         self.scenes.last_mut().unwrap().update(&mut self.tm)
     }
 
+    /// Switches scenes as if the current one asked for `next` in [`Scene::next_scene`].
+    pub fn switch(&mut self, next: NextScene) -> Result<()> {
+        let popped = match &next {
+            NextScene::Pop | NextScene::PopWithResult(_) => 1,
+            NextScene::PopN(num) => *num,
+            _ => 0,
+        };
+        if popped >= self.scenes.len() {
+            bail!("Cannot pop {popped} of {} scenes", self.scenes.len());
+        }
+        // replacing is left out since it's how scenes hand over with transitions of their own, like loading into the game
+        let transiting = matches!(next, NextScene::Pop | NextScene::PopN(_) | NextScene::PopWithResult(_) | NextScene::Overlay(_));
+        match next {
+            NextScene::None => {}
+            NextScene::Pop => {
+                self.scenes.pop();
+                self.tm.seek_to(self.times.pop().unwrap());
+                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+            }
+            NextScene::PopN(num) => {
+                for _ in 0..num {
+                    self.scenes.pop();
+                    self.tm.seek_to(self.times.pop().unwrap());
+                }
+                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+            }
+            NextScene::PopWithResult(result) => {
+                self.scenes.pop();
+                self.tm.seek_to(self.times.pop().unwrap());
+                self.scenes.last_mut().unwrap().on_result(&mut self.tm, result)?;
+                self.scenes.last_mut().unwrap().enter(&mut self.tm, self.target_chooser.choose())?;
+            }
+            NextScene::Exit => {
+                self.should_exit = true;
+            }
+            NextScene::Overlay(mut scene) => {
+                self.times.push(self.tm.now());
+                scene.enter(&mut self.tm, self.target_chooser.choose())?;
+                self.scenes.push(scene);
+            }
+            NextScene::Replace(mut scene) => {
+                scene.enter(&mut self.tm, self.target_chooser.choose())?;
+                *self.scenes.last_mut().unwrap() = scene;
+            }
+        }
+        if transiting && self.scenes.last().unwrap().uses_transition() {
+            self.reveal.animate(0., 1., self.tm.real_time() as f32);
+        }
+        Ok(())
+    }
+
+    /// Pushes `scene` over the current one, which gets its result in [`Scene::on_result`] if it's popped with one.
+    pub fn push(&mut self, scene: Box<dyn Scene>) -> Result<()> {
+        self.switch(NextScene::Overlay(scene))
+    }
+
+    pub fn pop(&mut self) -> Result<()> {
+        self.switch(NextScene::Pop)
+    }
+
+    /// Pops the current scene, passing `result` to the one below, see [`NextScene::pop_with`].
+    pub fn pop_with<T: Any>(&mut self, result: T) -> Result<()> {
+        self.switch(NextScene::pop_with(result))
+    }
+
+    pub fn replace(&mut self, scene: Box<dyn Scene>) -> Result<()> {
+        self.switch(NextScene::Replace(scene))
+    }
+
+    /// The scene being shown.
+    pub fn top(&mut self) -> &mut dyn Scene {
+        self.scenes.last_mut().unwrap().as_mut()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Covers what's not yet revealed of the scene, `p` going from 0 to 1.
+    fn draw_transition(&self, ui: &mut Ui, p: f32) {
+        let r = ui.screen_rect();
+        match self.transition {
+            SceneTransition::None => {}
+            SceneTransition::Fade => {
+                ui.fill_rect(r, Color::new(0., 0., 0., 1. - p));
+            }
+            SceneTransition::Slide => {
+                ui.fill_rect(Rect::new(r.x + r.w * p, r.y, r.w * (1. - p), r.h), BLACK);
+            }
+            SceneTransition::CircleWipe => {
+                let ct = r.center();
+                let outer = (r.w * r.w + r.h * r.h).sqrt() / 2.;
+                let inner = outer * p;
+                const SEGMENTS: u16 = 64;
+                let mut b = ui.builder(BLACK);
+                for i in 0..SEGMENTS {
+                    let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+                    let (sin, cos) = angle.sin_cos();
+                    b.add(ct.x + cos * inner, ct.y + sin * inner);
+                    // a bit further so that the corners stay covered between the segments
+                    b.add(ct.x + cos * outer * 1.1, ct.y + sin * outer * 1.1);
+                    let next = (i + 1) % SEGMENTS;
+                    b.triangle(i * 2, i * 2 + 1, next * 2);
+                    b.triangle(i * 2 + 1, next * 2, next * 2 + 1);
+                }
+                b.commit();
+            }
+        }
+    }
+
     pub fn render(&mut self, ui: &mut Ui) -> Result<()> {
         if self.paused {
             return Ok(());
         }
         ui.set_touches(self.touches.take().unwrap());
         ui.scope(|ui| self.scenes.last_mut().unwrap().render(&mut self.tm, ui))?;
-        if self.show_billboard {
+        // drawn over the scene where it's rendered to, so that it's in the target rendered videos are read from
+        let t = self.tm.real_time() as f32;
+        if self.transition != SceneTransition::None && !self.reveal.finished(t) {
+            self.draw_transition(ui, self.reveal.now(t));
+        }
+        let keyboard = crate::ui::keyboard_shown();
+        if self.show_billboard || keyboard {
             let mut gl = unsafe { get_internal_gl() };
             gl.flush();
             gl.quad_gl.render_pass(None);
             gl.quad_gl.viewport(None);
-            BILLBOARD.with(|it| {
-                let mut guard = it.borrow_mut();
-                let t = guard.1.now() as f32;
-                guard.0.render(ui, t);
-            });
+            if keyboard {
+                crate::ui::render_keyboard(ui);
+            }
+            if self.show_billboard {
+                BILLBOARD.with(|it| {
+                    let mut guard = it.borrow_mut();
+                    let t = guard.1.now() as f32;
+                    guard.0.render(ui, t);
+                });
+            }
+        }
+        if !self.show_billboard {
+            crate::ui::discard_messages();
         }
         DIALOG.with(|it| {
             if let Some(dialog) = it.borrow_mut().as_mut() {
@@ -485,21 +642,29 @@ fn draw_background(tex: Texture2D) {
 }
 
 fn draw_illustration(tex: Texture2D, x: f32, y: f32, w: f32, h: f32, color: Color) -> Rect {
+    draw_illustration_ex(tex, x, y, w, h, color, ScaleType::CropCenter)
+}
+
+/// Like [`draw_illustration`], fitting the illustration into the jacket with `scale_type`. The returned rect is the whole jacket anyway.
+fn draw_illustration_ex(tex: Texture2D, x: f32, y: f32, w: f32, h: f32, color: Color, scale_type: ScaleType) -> Rect {
     let scale = 0.076;
     let w = scale * 13. * w;
     let h = scale * 7. * h;
     let r = Rect::new(x - w / 2., y - h / 2., w, h);
-    let tr = {
-        let exp = w / h;
-        let act = tex.width() / tex.height();
-        if exp > act {
-            let h = act / exp;
-            Rect::new(0., 0.5 - h / 2., 1., h)
-        } else {
-            let w = exp / act;
-            Rect::new(0.5 - w / 2., 0., w, 1.)
+    let full = Rect::new(0., 0., 1., 1.);
+    match scale_type {
+        ScaleType::CropCenter => {
+            let tr = source_of_image(&tex, r, scale_type).unwrap();
+            crate::ext::draw_parallelogram(r, Some((tex, tr)), color, true);
         }
-    };
-    crate::ext::draw_parallelogram(r, Some((tex, tr)), color, true);
+        ScaleType::Inside => {
+            let (exp, act) = (w / h, tex.width() / tex.height());
+            let (w, h) = if exp > act { (h * act, h) } else { (w, w / act) };
+            crate::ext::draw_parallelogram(Rect::new(x - w / 2., y - h / 2., w, h), Some((tex, full)), color, true);
+        }
+        ScaleType::Fit => {
+            crate::ext::draw_parallelogram(r, Some((tex, full)), color, true);
+        }
+    }
     r
 }
@@ -3,20 +3,25 @@ crate::tl_file!("ending");
 use super::{draw_background, draw_illustration, NextScene, Scene};
 use crate::{
     config::Config,
+    core::{MSRenderTarget, JUDGE_LINE_GOOD_COLOR, JUDGE_LINE_PERFECT_COLOR},
     ext::{
         create_audio_manger, draw_parallelogram, draw_parallelogram_ex, draw_text_aligned, screen_aspect, SafeTexture, ScaleType, PARALLELOGRAM_SLOPE,
     },
     info::ChartInfo,
-    judge::{Judge, PlayResult},
-    scene::show_message,
+    judge::{Judge, Judgement, PlayResult, LIMIT_BAD, LIMIT_GOOD, LIMIT_PERFECT},
+    scene::{show_error, show_message},
     task::Task,
     ui::{Dialog, MessageHandle, Ui},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::Local;
+use image::RgbaImage;
 use macroquad::prelude::*;
 use sasa::{AudioClip, AudioManager, Music, MusicParams};
 use serde::Deserialize;
-use std::{cell::RefCell, ops::DerefMut};
+use std::{cell::RefCell, ops::DerefMut, path::Path};
+
+const BAD_COLOR: Color = Color::new(1., 0.35, 0.35, 0.9);
 
 #[derive(Deserialize)]
 pub struct RecordUpdateState {
@@ -36,6 +41,7 @@ pub struct EndingScene {
     bgm: Music,
 
     info: ChartInfo,
+    tier_badge: Option<(String, Color)>,
     result: PlayResult,
     player_name: String,
     player_rks: f32,
@@ -43,9 +49,15 @@ pub struct EndingScene {
     challenge_rank: u32,
     autoplay: bool,
     speed: f32,
-    next: u8, // 0 -> none, 1 -> pop, 2 -> exit
+    next: u8, // 0 -> none, 1 -> pop, 2 -> exit, 3 -> leaderboard
     update_state: Option<RecordUpdateState>,
     rated: bool,
+    previous_best: Option<(u32, f64)>,
+    export: bool,
+    export_dir: Option<String>,
+    sample_count: u32,
+    #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+    leaderboard: Option<crate::leaderboard::Leaderboard>,
 
     upload_fn: Option<fn(String) -> Task<Result<RecordUpdateState>>>,
     upload_task: Option<(Task<Result<RecordUpdateState>>, MessageHandle)>,
@@ -62,6 +74,7 @@ impl EndingScene {
         icon_proceed: SafeTexture,
         info: ChartInfo,
         result: PlayResult,
+        previous_best: Option<(u32, f64)>,
         challenge_texture: SafeTexture,
         config: &Config,
         bgm: AudioClip,
@@ -72,7 +85,7 @@ impl EndingScene {
         let bgm = audio.create_music(
             bgm,
             MusicParams {
-                amplifier: config.volume_music,
+                amplifier: config.ending_volume(),
                 loop_: true,
                 ..Default::default()
             },
@@ -97,7 +110,14 @@ impl EndingScene {
                 })
             },
             rated: upload_task.is_some(),
+            previous_best,
+            export: false,
+            export_dir: config.export_dir.clone(),
+            sample_count: config.sample_count,
+            #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+            leaderboard: crate::leaderboard::Leaderboard::from_config(config),
 
+            tier_badge: config.tier_badge(&info),
             info,
             result,
             player_name: config.player_name.clone(),
@@ -113,73 +133,77 @@ impl EndingScene {
             record_data,
         })
     }
-}
-
-thread_local! {
-    static RE_UPLOAD: RefCell<bool> = RefCell::default();
-}
-
-impl Scene for EndingScene {
-    fn enter(&mut self, tm: &mut crate::time::TimeManager, target: Option<RenderTarget>) -> Result<()> {
-        tm.reset();
-        tm.seek_to(-0.4);
-        self.target = target;
-        Ok(())
-    }
 
-    fn pause(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
-        self.bgm.pause()?;
-        tm.pause();
-        Ok(())
+    fn has_leaderboard(&self) -> bool {
+        #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+        if self.leaderboard.is_some() && self.info.hash.is_some() {
+            return true;
+        }
+        false
     }
 
-    fn resume(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
-        self.bgm.play()?;
-        tm.resume();
-        Ok(())
+    /// Renders the result offscreen without the buttons and saves it as a PNG, returning where it's saved.
+    fn export_image(&mut self, ui: &mut Ui) -> Result<String> {
+        let (w, h) = (screen_width() as u32, screen_height() as u32);
+        let target = MSRenderTarget::new((w, h), self.sample_count.max(1), true);
+        let old_target = std::mem::replace(&mut self.target, Some(target.input()));
+        let drawn = self.draw(ui, f32::INFINITY, true);
+        self.target = old_target;
+        drawn?;
+        unsafe { get_internal_gl() }.flush();
+        target.blit();
+        let image = target.output().texture.get_texture_data();
+        let row = w as usize * 4;
+        let flipped: Vec<u8> = image.bytes.chunks_exact(row).rev().flatten().copied().collect();
+        let name: String = self.info.name.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+        let path = Path::new(self.export_dir.as_deref().unwrap_or("."))
+            .join(format!("{name}-{}.png", Local::now().format("%Y%m%d-%H%M%S")))
+            .display()
+            .to_string();
+        RgbaImage::from_raw(w, h, flipped)
+            .context("Invalid image")?
+            .save(&path)
+            .context("Failed to save image")?;
+        Ok(path)
     }
 
-    fn update(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
-        self.audio.recover_if_needed()?;
-        if tm.now() >= 0. && self.target.is_none() && self.bgm.paused() {
-            self.bgm.play()?;
+    /// Draws where each note is hit over the song, early ones above and late ones below.
+    fn draw_timings(&self, ui: &mut Ui, rect: Rect) {
+        let timings = &self.result.timings;
+        let length = timings.iter().map(|it| it.time).fold(0., f32::max).max(1.);
+        let y_of = |diff: f32| rect.center().y + diff.clamp(-LIMIT_BAD, LIMIT_BAD) / LIMIT_BAD * rect.h / 2.;
+        for (diff, alpha) in [
+            (0., 0.5),
+            (-LIMIT_PERFECT, 0.2),
+            (LIMIT_PERFECT, 0.2),
+            (-LIMIT_GOOD, 0.1),
+            (LIMIT_GOOD, 0.1),
+        ] {
+            draw_line(rect.x, y_of(diff), rect.right(), y_of(diff), 0.002, Color::new(1., 1., 1., alpha));
         }
-        if RE_UPLOAD.with(|it| std::mem::replace(it.borrow_mut().deref_mut(), false)) && self.upload_task.is_none() {
-            self.upload_task = self
-                .record_data
-                .clone()
-                .map(|data| ((self.upload_fn.unwrap())(data), show_message(tl!("uploading")).handle()));
-        }
-        if let Some((task, handle)) = &mut self.upload_task {
-            if let Some(result) = task.take() {
-                handle.cancel();
-                match result {
-                    Err(err) => {
-                        let error = format!("{:?}", err.context(tl!("upload-failed")));
-                        Dialog::plain(tl!("upload-failed"), error)
-                            .buttons(vec![tl!("upload-cancel").to_string(), tl!("upload-retry").to_string()])
-                            .listener(move |pos| {
-                                if pos == 1 {
-                                    RE_UPLOAD.with(|it| *it.borrow_mut() = true);
-                                }
-                            })
-                            .show();
-                    }
-                    Ok(state) => {
-                        self.update_state = Some(state);
-                        show_message(tl!("uploaded")).ok();
-                    }
+        for timing in timings {
+            let x = rect.x + timing.time / length * rect.w;
+            match timing.diff {
+                Some(diff) => {
+                    let color = match timing.judgement {
+                        Judgement::Perfect => JUDGE_LINE_PERFECT_COLOR,
+                        Judgement::Good => JUDGE_LINE_GOOD_COLOR,
+                        _ => BAD_COLOR,
+                    };
+                    draw_circle(x, y_of(diff), 0.003, color);
                 }
-                self.upload_task = None;
+                None => draw_line(x, rect.y, x, rect.bottom(), 0.002, Color { a: 0.4, ..BAD_COLOR }),
             }
         }
-        Ok(())
+        let color = Color::new(1., 1., 1., 0.6);
+        draw_text_aligned(ui, &tl!("early"), rect.x, rect.y, (0., 0.), 0.24, color);
+        draw_text_aligned(ui, &tl!("late"), rect.x, rect.bottom(), (0., 1.), 0.24, color);
     }
 
-    fn render(&mut self, tm: &mut crate::time::TimeManager, ui: &mut Ui) -> Result<()> {
+    /// Draws the result at time `now`, leaving out the buttons if `exporting`.
+    fn draw(&mut self, ui: &mut Ui, now: f32, exporting: bool) -> Result<()> {
         let asp = screen_aspect();
         let top = 1. / asp;
-        let now = tm.now() as f32;
         let gl = unsafe { get_internal_gl() }.quad_gl;
         let res = &self.result;
         set_camera(&Camera2D {
@@ -207,7 +231,19 @@ impl Scene for EndingScene {
             Color::new(0., 0., 0., 0.7),
             false,
         );
-        let rr = draw_text_aligned(ui, &self.info.level, r.right() - r.h / 7. * 13. * 0.13 - 0.01, r.bottom() - top / 20., (1., 1.), 0.46, WHITE);
+        let level = if self.tier_badge.is_some() {
+            self.info.level_without_tier()
+        } else {
+            &self.info.level
+        };
+        let mut rr = draw_text_aligned(ui, level, r.right() - r.h / 7. * 13. * 0.13 - 0.01, r.bottom() - top / 20., (1., 1.), 0.46, WHITE);
+        if let Some((label, color)) = &self.tier_badge {
+            let t = ui.text(label).size(0.4).measure();
+            let b = Rect::new(rr.x - 0.02 - t.w - 0.04, rr.center().y - t.h / 2. - 0.01, t.w + 0.04, t.h + 0.02);
+            draw_parallelogram(b, None, *color, false);
+            draw_text_aligned(ui, label, b.center().x, b.center().y, (0.5, 0.5), 0.4, WHITE);
+            rr = b;
+        }
         let p = (r.x + 0.04, r.bottom() - top / 20.);
         let mw = rr.x - 0.02 - p.0;
         let mut size = 0.7;
@@ -229,7 +265,9 @@ impl Scene for EndingScene {
         let main = Rect::new(r.right() - 0.05, r.y, r.w * 0.84, r.h / 2.);
         draw_parallelogram(main, None, c, true);
         {
-            let spd = if (self.speed - 1.).abs() <= 1e-4 {
+            let spd = if let Some(rate_mod) = res.rate_mod {
+                format!(" {} {:.2}x", rate_mod.label(), self.speed)
+            } else if (self.speed - 1.).abs() <= 1e-4 {
                 String::new()
             } else {
                 format!(" {:.2}x", self.speed)
@@ -237,7 +275,11 @@ impl Scene for EndingScene {
             let text = if self.autoplay {
                 format!("PRPR[AUTOPLAY] {spd}")
             } else if !self.rated {
-                format!("PRPR[UNRATED] {spd}")
+                match self.previous_best {
+                    Some((best, _)) if res.score > best => format!("PRPR[UNRATED] {spd}  NEW BEST +{:07}", res.score - best),
+                    Some((best, _)) => format!("PRPR[UNRATED] {spd}  BEST {best:07}"),
+                    None => format!("PRPR[UNRATED] {spd}"),
+                }
             } else if let Some(state) = &self.update_state {
                 format!(
                     "PRPR {spd}  {}",
@@ -288,6 +330,12 @@ impl Scene for EndingScene {
             let r = draw_text_aligned(ui, "Max Combo", s1.x + dx, s1.bottom() - dy, (0., 1.), 0.34, WHITE);
             draw_text_aligned(ui, &res.max_combo.to_string(), r.x, r.y - 0.01, (0., 1.), 0.7, WHITE);
             let r = draw_text_aligned(ui, "Accuracy", s1.right() - dx, s1.bottom() - dy, (1., 1.), 0.34, WHITE);
+            if let Some((_, best)) = self.previous_best {
+                if res.accuracy > best {
+                    let text = format!("NEW BEST +{:.2}%", (res.accuracy - best) * 100.);
+                    draw_text_aligned(ui, &text, r.x - 0.02, r.bottom(), (1., 1.), 0.28, JUDGE_LINE_PERFECT_COLOR);
+                }
+            }
             draw_text_aligned(ui, &format!("{:.2}%", res.accuracy * 100.), r.right(), r.y - 0.01, (1., 1.), 0.7, WHITE);
         }
         gl.pop_model_matrix();
@@ -313,13 +361,21 @@ impl Scene for EndingScene {
             let l = s2.x + s2.w * 0.72;
             let rt = s2.x + s2.w * 0.94;
             let cy = s2.center().y;
-            let r = draw_text_aligned(ui, "Early", l, cy - dy2 / 2., (0., 1.), sm, WHITE);
+            let r = draw_text_aligned(ui, &tl!("early"), l, cy - dy2 / 2., (0., 1.), sm, WHITE);
             draw_text_aligned(ui, &res.early.to_string(), rt, r.bottom(), (1., 1.), sm, WHITE);
-            let r = draw_text_aligned(ui, "Late", l, cy + dy2 / 2., (0., 0.), 0.3, WHITE);
+            let r = draw_text_aligned(ui, &tl!("late"), l, cy + dy2 / 2., (0., 0.), 0.3, WHITE);
             draw_text_aligned(ui, &res.late.to_string(), rt, r.y, (1., 0.), sm, WHITE);
         }
         gl.pop_model_matrix();
 
+        let graph = Rect::new(r.x + 0.1, r.bottom() + 0.03, r.w - 0.1, top - r.bottom() - 0.06);
+        if !res.timings.is_empty() && graph.h > 0.06 {
+            tran(gl, (1. - ran(now, 0.6, 1.8)).powi(3));
+            draw_parallelogram(graph, None, c, true);
+            self.draw_timings(ui, Rect::new(graph.x + graph.h * slope, graph.y + 0.012, graph.w - graph.h * slope * 2., graph.h - 0.024));
+            gl.pop_model_matrix();
+        }
+
         fn touched(rect: Rect) -> bool {
             Judge::get_touches()
                 .iter()
@@ -336,32 +392,57 @@ impl Scene for EndingScene {
             dest_size: Some(vec2(hs * 2., hs * 2.)),
             ..Default::default()
         };
-        tran(gl, -p * 0.085);
-        let r = Rect::new(-1. - h * slope, -top + dy, w, h);
-        draw_parallelogram(r, None, c, true);
-        draw_parallelogram(Rect::new(r.x + r.w * (1. - s), r.y, r.w * s, r.h), None, WHITE, false);
-        let ct = r.center();
-        draw_texture_ex(*self.icon_retry, ct.x - hs, ct.y - hs, WHITE, params.clone());
-        gl.pop_model_matrix();
-        if p <= 0. && touched(r) {
-            if self.upload_task.is_some() {
-                show_message(tl!("still-uploading"));
+        if !exporting {
+            tran(gl, -p * 0.085);
+            let r = Rect::new(-1. - h * slope, -top + dy, w, h);
+            draw_parallelogram(r, None, c, true);
+            draw_parallelogram(Rect::new(r.x + r.w * (1. - s), r.y, r.w * s, r.h), None, WHITE, false);
+            let ct = r.center();
+            draw_texture_ex(*self.icon_retry, ct.x - hs, ct.y - hs, WHITE, params.clone());
+            gl.pop_model_matrix();
+            if p <= 0. && touched(r) {
+                if self.upload_task.is_some() {
+                    show_message(tl!("still-uploading"));
+                }
+                self.next = 1;
             }
-            self.next = 1;
-        }
 
-        tran(gl, p * 0.085);
-        let r = Rect::new(1. + h * slope - w, top - dy - h, w, h);
-        draw_parallelogram(r, None, c, true);
-        draw_parallelogram(Rect::new(r.x + r.w * s, r.y, r.w * s, r.h), None, WHITE, false);
-        let ct = r.center();
-        draw_texture_ex(*self.icon_proceed, ct.x - hs, ct.y - hs, WHITE, params);
-        gl.pop_model_matrix();
-        if p <= 0. && touched(r) {
-            if self.upload_task.is_some() {
-                show_message(tl!("still-uploading"));
+            tran(gl, p * 0.085);
+            let r = Rect::new(1. + h * slope - w, top - dy - h, w, h);
+            draw_parallelogram(r, None, c, true);
+            draw_parallelogram(Rect::new(r.x + r.w * s, r.y, r.w * s, r.h), None, WHITE, false);
+            let ct = r.center();
+            draw_texture_ex(*self.icon_proceed, ct.x - hs, ct.y - hs, WHITE, params);
+            gl.pop_model_matrix();
+            if p <= 0. && touched(r) {
+                if self.upload_task.is_some() {
+                    show_message(tl!("still-uploading"));
+                }
+                self.next = 2;
+            }
+
+            tran(gl, -p * 0.085);
+            let r = Rect::new(-1. - h * slope, top - dy - h, w, h);
+            draw_parallelogram(r, None, c, true);
+            draw_parallelogram(Rect::new(r.x + r.w * (1. - s), r.y, r.w * s, r.h), None, WHITE, false);
+            let ct = r.center();
+            draw_text_aligned(ui, &tl!("export"), ct.x - r.w * s / 2., ct.y, (0.5, 0.5), 0.4, WHITE);
+            gl.pop_model_matrix();
+            if p <= 0. && touched(r) {
+                self.export = true;
+            }
+
+            if self.has_leaderboard() {
+                tran(gl, -p * 0.085);
+                let r = Rect::new(r.right() + 0.01, r.y, w * 1.4, h);
+                draw_parallelogram(r, None, c, true);
+                let ct = r.center();
+                draw_text_aligned(ui, &tl!("leaderboard"), ct.x, ct.y, (0.5, 0.5), 0.4, WHITE);
+                gl.pop_model_matrix();
+                if p <= 0. && touched(r) {
+                    self.next = 3;
+                }
             }
-            self.next = 2;
         }
 
         let alpha = ran(now, 1.5, 1.9);
@@ -395,8 +476,88 @@ impl Scene for EndingScene {
 
         Ok(())
     }
+}
+
+thread_local! {
+    static RE_UPLOAD: RefCell<bool> = RefCell::default();
+}
+
+impl Scene for EndingScene {
+    fn enter(&mut self, tm: &mut crate::time::TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        tm.reset();
+        tm.seek_to(-0.4);
+        self.target = target;
+        Ok(())
+    }
+
+    fn pause(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
+        self.bgm.pause()?;
+        tm.pause();
+        Ok(())
+    }
+
+    fn resume(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
+        self.bgm.play()?;
+        tm.resume();
+        Ok(())
+    }
+
+    fn update(&mut self, tm: &mut crate::time::TimeManager) -> Result<()> {
+        self.audio.recover_if_needed()?;
+        if tm.now() >= 0. && self.target.is_none() && self.bgm.paused() {
+            self.bgm.play()?;
+        }
+        if RE_UPLOAD.with(|it| std::mem::replace(it.borrow_mut().deref_mut(), false)) && self.upload_task.is_none() {
+            self.upload_task = self
+                .record_data
+                .clone()
+                .map(|data| ((self.upload_fn.unwrap())(data), show_message(tl!("uploading")).handle()));
+        }
+        if let Some((task, handle)) = &mut self.upload_task {
+            if let Some(result) = task.take() {
+                handle.cancel();
+                match result {
+                    Err(err) => {
+                        let error = format!("{:?}", err.context(tl!("upload-failed")));
+                        Dialog::plain(tl!("upload-failed"), error)
+                            .buttons(vec![tl!("upload-cancel").to_string(), tl!("upload-retry").to_string()])
+                            .listener(move |pos| {
+                                if pos == 1 {
+                                    RE_UPLOAD.with(|it| *it.borrow_mut() = true);
+                                }
+                            })
+                            .show();
+                    }
+                    Ok(state) => {
+                        self.update_state = Some(state);
+                        show_message(tl!("uploaded")).ok();
+                    }
+                }
+                self.upload_task = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, tm: &mut crate::time::TimeManager, ui: &mut Ui) -> Result<()> {
+        if std::mem::take(&mut self.export) {
+            match self.export_image(ui) {
+                Ok(path) => show_message(tl!("exported", "path" => path)).ok(),
+                Err(err) => show_error(err.context(tl!("export-failed"))),
+            }
+        }
+        self.draw(ui, tm.now() as f32, false)
+    }
 
     fn next_scene(&mut self, _tm: &mut crate::time::TimeManager) -> NextScene {
+        #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+        if self.next == 3 {
+            self.next = 0;
+            if let (Some(leaderboard), Some(hash)) = (&self.leaderboard, &self.info.hash) {
+                let scene = super::LeaderboardScene::new(leaderboard.clone(), hash.clone(), self.info.name.clone(), self.player_name.clone());
+                return NextScene::Overlay(Box::new(scene));
+            }
+        }
         if self.next != 0 {
             let _ = self.bgm.pause();
         }
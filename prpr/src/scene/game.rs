@@ -2,24 +2,33 @@
 
 crate::tl_file!("game");
 
-use super::{draw_background, ending::RecordUpdateState, request_input, return_input, show_message, take_input, EndingScene, NextScene, Scene};
+use super::{
+    draw_background,
+    ending::RecordUpdateState,
+    hud::{hud_matrix, HudEditor, HudItem},
+    pause::{PauseAction, PauseMenu, OFFSET_STEP},
+    request_input, return_input, show_error, show_message, take_input, EndingScene, NextScene, Scene,
+};
 use crate::{
     config::Config,
-    core::{copy_fbo, BadNote, Chart, ChartExtra, Effect, Point, Resource, UIElement, Vector, JUDGE_LINE_GOOD_COLOR, JUDGE_LINE_PERFECT_COLOR},
-    ext::{screen_aspect, RectExt, SafeTexture},
+    core::{
+        copy_fbo, BadNote, Chart, ChartExtra, Effect, Matrix, Point, Resource, Tweenable, UIElement, Vector, Visualizer, JUDGE_LINE_GOOD_COLOR,
+        JUDGE_LINE_PERFECT_COLOR,
+    },
+    ext::{poll_future, screen_aspect, LocalTask, RectExt, SafeTexture},
     fs::FileSystem,
-    info::{ChartFormat, ChartInfo},
-    judge::Judge,
+    info::{chart_hash, ChartFormat, ChartInfo},
+    judge::{play_sfx, Judge, PlayResult, Replay},
     parse::{parse_extra, parse_pec, parse_phigros, parse_rpe},
     task::Task,
     time::TimeManager,
-    ui::{RectButton, Ui},
+    ui::{reduce_motion, RectButton, Ui},
 };
 use anyhow::{bail, Context, Result};
 use concat_string::concat_string;
 use lyon::path::Path;
 use macroquad::{prelude::*, window::InternalGlContext};
-use sasa::{Music, MusicParams};
+use sasa::{AudioClip, Music, MusicParams, PlaySfxParams, Sfx};
 use std::{
     io::ErrorKind,
     ops::{DerefMut, Range},
@@ -38,8 +47,20 @@ use inner::*;
 
 pub static FFMPEG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
 
+/// The config last reloaded by the app, along with how many times it's been reloaded.
+static RELOADED: Mutex<(usize, Option<Config>)> = Mutex::new((0, None));
+
+/// Applies the fields of `config` that can be changed while playing to the game being played and the ones started later.
+pub fn reload_config(config: Config) {
+    let mut reloaded = RELOADED.lock().unwrap();
+    reloaded.0 += 1;
+    reloaded.1 = Some(config);
+}
+
 const WAIT_TIME: f32 = 0.5;
 const AFTER_TIME: f32 = 0.7;
+const COMBO_EFFECT_TIME: f32 = 0.6;
+const OFFSET_HINT_TIME: f64 = 1.5;
 
 fn fmt_time(t: f32) -> String {
     let f = t < 0.;
@@ -65,6 +86,13 @@ pub enum GameMode {
     Exercise,
 }
 
+#[derive(Clone, Copy)]
+enum ComboEvent {
+    Milestone,
+    /// The combo that was lost.
+    Break(u32),
+}
+
 #[derive(Clone)]
 enum State {
     Starting,
@@ -86,13 +114,18 @@ pub struct GameScene {
     chart_str: String,
     chart_format: ChartFormat,
     info_offset: f32,
+    /// Offset in the chart info before the one remembered for the chart is added, see [`Self::save_chart_offset`].
+    file_offset: f32,
     compatible_mode: bool,
     effects: Vec<Effect>,
+    visualizer: Option<Visualizer>,
 
     first_in: bool,
     exercise_range: Range<f32>,
     exercise_press: Option<(i8, u64)>,
     exercise_btns: (RectButton, RectButton),
+    metronome: Option<Sfx>,
+    metronome_beat: i32,
 
     pub music: Music,
 
@@ -102,8 +135,22 @@ pub struct GameScene {
     last_update_time: f64,
     pause_rewind: Option<f64>,
     pause_first_time: f32,
+    pause_menu: PauseMenu,
+    hud_editor: Option<HudEditor>,
+    /// The real time until which the offset of the chart is shown, after it's changed while playing.
+    offset_hint: Option<f64>,
+    /// The speed remembered for the chart, which takes precedence over the one in the config.
+    chart_speed: Option<f32>,
+    /// How many reloads of the config have been applied, see [`reload_config`].
+    reloads: usize,
+    /// The file system of the chart, taken while [`Self::chart_task`] reloads it.
+    fs: Option<Box<dyn FileSystem>>,
+    last_modified_check: f64,
+    chart_task: LocalTask<(Box<dyn FileSystem>, Result<(Chart, String, ChartFormat)>)>,
 
     bad_notes: Vec<BadNote>,
+    last_combo: u32,
+    combo_event: Option<(ComboEvent, f32)>,
 
     upload_fn: Option<fn(String) -> Task<Result<RecordUpdateState>>>,
 }
@@ -112,6 +159,8 @@ macro_rules! reset {
     ($self:ident, $res:expr, $tm:ident) => {{
         $self.bad_notes.clear();
         $self.judge.reset();
+        $self.last_combo = 0;
+        $self.combo_event = None;
         $self.chart.reset();
         $res.judge_line_color = JUDGE_LINE_PERFECT_COLOR;
         $self.music.pause()?;
@@ -126,6 +175,59 @@ impl GameScene {
     pub const BEFORE_TIME: f32 = 0.7;
     pub const FADEOUT_TIME: f32 = WAIT_TIME + AFTER_TIME + 0.3;
 
+    /// Takes the global effects out of the chart, adding FXAA to its effects if enabled.
+    fn take_effects(chart: &mut Chart, config: &Config) -> Vec<Effect> {
+        if config.fxaa {
+            chart
+                .extra
+                .effects
+                .push(Effect::new(0.0..f32::INFINITY, include_str!("fxaa.glsl"), Vec::new(), false).unwrap());
+        }
+        std::mem::take(&mut chart.extra.global_effects)
+    }
+
+    /// Reloads the chart when its files are modified, such as a chart folder being edited, and restarts it.
+    fn check_modified(&mut self, tm: &mut TimeManager) -> Result<()> {
+        if let Some(task) = &mut self.chart_task {
+            if let Some((fs, result)) = poll_future(task.as_mut()) {
+                self.chart_task = None;
+                self.fs = Some(fs);
+                match result {
+                    Ok((mut chart, chart_str, chart_format)) => {
+                        self.effects = Self::take_effects(&mut chart, &self.res.config);
+                        self.judge = Judge::new(&chart);
+                        self.chart = chart;
+                        self.chart_str = chart_str;
+                        self.chart_format = chart_format;
+                        reset!(self, self.res, tm);
+                        show_message(tl!("chart-reloaded")).ok();
+                    }
+                    Err(err) => show_error(err.context(tl!("chart-reload-failed"))),
+                }
+            }
+            return Ok(());
+        }
+        let t = tm.real_time();
+        // replays are bound to the chart they're recorded on
+        if t - self.last_modified_check < 1. || self.judge.is_replaying() {
+            return Ok(());
+        }
+        self.last_modified_check = t;
+        let Some(mut fs) = self.fs.take() else {
+            return Ok(());
+        };
+        if fs.take_modified().is_empty() {
+            self.fs = Some(fs);
+            return Ok(());
+        }
+        let info = self.res.info.clone();
+        self.chart_task = Some(Box::pin(async move {
+            let result = Self::load_chart(fs.deref_mut(), &info).await;
+            (fs, result)
+        }));
+        Ok(())
+    }
+
     pub async fn load_chart_bytes(fs: &mut dyn FileSystem, info: &ChartInfo) -> Result<Vec<u8>> {
         if let Ok(bytes) = fs.load_file(&info.chart).await {
             return Ok(bytes);
@@ -178,7 +280,7 @@ impl GameScene {
 
     pub async fn new(
         mode: GameMode,
-        info: ChartInfo,
+        mut info: ChartInfo,
         mut config: Config,
         mut fs: Box<dyn FileSystem>,
         player: (Option<SafeTexture>, Option<String>),
@@ -197,22 +299,53 @@ impl GameScene {
             _ => {}
         }
         let (mut chart, chart_str, chart_format) = Self::load_chart(fs.deref_mut(), &info).await?;
-        let effects = std::mem::take(&mut chart.extra.global_effects);
-        if config.fxaa {
-            chart
-                .extra
-                .effects
-                .push(Effect::new(0.0..f32::INFINITY, include_str!("fxaa.glsl"), Vec::new(), false).unwrap());
+        if info.hash.is_none() {
+            info.hash = Some(chart_hash(&chart_str));
+        }
+        let file_offset = info.offset;
+        let mut chart_speed = None;
+        #[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+        if let Some(path) = &config.scores_path {
+            match crate::scores::ScoreDatabase::open(path).and_then(|db| db.settings(info.hash.as_ref().unwrap())) {
+                Ok(settings) => {
+                    if let Some(offset) = settings.offset {
+                        info.offset += offset;
+                    }
+                    chart_speed = settings.speed;
+                    config.speed = settings.speed.unwrap_or(config.speed);
+                    config.aspect_ratio = settings.aspect_ratio.or(config.aspect_ratio);
+                }
+                Err(err) => warn!("Failed to load chart settings: {:?}", err),
+            }
+        }
+        if let Some(rate_mod) = config.rate_mod {
+            // changes both tempo and pitch, the timing is rescaled the same way as `speed`
+            config.speed *= rate_mod.rate();
         }
+        let effects = Self::take_effects(&mut chart, &config);
 
         let info_offset = info.offset;
         let (avatar, player) = player;
-        let mut res = Resource::new(config, info, fs, avatar, background, illustration, chart.extra.effects.is_empty() && effects.is_empty())
+        let has_no_effect = chart.extra.effects.is_empty() && effects.is_empty();
+        let mut res = Resource::new(config, info, fs.deref_mut(), avatar, background, illustration, has_no_effect)
             .await
             .context("Failed to load resources")?;
         let exercise_range = (chart.offset + info_offset + res.config.offset)..res.track_length;
 
-        let judge = Judge::new(&chart);
+        let mut judge = Judge::new(&chart);
+        if let Some(path) = &res.config.replay {
+            let replay: Replay = serde_json::from_str(&std::fs::read_to_string(path).context("Failed to read replay")?).context("Invalid replay")?;
+            if replay.chart_hash.is_some() && replay.chart_hash != res.info.hash {
+                warn!("The replay is recorded on another version of this chart");
+            }
+            judge.set_replay(replay)?;
+        }
+        let visualizer = res.config.visualizer.map(Visualizer::new);
+        let metronome = if mode == GameMode::Exercise && res.config.metronome && !chart.bpm_list.borrow().is_empty() {
+            Some(res.audio.create_sfx(AudioClip::new(load_file("cali_hit.ogg").await?)?, Some(2))?)
+        } else {
+            None
+        };
 
         let music = Self::new_music(&mut res)?;
         Ok(Self {
@@ -229,12 +362,16 @@ impl GameScene {
             chart_format,
             compatible_mode: false,
             effects,
+            visualizer,
             info_offset,
+            file_offset,
 
             first_in: false,
             exercise_range,
             exercise_press: None,
             exercise_btns: (RectButton::new(), RectButton::new()),
+            metronome,
+            metronome_beat: i32::MIN,
 
             music,
 
@@ -244,8 +381,18 @@ impl GameScene {
             last_update_time: 0.,
             pause_rewind: None,
             pause_first_time: f32::NEG_INFINITY,
+            pause_menu: PauseMenu::new(),
+            hud_editor: None,
+            offset_hint: None,
+            chart_speed,
+            reloads: 0,
+            fs: Some(fs),
+            last_modified_check: 0.,
+            chart_task: None,
 
             bad_notes: Vec::new(),
+            last_combo: 0,
+            combo_event: None,
 
             upload_fn,
         })
@@ -262,6 +409,33 @@ impl GameScene {
         )
     }
 
+    /// Applies the fields of a reloaded config that can be changed while playing, recreating the music if its volume or speed changed.
+    fn apply_config(&mut self, tm: &mut TimeManager, config: &Config) -> Result<()> {
+        let old = (self.res.config.volume_music, self.res.config.speed);
+        self.res.config.apply_live(config);
+        let mut speed = self.chart_speed.unwrap_or(config.speed);
+        if let Some(rate_mod) = self.res.config.rate_mod {
+            speed *= rate_mod.rate();
+        }
+        self.res.config.speed = speed;
+        if (self.res.config.volume_music, self.res.config.speed) == old {
+            return Ok(());
+        }
+        let playing = matches!(self.state, State::Playing);
+        let (position, paused) = (self.music.position(), self.music.paused());
+        self.music = Self::new_music(&mut self.res)?;
+        if playing {
+            self.music.seek_to(position)?;
+            if !paused {
+                self.music.play()?;
+            }
+        }
+        let now = tm.now();
+        tm.speed = speed as _;
+        tm.seek_to(now);
+        Ok(())
+    }
+
     fn ui(&mut self, ui: &mut Ui, tm: &mut TimeManager) -> Result<()> {
         let time = tm.now() as f32;
         let p = match self.state {
@@ -286,6 +460,10 @@ impl GameScene {
         let pause_w = 0.015;
         let pause_h = pause_w * 3.2;
         let pause_center = Point::new(pause_w * 4.0 - 1., top + eps * 3.5 - (1. - p) * 0.4 + pause_h / 2.);
+        let layout = self.hud_editor.as_ref().map_or(res.config.hud_layout, |it| it.layout);
+        let mut bounds = [None; 4];
+        // the button is scaled about its center, which is thus only moved
+        let pause_placed = pause_center + Vector::new(layout.pause.x, layout.pause.y);
         if res.config.interactive
             && !tm.paused()
             && self.pause_rewind.is_none()
@@ -293,7 +471,7 @@ impl GameScene {
                 touch.phase == TouchPhase::Started && {
                     let p = touch.position;
                     let p = Point::new(p.x, p.y);
-                    (pause_center - p).norm() < 0.05
+                    (pause_placed - p).norm() < 0.05 * layout.pause.scale
                 }
             })
         {
@@ -309,50 +487,91 @@ impl GameScene {
             }
         }
         if tm.now() as f32 - self.pause_first_time <= PAUSE_CLICK_INTERVAL {
-            ui.fill_circle(pause_center.x, pause_center.y, 0.05, Color::new(1., 1., 1., 0.5));
+            ui.fill_circle(pause_placed.x, pause_placed.y, 0.05 * layout.pause.scale, Color::new(1., 1., 1., 0.5));
         }
 
         let margin = 0.03;
 
-        self.chart.with_element(ui, res, UIElement::Score, |ui, color, scale| {
-            ui.text(format!("{:07}", self.judge.score()))
-                .pos(1. - margin, top + eps * 2.2 - (1. - p) * 0.4)
-                .anchor(1., 0.)
-                .size(0.8)
-                .color(Color { a: color.a * c.a, ..color })
-                .scale(scale)
-                .draw();
-        });
-        self.chart.with_element(ui, res, UIElement::Pause, |ui, color, scale| {
-            let mut r = Rect::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2., pause_w, pause_h);
-            let ct = pause_center.coords;
-            let c = Color { a: color.a * c.a, ..color };
-            ui.with(scale.prepend_translation(&-ct).append_translation(&ct), |ui| {
-                ui.fill_rect(r, c);
-                r.x += pause_w * 2.;
-                ui.fill_rect(r, c);
-            });
-        });
-        if self.judge.combo() >= 3 {
-            let btm = self.chart.with_element(ui, res, UIElement::ComboNumber, |ui, color, scale| {
-                ui.text(self.judge.combo().to_string())
-                    .pos(0., top + eps * 2. - (1. - p) * 0.4)
-                    .anchor(0.5, 0.)
+        let pivot = Vector::new(1. - margin, top + eps * 2.2 - (1. - p) * 0.4);
+        let r = ui.with(hud_matrix(&layout.score, pivot), |ui| {
+            self.chart.with_element(ui, res, UIElement::Score, |ui, color, scale| {
+                ui.text(format!("{:07}", self.judge.score()))
+                    .pos(pivot.x, pivot.y)
+                    .anchor(1., 0.)
+                    .size(0.8)
                     .color(Color { a: color.a * c.a, ..color })
                     .scale(scale)
                     .draw()
-                    .bottom()
+            })
+        });
+        bounds[HudItem::Score as usize] = Some((r, pivot));
+        ui.with(hud_matrix(&layout.pause, pause_center.coords), |ui| {
+            self.chart.with_element(ui, res, UIElement::Pause, |ui, color, scale| {
+                let mut r = Rect::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2., pause_w, pause_h);
+                let ct = pause_center.coords;
+                let c = Color { a: color.a * c.a, ..color };
+                ui.with(scale.prepend_translation(&-ct).append_translation(&ct), |ui| {
+                    ui.fill_rect(r, c);
+                    r.x += pause_w * 2.;
+                    ui.fill_rect(r, c);
+                });
             });
-            self.chart.with_element(ui, res, UIElement::Combo, |ui, color, scale| {
-                ui.text(if res.config.autoplay { "AUTOPLAY" } else { "COMBO" })
-                    .pos(0., btm + 0.01)
+        });
+        bounds[HudItem::Pause as usize] =
+            Some((Rect::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2., pause_w * 3., pause_h), pause_center.coords));
+        let pivot = Vector::new(0., top + eps * 2. - (1. - p) * 0.4);
+        let combo_effect = self
+            .combo_event
+            .map(|(event, start)| (event, (time - start) / COMBO_EFFECT_TIME))
+            .filter(|(_, p)| (0.0..1.0).contains(p));
+        if let Some((ComboEvent::Break(combo), ep)) = combo_effect {
+            let fall = if reduce_motion() { 0. } else { ep * 0.06 };
+            let color = res.res_pack.info.combo.break_color();
+            ui.with(hud_matrix(&layout.combo, pivot), |ui| {
+                ui.text(combo.to_string())
+                    .pos(pivot.x, pivot.y + fall)
                     .anchor(0.5, 0.)
-                    .size(0.4)
-                    .color(Color { a: color.a * c.a, ..color })
-                    .scale(scale)
+                    .color(Color {
+                        a: color.a * c.a * (1. - ep),
+                        ..color
+                    })
                     .draw();
             });
         }
+        // always shown while being edited so that it can be placed
+        if self.judge.combo() >= 3 || self.hud_editor.is_some() {
+            let milestone = match combo_effect {
+                Some((ComboEvent::Milestone, ep)) => 1. - ep,
+                _ => 0.,
+            };
+            let pulse = if reduce_motion() { 1. } else { 1. + milestone.powi(3) * 0.3 };
+            let milestone_color = res.res_pack.info.combo.milestone_color();
+            let r = ui.with(hud_matrix(&layout.combo, pivot), |ui| {
+                let number = self.chart.with_element(ui, res, UIElement::ComboNumber, |ui, color, scale| {
+                    let color = Color::tween(&color, &milestone_color, milestone);
+                    let pulse = Matrix::new_scaling(pulse).prepend_translation(&-pivot).append_translation(&pivot);
+                    ui.with(pulse, |ui| {
+                        ui.text(self.judge.combo().to_string())
+                            .pos(pivot.x, pivot.y)
+                            .anchor(0.5, 0.)
+                            .color(Color { a: color.a * c.a, ..color })
+                            .scale(scale)
+                            .draw()
+                    })
+                });
+                let label = self.chart.with_element(ui, res, UIElement::Combo, |ui, color, scale| {
+                    ui.text(if res.config.autoplay { "AUTOPLAY" } else { "COMBO" })
+                        .pos(0., number.bottom() + 0.01)
+                        .anchor(0.5, 0.)
+                        .size(0.4)
+                        .color(Color { a: color.a * c.a, ..color })
+                        .scale(scale)
+                        .draw()
+                });
+                number.combine_with(label)
+            });
+            bounds[HudItem::Combo as usize] = Some((r, pivot));
+        }
         let lf = -1. + margin;
         let bt = -top - eps * 2.8;
         self.chart.with_element(ui, res, UIElement::Name, |ui, color, scale| {
@@ -376,109 +595,79 @@ impl GameScene {
         let hw = 0.003;
         let height = eps * 1.2;
         let dest = 2. * res.time / res.track_length;
-        self.chart.with_element(ui, res, UIElement::Bar, |ui, color, scale| {
-            let ct = Vector::new(0., top + height / 2.);
-            ui.with(scale.prepend_translation(&-ct).append_translation(&ct), |ui| {
-                ui.fill_rect(
-                    Rect::new(-1., top, dest, height),
-                    Color {
-                        a: color.a * c.a * 0.6,
-                        ..color
-                    },
-                );
-                ui.fill_rect(Rect::new(-1. + dest - hw, top, hw * 2., height), Color { a: color.a * c.a, ..color });
+        let ct = Vector::new(0., top + height / 2.);
+        ui.with(hud_matrix(&layout.progress, ct), |ui| {
+            self.chart.with_element(ui, res, UIElement::Bar, |ui, color, scale| {
+                ui.with(scale.prepend_translation(&-ct).append_translation(&ct), |ui| {
+                    ui.fill_rect(
+                        Rect::new(-1., top, dest, height),
+                        Color {
+                            a: color.a * c.a * 0.6,
+                            ..color
+                        },
+                    );
+                    ui.fill_rect(Rect::new(-1. + dest - hw, top, hw * 2., height), Color { a: color.a * c.a, ..color });
+                });
             });
         });
+        bounds[HudItem::Progress as usize] = Some((Rect::new(-1., top, 2., height), ct));
+        if let Some(editor) = &mut self.hud_editor {
+            editor.bounds = bounds;
+        }
         Ok(())
     }
 
     fn overlay_ui(&mut self, ui: &mut Ui, tm: &mut TimeManager) -> Result<()> {
         let c = Color::new(1., 1., 1., self.res.alpha);
         let res = &mut self.res;
-        if tm.paused() {
-            let h = 1. / res.aspect_ratio;
-            draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., 0.6));
+        if let Some(editor) = &mut self.hud_editor {
+            if let Some(save) = editor.render(ui) {
+                if save {
+                    res.config.hud_layout = editor.layout;
+                }
+                self.hud_editor = None;
+            }
+        } else if tm.paused() {
             let o = if self.mode == GameMode::Exercise { -0.3 } else { 0. };
-            let s = 0.06;
-            let w = 0.05;
-            draw_texture_ex(
-                *res.icon_back,
-                -s * 3. - w,
-                -s + o,
-                c,
-                DrawTextureParams {
-                    dest_size: Some(vec2(s * 2., s * 2.)),
-                    ..Default::default()
-                },
-            );
-            draw_texture_ex(
-                *res.icon_retry,
-                -s,
-                -s + o,
-                c,
-                DrawTextureParams {
-                    dest_size: Some(vec2(s * 2., s * 2.)),
-                    ..Default::default()
-                },
-            );
-            draw_texture_ex(
-                *res.icon_resume,
-                s + w,
-                -s + o,
-                c,
-                DrawTextureParams {
-                    dest_size: Some(vec2(s * 2., s * 2.)),
-                    ..Default::default()
-                },
-            );
-            if res.config.interactive {
-                let mut clicked = None;
-                for touch in Judge::get_touches() {
-                    if touch.phase != TouchPhase::Started {
-                        continue;
-                    }
-                    let p = touch.position;
-                    let p = Point::new(p.x, p.y);
-                    for i in -1..=1 {
-                        let ct = Point::new((s * 2. + w) * i as f32, o);
-                        let d = p - ct;
-                        if d.x.abs() <= s && d.y.abs() <= s {
-                            clicked = Some(i);
-                            break;
-                        }
-                    }
+            let old_offset = self.info_offset;
+            let chart_offset = (self.mode != GameMode::TweakOffset).then_some(&mut self.info_offset);
+            let action = self.pause_menu.render(ui, res, o, chart_offset);
+            if self.info_offset != old_offset {
+                Self::save_chart_offset(res, self.info_offset - self.file_offset);
+            }
+            match action {
+                Some(PauseAction::EditHud) => {
+                    self.hud_editor = Some(HudEditor::new(res.config.hud_layout));
                 }
-                match clicked {
-                    Some(-1) => {
-                        self.should_exit = true;
-                    }
-                    Some(0) => {
-                        reset!(self, res, tm);
+                Some(PauseAction::Exit) => {
+                    self.should_exit = true;
+                }
+                Some(PauseAction::Restart) => {
+                    reset!(self, res, tm);
+                }
+                Some(PauseAction::Resume) => {
+                    let mut pos = self.music.position();
+                    if self.mode == GameMode::Exercise && tm.now() > self.exercise_range.end as f64 {
+                        tm.seek_to(self.exercise_range.start as f64);
+                        self.music.seek_to(self.exercise_range.start)?;
+                        pos = self.exercise_range.start;
                     }
-                    Some(1) => {
-                        let mut pos = self.music.position();
-                        if self.mode == GameMode::Exercise && tm.now() > self.exercise_range.end as f64 {
-                            tm.seek_to(self.exercise_range.start as f64);
-                            self.music.seek_to(self.exercise_range.start)?;
-                            pos = self.exercise_range.start;
-                        }
-                        self.music.play()?;
-                        res.time -= 3.;
-                        let dst = pos - 3.;
-                        if dst < 0. {
-                            self.music.pause()?;
-                            self.state = State::BeforeMusic;
-                        } else {
-                            self.music.seek_to(dst)?;
-                        }
-                        tm.resume();
-                        tm.seek_to(tm.now() - 3.);
-                        self.pause_rewind = Some(tm.now() - 0.2);
+                    self.music.play()?;
+                    res.time -= 3.;
+                    let dst = pos - 3.;
+                    if dst < 0. {
+                        self.music.pause()?;
+                        self.state = State::BeforeMusic;
+                    } else {
+                        self.music.seek_to(dst)?;
                     }
-                    _ => {}
+                    tm.resume();
+                    tm.seek_to(tm.now() - 3.);
+                    self.pause_rewind = Some(tm.now() - 0.2);
                 }
+                None => {}
             }
-            if self.mode == GameMode::Exercise {
+            if self.mode == GameMode::Exercise && !self.pause_menu.showing_settings() {
                 ui.dy(0.06);
                 let hw = 0.7;
                 let h = 0.06;
@@ -576,6 +765,19 @@ impl GameScene {
                 tx.draw();
             }
         }
+        if let Some(until) = self.offset_hint {
+            if tm.real_time() < until {
+                let top = 1. / self.res.aspect_ratio;
+                ui.text(tl!("offset-hint", "offset" => format!("{:+.0}", self.info_offset * 1000.)))
+                    .pos(0., -top + 0.3)
+                    .anchor(0.5, 0.5)
+                    .size(0.5)
+                    .color(c)
+                    .draw();
+            } else {
+                self.offset_hint = None;
+            }
+        }
         if let Some(time) = self.pause_rewind {
             let dt = tm.now() - time;
             let t = 3 - dt.floor() as i32;
@@ -591,6 +793,27 @@ impl GameScene {
         Ok(())
     }
 
+    /// Plays the feedback of the resource pack when the combo reaches a milestone or breaks.
+    fn update_combo(&mut self, t: f32) {
+        let combo = self.judge.combo();
+        let last = std::mem::replace(&mut self.last_combo, combo);
+        let style = &self.res.res_pack.info.combo;
+        let (event, sfx) = if combo < last {
+            if !style.break_effect || last < 3 {
+                return;
+            }
+            (ComboEvent::Break(last), &mut self.res.sfx_combo_break)
+        } else if style.milestone != 0 && combo / style.milestone > last / style.milestone {
+            (ComboEvent::Milestone, &mut self.res.sfx_combo_milestone)
+        } else {
+            return;
+        };
+        self.combo_event = Some((event, t));
+        if let Some(sfx) = sfx {
+            play_sfx(sfx, &self.res.config);
+        }
+    }
+
     fn interactive(res: &Resource, state: &State) -> bool {
         res.config.interactive && matches!(state, State::Playing)
     }
@@ -599,6 +822,55 @@ impl GameScene {
         self.chart.offset + self.res.config.offset + self.info_offset
     }
 
+    /// Saves the result to the score database if configured, returning the best score and accuracy before this play.
+    #[cfg_attr(not(all(feature = "scores", not(target_arch = "wasm32"))), allow(unused_variables))]
+    fn save_score(&self, result: &PlayResult) -> Option<(u32, f64)> {
+        #[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+        if let (Some(path), Some(hash)) = (&self.res.config.scores_path, &self.res.info.hash) {
+            let saved = crate::scores::ScoreDatabase::open(path).and_then(|db| {
+                let best = db.best(hash)?;
+                let config = &self.res.config;
+                db.insert(hash, &self.res.info.name, result, config.speed, self.res.track_length / config.speed)?;
+                Ok(best)
+            });
+            match saved {
+                Ok(best) => return best,
+                Err(err) => show_error(err.context(tl!("score-save-failed"))),
+            }
+        }
+        None
+    }
+
+    /// Remembers the offset of the chart in the score database if configured, so that it's used whenever the chart is played.
+    ///
+    /// `offset` is the difference from the offset in the chart info, see [`crate::scores::ChartSettings::offset`].
+    #[cfg_attr(not(all(feature = "scores", not(target_arch = "wasm32"))), allow(unused_variables))]
+    fn save_chart_offset(res: &Resource, offset: f32) {
+        #[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+        if let (Some(path), Some(hash)) = (&res.config.scores_path, &res.info.hash) {
+            let saved = crate::scores::ScoreDatabase::open(path).and_then(|db| {
+                let settings = crate::scores::ChartSettings {
+                    offset: Some(offset),
+                    ..db.settings(hash)?
+                };
+                db.save_settings(hash, &settings)
+            });
+            if let Err(err) = saved {
+                show_error(err.context(tl!("offset-save-failed")));
+            }
+        }
+    }
+
+    /// Sends the result to the leaderboard if configured.
+    #[cfg_attr(not(all(feature = "http", not(target_arch = "wasm32"))), allow(unused_variables))]
+    fn submit_score(&self, result: &PlayResult) {
+        #[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+        if let (Some(leaderboard), Some(hash)) = (crate::leaderboard::Leaderboard::from_config(&self.res.config), &self.res.info.hash) {
+            let config = &self.res.config;
+            leaderboard.submit_in_background(crate::leaderboard::Submission::new(hash.clone(), config.player_name.clone(), result, config.speed));
+        }
+    }
+
     fn tweak_offset(&mut self, ui: &mut Ui, ita: bool) {
         ui.scope(|ui| {
             let width = 0.55;
@@ -687,6 +959,18 @@ impl Scene for GameScene {
 
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
         self.res.audio.recover_if_needed()?;
+        let reloaded = {
+            let (count, config) = &*RELOADED.lock().unwrap();
+            if std::mem::replace(&mut self.reloads, *count) != *count {
+                config.clone()
+            } else {
+                None
+            }
+        };
+        if let Some(config) = reloaded {
+            self.apply_config(tm, &config)?;
+        }
+        self.check_modified(tm)?;
         if matches!(self.state, State::Playing) {
             tm.update(self.music.position() as f64);
         }
@@ -758,6 +1042,29 @@ impl Scene for GameScene {
                             }
                         }
                     }
+                    let result = PlayResult {
+                        rate_mod: self.res.config.rate_mod,
+                        ..self.judge.result()
+                    };
+                    let recorded = self.mode == GameMode::Normal && !self.res.config.autoplay && !self.judge.is_replaying();
+                    let previous_best = if recorded { self.save_score(&result) } else { None };
+                    if recorded {
+                        self.submit_score(&result);
+                    }
+                    if let Some(path) = &self.res.config.record_replay {
+                        if recorded {
+                            let replay = Replay {
+                                chart_hash: self.res.info.hash.clone(),
+                                ..self.judge.replay()
+                            };
+                            if let Err(err) = serde_json::to_string(&replay)
+                                .map_err(anyhow::Error::from)
+                                .and_then(|it| Ok(std::fs::write(path, it)?))
+                            {
+                                show_error(err.context(tl!("replay-save-failed")));
+                            }
+                        }
+                    }
                     self.next_scene = match self.mode {
                         GameMode::Normal => Some(NextScene::Overlay(Box::new(EndingScene::new(
                             self.res.background.clone(),
@@ -767,7 +1074,8 @@ impl Scene for GameScene {
                             self.res.icon_retry.clone(),
                             self.res.icon_proceed.clone(),
                             self.res.info.clone(),
-                            self.judge.result(),
+                            result,
+                            previous_best,
                             self.res.challenge_icons[self.res.config.challenge_color.clone() as usize].clone(),
                             &self.res.config,
                             self.res.res_pack.ending.clone(),
@@ -782,12 +1090,32 @@ impl Scene for GameScene {
                 self.res.track_length
             }
         };
+        if let Some(visualizer) = &mut self.visualizer {
+            visualizer.update(&self.res.music, time);
+        }
         let time = (time - offset).max(0.);
         self.res.time = time;
+        if let Some(sfx) = &mut self.metronome {
+            let beat = self.chart.bpm_list.borrow_mut().beat(time).floor() as i32;
+            if beat != self.metronome_beat {
+                // only click when advancing by one beat, so that seeking stays silent
+                if beat == self.metronome_beat.wrapping_add(1)
+                    && matches!(self.state, State::Playing)
+                    && !tm.paused()
+                    && self.res.config.volume_metronome > 1e-2
+                {
+                    let _ = sfx.play(PlaySfxParams {
+                        amplifier: self.res.config.volume_metronome,
+                    });
+                }
+                self.metronome_beat = beat;
+            }
+        }
         if !tm.paused() && self.pause_rewind.is_none() {
             self.gl.quad_gl.viewport(self.res.camera.viewport);
             self.judge.update(&mut self.res, &mut self.chart, &mut self.bad_notes);
             self.gl.quad_gl.viewport(None);
+            self.update_combo(tm.now() as f32);
         }
         let counts = self.judge.counts();
         self.res.judge_line_color = if counts[2] + counts[3] == 0 {
@@ -802,9 +1130,10 @@ impl Scene for GameScene {
         self.res.judge_line_color.a *= self.res.alpha;
         self.chart.update(&mut self.res);
         let res = &mut self.res;
-        if res.config.interactive && is_key_pressed(KeyCode::Space) {
+        if res.config.interactive && self.hud_editor.is_none() && is_key_pressed(KeyCode::Space) {
             if tm.paused() {
                 if matches!(self.state, State::Playing) {
+                    self.pause_menu.close_settings();
                     self.music.play()?;
                     tm.resume();
                 }
@@ -831,6 +1160,25 @@ impl Scene for GameScene {
             if is_key_pressed(KeyCode::Q) {
                 self.should_exit = true;
             }
+            if self.mode != GameMode::TweakOffset {
+                let step = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) {
+                    0.001
+                } else {
+                    OFFSET_STEP
+                };
+                let delta = if is_key_pressed(KeyCode::Minus) {
+                    -step
+                } else if is_key_pressed(KeyCode::Equal) {
+                    step
+                } else {
+                    0.
+                };
+                if delta != 0. {
+                    self.info_offset += delta;
+                    Self::save_chart_offset(res, self.info_offset - self.file_offset);
+                    self.offset_hint = Some(tm.real_time() + OFFSET_HINT_TIME);
+                }
+            }
         }
         for e in &mut self.effects {
             e.update(&self.res);
@@ -890,7 +1238,7 @@ impl Scene for GameScene {
     }
 
     fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
-        if self.mode == GameMode::Exercise && tm.paused() {
+        if self.mode == GameMode::Exercise && tm.paused() && !self.pause_menu.showing_settings() {
             if self.exercise_btns.0.touch(touch) {
                 request_input("exercise_start", &fmt_time(self.exercise_range.start));
                 return Ok(true);
@@ -925,15 +1273,24 @@ impl Scene for GameScene {
             render_target: chart_onto,
             ..Default::default()
         });
-        clear_background(BLACK);
-        draw_background(*res.background);
+        if res.config.transparent_background {
+            clear_background(Color::new(0., 0., 0., 0.));
+        } else {
+            clear_background(BLACK);
+            draw_background(*res.background);
+        }
         pop_camera_state();
 
         self.gl.quad_gl.render_pass(chart_onto.map(|it| it.render_pass));
         self.gl.quad_gl.viewport(res.camera.viewport);
 
-        let h = 1. / res.aspect_ratio;
-        draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., res.alpha * res.info.background_dim));
+        if !res.config.transparent_background {
+            let h = 1. / res.aspect_ratio;
+            draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., res.alpha * res.config.background_dim.unwrap_or(res.info.background_dim)));
+        }
+        if let Some(visualizer) = &self.visualizer {
+            visualizer.render(res);
+        }
 
         self.chart.render(ui, res);
 
@@ -947,7 +1304,7 @@ impl Scene for GameScene {
         self.bad_notes.retain(|dummy| dummy.render(res));
         let t = tm.real_time();
         let dt = (t - std::mem::replace(&mut self.last_update_time, t)) as f32;
-        if res.config.particle {
+        if !res.config.reduce_motion {
             res.emitter.draw(dt);
         }
         self.ui(ui, tm)?;
@@ -1034,4 +1391,8 @@ impl Scene for GameScene {
             NextScene::None
         }
     }
+
+    fn uses_transition(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,214 @@
+crate::tl_file!("hud");
+
+use crate::{
+    config::{HudElement, HudLayout},
+    core::{Matrix, Vector},
+    ext::RectExt,
+    judge::Judge,
+    ui::Ui,
+};
+use macroquad::prelude::*;
+use std::sync::Mutex;
+
+const MIN_SCALE: f32 = 0.5;
+const MAX_SCALE: f32 = 2.;
+
+/// The layout last saved in game, waiting to be stored by the app.
+static SAVED: Mutex<Option<HudLayout>> = Mutex::new(None);
+
+/// Takes the HUD layout saved in game since the last call, so that the app can write it to its config.
+pub fn take_hud_layout() -> Option<HudLayout> {
+    SAVED.lock().unwrap().take()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HudItem {
+    Combo,
+    Pause,
+    Progress,
+    Score,
+}
+
+impl HudItem {
+    pub const ALL: [Self; 4] = [Self::Combo, Self::Pause, Self::Progress, Self::Score];
+}
+
+fn element(layout: &HudLayout, item: HudItem) -> &HudElement {
+    match item {
+        HudItem::Combo => &layout.combo,
+        HudItem::Pause => &layout.pause,
+        HudItem::Progress => &layout.progress,
+        HudItem::Score => &layout.score,
+    }
+}
+
+fn element_mut(layout: &mut HudLayout, item: HudItem) -> &mut HudElement {
+    match item {
+        HudItem::Combo => &mut layout.combo,
+        HudItem::Pause => &mut layout.pause,
+        HudItem::Progress => &mut layout.progress,
+        HudItem::Score => &mut layout.score,
+    }
+}
+
+/// Moves and scales an element drawn around `pivot` as `element` says.
+pub fn hud_matrix(element: &HudElement, pivot: Vector) -> Matrix {
+    Matrix::new_scaling(element.scale)
+        .prepend_translation(&-pivot)
+        .append_translation(&(pivot + Vector::new(element.x, element.y)))
+}
+
+/// Where `rect`, drawn around `pivot`, ends up after `element` is applied.
+pub fn hud_rect(element: &HudElement, rect: Rect, pivot: Vector) -> Rect {
+    let s = element.scale;
+    Rect::new(pivot.x + element.x + (rect.x - pivot.x) * s, pivot.y + element.y + (rect.y - pivot.y) * s, rect.w * s, rect.h * s)
+}
+
+struct Gesture {
+    center: Vec2,
+    distance: f32,
+    element: HudElement,
+}
+
+/// Lets the HUD elements be dragged around and pinched to scale while the game is paused.
+pub struct HudEditor {
+    pub layout: HudLayout,
+    /// Where each item was drawn last frame before being placed, and the point it's scaled about.
+    pub bounds: [Option<(Rect, Vector)>; 4],
+    selected: Option<HudItem>,
+    /// Touches holding the selected item, at most two.
+    points: Vec<(u64, Vec2)>,
+    gesture: Option<Gesture>,
+}
+
+impl HudEditor {
+    pub fn new(layout: HudLayout) -> Self {
+        Self {
+            layout,
+            bounds: [None; 4],
+            selected: None,
+            points: Vec::new(),
+            gesture: None,
+        }
+    }
+
+    fn placed(&self, item: HudItem) -> Option<Rect> {
+        let (rect, pivot) = self.bounds[item as usize]?;
+        Some(hud_rect(element(&self.layout, item), rect, pivot))
+    }
+
+    fn hit(&self, p: Vec2) -> Option<HudItem> {
+        HudItem::ALL
+            .into_iter()
+            .find(|it| self.placed(*it).map_or(false, |r| r.feather(0.02).contains(p)))
+    }
+
+    fn handle_touches(&mut self, toolbar: Rect) {
+        let count = self.points.len();
+        for touch in Judge::get_touches() {
+            let owned = self.points.iter().position(|it| it.0 == touch.id);
+            match touch.phase {
+                TouchPhase::Started => {
+                    if toolbar.contains(touch.position) || self.points.len() >= 2 {
+                        continue;
+                    }
+                    if self.points.is_empty() {
+                        self.selected = self.hit(touch.position);
+                        if self.selected.is_none() {
+                            continue;
+                        }
+                    }
+                    self.points.push((touch.id, touch.position));
+                }
+                TouchPhase::Moved | TouchPhase::Stationary => {
+                    if let Some(index) = owned {
+                        self.points[index].1 = touch.position;
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    if let Some(index) = owned {
+                        self.points.remove(index);
+                    }
+                }
+            }
+        }
+        let Some(item) = self.selected else {
+            self.points.clear();
+            return;
+        };
+        if self.points.is_empty() {
+            self.gesture = None;
+            return;
+        }
+        let center = self.points.iter().map(|it| it.1).sum::<Vec2>() / self.points.len() as f32;
+        let distance = if self.points.len() == 2 {
+            self.points[0].1.distance(self.points[1].1)
+        } else {
+            0.
+        };
+        let element = element_mut(&mut self.layout, item);
+        // starts over whenever a finger is added or lifted, so that the element doesn't jump
+        if self.points.len() != count || self.gesture.is_none() {
+            self.gesture = Some(Gesture {
+                center,
+                distance,
+                element: *element,
+            });
+        }
+        let gesture = self.gesture.as_ref().unwrap();
+        element.x = gesture.element.x + center.x - gesture.center.x;
+        element.y = gesture.element.y + center.y - gesture.center.y;
+        if distance > 0. && gesture.distance > 0. {
+            element.scale = (gesture.element.scale * distance / gesture.distance).clamp(MIN_SCALE, MAX_SCALE);
+        }
+    }
+
+    /// Draws the editor over the HUD, returning `Some(true)` if the layout is to be saved and `Some(false)` if discarded.
+    pub fn render(&mut self, ui: &mut Ui) -> Option<bool> {
+        let bottom = ui.top;
+        let toolbar = Rect::new(-0.6, bottom - 0.28, 1.2, 0.26);
+        self.handle_touches(toolbar);
+
+        for item in HudItem::ALL {
+            let Some(r) = self.placed(item) else {
+                continue;
+            };
+            let r = r.feather(0.01);
+            if self.selected == Some(item) {
+                ui.fill_rect(r, Color { a: 0.3, ..ui.accent() });
+                draw_rectangle_lines(r.x, r.y, r.w, r.h, 0.008, ui.accent());
+            } else {
+                draw_rectangle_lines(r.x, r.y, r.w, r.h, 0.006, Color::new(1., 1., 1., 0.6));
+            }
+        }
+
+        ui.fill_rect(toolbar, Color::new(0., 0., 0., 0.7));
+        let mut result = None;
+        ui.scope(|ui| {
+            ui.dx(toolbar.x + 0.04);
+            ui.dy(toolbar.y + 0.03);
+            if let Some(item) = self.selected {
+                let element = element_mut(&mut self.layout, item);
+                ui.slider_with(tl!("scale"), MIN_SCALE..MAX_SCALE, 0.05, &mut element.scale, Some(toolbar.w - 0.2), |it| {
+                    format!("{:.0}%", it * 100.)
+                });
+            } else {
+                ui.text(tl!("hint")).size(0.42).color(Color::new(1., 1., 1., 0.7)).draw();
+            }
+        });
+        let (w, h) = (0.3, 0.07);
+        let y = toolbar.bottom() - h - 0.03;
+        if ui.button("hud#reset", Rect::new(-w * 1.5 - 0.04, y, w, h), tl!("reset")) {
+            self.layout = HudLayout::default();
+            self.gesture = None;
+        }
+        if ui.button("hud#cancel", Rect::new(-w / 2., y, w, h), tl!("cancel")) {
+            result = Some(false);
+        }
+        if ui.button("hud#save", Rect::new(w / 2. + 0.04, y, w, h), tl!("save")) {
+            *SAVED.lock().unwrap() = Some(self.layout);
+            result = Some(true);
+        }
+        result
+    }
+}
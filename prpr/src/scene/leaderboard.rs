@@ -0,0 +1,202 @@
+crate::tl_file!("leaderboard");
+
+use super::{NextScene, Scene};
+use crate::{
+    ext::screen_aspect,
+    leaderboard::{Leaderboard, Scores},
+    task::Task,
+    time::TimeManager,
+    ui::{Grid, Ui},
+};
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use macroquad::prelude::*;
+
+const ROW_HEIGHT: f32 = 0.1;
+
+/// Shows the top scores of a chart, fetched from the leaderboard service.
+pub struct LeaderboardScene {
+    leaderboard: Leaderboard,
+    hash: String,
+    name: String,
+    /// Entries of this player are highlighted.
+    player: String,
+    target: Option<RenderTarget>,
+    list: Grid,
+    task: Option<Task<Result<Scores>>>,
+    scores: Option<Scores>,
+    error: Option<String>,
+    back_rect: Option<Rect>,
+    next_scene: Option<NextScene>,
+}
+
+impl LeaderboardScene {
+    pub fn new(leaderboard: Leaderboard, hash: String, name: String, player: String) -> Self {
+        let mut res = Self {
+            leaderboard,
+            hash,
+            name,
+            player,
+            target: None,
+            list: Grid::list(ROW_HEIGHT),
+            task: None,
+            scores: None,
+            error: None,
+            back_rect: None,
+            next_scene: None,
+        };
+        res.load();
+        res
+    }
+
+    fn load(&mut self) {
+        let (leaderboard, hash) = (self.leaderboard.clone(), self.hash.clone());
+        self.task = Some(Task::new(async move { leaderboard.top(&hash).await }));
+        self.error = None;
+    }
+}
+
+impl Scene for LeaderboardScene {
+    fn enter(&mut self, _tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        self.target = target;
+        Ok(())
+    }
+
+    fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        if self.back_rect.map_or(false, |it| it.contains(touch.position)) {
+            return Ok(false);
+        }
+        if touch.phase == TouchPhase::Ended && self.task.is_none() && self.scores.is_none() {
+            // tap to retry
+            self.load();
+            return Ok(true);
+        }
+        Ok(self.list.touch(touch, tm.now() as f32))
+    }
+
+    fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        self.list.update(tm.now() as f32);
+        if let Some(task) = &mut self.task {
+            if let Some(result) = task.take() {
+                self.task = None;
+                match result {
+                    Ok(scores) => self.scores = Some(scores),
+                    Err(err) => self.error = Some(format!("{:?}", err.context(tl!("load-failed")))),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, _tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        let top = 1. / screen_aspect();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -screen_aspect()),
+            render_target: self.target,
+            ..Default::default()
+        });
+        clear_background(Color::new(0.08, 0.08, 0.1, 1.));
+        ui.text(tl!("title")).pos(-0.92, -top + 0.04).size(0.8).draw();
+        ui.text(&self.name)
+            .pos(-0.92, -top + 0.15)
+            .size(0.42)
+            .max_width(1.5)
+            .color(Color::new(1., 1., 1., 0.7))
+            .draw();
+
+        let r = Rect::new(0.72, -top + 0.04, 0.24, 0.08);
+        self.back_rect = Some(ui.rect_to_global(r));
+        if ui.button("leaderboard#back", r, tl!("back")) {
+            self.next_scene = Some(NextScene::Pop);
+        }
+
+        fn hint(ui: &mut Ui, text: &str) {
+            ui.text(text)
+                .pos(0., 0.)
+                .anchor(0.5, 0.5)
+                .size(0.5)
+                .max_width(1.8)
+                .multiline()
+                .color(GRAY)
+                .draw();
+        }
+        if self.task.is_some() {
+            hint(ui, &tl!("loading"));
+            return Ok(());
+        }
+        let Some(scores) = &self.scores else {
+            if let Some(error) = &self.error {
+                hint(ui, &format!("{error}\n\n{}", tl!("tap-to-retry")));
+            }
+            return Ok(());
+        };
+        if scores.entries.is_empty() {
+            hint(ui, &tl!("empty"));
+        }
+        if scores.offline {
+            ui.text(tl!("offline"))
+                .pos(0.96, -top + 0.16)
+                .anchor(1., 0.)
+                .size(0.36)
+                .color(Color::new(1., 0.7, 0.3, 1.))
+                .draw();
+        }
+        let width = 1.84;
+        let (list, player) = (&mut self.list, &self.player);
+        ui.scope(|ui| {
+            ui.dx(-width / 2.);
+            ui.dy(-top + 0.24);
+            list.size((width, top * 2. - 0.26));
+            list.render(ui, scores.entries.len(), |ui, index, r| {
+                let entry = &scores.entries[index];
+                let r = Rect { h: r.h - 0.01, ..r };
+                let mine = entry.player == *player;
+                ui.fill_rect(
+                    r,
+                    if mine {
+                        Color { a: 0.3, ..ui.accent() }
+                    } else {
+                        Color::new(1., 1., 1., 0.08)
+                    },
+                );
+                let cy = r.center().y;
+                ui.text(format!("#{}", index + 1))
+                    .pos(r.x + 0.03, cy)
+                    .anchor(0., 0.5)
+                    .size(0.5)
+                    .no_baseline()
+                    .draw();
+                ui.text(&entry.player)
+                    .pos(r.x + 0.2, cy)
+                    .anchor(0., 0.5)
+                    .size(0.5)
+                    .max_width(0.7)
+                    .no_baseline()
+                    .draw();
+                let time = Local
+                    .timestamp_opt(entry.time, 0)
+                    .single()
+                    .map(|it| it.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                ui.text(time)
+                    .pos(r.x + 0.95, cy)
+                    .anchor(0., 0.5)
+                    .size(0.34)
+                    .color(GRAY)
+                    .no_baseline()
+                    .draw();
+                ui.text(format!("{:07}  {:.2}%  {}x", entry.score, entry.accuracy * 100., entry.max_combo))
+                    .pos(r.right() - 0.03, cy)
+                    .anchor(1., 0.5)
+                    .size(0.42)
+                    .no_baseline()
+                    .draw();
+            });
+        });
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        self.next_scene.take().unwrap_or_default()
+    }
+}
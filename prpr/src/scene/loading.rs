@@ -1,9 +1,10 @@
-use super::{draw_background, draw_illustration, ending::RecordUpdateState, game::GameMode, GameScene, NextScene, Scene};
+use super::{draw_background, draw_illustration_ex, ending::RecordUpdateState, game::GameMode, GameScene, NextScene, Scene};
 use crate::{
     config::Config,
-    ext::{draw_parallelogram, draw_text_aligned, poll_future, screen_aspect, LocalTask, SafeTexture, BLACK_TEXTURE},
+    core::ResourcePack,
+    ext::{draw_parallelogram, draw_text_aligned, poll_future, screen_aspect, LocalTask, SafeTexture, ScaleType, BLACK_TEXTURE},
     fs::FileSystem,
-    info::ChartInfo,
+    info::{ChartInfo, LoadingIndicator, LoadingStyle},
     task::Task,
     time::TimeManager,
     ui::Ui,
@@ -22,16 +23,22 @@ const WAIT_TIME: f32 = 0.4;
 
 pub struct LoadingScene {
     info: ChartInfo,
+    tier_badge: Option<(String, Color)>,
+    best: Option<(u32, f64)>,
     background: SafeTexture,
     illustration: SafeTexture,
+    illustration_scale: ScaleType,
+    indicator: LoadingIndicator,
     load_task: LocalTask<Result<GameScene>>,
     next_scene: Option<NextScene>,
     finish_time: f32,
+    before_time: f32,
     target: Option<RenderTarget>,
 }
 
 impl LoadingScene {
     pub const TOTAL_TIME: f32 = BEFORE_TIME + TRANSITION_TIME + WAIT_TIME;
+    pub const MIN_TIME: f32 = TRANSITION_TIME + WAIT_TIME;
 
     pub async fn new(
         mode: GameMode,
@@ -78,21 +85,55 @@ impl LoadingScene {
             .map(|(ill, back)| (ill.into(), back.into()))
             .unwrap_or_else(|| (BLACK_TEXTURE.clone(), BLACK_TEXTURE.clone()));
         let get_size_fn = get_size_fn.unwrap_or_else(|| Rc::new(|| (screen_width() as u32, screen_height() as u32)));
+        let skin = match ResourcePack::info_from_path(config.res_pack_path.as_ref()).await {
+            Ok(it) => it.loading,
+            Err(err) => {
+                warn!("Failed to load resource pack info: {:?}", err);
+                LoadingStyle::default()
+            }
+        };
+        let style = info.loading.clone().or(skin);
         if info.tip.is_none() {
-            info.tip = Some(crate::config::TIPS.choose().cloned().unwrap());
+            info.tip = Some(style.tips.choose().unwrap_or_else(|| crate::config::TIPS.choose().unwrap()).clone());
         }
+        #[cfg(all(feature = "scores", not(target_arch = "wasm32")))]
+        let best = match &config.scores_path {
+            Some(path) => match crate::library::ensure_hash(fs.as_mut(), &mut info).await {
+                Some(hash) => crate::scores::ScoreDatabase::open(path)
+                    .and_then(|db| db.best(hash))
+                    .unwrap_or_else(|err| {
+                        warn!("Failed to query best score: {:?}", err);
+                        None
+                    }),
+                None => None,
+            },
+            None => None,
+        };
+        #[cfg(not(all(feature = "scores", not(target_arch = "wasm32"))))]
+        let best = None;
+        let tier_badge = config.tier_badge(&info);
         let future =
             Box::pin(GameScene::new(mode, info.clone(), config, fs, player, background.clone(), illustration.clone(), get_size_fn, upload_fn));
         Ok(Self {
             info,
+            tier_badge,
+            best,
             background,
             illustration,
+            illustration_scale: style.illustration_scale.unwrap_or_default(),
+            indicator: style.indicator.unwrap_or(LoadingIndicator::Text),
             load_task: Some(future),
             next_scene: None,
             finish_time: f32::INFINITY,
+            before_time: style.duration.map_or(BEFORE_TIME, |it| (it - Self::MIN_TIME).max(0.)),
             target: None,
         })
     }
+
+    /// Changes how long the card lasts, which can't be shorter than [`Self::MIN_TIME`].
+    pub fn set_total_time(&mut self, total_time: f32) {
+        self.before_time = (total_time - Self::MIN_TIME).max(0.);
+    }
 }
 
 impl Scene for LoadingScene {
@@ -115,7 +156,7 @@ impl Scene for LoadingScene {
                         self.load_task = None;
                         self.next_scene =
                             Some(game_scene.map_or_else(|e| NextScene::PopWithResult(Box::new(e)), |it| NextScene::Replace(Box::new(it))));
-                        self.finish_time = tm.now() as f32 + BEFORE_TIME;
+                        self.finish_time = tm.now() as f32 + self.before_time;
                         break;
                     }
                 }
@@ -146,7 +187,7 @@ impl Scene for LoadingScene {
             gl.push_model_matrix(Mat4::from_translation(vec3(dx, 0., 0.)));
         }
         let vo = -top / 10.;
-        let r = draw_illustration(*self.illustration, 0.38, vo, 1., 1., WHITE);
+        let r = draw_illustration_ex(*self.illustration, 0.38, vo, 1., 1., WHITE, self.illustration_scale);
         let h = r.h / 3.6;
         let main = Rect::new(-0.88, vo - h / 2. - top / 10., 0.78, h);
         draw_parallelogram(main, None, Color::new(0., 0., 0., 0.7), true);
@@ -167,33 +208,56 @@ impl Scene for LoadingScene {
         let sub = Rect::new(main.x + main.w * 0.71, main.y - main.h * ext, main.w * 0.26, main.h * (1. + ext * 2.));
         let mut ct = sub.center();
         ct.x += sub.w * 0.02;
-        draw_parallelogram(sub, None, WHITE, true);
-        draw_text_aligned(ui, &(self.info.difficulty as u32).to_string(), ct.x, ct.y + sub.h * 0.05, (0.5, 1.), 0.88, BLACK);
-        draw_text_aligned(ui, self.info.level.split_whitespace().next().unwrap_or_default(), ct.x, ct.y + sub.h * 0.09, (0.5, 0.), 0.34, BLACK);
+        let (label, bg, fg) = match &self.tier_badge {
+            Some((label, color)) => (label.as_str(), *color, WHITE),
+            None => (self.info.level.split_whitespace().next().unwrap_or_default(), WHITE, BLACK),
+        };
+        draw_parallelogram(sub, None, bg, true);
+        draw_text_aligned(ui, &(self.info.difficulty as u32).to_string(), ct.x, ct.y + sub.h * 0.05, (0.5, 1.), 0.88, fg);
+        draw_text_aligned(ui, label, ct.x, ct.y + sub.h * 0.09, (0.5, 0.), 0.34, fg);
         let t = draw_text_aligned(ui, "Chart", main.x + main.w / 6., main.y + main.h * 1.2, (0., 0.), 0.3, WHITE);
         draw_text_aligned(ui, &self.info.charter, t.x, t.y + top / 20., (0., 0.), 0.47, WHITE);
         let w = 0.027;
         let t = draw_text_aligned(ui, "Illustration", t.x - w, t.y + w / 0.13 / 13. * 5., (0., 0.), 0.3, WHITE);
         draw_text_aligned(ui, &self.info.illustrator, t.x, t.y + top / 20., (0., 0.), 0.47, WHITE);
+        if let Some((score, accuracy)) = self.best {
+            draw_text_aligned(ui, &format!("BEST {score:07}  {:.2}%", accuracy * 100.), r.right(), r.bottom() + 0.02, (1., 0.), 0.4, WHITE);
+        }
 
         draw_text_aligned(ui, self.info.tip.as_ref().unwrap(), -0.91, top * 0.92, (0., 1.), 0.47, WHITE);
-        let t = draw_text_aligned(ui, "Loading...", 0.87, top * 0.92, (1., 1.), 0.44, WHITE);
-        let we = 0.2;
-        let he = 0.5;
-        let r = Rect::new(t.x - t.w * we, t.y - t.h * he, t.w * (1. + we * 2.), t.h * (1. + he * 2.));
-
-        let p = 0.6;
-        let s = 0.2;
-        let t = ((now - 0.3).max(0.) % (p * 2. + s)) / p;
-        let st = (t - 1.).clamp(0., 1.).powi(3);
-        let en = 1. - (1. - t.min(1.)).powi(3);
-
-        let mut r = Rect::new(r.x + r.w * st, r.y, r.w * (en - st), r.h);
-        ui.fill_rect(r, WHITE);
-        r.x += dx;
-        ui.scissor(Some(r));
-        draw_text_aligned(ui, "Loading...", 0.87, top * 0.92, (1., 1.), 0.44, BLACK);
-        ui.scissor(None);
+        match self.indicator {
+            LoadingIndicator::Text => {
+                let t = draw_text_aligned(ui, "Loading...", 0.87, top * 0.92, (1., 1.), 0.44, WHITE);
+                let we = 0.2;
+                let he = 0.5;
+                let r = Rect::new(t.x - t.w * we, t.y - t.h * he, t.w * (1. + we * 2.), t.h * (1. + he * 2.));
+
+                let p = 0.6;
+                let s = 0.2;
+                let t = ((now - 0.3).max(0.) % (p * 2. + s)) / p;
+                let st = (t - 1.).clamp(0., 1.).powi(3);
+                let en = 1. - (1. - t.min(1.)).powi(3);
+
+                let mut r = Rect::new(r.x + r.w * st, r.y, r.w * (en - st), r.h);
+                ui.fill_rect(r, WHITE);
+                r.x += dx;
+                ui.scissor(Some(r));
+                draw_text_aligned(ui, "Loading...", 0.87, top * 0.92, (1., 1.), 0.44, BLACK);
+                ui.scissor(None);
+            }
+            LoadingIndicator::Spinner => {
+                let radius = 0.03;
+                let (cx, cy) = (0.87 - radius, top * 0.92 - radius);
+                let count = 12;
+                let head = (now * 1.5).fract() * count as f32;
+                for i in 0..count {
+                    let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+                    let alpha = 1. - ((head - i as f32).rem_euclid(count as f32) / count as f32);
+                    draw_circle(cx + angle.cos() * radius, cy + angle.sin() * radius, 0.005, Color::new(1., 1., 1., alpha));
+                }
+            }
+            LoadingIndicator::None => {}
+        }
 
         if dx != 0. {
             gl.pop_model_matrix();
@@ -212,4 +276,8 @@ impl Scene for LoadingScene {
         }
         NextScene::None
     }
+
+    fn uses_transition(&self) -> bool {
+        false
+    }
 }
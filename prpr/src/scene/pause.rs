@@ -0,0 +1,140 @@
+crate::tl_file!("pause");
+
+use crate::{core::Resource, judge::Judge, ui::Ui};
+use macroquad::prelude::*;
+
+/// How much the offset of the chart is changed by each press.
+pub const OFFSET_STEP: f32 = 0.005;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Exit,
+    /// Opens the editor of the HUD layout.
+    EditHud,
+}
+
+/// The menu shown over a paused game. Settings changed here last until the game is closed.
+#[derive(Default)]
+pub struct PauseMenu {
+    settings: bool,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn showing_settings(&self) -> bool {
+        self.settings
+    }
+
+    pub fn close_settings(&mut self) {
+        self.settings = false;
+    }
+
+    /// Draws the menu with its buttons centered at `y`, returning the action chosen if any.
+    ///
+    /// The offset of this chart is adjusted in the settings if `chart_offset` is given.
+    pub fn render(&mut self, ui: &mut Ui, res: &mut Resource, y: f32, chart_offset: Option<&mut f32>) -> Option<PauseAction> {
+        let h = 1. / res.aspect_ratio;
+        draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., 0.6));
+        if self.settings {
+            return self.render_settings(ui, res, y, chart_offset);
+        }
+        let c = Color::new(1., 1., 1., res.alpha);
+        let s = 0.06;
+        let w = 0.05;
+        let buttons = [
+            (PauseAction::Exit, *res.icon_back),
+            (PauseAction::Restart, *res.icon_retry),
+            (PauseAction::Resume, *res.icon_resume),
+        ];
+        for (i, (_, icon)) in buttons.iter().enumerate() {
+            draw_texture_ex(
+                *icon,
+                (s * 2. + w) * (i as f32 - 1.) - s,
+                y - s,
+                c,
+                DrawTextureParams {
+                    dest_size: Some(vec2(s * 2., s * 2.)),
+                    ..Default::default()
+                },
+            );
+        }
+        let settings = ui
+            .text(tl!("settings"))
+            .pos(0., y + s + 0.05)
+            .anchor(0.5, 0.)
+            .size(0.46)
+            .color(Color::new(1., 1., 1., res.alpha * 0.8))
+            .draw();
+        if !res.config.interactive {
+            return None;
+        }
+        let settings = ui.rect_to_global(settings.feather(0.02));
+        for touch in Judge::get_touches() {
+            if touch.phase != TouchPhase::Started {
+                continue;
+            }
+            let p = touch.position;
+            if settings.contains(p) {
+                self.settings = true;
+                return None;
+            }
+            for (i, (action, _)) in buttons.iter().enumerate() {
+                let d = p - vec2((s * 2. + w) * (i as f32 - 1.), y);
+                if d.x.abs() <= s && d.y.abs() <= s {
+                    return Some(*action);
+                }
+            }
+        }
+        None
+    }
+
+    fn render_settings(&mut self, ui: &mut Ui, res: &mut Resource, y: f32, chart_offset: Option<&mut f32>) -> Option<PauseAction> {
+        let config = &mut res.config;
+        let width = 0.8;
+        if let Some(offset) = chart_offset {
+            let y = y + 0.1;
+            ui.text(tl!("chart-offset"))
+                .pos(-width / 2., y)
+                .anchor(0., 0.5)
+                .size(0.46)
+                .no_baseline()
+                .draw();
+            ui.text(format!("{:+.0}ms", *offset * 1000.))
+                .pos(width / 2. - 0.17, y)
+                .anchor(0.5, 0.5)
+                .size(0.46)
+                .no_baseline()
+                .draw();
+            if ui.button("pause#offset-down", Rect::new(width / 2. - 0.34, y - 0.035, 0.08, 0.07), "-") {
+                *offset -= OFFSET_STEP;
+            }
+            if ui.button("pause#offset-up", Rect::new(width / 2. - 0.08, y - 0.035, 0.08, 0.07), "+") {
+                *offset += OFFSET_STEP;
+            }
+        }
+        ui.scope(|ui| {
+            ui.dx(-width / 2.);
+            ui.dy(y - 0.24);
+            let mut offset = config.offset * 1000.;
+            let r = ui.slider_with(tl!("offset"), -500.0..500., 5., &mut offset, Some(width), |it| format!("{it:.0}ms"));
+            config.offset = offset / 1000.;
+            ui.dy(r.h + 0.05);
+            let r = ui.slider_with(tl!("volume-sfx"), 0.0..2., 0.05, &mut config.volume_sfx, Some(width), |it| format!("{:.0}%", it * 100.));
+            ui.dy(r.h + 0.05);
+            ui.toggle(tl!("double-click-pause"), &mut config.double_click_to_pause);
+        });
+        if ui.button("pause#edit-hud", Rect::new(-0.38, y + 0.2, 0.36, 0.07), tl!("edit-hud")) {
+            self.settings = false;
+            return Some(PauseAction::EditHud);
+        }
+        if ui.button("pause#done", Rect::new(0.02, y + 0.2, 0.36, 0.07), tl!("done")) {
+            self.settings = false;
+        }
+        None
+    }
+}
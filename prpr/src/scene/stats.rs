@@ -0,0 +1,185 @@
+crate::tl_file!("stats");
+
+use super::{NextScene, Scene};
+use crate::{
+    config::Config,
+    ext::screen_aspect,
+    scores::{ScoreDatabase, Statistics},
+    time::TimeManager,
+    ui::Ui,
+};
+use anyhow::{Context, Result};
+use chrono::Local;
+use macroquad::prelude::*;
+
+const PANEL_COLOR: Color = Color::new(1., 1., 1., 0.08);
+const LABEL_COLOR: Color = Color::new(1., 1., 1., 0.6);
+
+/// The profile of the player along with statistics of every play in the score database.
+pub struct StatsScene {
+    player_name: String,
+    player_rks: f32,
+    stats: Statistics,
+    target: Option<RenderTarget>,
+    next_scene: Option<NextScene>,
+}
+
+impl StatsScene {
+    pub fn new(config: &Config) -> Result<Self> {
+        let path = config
+            .scores_path
+            .as_ref()
+            .context("Statistics come from the database at scoresPath, which is not set")?;
+        Ok(Self {
+            player_name: config.player_name.clone(),
+            player_rks: config.player_rks,
+            stats: ScoreDatabase::open(path)?.statistics()?,
+            target: None,
+            next_scene: None,
+        })
+    }
+
+    fn render_trend(&self, ui: &mut Ui, r: Rect) {
+        ui.fill_rect(r, PANEL_COLOR);
+        ui.text(tl!("accuracy-trend"))
+            .pos(r.x + 0.02, r.y + 0.02)
+            .size(0.4)
+            .color(LABEL_COLOR)
+            .draw();
+        let trend = &self.stats.accuracy_trend;
+        if trend.is_empty() {
+            ui.text(tl!("no-plays"))
+                .pos(r.center().x, r.center().y)
+                .anchor(0.5, 0.5)
+                .size(0.4)
+                .color(GRAY)
+                .draw();
+            return;
+        }
+        let chart = Rect::new(r.x + 0.1, r.y + 0.1, r.w - 0.14, r.h - 0.18);
+        // zoomed in on the accuracies reached, in steps of 10%
+        let low = (trend.iter().map(|it| it.1).fold(1., f64::min) * 10.).floor() as f32 / 10.;
+        let low = low.min(0.9);
+        let y_of = |accuracy: f64| chart.bottom() - (accuracy as f32 - low) / (1. - low) * chart.h;
+        let x_of = |index: usize| {
+            if trend.len() == 1 {
+                chart.center().x
+            } else {
+                chart.x + index as f32 / (trend.len() - 1) as f32 * chart.w
+            }
+        };
+        for accuracy in [low as f64, (low as f64 + 1.) / 2., 1.] {
+            let y = y_of(accuracy);
+            draw_line(chart.x, y, chart.right(), y, 0.002, Color::new(1., 1., 1., 0.2));
+            ui.text(format!("{:.0}%", accuracy * 100.))
+                .pos(chart.x - 0.02, y)
+                .anchor(1., 0.5)
+                .size(0.28)
+                .color(LABEL_COLOR)
+                .no_baseline()
+                .draw();
+        }
+        let accent = ui.accent();
+        for (index, pair) in trend.windows(2).enumerate() {
+            draw_line(x_of(index), y_of(pair[0].1), x_of(index + 1), y_of(pair[1].1), 0.005, accent);
+        }
+        for (index, (_, accuracy)) in trend.iter().enumerate() {
+            draw_circle(x_of(index), y_of(*accuracy), 0.008, accent);
+        }
+        let date = |index: usize| trend[index].0.with_timezone(&Local).format("%m-%d").to_string();
+        ui.text(date(0)).pos(chart.x, chart.bottom() + 0.02).size(0.28).color(LABEL_COLOR).draw();
+        if trend.len() > 1 {
+            ui.text(date(trend.len() - 1))
+                .pos(chart.right(), chart.bottom() + 0.02)
+                .anchor(1., 0.)
+                .size(0.28)
+                .color(LABEL_COLOR)
+                .draw();
+        }
+    }
+
+    fn render_most_played(&self, ui: &mut Ui, r: Rect) {
+        ui.fill_rect(r, PANEL_COLOR);
+        ui.text(tl!("most-played"))
+            .pos(r.x + 0.02, r.y + 0.02)
+            .size(0.4)
+            .color(LABEL_COLOR)
+            .draw();
+        let most = self.stats.most_played.first().map_or(1, |it| it.1).max(1);
+        let row = ((r.h - 0.1) / self.stats.most_played.len().max(1) as f32).min(0.1);
+        for (index, (name, count)) in self.stats.most_played.iter().enumerate() {
+            let y = r.y + 0.1 + index as f32 * row;
+            ui.text(name).pos(r.x + 0.02, y).size(0.36).max_width(r.w - 0.14).draw();
+            let bar = Rect::new(r.x + 0.02, y + row * 0.5, (r.w - 0.14) * *count as f32 / most as f32, row * 0.3);
+            ui.fill_rect(bar, ui.accent());
+            ui.text(count.to_string())
+                .pos(bar.right() + 0.01, bar.center().y)
+                .anchor(0., 0.5)
+                .size(0.3)
+                .no_baseline()
+                .draw();
+        }
+    }
+}
+
+impl Scene for StatsScene {
+    fn enter(&mut self, _tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        self.target = target;
+        Ok(())
+    }
+
+    fn update(&mut self, _tm: &mut TimeManager) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&mut self, _tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        let top = 1. / screen_aspect();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -screen_aspect()),
+            render_target: self.target,
+            ..Default::default()
+        });
+        clear_background(Color::new(0.08, 0.08, 0.1, 1.));
+        let r = ui.text(&self.player_name).pos(-0.92, -top + 0.04).size(0.8).draw();
+        ui.text(tl!("rks", "rks" => format!("{:.2}", self.player_rks)))
+            .pos(r.right() + 0.03, r.bottom())
+            .anchor(0., 1.)
+            .size(0.42)
+            .color(LABEL_COLOR)
+            .draw();
+        if ui.button("stats#back", Rect::new(0.72, -top + 0.04, 0.24, 0.08), tl!("back")) {
+            self.next_scene = Some(NextScene::Pop);
+        }
+
+        let stats = &self.stats;
+        let minutes = (stats.play_time / 60.) as u64;
+        let tiles = [
+            (tl!("plays"), stats.plays.to_string()),
+            (tl!("play-time"), tl!("play-time-value", "hours" => minutes / 60, "minutes" => minutes % 60)),
+            (tl!("notes-hit"), stats.notes_hit.to_string()),
+            (tl!("accuracy"), format!("{:.2}%", stats.accuracy * 100.)),
+        ];
+        let (w, h, gap) = (0.445, 0.16, 0.02);
+        for (index, (label, value)) in tiles.into_iter().enumerate() {
+            let r = Rect::new(-0.92 + index as f32 * (w + gap), -top + 0.18, w, h);
+            ui.fill_rect(r, PANEL_COLOR);
+            ui.text(label).pos(r.x + 0.02, r.y + 0.02).size(0.36).color(LABEL_COLOR).draw();
+            ui.text(value)
+                .pos(r.x + 0.02, r.bottom() - 0.02)
+                .anchor(0., 1.)
+                .size(0.7)
+                .max_width(r.w - 0.04)
+                .draw();
+        }
+
+        let y = -top + 0.18 + h + gap;
+        let h = top - 0.04 - y;
+        self.render_trend(ui, Rect::new(-0.92, y, 1.08, h));
+        self.render_most_played(ui, Rect::new(0.18, y, 0.74, h));
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        self.next_scene.take().unwrap_or_default()
+    }
+}
@@ -0,0 +1,321 @@
+crate::tl_file!("tutorial");
+
+use super::{NextScene, Scene};
+use crate::{
+    core::{ResourcePack, JUDGE_LINE_PERFECT_COLOR},
+    ext::{screen_aspect, SafeTexture},
+    time::TimeManager,
+    ui::Ui,
+};
+use anyhow::Result;
+use macroquad::prelude::*;
+use std::{borrow::Cow, collections::HashMap};
+
+/// How long a note takes from entering the screen to reaching the line.
+const FALL_TIME: f32 = 1.6;
+const SPEED: f32 = 0.7;
+const NOTE_WIDTH: f32 = 0.26;
+/// Hitting a note this long before or after it reaches the line still counts, as a good in game does.
+const GOOD_RANGE: f32 = 0.16;
+const FLICK_DISTANCE: f32 = 0.05;
+const HOLD_LENGTH: f32 = 1.2;
+/// Releasing a hold this long before its end is forgiven.
+const HOLD_TOLERANCE: f32 = 0.2;
+const RESULT_TIME: f32 = 1.2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Intro,
+    Tap,
+    Drag,
+    Hold,
+    Flick,
+    Done,
+}
+
+impl Step {
+    fn next(self) -> Self {
+        match self {
+            Self::Intro => Self::Tap,
+            Self::Tap => Self::Drag,
+            Self::Drag => Self::Hold,
+            Self::Hold => Self::Flick,
+            Self::Flick | Self::Done => Self::Done,
+        }
+    }
+
+    fn has_note(self) -> bool {
+        !matches!(self, Self::Intro | Self::Done)
+    }
+
+    fn title(self) -> Cow<'static, str> {
+        match self {
+            Self::Intro => tl!("intro-title"),
+            Self::Tap => tl!("tap-title"),
+            Self::Drag => tl!("drag-title"),
+            Self::Hold => tl!("hold-title"),
+            Self::Flick => tl!("flick-title"),
+            Self::Done => tl!("done-title"),
+        }
+    }
+
+    fn description(self) -> Cow<'static, str> {
+        match self {
+            Self::Intro => tl!("intro"),
+            Self::Tap => tl!("tap"),
+            Self::Drag => tl!("drag"),
+            Self::Hold => tl!("hold"),
+            Self::Flick => tl!("flick"),
+            Self::Done => tl!("done"),
+        }
+    }
+}
+
+/// Teaches each kind of note with a note falling again and again until it's hit.
+pub struct TutorialScene {
+    res_pack: ResourcePack,
+    target: Option<RenderTarget>,
+    step: Step,
+    /// When the current note reaches the line.
+    note_time: f32,
+    /// Where each touch down was last, and where a flick is measured from.
+    touches: HashMap<u64, (Vec2, Vec2)>,
+    /// The touch holding the current hold note.
+    holding: Option<u64>,
+    /// Whether the current note was hit, and the message shown until the time given.
+    result: Option<(bool, Cow<'static, str>, f32)>,
+    skip_rect: Option<Rect>,
+    next_scene: Option<NextScene>,
+}
+
+impl TutorialScene {
+    pub fn new(res_pack: ResourcePack) -> Self {
+        Self {
+            res_pack,
+            target: None,
+            step: Step::Intro,
+            note_time: 0.,
+            touches: HashMap::new(),
+            holding: None,
+            result: None,
+            skip_rect: None,
+            next_scene: None,
+        }
+    }
+
+    fn line_y(top: f32) -> f32 {
+        top * 0.45
+    }
+
+    fn finish(&mut self, hit: bool, message: Cow<'static, str>, t: f32) {
+        self.result = Some((hit, message, t + RESULT_TIME));
+        self.holding = None;
+    }
+
+    fn judge_touch(&mut self, touch: &Touch, t: f32) {
+        let dt = t - self.note_time;
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.insert(touch.id, (touch.position, touch.position));
+                match self.step {
+                    Step::Tap | Step::Hold if dt.abs() <= GOOD_RANGE => {
+                        if self.step == Step::Tap {
+                            self.finish(true, tl!("nice"), t);
+                        } else {
+                            self.holding = Some(touch.id);
+                        }
+                    }
+                    Step::Tap | Step::Hold if dt > -GOOD_RANGE * 2. && dt < 0. && self.holding.is_none() => {
+                        self.finish(false, tl!("too-early"), t);
+                    }
+                    _ => {}
+                }
+            }
+            TouchPhase::Moved | TouchPhase::Stationary => {
+                let Some((now, from)) = self.touches.get_mut(&touch.id) else {
+                    return;
+                };
+                *now = touch.position;
+                if dt.abs() > GOOD_RANGE {
+                    // only swiping as the note passes counts
+                    *from = touch.position;
+                } else if self.step == Step::Flick && from.distance(touch.position) >= FLICK_DISTANCE {
+                    self.finish(true, tl!("nice"), t);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+                if self.holding == Some(touch.id) && dt < HOLD_LENGTH - HOLD_TOLERANCE {
+                    self.finish(false, tl!("hold-released"), t);
+                }
+            }
+        }
+    }
+
+    fn draw_note(&self, tex: &SafeTexture, source: Option<Rect>, y: f32, h: f32, alpha: f32) {
+        draw_texture_ex(
+            **tex,
+            -NOTE_WIDTH / 2.,
+            y - h / 2.,
+            Color::new(1., 1., 1., alpha),
+            DrawTextureParams {
+                dest_size: Some(vec2(NOTE_WIDTH, h)),
+                source: source.map(|it| Rect::new(it.x * tex.width(), it.y * tex.height(), it.w * tex.width(), it.h * tex.height())),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn render_note(&self, t: f32, line: f32, top: f32) {
+        let style = &self.res_pack.note_style;
+        let y = line - (self.note_time - t) * SPEED;
+        let alpha = ((y + top) / 0.2).clamp(0., 1.);
+        let tex = match self.step {
+            Step::Tap => &style.click,
+            Step::Drag => &style.drag,
+            Step::Flick => &style.flick,
+            Step::Hold => {
+                let end = line - (self.note_time + HOLD_LENGTH - t) * SPEED;
+                let head = if self.holding.is_some() { line } else { y };
+                let scale = NOTE_WIDTH / style.hold.width();
+                let head_h = style.hold_atlas.1 as f32 * scale;
+                let tail_h = style.hold_atlas.0 as f32 * scale;
+                if head > end {
+                    let body = style.hold_body.as_ref().unwrap_or(&style.hold);
+                    let source = if style.hold_body.is_some() { None } else { Some(style.hold_body_rect()) };
+                    self.draw_note(body, source, (head + end) / 2., head - end, alpha);
+                }
+                self.draw_note(&style.hold, Some(style.hold_tail_rect()), end - tail_h / 2., tail_h, alpha);
+                if self.holding.is_none() {
+                    self.draw_note(&style.hold, Some(style.hold_head_rect()), head + head_h / 2., head_h, alpha);
+                }
+                return;
+            }
+            _ => return,
+        };
+        self.draw_note(tex, None, y, NOTE_WIDTH * tex.height() / tex.width(), alpha);
+    }
+}
+
+impl Scene for TutorialScene {
+    fn enter(&mut self, _tm: &mut TimeManager, target: Option<RenderTarget>) -> Result<()> {
+        self.target = target;
+        Ok(())
+    }
+
+    fn touch(&mut self, tm: &mut TimeManager, touch: &Touch) -> Result<bool> {
+        if self.skip_rect.map_or(false, |it| it.contains(touch.position)) {
+            return Ok(false);
+        }
+        let t = tm.now() as f32;
+        if !self.step.has_note() {
+            if touch.phase == TouchPhase::Started {
+                if self.step == Step::Done {
+                    self.next_scene = Some(NextScene::Pop);
+                } else {
+                    self.step = self.step.next();
+                    self.note_time = t + FALL_TIME;
+                }
+            }
+            return Ok(true);
+        }
+        if self.result.is_none() {
+            self.judge_touch(touch, t);
+        } else if matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled) {
+            self.touches.remove(&touch.id);
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        let t = tm.now() as f32;
+        if let Some((hit, _, until)) = &self.result {
+            if t >= *until {
+                if *hit {
+                    self.step = self.step.next();
+                }
+                self.result = None;
+                self.note_time = t + FALL_TIME;
+            }
+            return Ok(());
+        }
+        if !self.step.has_note() {
+            return Ok(());
+        }
+        let dt = t - self.note_time;
+        match self.step {
+            Step::Drag if dt >= 0. => {
+                if self.touches.is_empty() {
+                    self.finish(false, tl!("drag-missed"), t);
+                } else {
+                    self.finish(true, tl!("nice"), t);
+                }
+            }
+            Step::Hold if self.holding.is_some() && dt >= HOLD_LENGTH => self.finish(true, tl!("nice"), t),
+            Step::Tap | Step::Hold if self.holding.is_none() && dt > GOOD_RANGE => self.finish(false, tl!("too-late"), t),
+            Step::Flick if dt > GOOD_RANGE => self.finish(false, tl!("flick-missed"), t),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, tm: &mut TimeManager, ui: &mut Ui) -> Result<()> {
+        let t = tm.now() as f32;
+        let top = 1. / screen_aspect();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -screen_aspect()),
+            render_target: self.target,
+            ..Default::default()
+        });
+        clear_background(Color::new(0.08, 0.08, 0.1, 1.));
+
+        ui.text(self.step.title()).pos(-0.92, -top + 0.06).size(0.9).draw();
+        ui.text(self.step.description())
+            .pos(-0.92, -top + 0.2)
+            .size(0.5)
+            .max_width(1.84)
+            .multiline()
+            .color(Color::new(1., 1., 1., 0.8))
+            .draw();
+
+        let line = Self::line_y(top);
+        if self.step.has_note() {
+            ui.fill_rect(Rect::new(-1., line - 0.004, 2., 0.008), JUDGE_LINE_PERFECT_COLOR);
+            if self.result.is_none() {
+                self.render_note(t, line, top);
+            }
+        } else {
+            let alpha = 0.5 + (t * 3.).sin() * 0.3;
+            ui.text(tl!("tap-to-continue"))
+                .pos(0., line)
+                .anchor(0.5, 0.5)
+                .size(0.5)
+                .color(Color::new(1., 1., 1., alpha))
+                .draw();
+        }
+        if let Some((hit, message, _)) = &self.result {
+            let color = if *hit { ui.accent() } else { Color::new(0.96, 0.26, 0.21, 1.) };
+            ui.text(message.as_ref())
+                .pos(0., line - 0.2)
+                .anchor(0.5, 0.5)
+                .size(0.7)
+                .color(color)
+                .draw();
+        }
+
+        if self.step != Step::Done {
+            let r = Rect::new(0.72, -top + 0.04, 0.24, 0.08);
+            self.skip_rect = Some(ui.rect_to_global(r));
+            if ui.button("tutorial#skip", r, tl!("skip")) {
+                self.next_scene = Some(NextScene::Pop);
+            }
+        } else {
+            self.skip_rect = None;
+        }
+        Ok(())
+    }
+
+    fn next_scene(&mut self, _tm: &mut TimeManager) -> NextScene {
+        self.next_scene.take().unwrap_or_default()
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{config::RateMod, judge::PlayResult};
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub struct PlayRecord {
+    pub score: u32,
+    pub accuracy: f64,
+    pub max_combo: u32,
+    pub num_of_notes: u32,
+    pub counts: [u32; 4],
+    pub early: u32,
+    pub late: u32,
+    pub rate_mod: Option<RateMod>,
+    pub speed: f32,
+    pub time: DateTime<Utc>,
+}
+
+impl PlayRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let rate_mod: Option<String> = row.get("rate_mod")?;
+        Ok(Self {
+            score: row.get("score")?,
+            accuracy: row.get("accuracy")?,
+            max_combo: row.get("max_combo")?,
+            num_of_notes: row.get("num_of_notes")?,
+            counts: [row.get("perfect")?, row.get("good")?, row.get("bad")?, row.get("miss")?],
+            early: row.get("early")?,
+            late: row.get("late")?,
+            rate_mod: rate_mod.and_then(|it| RateMod::ALL.into_iter().find(|m| m.label() == it)),
+            speed: row.get("speed")?,
+            time: Utc.timestamp_opt(row.get("time")?, 0).single().unwrap_or_default(),
+        })
+    }
+}
+
+/// A row of the plays, along with the name of the chart, as exchanged between devices.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedPlay {
+    pub chart: String,
+    pub name: Option<String>,
+    pub score: u32,
+    pub accuracy: f64,
+    pub max_combo: u32,
+    pub num_of_notes: u32,
+    pub counts: [u32; 4],
+    pub early: u32,
+    pub late: u32,
+    pub rate_mod: Option<String>,
+    pub speed: f32,
+    pub time: i64,
+    pub duration: Option<f32>,
+}
+
+/// How many of the latest days played the accuracy trend covers.
+const TREND_DAYS: usize = 30;
+const MOST_PLAYED: usize = 5;
+
+pub struct Statistics {
+    pub plays: u32,
+    /// In seconds, leaving out plays saved before durations were.
+    pub play_time: f64,
+    pub notes_hit: u64,
+    pub accuracy: f64,
+    /// The average accuracy of each day played in local time, oldest first.
+    pub accuracy_trend: Vec<(DateTime<Utc>, f64)>,
+    /// Names of the charts played most along with how many times they're played.
+    pub most_played: Vec<(String, u32)>,
+}
+
+/// Overrides of the config for a single chart, replacing the global ones whenever the chart is played.
+#[derive(Clone, Default)]
+pub struct ChartSettings {
+    /// Added to the offset in the chart info, so that it stays right when the info is changed.
+    pub offset: Option<f32>,
+    pub speed: Option<f32>,
+    pub aspect_ratio: Option<f32>,
+}
+
+impl ChartSettings {
+    pub fn is_empty(&self) -> bool {
+        self.offset.is_none() && self.speed.is_none() && self.aspect_ratio.is_none()
+    }
+}
+
+/// Every finished play and the settings of each chart, keyed by the hash of the chart so that they survive moving or renaming it.
+pub struct ScoreDatabase(Connection);
+
+impl ScoreDatabase {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path).context("Failed to open score database")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS plays (
+                id INTEGER PRIMARY KEY,
+                chart TEXT NOT NULL,
+                score INTEGER NOT NULL,
+                accuracy REAL NOT NULL,
+                max_combo INTEGER NOT NULL,
+                num_of_notes INTEGER NOT NULL,
+                perfect INTEGER NOT NULL,
+                good INTEGER NOT NULL,
+                bad INTEGER NOT NULL,
+                miss INTEGER NOT NULL,
+                early INTEGER NOT NULL,
+                late INTEGER NOT NULL,
+                rate_mod TEXT,
+                speed REAL NOT NULL,
+                time INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS plays_chart ON plays (chart);
+            CREATE TABLE IF NOT EXISTS chart_settings (
+                chart TEXT PRIMARY KEY,
+                offset REAL,
+                speed REAL,
+                aspect_ratio REAL
+            );
+            CREATE TABLE IF NOT EXISTS chart_names (
+                chart TEXT PRIMARY KEY,
+                name TEXT NOT NULL
+            );",
+        )
+        .context("Failed to initialize score database")?;
+        // added after the table, so older databases need it added
+        let has_duration: bool =
+            conn.query_row("SELECT COUNT(*) > 0 FROM pragma_table_info('plays') WHERE name = 'duration'", [], |row| row.get(0))?;
+        if !has_duration {
+            conn.execute("ALTER TABLE plays ADD COLUMN duration REAL", [])
+                .context("Failed to upgrade score database")?;
+        }
+        Ok(Self(conn))
+    }
+
+    /// Saves a play of the chart named `name`, which took `duration` seconds.
+    pub fn insert(&self, chart: &str, name: &str, result: &PlayResult, speed: f32, duration: f32) -> Result<()> {
+        let [perfect, good, bad, miss] = result.counts;
+        self.0
+            .execute(
+                "INSERT INTO plays (chart, score, accuracy, max_combo, num_of_notes, perfect, good, bad, miss, early, late, rate_mod, speed, time,
+                    duration)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                params![
+                    chart,
+                    result.score,
+                    result.accuracy,
+                    result.max_combo,
+                    result.num_of_notes,
+                    perfect,
+                    good,
+                    bad,
+                    miss,
+                    result.early,
+                    result.late,
+                    result.rate_mod.map(|it| it.label()),
+                    speed,
+                    Utc::now().timestamp(),
+                    duration,
+                ],
+            )
+            .context("Failed to save play result")?;
+        self.0
+            .execute("INSERT OR REPLACE INTO chart_names (chart, name) VALUES (?1, ?2)", [chart, name])
+            .context("Failed to save play result")?;
+        Ok(())
+    }
+
+    /// The best score and the best accuracy ever achieved on the chart, which may come from different plays.
+    pub fn best(&self, chart: &str) -> Result<Option<(u32, f64)>> {
+        Ok(self
+            .0
+            .query_row("SELECT MAX(score), MAX(accuracy) FROM plays WHERE chart = ?1", [chart], |row| {
+                Ok(row.get::<_, Option<u32>>(0)?.zip(row.get::<_, Option<f64>>(1)?))
+            })
+            .optional()?
+            .flatten())
+    }
+
+    /// The latest `limit` plays of the chart, newest first.
+    pub fn history(&self, chart: &str, limit: usize) -> Result<Vec<PlayRecord>> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT * FROM plays WHERE chart = ?1 ORDER BY time DESC, id DESC LIMIT ?2")?;
+        let records = stmt
+            .query_map(params![chart, limit as i64], PlayRecord::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    pub fn settings(&self, chart: &str) -> Result<ChartSettings> {
+        Ok(self
+            .0
+            .query_row("SELECT offset, speed, aspect_ratio FROM chart_settings WHERE chart = ?1", [chart], |row| {
+                Ok(ChartSettings {
+                    offset: row.get(0)?,
+                    speed: row.get(1)?,
+                    aspect_ratio: row.get(2)?,
+                })
+            })
+            .optional()?
+            .unwrap_or_default())
+    }
+
+    /// Replaces the settings of the chart, removing them if `settings` is empty.
+    pub fn save_settings(&self, chart: &str, settings: &ChartSettings) -> Result<()> {
+        if settings.is_empty() {
+            self.0.execute("DELETE FROM chart_settings WHERE chart = ?1", [chart])?;
+        } else {
+            self.0.execute(
+                "INSERT OR REPLACE INTO chart_settings (chart, offset, speed, aspect_ratio) VALUES (?1, ?2, ?3, ?4)",
+                params![chart, settings.offset, settings.speed, settings.aspect_ratio],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Every play saved, for syncing them with [`Self::merge_plays`].
+    pub fn plays(&self) -> Result<Vec<SyncedPlay>> {
+        let mut stmt = self
+            .0
+            .prepare("SELECT plays.*, chart_names.name FROM plays LEFT JOIN chart_names ON chart_names.chart = plays.chart ORDER BY time, id")?;
+        let plays = stmt
+            .query_map([], |row| {
+                Ok(SyncedPlay {
+                    chart: row.get("chart")?,
+                    name: row.get("name")?,
+                    score: row.get("score")?,
+                    accuracy: row.get("accuracy")?,
+                    max_combo: row.get("max_combo")?,
+                    num_of_notes: row.get("num_of_notes")?,
+                    counts: [row.get("perfect")?, row.get("good")?, row.get("bad")?, row.get("miss")?],
+                    early: row.get("early")?,
+                    late: row.get("late")?,
+                    rate_mod: row.get("rate_mod")?,
+                    speed: row.get("speed")?,
+                    time: row.get("time")?,
+                    duration: row.get("duration")?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(plays)
+    }
+
+    /// Adds the plays not saved yet, telling them apart by the chart and the time played. Returns how many are added.
+    pub fn merge_plays(&mut self, plays: &[SyncedPlay]) -> Result<usize> {
+        let tx = self.0.transaction()?;
+        let mut added = 0;
+        for play in plays {
+            let [perfect, good, bad, miss] = play.counts;
+            added += tx.execute(
+                "INSERT INTO plays (chart, score, accuracy, max_combo, num_of_notes, perfect, good, bad, miss, early, late, rate_mod, speed, time,
+                    duration)
+                SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15
+                WHERE NOT EXISTS (SELECT 1 FROM plays WHERE chart = ?1 AND time = ?14)",
+                params![
+                    play.chart,
+                    play.score,
+                    play.accuracy,
+                    play.max_combo,
+                    play.num_of_notes,
+                    perfect,
+                    good,
+                    bad,
+                    miss,
+                    play.early,
+                    play.late,
+                    play.rate_mod,
+                    play.speed,
+                    play.time,
+                    play.duration,
+                ],
+            )?;
+            if let Some(name) = &play.name {
+                tx.execute("INSERT OR IGNORE INTO chart_names (chart, name) VALUES (?1, ?2)", [&play.chart, name])?;
+            }
+        }
+        tx.commit().context("Failed to merge plays")?;
+        Ok(added)
+    }
+
+    /// Sums up every play saved.
+    pub fn statistics(&self) -> Result<Statistics> {
+        let (plays, play_time, notes_hit, accuracy) = self.0.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(duration), 0), COALESCE(SUM(perfect + good), 0), COALESCE(AVG(accuracy), 0) FROM plays",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        let mut stmt = self.0.prepare(&format!(
+            "SELECT date(time, 'unixepoch', 'localtime') AS day, MIN(time), AVG(accuracy) FROM plays
+            GROUP BY day ORDER BY day DESC LIMIT {TREND_DAYS}"
+        ))?;
+        // days are local to the player; each one is keyed by its first play
+        let mut accuracy_trend = stmt
+            .query_map([], |row| Ok((Utc.timestamp_opt(row.get(1)?, 0).single().unwrap_or_default(), row.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        accuracy_trend.reverse();
+        let mut stmt = self.0.prepare(&format!(
+            "SELECT COALESCE(chart_names.name, plays.chart), COUNT(*) AS count FROM plays
+            LEFT JOIN chart_names ON chart_names.chart = plays.chart
+            GROUP BY plays.chart ORDER BY count DESC LIMIT {MOST_PLAYED}"
+        ))?;
+        let most_played = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(Statistics {
+            plays,
+            play_time,
+            notes_hit,
+            accuracy,
+            accuracy_trend,
+            most_played,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(score: u32, accuracy: f64) -> PlayResult {
+        PlayResult {
+            score,
+            accuracy,
+            max_combo: 10,
+            num_of_notes: 12,
+            counts: [8, 2, 1, 1],
+            early: 2,
+            late: 1,
+            ..Default::default()
+        }
+    }
+
+    fn database() -> ScoreDatabase {
+        ScoreDatabase::open(":memory:").unwrap()
+    }
+
+    #[test]
+    fn best_of_each_chart() {
+        let db = database();
+        assert_eq!(db.best("a").unwrap(), None);
+        db.insert("a", "Chart A", &result(900_000, 0.95), 1., 60.).unwrap();
+        db.insert("a", "Chart A", &result(950_000, 0.9), 1., 60.).unwrap();
+        db.insert("b", "Chart B", &result(1_000_000, 1.), 1., 60.).unwrap();
+        // the best score and accuracy come from different plays
+        assert_eq!(db.best("a").unwrap(), Some((950_000, 0.95)));
+    }
+
+    #[test]
+    fn history_newest_first() {
+        let db = database();
+        db.insert("a", "Chart A", &result(1, 0.1), 1., 60.).unwrap();
+        db.insert(
+            "a",
+            "Chart A",
+            &PlayResult {
+                rate_mod: Some(RateMod::NightcorePlus),
+                ..result(2, 0.2)
+            },
+            1.5,
+            60.,
+        )
+        .unwrap();
+        db.insert("a", "Chart A", &result(3, 0.3), 1., 60.).unwrap();
+        let history = db.history("a", 2).unwrap();
+        assert_eq!(history.iter().map(|it| it.score).collect::<Vec<_>>(), [3, 2]);
+        let record = &history[1];
+        assert_eq!(record.rate_mod, Some(RateMod::NightcorePlus));
+        assert_eq!(record.speed, 1.5);
+        assert_eq!((record.max_combo, record.num_of_notes, record.counts), (10, 12, [8, 2, 1, 1]));
+        assert_eq!((record.early, record.late), (2, 1));
+        assert!(db.history("b", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn settings_saved_and_removed() {
+        let db = database();
+        assert!(db.settings("a").unwrap().is_empty());
+        let settings = ChartSettings {
+            offset: Some(-0.05),
+            aspect_ratio: Some(16. / 9.),
+            ..Default::default()
+        };
+        db.save_settings("a", &settings).unwrap();
+        let saved = db.settings("a").unwrap();
+        assert_eq!((saved.offset, saved.speed, saved.aspect_ratio), (Some(-0.05), None, Some(16. / 9.)));
+        assert!(db.settings("b").unwrap().is_empty());
+        db.save_settings("a", &ChartSettings::default()).unwrap();
+        assert!(db.settings("a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn merge_plays_once() {
+        let from = database();
+        from.insert("a", "Chart A", &result(1, 0.1), 1., 30.).unwrap();
+        from.insert("b", "Chart B", &result(2, 0.2), 1., 30.).unwrap();
+        let plays = from.plays().unwrap();
+        assert_eq!(plays.len(), 2);
+        assert_eq!(plays[0].name.as_deref(), Some("Chart A"));
+
+        let mut to = database();
+        assert_eq!(to.merge_plays(&plays).unwrap(), 2);
+        assert_eq!(to.merge_plays(&plays).unwrap(), 0);
+        let merged = to.plays().unwrap();
+        assert_eq!(
+            merged
+                .iter()
+                .map(|it| (it.chart.as_str(), it.name.as_deref(), it.score))
+                .collect::<Vec<_>>(),
+            [("a", Some("Chart A"), 1), ("b", Some("Chart B"), 2)]
+        );
+    }
+
+    #[test]
+    fn statistics_of_plays() {
+        let db = database();
+        let stats = db.statistics().unwrap();
+        assert_eq!((stats.plays, stats.play_time, stats.notes_hit), (0, 0., 0));
+        assert!(stats.accuracy_trend.is_empty() && stats.most_played.is_empty());
+
+        db.insert("a", "Chart A", &result(1, 0.5), 1., 30.).unwrap();
+        db.insert("a", "Chart A", &result(2, 1.), 1., 45.).unwrap();
+        db.insert("b", "Chart B", &result(3, 0.75), 1., 15.).unwrap();
+        let stats = db.statistics().unwrap();
+        assert_eq!((stats.plays, stats.play_time, stats.notes_hit), (3, 90., 30));
+        assert_eq!(stats.accuracy, 0.75);
+        assert_eq!(stats.accuracy_trend.len(), 1);
+        assert_eq!(stats.most_played, [("Chart A".to_owned(), 2), ("Chart B".to_owned(), 1)]);
+    }
+}
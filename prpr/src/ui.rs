@@ -1,14 +1,17 @@
+mod animation;
+pub use animation::{Eased, Spring};
+
 mod billboard;
-pub use billboard::{BillBoard, Message, MessageHandle, MessageKind};
+pub use billboard::{discard_messages, dismiss_messages, BillBoard, Message, MessageHandle, MessageKind, MAX_VISIBLE};
 
 mod chart_info;
 pub use chart_info::*;
 
 mod dialog;
-pub use dialog::Dialog;
+pub use dialog::{Dialog, ProgressHandle};
 
 mod scroll;
-pub use scroll::Scroll;
+pub use scroll::{Grid, Scroll};
 
 mod shading;
 pub use shading::*;
@@ -16,13 +19,20 @@ pub use shading::*;
 mod text;
 pub use text::{DrawText, TextPainter};
 
+mod keyboard;
+pub(crate) use keyboard::{keyboard_shown, render_keyboard, touch_keyboard};
+
+mod text_input;
+pub use text_input::set_preedit;
+pub(crate) use text_input::update_text_input;
+
 pub use glyph_brush::ab_glyph::FontArc;
 
 use crate::{
-    core::{Matrix, Point, Vector},
+    config::Config,
+    core::{Matrix, Point, Tweenable, Vector},
     ext::{get_viewport, nalgebra_to_glm, screen_aspect, source_of_image, RectExt, ScaleType},
     judge::Judge,
-    scene::{request_input, return_input, take_input},
 };
 use lyon::{
     lyon_tessellation::{BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, VertexBuffers},
@@ -31,7 +41,12 @@ use lyon::{
 };
 use macroquad::prelude::*;
 use miniquad::PassAction;
-use std::{borrow::Cow, cell::RefCell, collections::HashMap, ops::Range};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ops::Range,
+};
 
 struct ShadedConstructor<T: Shading>(Matrix, pub T);
 impl<T: Shading> FillVertexConstructor<Vertex> for ShadedConstructor<T> {
@@ -132,6 +147,26 @@ impl RectButton {
 
 thread_local! {
     static STATE: RefCell<HashMap<String, Option<u64>>> = RefCell::new(HashMap::new());
+    static PRESS: RefCell<HashMap<String, Spring>> = RefCell::new(HashMap::new());
+    /// Only one dropdown is open at a time.
+    static OPEN_DROPDOWN: RefCell<Option<String>> = RefCell::new(None);
+    static TEXT_SCALE: Cell<f32> = Cell::new(1.);
+    static REDUCE_MOTION: Cell<bool> = Cell::new(false);
+}
+
+/// Applies the accessibility options in `config` to all UI from now on.
+pub fn apply_accessibility(config: &Config) {
+    TEXT_SCALE.with(|it| it.set(config.text_scale.clamp(0.5, 2.)));
+    REDUCE_MOTION.with(|it| it.set(config.reduce_motion));
+}
+
+pub fn text_scale() -> f32 {
+    TEXT_SCALE.with(Cell::get)
+}
+
+/// Whether animations should jump to their ends.
+pub fn reduce_motion() -> bool {
+    REDUCE_MOTION.with(Cell::get)
 }
 
 pub struct InputParams {
@@ -397,7 +432,14 @@ impl<'a> Ui<'a> {
         STATE.with(|state| {
             let mut state = state.borrow_mut();
             let entry = state.entry(id.to_owned()).or_default();
-            self.fill_rect(rect, if entry.is_some() { Color::new(1., 1., 1., 0.5) } else { WHITE });
+            let pressed = PRESS.with(|press| {
+                let mut press = press.borrow_mut();
+                let spring = press.entry(id.to_owned()).or_insert_with(|| Spring::critical(0., 900.));
+                spring.set(if entry.is_some() { 1. } else { 0. });
+                spring.update(get_time() as f32)
+            });
+            let shrink = rect.w.min(rect.h) * 0.06 * pressed;
+            self.fill_rect(rect.feather(-shrink), Color::tween(&WHITE, &Color::new(1., 1., 1., 0.5), pressed));
             let ct = rect.center();
             self.text(text)
                 .pos(ct.x, ct.y)
@@ -436,24 +478,99 @@ impl<'a> Ui<'a> {
         let r = self.text(label).anchor(1., 0.).size(0.4).draw();
         let lf = r.x;
         let r = Rect::new(0.02, r.y - 0.01, params.length, r.h + 0.02);
-        if if params.password {
-            self.button(&id, r, &"*".repeat(value.chars().count()))
-        } else {
-            self.button(&id, r, value.as_str())
-        } {
-            request_input(&id, value);
+        text_input::text_box(self, &id, r, value, params.password);
+        Rect::new(lf, r.y, r.right() - lf, r.h)
+    }
+
+    /// A switch turning `value` on and off, with the text to its right.
+    pub fn toggle(&mut self, text: impl Into<String>, value: &mut bool) -> Rect {
+        let text = text.into();
+        let id = format!("toggle#{text}");
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let entry = state.entry(id.clone()).or_default();
+            let (w, h) = (0.08, 0.04);
+            let text = self.text(text).pos(w + 0.02, 0.).size(0.5).no_baseline().draw();
+            let cy = text.center().y;
+            let p = PRESS.with(|press| {
+                let mut press = press.borrow_mut();
+                let spring = press.entry(id).or_insert_with(|| Spring::critical(if *value { 1. } else { 0. }, 400.));
+                spring.set(if *value { 1. } else { 0. });
+                spring.update(get_time() as f32)
+            });
+            let track = Rect::new(0., cy - h / 2., w, h);
+            let (r, knob) = (h / 2., h / 2. - 0.005);
+            let color = Color::tween(&Color::new(1., 1., 1., 0.4), &self.accent(), p);
+            self.fill_rect(Rect::new(r, track.y, w - r * 2., h), color);
+            self.fill_circle(r, cy, r, color);
+            self.fill_circle(w - r, cy, r, color);
+            self.fill_circle(r + (w - r * 2.) * p, cy, knob, WHITE);
+            let r = Rect::new(0., track.y.min(text.y), text.right(), track.h.max(text.h));
+            if self.clicked(r, entry) {
+                *value ^= true;
+            }
+            r
+        })
+    }
+
+    /// A box showing which of `options` `value` is, named by `name`, which lists them below to choose from when tapped.
+    ///
+    /// It's laid out like [`Self::input`], and the returned rect covers the list while it's open.
+    pub fn dropdown<T: Clone + PartialEq>(
+        &mut self,
+        label: impl Into<String>,
+        value: &mut T,
+        options: &[T],
+        name: impl Fn(&T) -> String,
+        length: f32,
+    ) -> Rect {
+        let label = label.into();
+        let id = format!("dropdown#{label}");
+        let r = self.text(label).anchor(1., 0.).size(0.4).draw();
+        let lf = r.x;
+        let r = Rect::new(0.02, r.y - 0.01, length, r.h + 0.02);
+        let open = OPEN_DROPDOWN.with(|it| it.borrow().as_deref() == Some(id.as_str()));
+        if self.button(&id, r, format!("{}  {}", name(value), if open { '▴' } else { '▾' })) {
+            OPEN_DROPDOWN.with(|it| *it.borrow_mut() = if open { None } else { Some(id.clone()) });
         }
-        if let Some((its_id, text)) = take_input() {
-            if its_id == id {
-                *value = text;
-            } else {
-                return_input(its_id, text);
+        let mut bottom = r.bottom();
+        if open {
+            for (index, option) in options.iter().enumerate() {
+                let or = Rect::new(r.x, bottom, r.w, r.h);
+                let selected = option == value;
+                self.fill_rect(or, if selected { self.accent() } else { Color::new(1., 1., 1., 0.85) });
+                self.text(name(option))
+                    .pos(or.x + 0.01, or.center().y)
+                    .anchor(0., 0.5)
+                    .max_width(or.w - 0.02)
+                    .size(0.4)
+                    .color(if selected { WHITE } else { BLACK })
+                    .no_baseline()
+                    .draw();
+                if STATE.with(|state| self.clicked(or, state.borrow_mut().entry(format!("{id}:{index}")).or_default())) {
+                    *value = option.clone();
+                    OPEN_DROPDOWN.with(|it| *it.borrow_mut() = None);
+                }
+                bottom = or.bottom();
             }
         }
-        Rect::new(lf, r.y, r.right() - lf, r.h)
+        Rect::new(lf, r.y, r.right() - lf, bottom - r.y)
     }
 
     pub fn slider(&mut self, text: impl Into<String>, range: Range<f32>, step: f32, value: &mut f32, length: Option<f32>) -> Rect {
+        self.slider_with(text, range, step, value, length, |value| format!("{value:.3}"))
+    }
+
+    /// A slider with its value labeled as `format` gives, e.g. as a percentage.
+    pub fn slider_with(
+        &mut self,
+        text: impl Into<String>,
+        range: Range<f32>,
+        step: f32,
+        value: &mut f32,
+        length: Option<f32>,
+        format: impl Fn(f32) -> String,
+    ) -> Rect {
         let text = text.into();
         STATE.with(|state| {
             let mut state = state.borrow_mut();
@@ -461,7 +578,7 @@ impl<'a> Ui<'a> {
 
             let len = length.unwrap_or(0.3);
             let s = 0.002;
-            let tr = self.text(format!("{text}: {value:.3}")).size(0.4).draw();
+            let tr = self.text(format!("{text}: {}", format(*value))).size(0.4).draw();
             let cy = tr.h + 0.03;
             let r = Rect::new(0., cy - s, len, s * 2.);
             self.fill_rect(r, WHITE);
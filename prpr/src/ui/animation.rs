@@ -0,0 +1,226 @@
+use crate::core::{TweenId, Tweenable, TWEEN_FUNCTIONS};
+
+/// A value moving to its target along an easing curve in a fixed duration.
+#[derive(Clone)]
+pub struct Eased<T: Tweenable> {
+    from: T,
+    to: T,
+    start: f32,
+    duration: f32,
+    tween: TweenId,
+}
+
+impl<T: Tweenable> Eased<T> {
+    /// Stays at `value` until [`Self::set`] is called.
+    pub fn new(value: T, duration: f32, tween: TweenId) -> Self {
+        Self {
+            from: value.clone(),
+            to: value,
+            start: f32::NEG_INFINITY,
+            duration,
+            tween,
+        }
+    }
+
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+
+    pub fn progress(&self, t: f32) -> f32 {
+        if self.duration <= 0. || super::reduce_motion() {
+            1.
+        } else {
+            ((t - self.start) / self.duration).clamp(0., 1.)
+        }
+    }
+
+    pub fn finished(&self, t: f32) -> bool {
+        self.progress(t) >= 1.
+    }
+
+    pub fn now(&self, t: f32) -> T {
+        T::tween(&self.from, &self.to, TWEEN_FUNCTIONS[self.tween as usize](self.progress(t)))
+    }
+
+    /// Moves from where it is at `t` to `target`.
+    pub fn set(&mut self, target: T, t: f32) {
+        self.from = self.now(t);
+        self.to = target;
+        self.start = t;
+    }
+
+    /// Moves from `from` to `to`, starting at `t`.
+    pub fn animate(&mut self, from: T, to: T, t: f32) {
+        self.from = from;
+        self.to = to;
+        self.start = t;
+    }
+}
+
+/// A damped spring following its target. Unlike [`Eased`], the motion stays smooth when the target changes halfway.
+#[derive(Clone)]
+pub struct Spring {
+    value: f32,
+    velocity: f32,
+    target: f32,
+    stiffness: f32,
+    damping: f32,
+    last_time: Option<f32>,
+}
+
+impl Spring {
+    const STEP: f32 = 1. / 240.;
+
+    pub fn new(value: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            value,
+            velocity: 0.,
+            target: value,
+            stiffness,
+            damping,
+            last_time: None,
+        }
+    }
+
+    /// A spring reaching its target as fast as possible without overshooting.
+    pub fn critical(value: f32, stiffness: f32) -> Self {
+        Self::new(value, stiffness, 2. * stiffness.sqrt())
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn set(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    /// Jumps to `value` and stays there.
+    pub fn snap(&mut self, value: f32) {
+        self.value = value;
+        self.target = value;
+        self.velocity = 0.;
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn settled(&self) -> bool {
+        (self.value - self.target).abs() < 1e-3 && self.velocity.abs() < 1e-3
+    }
+
+    /// Advances the spring to `t` and returns its value then.
+    pub fn update(&mut self, t: f32) -> f32 {
+        if super::reduce_motion() {
+            self.last_time = Some(t);
+            self.snap(self.target);
+            return self.value;
+        }
+        // long pauses (e.g. a hidden window) would otherwise make it jump
+        let mut dt = self.last_time.map_or(0., |last| (t - last).clamp(0., 0.1));
+        self.last_time = Some(t);
+        while dt > 0. {
+            let step = dt.min(Self::STEP);
+            let force = (self.target - self.value) * self.stiffness - self.velocity * self.damping;
+            self.velocity += force * step;
+            self.value += self.velocity * step;
+            dt -= step;
+        }
+        if self.settled() {
+            self.snap(self.target);
+        }
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        core::{easing_from, TweenMajor, TweenMinor},
+        ui::apply_accessibility,
+    };
+
+    fn linear(value: f32) -> Eased<f32> {
+        Eased::new(value, 2., easing_from(TweenMajor::Plain, TweenMinor::InOut))
+    }
+
+    #[test]
+    fn eased_stays_until_set() {
+        let eased = linear(1.);
+        assert_eq!(eased.now(0.), 1.);
+        assert!(eased.finished(0.));
+    }
+
+    #[test]
+    fn eased_moves_to_target() {
+        let mut eased = linear(0.);
+        eased.set(4., 10.);
+        assert_eq!(*eased.target(), 4.);
+        assert_eq!(eased.now(9.), 0.);
+        assert_eq!(eased.now(11.), 2.);
+        assert!(!eased.finished(11.));
+        assert_eq!(eased.now(13.), 4.);
+        assert!(eased.finished(12.));
+        // retargeting halfway starts from where it is
+        eased.set(0., 11.);
+        assert_eq!(eased.now(11.), 2.);
+        assert_eq!(eased.now(12.), 1.);
+        eased.animate(8., 6., 20.);
+        assert_eq!(eased.now(21.), 7.);
+    }
+
+    #[test]
+    fn eased_jumps_with_reduced_motion() {
+        let mut eased = linear(0.);
+        eased.set(4., 10.);
+        apply_accessibility(&Config {
+            reduce_motion: true,
+            ..Default::default()
+        });
+        let now = eased.now(10.);
+        apply_accessibility(&Config::default());
+        assert_eq!(now, 4.);
+    }
+
+    #[test]
+    fn spring_settles_without_overshooting() {
+        let mut spring = Spring::critical(0., 200.);
+        spring.update(0.);
+        spring.set(1.);
+        let mut last = 0.;
+        for i in 1..=100 {
+            let value = spring.update(i as f32 / 60.);
+            assert!(value >= last && value <= 1., "{value} after {last}");
+            last = value;
+        }
+        assert!(spring.settled());
+        assert_eq!(spring.value(), 1.);
+    }
+
+    #[test]
+    fn spring_ignores_long_pauses() {
+        let mut spring = Spring::critical(0., 200.);
+        spring.update(0.);
+        spring.set(1.);
+        // at most 0.1s is simulated
+        let paused = spring.update(100.);
+        let mut other = Spring::critical(0., 200.);
+        other.update(0.);
+        other.set(1.);
+        assert_eq!(paused, other.update(0.1));
+        assert!(paused < 1.);
+    }
+
+    #[test]
+    fn spring_snaps() {
+        let mut spring = Spring::new(0., 100., 1.);
+        spring.set(5.);
+        spring.snap(2.);
+        assert_eq!((spring.value(), spring.target()), (2., 2.));
+        assert!(spring.settled());
+        assert_eq!(spring.update(1.), 2.);
+    }
+}
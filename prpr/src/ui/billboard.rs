@@ -1,17 +1,30 @@
 use crate::{
+    core::{easing_from, TweenMajor, TweenMinor},
     ext::{RectExt, SafeTexture, ScaleType},
-    ui::Ui,
+    ui::{Eased, Spring, Ui},
 };
 use macroquad::prelude::*;
-use std::{
-    mem::ManuallyDrop,
-    rc::{Rc, Weak},
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
 };
 
 pub const OUT_TIME: f32 = 0.8;
 pub const PADDING: f32 = 0.02;
+/// Older messages are dismissed early to make room when there are more.
+pub const MAX_VISIBLE: usize = 5;
 
-#[derive(Default, Clone)]
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+enum Pending {
+    Show(Message),
+    Dismiss(Option<u64>),
+}
+
+/// Messages shown or dismissed from any thread, taken in by the billboard the next time it's rendered.
+static PENDING: Mutex<Vec<Pending>> = Mutex::new(Vec::new());
+
+#[derive(Default, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum MessageKind {
     #[default]
@@ -33,47 +46,71 @@ impl MessageKind {
 }
 
 pub struct Message {
+    /// The handles of this message and of the same ones merged into it while it was shown, counted as `×n` if more than one.
+    ids: Vec<u64>,
     content: String,
     time: f32,
+    /// Infinite for messages staying until dismissed.
+    duration: f32,
     end_time: f32,
-    position: f32,
-    target_position: f32,
-    last_time: f32,
+    position: Spring,
+    /// How far it's slid out, from 0 (fully shown) to 1.
+    slide: Eased<f32>,
     width: f32,
+    /// Where it was drawn last time, to be tapped to dismiss.
+    rect: Option<Rect>,
     kind: MessageKind,
-    handle: Weak<()>,
 }
 
 impl Message {
-    pub fn new(content: String, time: f32, duration: f32, kind: MessageKind) -> (Self, MessageHandle) {
-        let rc = Rc::new(());
-        let handle = Rc::downgrade(&rc);
+    pub fn new(content: String, duration: f32, kind: MessageKind) -> (Self, MessageHandle) {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
         (
             Self {
+                ids: vec![id],
                 content,
-                time,
-                end_time: time + duration,
-                position: 0.,
-                target_position: 0.,
-                last_time: time,
+                time: 0.,
+                duration,
+                end_time: 0.,
+                position: Spring::critical(0., 200.),
+                slide: Eased::new(1., OUT_TIME, easing_from(TweenMajor::Cubic, TweenMinor::Out)),
                 width: 0.,
+                rect: None,
                 kind,
-                handle,
             },
-            MessageHandle(Some(ManuallyDrop::new(rc))),
+            MessageHandle(Some(id)),
         )
     }
+
+    /// Queues the message to be shown, which works from any thread.
+    pub fn show(self) {
+        PENDING.lock().unwrap().push(Pending::Show(self));
+    }
 }
 
-pub struct MessageHandle(Option<ManuallyDrop<Rc<()>>>);
+/// Refers to a shown message. It can be sent to other threads, and dropping it leaves the message as it is.
+#[derive(Clone, Copy)]
+pub struct MessageHandle(Option<u64>);
 impl MessageHandle {
     pub fn cancel(&mut self) {
-        if let Some(rc) = self.0.take() {
-            ManuallyDrop::into_inner(rc);
+        if let Some(id) = self.0.take() {
+            PENDING.lock().unwrap().push(Pending::Dismiss(Some(id)));
         }
     }
 }
 
+/// Drops the messages queued, for when no billboard is rendered to take them in.
+pub fn discard_messages() {
+    PENDING.lock().unwrap().clear();
+}
+
+/// Dismisses every message, including those queued but not shown yet.
+pub fn dismiss_messages() {
+    let mut pending = PENDING.lock().unwrap();
+    pending.retain(|it| !matches!(it, Pending::Show(_)));
+    pending.push(Pending::Dismiss(None));
+}
+
 pub struct BillBoard {
     messages: Vec<Message>,
     icons: Option<[SafeTexture; 4]>,
@@ -97,13 +134,73 @@ impl BillBoard {
         self.icons = Some(icons);
     }
 
-    pub fn add(&mut self, mut msg: Message) {
-        msg.position = self.messages.len() as f32;
-        msg.target_position = msg.position;
+    /// Shows the message at `t`. The same message still being shown is counted and kept longer instead.
+    pub fn add(&mut self, mut msg: Message, t: f32) {
+        if let Some(same) = self
+            .messages
+            .iter_mut()
+            .find(|it| t < it.end_time && it.kind == msg.kind && it.content == msg.content)
+        {
+            same.ids.append(&mut msg.ids);
+            same.end_time = same.end_time.max(t + msg.duration);
+            return;
+        }
+        msg.time = t;
+        msg.end_time = t + msg.duration;
+        msg.slide.animate(1., 0., t);
+        msg.position.snap(self.messages.len() as f32);
         self.messages.push(msg);
     }
 
+    /// Dismisses the message with the handle `id`, or all of them. A message merged into another only takes one from its count.
+    fn dismiss(&mut self, id: Option<u64>, t: f32) {
+        for msg in &mut self.messages {
+            if let Some(id) = id {
+                let Some(index) = msg.ids.iter().position(|it| *it == id) else {
+                    continue;
+                };
+                msg.ids.remove(index);
+                if !msg.ids.is_empty() {
+                    continue;
+                }
+            }
+            msg.end_time = msg.end_time.min(t);
+        }
+    }
+
+    /// Dismisses the message tapped, if any.
+    pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
+        if touch.phase != TouchPhase::Started {
+            return false;
+        }
+        let Some(msg) = self
+            .messages
+            .iter_mut()
+            .find(|it| t < it.end_time && it.rect.map_or(false, |r| r.contains(touch.position)))
+        else {
+            return false;
+        };
+        msg.end_time = t;
+        true
+    }
+
     pub fn render(&mut self, ui: &mut Ui, t: f32) {
+        for pending in std::mem::take(&mut *PENDING.lock().unwrap()) {
+            match pending {
+                Pending::Show(msg) => self.add(msg, t),
+                Pending::Dismiss(id) => self.dismiss(id, t),
+            }
+        }
+        let shown = self.messages.iter().filter(|it| t < it.end_time).count();
+        for msg in self
+            .messages
+            .iter_mut()
+            .filter(|it| t < it.end_time)
+            .take(shown.saturating_sub(MAX_VISIBLE))
+        {
+            msg.end_time = t;
+        }
+
         let rt = 1. - PADDING;
         let tp = -ui.top + PADDING;
         let h = 0.1;
@@ -111,33 +208,27 @@ impl BillBoard {
         let rh = h + 0.02;
         let mut pos = 0;
         self.messages.retain_mut(|msg| {
-            if msg.end_time > t && msg.handle.strong_count() == 0 {
-                msg.end_time = t;
-            }
-            let rt = if t >= msg.end_time {
-                let p = (t - msg.end_time) / OUT_TIME;
-                if p > 1. {
+            if t >= msg.end_time {
+                msg.rect = None;
+                if *msg.slide.target() == 0. {
+                    msg.slide.set(1., msg.end_time);
+                }
+                if msg.slide.finished(t) {
                     return false;
                 }
-                let p = 1. - (1. - p).powi(3);
-                rt + msg.width * p
             } else {
-                msg.target_position = pos as f32;
+                msg.position.set(pos as f32);
                 pos += 1;
-                if msg.width == 0. {
-                    3.
-                } else {
-                    let p = ((t - msg.time) / OUT_TIME).min(1.);
-                    let p = (1. - p).powi(3);
-                    rt + msg.width * p
-                }
+            }
+            let rt = if msg.width == 0. { 3. } else { rt + msg.width * msg.slide.now(t) };
+            let tp = tp + msg.position.update(t) * rh;
+            let content = if msg.ids.len() > 1 {
+                format!("{} ×{}", msg.content, msg.ids.len())
+            } else {
+                msg.content.clone()
             };
-            let p = (0.5_f32).powf((t - msg.last_time) / 0.1);
-            msg.position = msg.position * p + msg.target_position * (1. - p);
-            msg.last_time = t;
-            let tp = tp + msg.position * rh;
             let mut tx = ui
-                .text(&msg.content)
+                .text(content)
                 .pos(rt - pd, tp + h / 2.)
                 .anchor(1., 0.5)
                 .no_baseline()
@@ -146,8 +237,11 @@ impl BillBoard {
             let r = tx.measure();
             let mut r = Rect::new(r.x - pd - h, tp, r.w + pd * 2. + h, h);
             msg.width = r.w + 0.2;
-            tx.ui.fill_rect(r, msg.kind.color());
             if t < msg.end_time {
+                msg.rect = Some(tx.ui.rect_to_global(r));
+            }
+            tx.ui.fill_rect(r, msg.kind.color());
+            if t < msg.end_time && msg.duration.is_finite() {
                 tx.ui.fill_rect(
                     Rect::new(r.x, r.bottom() - 0.01, r.w * (1. - (t - msg.time) / (msg.end_time - msg.time)), 0.01),
                     Color::new(1., 1., 1., 0.3),
@@ -7,14 +7,37 @@ use crate::{
     scene::{request_input, return_input, show_message, take_input},
 };
 use anyhow::Result;
+use macroquad::prelude::{Color, RED};
 use std::{borrow::Cow, collections::HashMap};
 
+const ASPECT_RATIO_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoField {
+    Name,
+    Level,
+    AspectRatio,
+    Chart,
+    Music,
+    Illustration,
+}
+
 #[derive(Clone)]
 pub struct ChartInfoEdit {
     pub info: ChartInfo,
     pub chart: Option<String>,
     pub music: Option<String>,
     pub illustration: Option<String>,
+    /// Files at the root of the chart, used to check the references in the info. Not checked if `None`.
+    pub files: Option<Vec<String>>,
+}
+
+/// Whether the level looks like `IN Lv.13`, where the number may also be `?`.
+fn is_valid_level(level: &str) -> bool {
+    let Some((tier, num)) = level.split_once(" Lv.") else {
+        return false;
+    };
+    !tier.trim().is_empty() && (num == "?" || (!num.starts_with('.') && num.parse::<f32>().map_or(false, |it| it.is_finite() && it >= 0.)))
 }
 
 impl ChartInfoEdit {
@@ -24,9 +47,53 @@ impl ChartInfoEdit {
             chart: None,
             music: None,
             illustration: None,
+            files: None,
         }
     }
 
+    pub fn with_files(mut self, files: Vec<String>) -> Self {
+        // an empty list means the file system can't be listed, not that the chart is empty
+        self.files = Some(files).filter(|it| !it.is_empty());
+        self
+    }
+
+    /// Problems with the info that keep it from being used, in the order the fields are shown.
+    pub fn errors(&self) -> Vec<(InfoField, Cow<'static, str>)> {
+        let info = &self.info;
+        let mut res = Vec::new();
+        if info.name.trim().is_empty() {
+            res.push((InfoField::Name, tl!("error-name-empty")));
+        }
+        if !info.aspect_ratio.is_finite() || !ASPECT_RATIO_RANGE.contains(&info.aspect_ratio) {
+            res.push((
+                InfoField::AspectRatio,
+                tl!("error-aspect-ratio", "min" => ASPECT_RATIO_RANGE.start().to_string(), "max" => ASPECT_RATIO_RANGE.end().to_string()).into(),
+            ));
+        }
+        if let Some(files) = &self.files {
+            for (field, path, replaced) in [
+                (InfoField::Chart, &info.chart, &self.chart),
+                (InfoField::Music, &info.music, &self.music),
+                (InfoField::Illustration, &info.illustration, &self.illustration),
+            ] {
+                // files in subfolders are not listed
+                if replaced.is_none() && !path.contains('/') && !files.contains(path) {
+                    res.push((field, tl!("error-file-missing", "file" => path.as_str()).into()));
+                }
+            }
+        }
+        res
+    }
+
+    /// Things that look off but are allowed, as charts in the wild don't all follow the conventions.
+    pub fn warnings(&self) -> Vec<(InfoField, Cow<'static, str>)> {
+        let mut res = Vec::new();
+        if !is_valid_level(&self.info.level) {
+            res.push((InfoField::Level, tl!("warn-level-format")));
+        }
+        res
+    }
+
     pub async fn to_patches(&self) -> Result<HashMap<String, Vec<u8>>> {
         let mut res = HashMap::new();
         res.insert("info.yml".to_owned(), serde_yaml::to_string(&self.info)?.into_bytes());
@@ -48,6 +115,8 @@ impl ChartInfoEdit {
 
 pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (f32, f32) {
     let mut sy = 0.02;
+    let errors = edit.errors();
+    let warnings = edit.warnings();
     ui.scope(|ui| {
         let s = 0.01;
         ui.dx(0.01);
@@ -64,9 +133,26 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
         let rt = 0.2;
         ui.dx(rt);
         let len = width - 0.2;
+        macro_rules! error {
+            ($field:expr) => {
+                let mut found = errors.iter().map(|it| (it, RED)).chain(warnings.iter().map(|it| (it, ORANGE)));
+                if let Some(((_, msg), color)) = found.find(|((field, _), _)| *field == $field) {
+                    let r = ui
+                        .text(&**msg)
+                        .pos(0.02, 0.)
+                        .size(0.35)
+                        .max_width(len - 0.02)
+                        .multiline()
+                        .color(Color { a: 0.9, ..color })
+                        .draw();
+                    dy!(r.h + s);
+                }
+            };
+        }
         let info = &mut edit.info;
         let r = ui.input(tl!("chart-name"), &mut info.name, len);
         dy!(r.h + s);
+        error!(InfoField::Name);
         let r = ui.input(tl!("author"), &mut info.charter, len);
         dy!(r.h + s);
         let r = ui.input(tl!("composer"), &mut info.composer, len);
@@ -76,6 +162,7 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
 
         let r = ui.input(tl!("level-displayed"), &mut info.level, len);
         dy!(r.h + s);
+        error!(InfoField::Level);
 
         ui.dx(-rt);
         let r = ui.slider(tl!("diff"), 0.0..20.0, 0.1, &mut info.difficulty, Some(width - 0.2));
@@ -123,6 +210,7 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
                 info.aspect_ratio = value;
             }
         }
+        error!(InfoField::AspectRatio);
         dy!(ui.scope(|ui| {
             ui.text(tl!("ps")).anchor(1., 0.).size(0.35).draw();
             ui.text(tl!("aspect-hint")).size(0.35).max_width(len).multiline().draw().h + 0.03
@@ -137,17 +225,27 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
         {
             use crate::scene::{request_file, return_file, take_file};
             use macroquad::prelude::Rect;
-            let mut choose_file = |id: &str, label: Cow<'static, str>, value: &str| {
+            let mut choose_file = |id: &str, label: Cow<'static, str>, value: &str, replaced: &Option<String>, field: InfoField| {
                 let r = ui.text(label).size(0.4).anchor(1., 0.).draw();
                 let r = Rect::new(0.02, r.y - 0.01, len, r.h + 0.02);
-                if ui.button(id, r, value) {
+                let text = match replaced {
+                    Some(file) => {
+                        let name = std::path::Path::new(file)
+                            .file_name()
+                            .map_or(Cow::Borrowed(file.as_str()), |it| it.to_string_lossy());
+                        format!("{value} ← {name}")
+                    }
+                    None => value.to_owned(),
+                };
+                if ui.button(id, r, text) {
                     request_file(id);
                 }
                 dy!(r.h + s);
+                error!(field);
             };
-            choose_file("file_chart", tl!("chart-file"), &info.chart);
-            choose_file("file_music", tl!("music-file"), &info.music);
-            choose_file("file_illustration", tl!("illu-file"), &info.illustration);
+            choose_file("file_chart", tl!("chart-file"), &info.chart, &edit.chart, InfoField::Chart);
+            choose_file("file_music", tl!("music-file"), &info.music, &edit.music, InfoField::Music);
+            choose_file("file_illustration", tl!("illu-file"), &info.illustration, &edit.illustration, InfoField::Illustration);
             if let Some((id, file)) = take_file() {
                 match id.as_str() {
                     "file_chart" => {
@@ -217,3 +315,33 @@ pub fn render_chart_info(ui: &mut Ui, edit: &mut ChartInfoEdit, width: f32) -> (
     });
     (width, sy)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_levels() {
+        for level in ["IN Lv.13", "SP Lv.?", "AT Lv.15.5", "Custom Lv.0"] {
+            assert!(is_valid_level(level), "{level}");
+        }
+    }
+
+    #[test]
+    fn invalid_levels() {
+        for level in [
+            "IN 13",
+            "Lv.13",
+            " Lv.13",
+            "IN Lv.",
+            "IN Lv.-1",
+            "IN Lv..5",
+            "IN Lv.13a",
+            "IN Lv.inf",
+            "IN Lv.NaN",
+            "IN Lv. 13",
+        ] {
+            assert!(!is_valid_level(level), "{level}");
+        }
+    }
+}
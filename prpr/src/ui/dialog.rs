@@ -3,11 +3,40 @@ crate::tl_file!("dialog");
 use crate::scene::show_message;
 use anyhow::Error;
 
-use super::{RectButton, Scroll, Ui};
+use super::{text_input, RectButton, Scroll, Ui};
 use macroquad::prelude::*;
+use std::sync::{Arc, Mutex};
 
 const WIDTH_RADIO: f32 = 0.5;
 const HEIGHT_RATIO: f32 = 0.7;
+const PROMPT_INPUT_ID: &str = "dialog#prompt";
+
+#[derive(Default)]
+struct ProgressState {
+    value: Option<f32>,
+    message: Option<String>,
+    finished: bool,
+}
+
+/// Reports progress to a dialog from [`Dialog::progress`], from any thread.
+///
+/// The dialog closes once [`Self::finish`] is called or every handle is dropped.
+#[derive(Clone)]
+pub struct ProgressHandle(Arc<Mutex<ProgressState>>);
+impl ProgressHandle {
+    /// Sets the progress, from 0 to 1.
+    pub fn set(&self, value: f32) {
+        self.0.lock().unwrap().value = Some(value.clamp(0., 1.));
+    }
+
+    pub fn message(&self, message: impl Into<String>) {
+        self.0.lock().unwrap().message = Some(message.into());
+    }
+
+    pub fn finish(&self) {
+        self.0.lock().unwrap().finished = true;
+    }
+}
 
 #[must_use]
 pub struct Dialog {
@@ -15,7 +44,16 @@ pub struct Dialog {
     message: String,
     buttons: Vec<String>,
     listener: Option<Box<dyn FnMut(i32)>>, // -1 for cancel
+    dismissible: bool,
+    /// The text being entered and whether it's a password.
+    input: Option<(String, bool)>,
+    /// Touches for the text box, which doesn't get them otherwise while a dialog is shown.
+    touches: Vec<Touch>,
+    on_submit: Option<Box<dyn FnOnce(String)>>,
+    progress: Option<Arc<Mutex<ProgressState>>>,
 
+    chosen: i32,
+    time: f32,
     scroll: Scroll,
     window_rect: Option<Rect>,
     rect_buttons: Vec<RectButton>,
@@ -28,7 +66,14 @@ impl Default for Dialog {
             message: String::new(),
             buttons: vec![tl!("ok").to_string()],
             listener: None,
+            dismissible: true,
+            input: None,
+            touches: Vec::new(),
+            on_submit: None,
+            progress: None,
 
+            chosen: -1,
+            time: 0.,
             scroll: Scroll::new(),
             window_rect: None,
             rect_buttons: vec![RectButton::new()],
@@ -71,6 +116,56 @@ impl Dialog {
         }
     }
 
+    /// Asks to confirm an action, calling `on_confirm` only if confirmed.
+    pub fn confirm(title: impl Into<String>, message: impl Into<String>, on_confirm: impl FnOnce() + 'static) -> Self {
+        let mut on_confirm = Some(on_confirm);
+        Self::plain(title, message)
+            .buttons(vec![tl!("cancel").to_string(), tl!("confirm").to_string()])
+            .listener(move |pos| {
+                if pos == 1 {
+                    if let Some(f) = on_confirm.take() {
+                        f();
+                    }
+                }
+            })
+    }
+
+    /// Asks for a line of text starting as `initial`, calling `on_submit` with it if confirmed.
+    pub fn prompt(
+        title: impl Into<String>,
+        message: impl Into<String>,
+        initial: impl Into<String>,
+        on_submit: impl FnOnce(String) + 'static,
+    ) -> Self {
+        Self {
+            input: Some((initial.into(), false)),
+            on_submit: Some(Box::new(on_submit)),
+            ..Self::plain(title, message).buttons(vec![tl!("cancel").to_string(), tl!("confirm").to_string()])
+        }
+    }
+
+    /// Shows the progress of a task. It has no buttons unless given some, e.g. to cancel the task, and stays until the returned handle
+    /// finishes.
+    pub fn progress(title: impl Into<String>, message: impl Into<String>) -> (Self, ProgressHandle) {
+        let state = Arc::new(Mutex::new(ProgressState::default()));
+        (
+            Self {
+                dismissible: false,
+                progress: Some(Arc::clone(&state)),
+                ..Self::plain(title, message).buttons(Vec::new())
+            },
+            ProgressHandle(state),
+        )
+    }
+
+    /// Hides the text entered in a [`Self::prompt`].
+    pub fn password(mut self) -> Self {
+        if let Some((_, password)) = &mut self.input {
+            *password = true;
+        }
+        self
+    }
+
     pub fn title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
         self
@@ -92,39 +187,61 @@ impl Dialog {
         self
     }
 
+    /// Whether tapping outside closes the dialog, which is the case by default.
+    pub fn dismissible(mut self, dismissible: bool) -> Self {
+        self.dismissible = dismissible;
+        self
+    }
+
     pub fn show(self) {
         crate::scene::DIALOG.with(|it| *it.borrow_mut() = Some(self));
     }
 
+    /// Returns `false` if the dialog should be closed, after which [`Self::close`] is to be called.
     pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
         self.scroll.touch(touch, t);
-        let mut exit = false;
+        if self.input.is_some() {
+            self.touches.push(touch.clone());
+        }
         for (index, btn) in self.rect_buttons.iter_mut().enumerate() {
             if btn.touch(touch) {
-                if let Some(listener) = self.listener.as_mut() {
-                    listener(index as i32);
-                }
-                exit = true;
+                self.chosen = index as i32;
+                return false;
             }
         }
-        if exit {
-            return false;
-        }
-        if self
-            .window_rect
-            .map_or(true, |rect| rect.contains(touch.position) || touch.phase != TouchPhase::Started)
-        {
-            true
-        } else {
-            if let Some(listener) = self.listener.as_mut() {
-                listener(-1);
+        !self.dismissible
+            || self
+                .window_rect
+                .map_or(true, |rect| rect.contains(touch.position) || touch.phase != TouchPhase::Started)
+    }
+
+    /// Returns `false` if the dialog should be closed, after which [`Self::close`] is to be called.
+    pub fn update(&mut self, t: f32) -> bool {
+        self.time = t;
+        self.scroll.update(t);
+        if let Some(progress) = &self.progress {
+            let mut state = progress.lock().unwrap();
+            if let Some(message) = state.message.take() {
+                self.message = message;
+            }
+            if state.finished || Arc::strong_count(progress) == 1 {
+                return false;
             }
-            false
         }
+        true
     }
 
-    pub fn update(&mut self, t: f32) {
-        self.scroll.update(t);
+    /// Notifies the listener of the button chosen. Called once the dialog is no longer shown, so that it may show another one.
+    pub fn close(mut self) {
+        if let Some(listener) = self.listener.as_mut() {
+            listener(self.chosen);
+        }
+        if self.chosen == 1 {
+            if let Some(((text, _), on_submit)) = self.input.zip(self.on_submit) {
+                // the button may be tapped while still typing
+                on_submit(text_input::take_focused(PROMPT_INPUT_ID).unwrap_or(text));
+            }
+        }
     }
 
     pub fn render(&mut self, ui: &mut Ui) {
@@ -138,6 +255,11 @@ impl Dialog {
         let s = 0.013;
         let pad = 0.02;
         let bh = 0.06;
+        let extra = if self.input.is_some() || (self.progress.is_some() && !self.buttons.is_empty()) {
+            bh + s
+        } else {
+            0.
+        };
         ui.scope(|ui| {
             let s = 0.01;
             let pad = 0.02;
@@ -161,13 +283,41 @@ impl Dialog {
             dy!(r.h + s * 2.);
             ui.fill_rect(Rect::new(wr.x + pad, 0., wr.w - pad * 2., s), WHITE);
             dy!(s * 2.);
-            self.scroll.size((wr.w - pad * 2., wr.bottom() - h - bh - s * 2.));
+            self.scroll.size((wr.w - pad * 2., wr.bottom() - h - bh - extra - s * 2.));
             ui.dx(wr.x + pad);
             self.scroll.render(ui, |ui| {
                 let r = ui.text(&self.message).size(0.4).max_width(wr.w - pad * 2.).multiline().draw();
                 (r.w, r.h)
             });
         });
+        if let Some((text, password)) = &mut self.input {
+            let r = Rect::new(wr.x + pad, wr.bottom() - s * 2. - bh * 2., wr.w - pad * 2., bh);
+            ui.set_touches(std::mem::take(&mut self.touches));
+            text_input::text_box(ui, PROMPT_INPUT_ID, r, text, *password);
+        }
+        if let Some(progress) = &self.progress {
+            // in place of the buttons, or above them if there are any
+            let row = if self.buttons.is_empty() {
+                wr.bottom() - s - bh
+            } else {
+                wr.bottom() - s * 2. - bh * 2.
+            };
+            let r = Rect::new(wr.x + pad, row + bh / 2. - 0.01, wr.w - pad * 2., 0.02);
+            ui.fill_rect(r, Color::new(1., 1., 1., 0.3));
+            match progress.lock().unwrap().value {
+                Some(value) => ui.fill_rect(Rect { w: r.w * value, ..r }, WHITE),
+                None => {
+                    // no progress known yet, so a segment sweeps across instead
+                    let w = r.w * 0.3;
+                    let x = (self.time * 0.8).fract() * (r.w + w) - w;
+                    let (x, right) = (x.max(0.), (x + w).min(r.w));
+                    ui.fill_rect(Rect::new(r.x + x, r.y, right - x, r.h), WHITE);
+                }
+            }
+        }
+        if self.buttons.is_empty() {
+            return;
+        }
         ui.scope(|ui| {
             let bw = (wr.w - pad * (self.buttons.len() + 1) as f32) / self.buttons.len() as f32;
             let mut r = Rect::new(wr.x + pad, wr.bottom() - s - bh, bw, bh);
@@ -0,0 +1,224 @@
+crate::tl_file!("keyboard");
+
+use super::{
+    text_input::{focused, push_edit, Edit},
+    Ui,
+};
+use crate::scene::request_input;
+use macroquad::prelude::*;
+use miniquad::KeyMods;
+use std::cell::RefCell;
+
+const LETTERS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const SYMBOLS: [&str; 3] = ["1234567890", "-/:;()&@+", ".,?!'_="];
+/// How much of the screen's height the keyboard takes.
+const HEIGHT_RATIO: f32 = 0.42;
+const PREVIEW_HEIGHT: f32 = 0.09;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Shift,
+    Backspace,
+    Layer,
+    Space,
+    /// Opens the system's own input, for IMEs and the like.
+    System,
+    Done,
+}
+
+#[derive(Default)]
+struct Keyboard {
+    shift: bool,
+    symbols: bool,
+    /// Where the keys were drawn last time, in global coordinates.
+    keys: Vec<(Rect, Key)>,
+    area: Option<Rect>,
+    pressed: Option<(u64, Key)>,
+}
+
+thread_local! {
+    static KEYBOARD: RefCell<Keyboard> = RefCell::default();
+}
+
+fn no_mods() -> KeyMods {
+    KeyMods {
+        shift: false,
+        ctrl: false,
+        alt: false,
+        logo: false,
+    }
+}
+
+impl Keyboard {
+    fn press(&mut self, key: Key) {
+        match key {
+            Key::Char(c) => {
+                if self.shift {
+                    push_edit(Edit::Char(c.to_ascii_uppercase()));
+                    self.shift = false;
+                } else {
+                    push_edit(Edit::Char(c));
+                }
+            }
+            Key::Shift => self.shift ^= true,
+            Key::Backspace => push_edit(Edit::Key(KeyCode::Backspace, no_mods())),
+            Key::Layer => {
+                self.symbols ^= true;
+                self.shift = false;
+            }
+            Key::Space => push_edit(Edit::Char(' ')),
+            Key::System => {
+                if let Some((id, text, ..)) = focused() {
+                    request_input(id, &text);
+                }
+            }
+            Key::Done => push_edit(Edit::Key(KeyCode::Enter, no_mods())),
+        }
+    }
+
+    fn label(&self, key: Key) -> String {
+        match key {
+            Key::Char(c) if self.shift => c.to_ascii_uppercase().to_string(),
+            Key::Char(c) => c.to_string(),
+            Key::Shift => tl!("shift").into_owned(),
+            Key::Backspace => tl!("backspace").into_owned(),
+            Key::Layer if self.symbols => tl!("letters").into_owned(),
+            Key::Layer => tl!("symbols").into_owned(),
+            Key::Space => tl!("space").into_owned(),
+            Key::System => tl!("system").into_owned(),
+            Key::Done => tl!("done").into_owned(),
+        }
+    }
+
+    /// The keys of each row along with their widths, in that of a letter.
+    fn layout(&self) -> [Vec<(Key, f32)>; 4] {
+        let rows = if self.symbols { SYMBOLS } else { LETTERS };
+        let chars = |row: &str| row.chars().map(|c| (Key::Char(c), 1.)).collect::<Vec<_>>();
+        let mut third = if self.symbols { Vec::new() } else { vec![(Key::Shift, 1.5)] };
+        third.extend(chars(rows[2]));
+        third.push((Key::Backspace, 1.5));
+        [
+            chars(rows[0]),
+            chars(rows[1]),
+            third,
+            vec![(Key::Layer, 1.5), (Key::System, 1.5), (Key::Space, 5.), (Key::Done, 2.)],
+        ]
+    }
+}
+
+/// Whether the on-screen keyboard is shown, which is on mobile while a text box is being edited.
+pub(crate) fn keyboard_shown() -> bool {
+    cfg!(any(target_os = "android", target_os = "ios")) && focused().is_some()
+}
+
+/// Returns `true` if the touch is taken by the keyboard.
+pub(crate) fn touch_keyboard(touch: &Touch) -> bool {
+    if !keyboard_shown() {
+        return false;
+    }
+    KEYBOARD.with(|it| {
+        let mut kb = it.borrow_mut();
+        let Some(area) = kb.area else {
+            return false;
+        };
+        let key = kb.keys.iter().find(|(r, _)| r.contains(touch.position)).map(|it| it.1);
+        let pressed = kb.pressed.filter(|(id, _)| *id == touch.id);
+        match touch.phase {
+            TouchPhase::Started => {
+                if !area.contains(touch.position) {
+                    return false;
+                }
+                kb.pressed = key.map(|key| (touch.id, key));
+                true
+            }
+            TouchPhase::Moved | TouchPhase::Stationary => {
+                if pressed.map_or(false, |(_, it)| Some(it) != key) {
+                    kb.pressed = None;
+                }
+                pressed.is_some() || area.contains(touch.position)
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if let Some((_, it)) = pressed {
+                    kb.pressed = None;
+                    if touch.phase == TouchPhase::Ended && Some(it) == key {
+                        kb.press(it);
+                    }
+                }
+                pressed.is_some() || area.contains(touch.position)
+            }
+        }
+    })
+}
+
+pub(crate) fn render_keyboard(ui: &mut Ui) {
+    let Some((_, text, password, cursor)) = focused() else {
+        return;
+    };
+    KEYBOARD.with(|it| {
+        let mut kb = it.borrow_mut();
+        let h = ui.top * 2. * HEIGHT_RATIO;
+        let area = Rect::new(-1., ui.top - h, 2., h);
+        kb.area = Some(ui.rect_to_global(Rect::new(area.x, area.y - PREVIEW_HEIGHT, area.w, area.h + PREVIEW_HEIGHT)));
+        ui.fill_rect(area, Color::new(0.12, 0.12, 0.12, 0.96));
+
+        // the text box itself may well be covered, so what's typed is shown above
+        let pr = Rect::new(-1., area.y - PREVIEW_HEIGHT, 2., PREVIEW_HEIGHT);
+        ui.fill_rect(pr, Color::new(0.22, 0.22, 0.22, 0.96));
+        let chars: Vec<char> = text.chars().collect();
+        let shown = |chars: &[char]| if password { "*".repeat(chars.len()) } else { chars.iter().collect() };
+        let pad = 0.03;
+        let before = if cursor == 0 {
+            0.
+        } else {
+            ui.text(shown(&chars[..cursor])).size(0.5).no_baseline().measure().w
+        };
+        let x = pr.x + pad + (pr.w - pad * 2. - before).min(0.);
+        ui.scissor(Some(pr));
+        ui.text(shown(&chars))
+            .pos(x, pr.center().y)
+            .anchor(0., 0.5)
+            .size(0.5)
+            .no_baseline()
+            .draw();
+        ui.fill_rect(Rect::new(x + before, pr.y + pr.h * 0.2, 0.004, pr.h * 0.6), WHITE);
+        ui.scissor(None);
+
+        let pad = 0.01;
+        let rows = kb.layout();
+        let unit = (area.w - pad) / 10.;
+        let rh = (area.h - pad) / rows.len() as f32;
+        let mut keys = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let width: f32 = row.iter().map(|it| it.1).sum();
+            let mut x = area.x + pad / 2. + (10. - width) * unit / 2.;
+            let y = area.y + pad + i as f32 * rh;
+            for &(key, w) in row {
+                let r = Rect::new(x + pad / 2., y, w * unit - pad, rh - pad);
+                let pressed = kb.pressed.map_or(false, |it| it.1 == key);
+                let active = (key == Key::Shift && kb.shift) || key == Key::Done;
+                let color = if pressed {
+                    Color::new(1., 1., 1., 0.5)
+                } else if active {
+                    ui.accent()
+                } else if matches!(key, Key::Char(_) | Key::Space) {
+                    Color::new(0.35, 0.35, 0.35, 1.)
+                } else {
+                    Color::new(0.25, 0.25, 0.25, 1.)
+                };
+                ui.fill_rect(r, color);
+                let ct = r.center();
+                ui.text(kb.label(key))
+                    .pos(ct.x, ct.y)
+                    .anchor(0.5, 0.5)
+                    .max_width(r.w)
+                    .size(if matches!(key, Key::Char(_)) { 0.6 } else { 0.42 })
+                    .no_baseline()
+                    .draw();
+                keys.push((ui.rect_to_global(r), key));
+                x += w * unit;
+            }
+        }
+        kb.keys = keys;
+    });
+}
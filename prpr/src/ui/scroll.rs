@@ -3,11 +3,15 @@ use crate::{
     core::{Matrix, Point},
     judge::VelocityTracker,
 };
-use macroquad::prelude::{Rect, Touch, TouchPhase, Vec2};
+use macroquad::prelude::{Color, Rect, Touch, TouchPhase, Vec2};
 use nalgebra::Translation2;
 
 const THRESHOLD: f32 = 0.03;
 const EXTEND: f32 = 0.33;
+/// How long the scrollbar stays after scrolling stops, and how long it then takes to fade out.
+const BAR_STAY: f32 = 0.8;
+const BAR_FADE: f32 = 0.3;
+const BAR_WIDTH: f32 = 0.006;
 
 pub struct Scroller {
     touch: Option<(u64, f32, f32, bool)>,
@@ -105,6 +109,11 @@ impl Scroller {
         self.offset
     }
 
+    /// Whether it's being dragged or still moving.
+    pub fn active(&self) -> bool {
+        self.touch.map_or(false, |it| it.3) || self.speed.abs() > 1e-3
+    }
+
     pub fn set_offset(&mut self, val: f32) {
         self.offset = val;
     }
@@ -123,6 +132,10 @@ pub struct Scroll {
     pub y_scroller: Scroller,
     size: (f32, f32),
     matrix: Option<Matrix>,
+    scrollbar: bool,
+    time: f32,
+    /// When it last scrolled, for fading out the scrollbar.
+    active_time: f32,
 }
 
 impl Default for Scroll {
@@ -138,16 +151,27 @@ impl Scroll {
             y_scroller: Scroller::new(),
             size: (2., 2.),
             matrix: None,
+            scrollbar: true,
+            time: 0.,
+            active_time: f32::NEG_INFINITY,
         }
     }
 
+    /// Whether to show a scrollbar while scrolling, which is the case by default.
+    pub fn scrollbar(mut self, scrollbar: bool) -> Self {
+        self.scrollbar = scrollbar;
+        self
+    }
+
     pub fn set_offset(&mut self, x: f32, y: f32) {
         self.x_scroller.set_offset(x);
         self.y_scroller.set_offset(y);
     }
 
     pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
-        let Some(matrix) = self.matrix else { return false; };
+        let Some(matrix) = self.matrix else {
+            return false;
+        };
         let pt = touch.position;
         let pt = matrix.transform_point(&Point::new(pt.x, pt.y));
         if pt.x < 0. || pt.y < 0. || pt.x > self.size.0 || pt.y > self.size.1 {
@@ -160,6 +184,10 @@ impl Scroll {
     pub fn update(&mut self, t: f32) {
         self.x_scroller.update(t);
         self.y_scroller.update(t);
+        self.time = t;
+        if self.y_scroller.active() {
+            self.active_time = t;
+        }
     }
 
     pub fn position(&self, touch: &Touch) -> Option<(f32, f32)> {
@@ -178,9 +206,16 @@ impl Scroll {
         self.matrix = Some(ui.get_matrix().try_inverse().unwrap());
         ui.scissor(Some(Rect::new(0., 0., self.size.0, self.size.1)));
         let s = ui.with(Translation2::new(-self.x_scroller.offset(), -self.y_scroller.offset()).to_homogeneous(), content);
-        ui.scissor(None);
         self.x_scroller.size((s.0 - self.size.0).max(0.));
         self.y_scroller.size((s.1 - self.size.1).max(0.));
+        let alpha = 1. - ((self.time - self.active_time - BAR_STAY) / BAR_FADE).clamp(0., 1.);
+        if self.scrollbar && self.y_scroller.size > 0. && alpha > 0. {
+            let (h, max) = (self.size.1, self.y_scroller.size);
+            let bar = (h * h / (h + max)).max(0.05);
+            let y = self.y_scroller.offset().clamp(0., max) / max * (h - bar);
+            ui.fill_rect(Rect::new(self.size.0 - BAR_WIDTH * 2., y, BAR_WIDTH, bar), Color::new(1., 1., 1., alpha * 0.5));
+        }
+        ui.scissor(None);
     }
 
     pub fn size(&mut self, size: (f32, f32)) {
@@ -193,3 +228,170 @@ impl Scroll {
         Rect::new(0., 0., self.size.0, self.size.1)
     }
 }
+
+/// A scrolling container of items laid out in rows of `columns`, with only those on screen drawn.
+pub struct Grid {
+    pub scroll: Scroll,
+    columns: usize,
+    item_height: f32,
+    len: usize,
+    tapped: Option<usize>,
+}
+
+impl Grid {
+    pub fn new(columns: usize, item_height: f32) -> Self {
+        Self {
+            scroll: Scroll::new(),
+            columns: columns.max(1),
+            item_height,
+            len: 0,
+            tapped: None,
+        }
+    }
+
+    /// A grid of a single column.
+    pub fn list(item_height: f32) -> Self {
+        Self::new(1, item_height)
+    }
+
+    pub fn set_columns(&mut self, columns: usize) {
+        self.columns = columns.max(1);
+    }
+
+    pub fn size(&mut self, size: (f32, f32)) {
+        self.scroll.size(size);
+    }
+
+    fn item_width(&self) -> f32 {
+        self.scroll.size.0 / self.columns as f32
+    }
+
+    /// Where the item at `index` is, relative to the top left of the grid and taking scrolling into account.
+    pub fn item_rect(&self, index: usize) -> Rect {
+        let w = self.item_width();
+        Rect::new(
+            (index % self.columns) as f32 * w,
+            (index / self.columns) as f32 * self.item_height - self.scroll.y_scroller.offset(),
+            w,
+            self.item_height,
+        )
+    }
+
+    /// Scrolls just enough for the item at `index` to be shown entirely.
+    pub fn scroll_to(&mut self, index: usize) {
+        let top = (index / self.columns) as f32 * self.item_height;
+        let offset = self.scroll.y_scroller.offset().max(top + self.item_height - self.scroll.size.1).min(top);
+        self.scroll.y_scroller.set_offset(offset.clamp(0., self.scroll.y_scroller.size));
+    }
+
+    /// The item at `(x, y)`, relative to the top left of the content, if there's one.
+    fn index_at(&self, x: f32, y: f32) -> Option<usize> {
+        if x < 0. || y < 0. {
+            return None;
+        }
+        let column = (x / self.item_width()) as usize;
+        if column >= self.columns {
+            return None;
+        }
+        Some((y / self.item_height) as usize * self.columns + column).filter(|it| *it < self.len)
+    }
+
+    /// Returns `true` if the touch is taken for scrolling or tapping an item, which can then be taken by [`Self::tapped`].
+    pub fn touch(&mut self, touch: &Touch, t: f32) -> bool {
+        if self.scroll.touch(touch, t) {
+            return true;
+        }
+        if touch.phase == TouchPhase::Ended {
+            if let Some(index) = self.scroll.position(touch).and_then(|(x, y)| self.index_at(x, y)) {
+                self.tapped = Some(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn tapped(&mut self) -> Option<usize> {
+        self.tapped.take()
+    }
+
+    pub fn update(&mut self, t: f32) {
+        self.scroll.update(t);
+    }
+
+    /// Draws those of the `len` items on screen, each by `item` given its index and where it goes.
+    pub fn render(&mut self, ui: &mut Ui, len: usize, mut item: impl FnMut(&mut Ui, usize, Rect)) {
+        self.len = len;
+        let (columns, w, h) = (self.columns, self.item_width(), self.item_height);
+        let offset = self.scroll.y_scroller.offset().max(0.);
+        let first = (offset / h) as usize * columns;
+        let last = ((offset + self.scroll.size.1) / h).ceil() as usize * columns + columns;
+        let size = (self.scroll.size.0, ((len + columns - 1) / columns) as f32 * h);
+        self.scroll.render(ui, |ui| {
+            for index in first..last.min(len) {
+                item(ui, index, Rect::new((index % columns) as f32 * w, (index / columns) as f32 * h, w, h));
+            }
+            size
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four columns of items 0.5 wide and 0.25 high, in a grid of 2x1.
+    fn grid(len: usize) -> Grid {
+        let mut grid = Grid::new(4, 0.25);
+        grid.size((2., 1.));
+        grid.len = len;
+        grid
+    }
+
+    #[test]
+    fn index_at_items() {
+        let grid = grid(10);
+        assert_eq!(grid.index_at(0., 0.), Some(0));
+        assert_eq!(grid.index_at(0.75, 0.3), Some(5));
+        assert_eq!(grid.index_at(1.99, 0.26), Some(7));
+        assert_eq!(grid.index_at(0.6, 0.6), Some(9));
+    }
+
+    #[test]
+    fn index_at_outside() {
+        let grid = grid(10);
+        assert_eq!(grid.index_at(-0.1, 0.1), None);
+        assert_eq!(grid.index_at(0.1, -0.1), None);
+        assert_eq!(grid.index_at(2., 0.1), None);
+        // past the last item, in its row or below
+        assert_eq!(grid.index_at(1.1, 0.6), None);
+        assert_eq!(grid.index_at(0.1, 0.8), None);
+        assert_eq!(Grid::new(4, 0.25).index_at(0., 0.), None);
+    }
+
+    #[test]
+    fn item_rect_scrolls() {
+        let mut grid = grid(40);
+        assert_eq!(grid.item_rect(6), Rect::new(1., 0.25, 0.5, 0.25));
+        grid.scroll.y_scroller.set_offset(0.5);
+        assert_eq!(grid.item_rect(6), Rect::new(1., -0.25, 0.5, 0.25));
+    }
+
+    #[test]
+    fn scroll_to_shows_item() {
+        let mut grid = grid(40);
+        grid.scroll.y_scroller.size(1.5);
+        // already shown
+        grid.scroll_to(13);
+        assert_eq!(grid.scroll.y_scroller.offset(), 0.);
+        // below, scrolled to the bottom edge
+        grid.scroll_to(25);
+        assert_eq!(grid.scroll.y_scroller.offset(), 0.75);
+        assert_eq!(grid.item_rect(25).bottom(), 1.);
+        // above, scrolled to the top edge
+        grid.scroll_to(5);
+        assert_eq!(grid.scroll.y_scroller.offset(), 0.25);
+        // never past the end
+        grid.scroll_to(39);
+        assert_eq!(grid.scroll.y_scroller.offset(), 1.5);
+    }
+}
@@ -86,7 +86,7 @@ impl<'a, 's, 'ui> DrawText<'a, 's, 'ui> {
 
     fn measure_inner<'c>(&mut self, text: &'c str) -> (Section<'c>, Rect) {
         let vp = get_viewport();
-        let scale = 0.04 * self.size * vp.2 as f32;
+        let scale = 0.04 * self.size * super::text_scale() * vp.2 as f32;
         let mut section = Section::new().add_text(Text::new(text).with_scale(scale).with_color(self.color));
         let s = 2. / vp.2 as f32;
         if let Some(max_width) = self.max_width {
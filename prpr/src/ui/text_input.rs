@@ -0,0 +1,570 @@
+use super::Ui;
+use crate::scene::{return_input, take_input};
+use macroquad::{
+    prelude::*,
+    utils::{register_input_subscriber, repeat_all_miniquad_input},
+};
+use miniquad::{EventHandler, KeyMods};
+use once_cell::sync::Lazy;
+use std::{
+    cell::{Cell, RefCell},
+    ops::Range,
+    sync::Mutex,
+};
+
+const PADDING: f32 = 0.01;
+const TEXT_SIZE: f32 = 0.42;
+const BLINK_PERIOD: f64 = 1.;
+
+static SUBSCRIBER_ID: Lazy<usize> = Lazy::new(register_input_subscriber);
+/// The latest composition reported by the IME, taken in after the keys of the frame.
+static PREEDIT: Mutex<Option<String>> = Mutex::new(None);
+thread_local! {
+    /// The input being edited along with its id. Only one can be focused at a time.
+    static FOCUSED: RefCell<Option<(String, TextEditor)>> = RefCell::new(None);
+    /// Text of a box no longer focused, to be taken by it the next time it's drawn.
+    static COMMITTED: RefCell<Option<(String, String)>> = RefCell::new(None);
+    static EDITS: RefCell<Vec<Edit>> = RefCell::new(Vec::new());
+    /// Typed on the on-screen keyboard, taken in along with the keys next frame.
+    static PENDING: RefCell<Vec<Edit>> = RefCell::new(Vec::new());
+    static FRAME: Cell<u64> = Cell::new(0);
+}
+
+pub(super) enum Edit {
+    Char(char),
+    Key(KeyCode, KeyMods),
+    /// The text being composed with an IME, empty once it's committed or cancelled.
+    Preedit(String),
+}
+
+#[derive(Default)]
+struct Handler(Vec<Edit>);
+impl EventHandler for Handler {
+    fn update(&mut self, _: &mut miniquad::Context) {}
+    fn draw(&mut self, _: &mut miniquad::Context) {}
+
+    // text composed with an IME arrives here once committed, the composition itself through `set_preedit`
+    fn char_event(&mut self, _ctx: &mut miniquad::Context, character: char, _keymods: KeyMods, _repeat: bool) {
+        self.0.push(Edit::Char(character));
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut miniquad::Context, keycode: KeyCode, keymods: KeyMods, _repeat: bool) {
+        self.0.push(Edit::Key(keycode, keymods));
+    }
+}
+
+/// Takes in the keys typed this frame. Called every frame, even if no text box is shown, so that they don't pile up.
+pub(crate) fn update_text_input() {
+    let mut handler = Handler::default();
+    repeat_all_miniquad_input(&mut handler, *SUBSCRIBER_ID);
+    handler.0.extend(PENDING.with(|it| std::mem::take(&mut *it.borrow_mut())));
+    if let Some(text) = PREEDIT.lock().unwrap().take() {
+        handler.0.push(Edit::Preedit(text));
+    }
+    EDITS.with(|it| *it.borrow_mut() = handler.0);
+    let frame = FRAME.with(|it| {
+        it.set(it.get() + 1);
+        it.get()
+    });
+    // the box isn't shown anymore, e.g. after switching scenes
+    FOCUSED.with(|it| {
+        let mut it = it.borrow_mut();
+        if it.as_ref().map_or(false, |(_, editor)| editor.frame + 1 < frame) {
+            let (id, editor) = it.take().unwrap();
+            COMMITTED.with(|it| *it.borrow_mut() = Some((id, editor.string())));
+        }
+    });
+}
+
+pub(super) fn push_edit(edit: Edit) {
+    PENDING.with(|it| it.borrow_mut().push(edit));
+}
+
+/// Sets the text being composed with an IME, drawn at the cursor of the focused text box until it's committed.
+///
+/// miniquad doesn't report compositions, so this is called by the platform's IME glue, from any thread. An empty
+/// `text` ends it.
+pub fn set_preedit(text: &str) {
+    *PREEDIT.lock().unwrap() = Some(text.to_owned());
+}
+
+/// Ends editing the text box `id` if it's focused, returning what's been typed into it.
+pub(super) fn take_focused(id: &str) -> Option<String> {
+    FOCUSED.with(|it| {
+        let mut it = it.borrow_mut();
+        if it.as_ref().map_or(false, |(of, _)| of == id) {
+            it.take().map(|(_, editor)| editor.string())
+        } else {
+            None
+        }
+    })
+}
+
+/// The id of the text box being edited, its text, whether it's a password and where the cursor is.
+pub(super) fn focused() -> Option<(String, String, bool, usize)> {
+    FOCUSED.with(|it| {
+        it.borrow()
+            .as_ref()
+            .map(|(id, editor)| (id.clone(), editor.string(), editor.password, editor.cursor))
+    })
+}
+
+fn take_edits() -> Vec<Edit> {
+    EDITS.with(|it| std::mem::take(&mut *it.borrow_mut()))
+}
+
+fn clipboard_get() -> Option<String> {
+    unsafe { get_internal_gl() }.quad_context.clipboard_get()
+}
+
+fn clipboard_set(text: &str) {
+    unsafe { get_internal_gl() }.quad_context.clipboard_set(text);
+}
+
+/// The state of a text being edited in place, with the selection between `anchor` and `cursor`.
+struct TextEditor {
+    text: Vec<char>,
+    cursor: usize,
+    anchor: usize,
+    /// What's being composed with an IME, shown at the cursor but not part of `text` yet.
+    preedit: Vec<char>,
+    /// The touch selecting text by dragging.
+    dragging: Option<u64>,
+    /// How far the text is scrolled to the left to keep the cursor visible.
+    scroll: f32,
+    /// When the cursor last moved, so that it's shown solid while typing. Timed by [`miniquad::date::now`], which works without a window.
+    moved_at: f64,
+    password: bool,
+    /// The frame it was last drawn in.
+    frame: u64,
+}
+
+enum EditResult {
+    Editing,
+    Commit,
+    Cancel,
+}
+
+impl TextEditor {
+    fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        let len = chars.len();
+        Self {
+            text: chars,
+            cursor: len,
+            anchor: len,
+            preedit: Vec::new(),
+            dragging: None,
+            scroll: 0.,
+            moved_at: miniquad::date::now(),
+            password: false,
+            frame: FRAME.with(Cell::get),
+        }
+    }
+
+    fn string(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    fn selection(&self) -> Range<usize> {
+        self.cursor.min(self.anchor)..self.cursor.max(self.anchor)
+    }
+
+    fn selected(&self) -> String {
+        self.text[self.selection()].iter().collect()
+    }
+
+    fn move_to(&mut self, pos: usize, select: bool) {
+        self.cursor = pos.min(self.text.len());
+        if !select {
+            self.anchor = self.cursor;
+        }
+        self.moved_at = miniquad::date::now();
+    }
+
+    fn insert(&mut self, s: &str) {
+        let range = self.selection();
+        let chars: Vec<char> = s.chars().filter(|it| !it.is_control()).collect();
+        let pos = range.start + chars.len();
+        self.text.splice(range, chars);
+        self.move_to(pos, false);
+    }
+
+    /// Deletes the selection, or the character before the cursor (after it if `forward`) if there's none.
+    fn delete(&mut self, forward: bool, word: bool) {
+        if self.cursor == self.anchor {
+            let to = match (forward, word) {
+                (false, false) => self.cursor.saturating_sub(1),
+                (false, true) => self.word_start(self.cursor),
+                (true, false) => (self.cursor + 1).min(self.text.len()),
+                (true, true) => self.word_end(self.cursor),
+            };
+            self.anchor = to;
+        }
+        self.insert("");
+    }
+
+    fn word_start(&self, mut pos: usize) -> usize {
+        while pos > 0 && !self.text[pos - 1].is_alphanumeric() {
+            pos -= 1;
+        }
+        while pos > 0 && self.text[pos - 1].is_alphanumeric() {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn word_end(&self, mut pos: usize) -> usize {
+        while pos < self.text.len() && !self.text[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        while pos < self.text.len() && self.text[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn apply(&mut self, edit: Edit) -> EditResult {
+        match edit {
+            Edit::Char(c) => {
+                // the composition is committed as characters
+                self.preedit.clear();
+                let mut buf = [0; 4];
+                self.insert(c.encode_utf8(&mut buf));
+            }
+            Edit::Preedit(text) => {
+                self.preedit = text.chars().filter(|it| !it.is_control()).collect();
+                self.moved_at = miniquad::date::now();
+            }
+            Edit::Key(key, mods) => {
+                let select = mods.shift;
+                let word = mods.ctrl || mods.alt;
+                let command = mods.ctrl || mods.logo;
+                match key {
+                    KeyCode::Enter | KeyCode::KpEnter => return EditResult::Commit,
+                    KeyCode::Escape => return EditResult::Cancel,
+                    KeyCode::Left if !select && self.cursor != self.anchor => self.move_to(self.selection().start, false),
+                    KeyCode::Right if !select && self.cursor != self.anchor => self.move_to(self.selection().end, false),
+                    KeyCode::Left if word => self.move_to(self.word_start(self.cursor), select),
+                    KeyCode::Right if word => self.move_to(self.word_end(self.cursor), select),
+                    KeyCode::Left => self.move_to(self.cursor.saturating_sub(1), select),
+                    KeyCode::Right => self.move_to(self.cursor + 1, select),
+                    KeyCode::Home | KeyCode::Up => self.move_to(0, select),
+                    KeyCode::End | KeyCode::Down => self.move_to(self.text.len(), select),
+                    KeyCode::Backspace => self.delete(false, word),
+                    KeyCode::Delete => self.delete(true, word),
+                    KeyCode::A if command => {
+                        self.anchor = 0;
+                        self.move_to(self.text.len(), true);
+                    }
+                    KeyCode::C | KeyCode::X if command && self.cursor != self.anchor => {
+                        clipboard_set(&self.selected());
+                        if key == KeyCode::X {
+                            self.insert("");
+                        }
+                    }
+                    KeyCode::V if command => {
+                        if let Some(text) = clipboard_get() {
+                            self.insert(&text);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        EditResult::Editing
+    }
+}
+
+fn display(text: &[char], password: bool) -> String {
+    if password {
+        "*".repeat(text.len())
+    } else {
+        text.iter().collect()
+    }
+}
+
+fn width_of(ui: &mut Ui, text: &str) -> f32 {
+    if text.is_empty() {
+        0.
+    } else {
+        ui.text(text).size(TEXT_SIZE).no_baseline().measure().w
+    }
+}
+
+/// The position in `text` closest to `x`, relative to where the text starts.
+fn position_at(ui: &mut Ui, text: &[char], password: bool, x: f32) -> usize {
+    let mut last = 0.;
+    for i in 1..=text.len() {
+        let w = width_of(ui, &display(&text[..i], password));
+        if x < (last + w) / 2. {
+            return i - 1;
+        }
+        last = w;
+    }
+    text.len()
+}
+
+fn draw_unfocused(ui: &mut Ui, r: Rect, value: &str, password: bool) {
+    ui.fill_rect(r, WHITE);
+    ui.scissor(Some(r));
+    ui.text(display(&value.chars().collect::<Vec<_>>(), password))
+        .pos(r.x + PADDING, r.center().y)
+        .anchor(0., 0.5)
+        .size(TEXT_SIZE)
+        .color(BLACK)
+        .no_baseline()
+        .draw();
+    ui.scissor(None);
+}
+
+/// A text box edited in place, with cursor movement, selection, clipboard shortcuts and IME input.
+///
+/// `value` is only changed once editing ends, by pressing Enter or tapping elsewhere. Escape discards the changes.
+/// Text being composed with an IME is drawn underlined at the cursor, see [`set_preedit`].
+pub(super) fn text_box(ui: &mut Ui, id: &str, r: Rect, value: &mut String, password: bool) {
+    let committed = COMMITTED.with(|it| {
+        let mut it = it.borrow_mut();
+        if it.as_ref().map_or(false, |(of, _)| of == id) {
+            it.take()
+        } else {
+            None
+        }
+    });
+    if let Some((_, text)) = committed {
+        *value = text;
+    }
+    // entered with the system's input, see the on-screen keyboard
+    let entered = take_input().and_then(|(its_id, text)| {
+        if its_id == id {
+            Some(text)
+        } else {
+            return_input(its_id, text);
+            None
+        }
+    });
+    FOCUSED.with(|focused| {
+        let mut focused = focused.borrow_mut();
+        if let Some(text) = entered {
+            match focused.as_mut().filter(|it| it.0 == id) {
+                Some((_, editor)) => {
+                    editor.text = text.chars().collect();
+                    editor.move_to(editor.text.len(), false);
+                }
+                None => *value = text,
+            }
+        }
+        let grect = ui.rect_to_global(r);
+        let mut focus_at = None;
+        let mut unfocus = false;
+        let mut released = false;
+        let is_focused = focused.as_ref().map_or(false, |it| it.0 == id);
+        let dragging = focused.as_ref().filter(|_| is_focused).and_then(|it| it.1.dragging);
+        ui.ensure_touches().retain(|touch| {
+            let inside = grect.contains(touch.position);
+            match touch.phase {
+                TouchPhase::Started if inside => {
+                    focus_at = Some((touch.id, touch.position));
+                    false
+                }
+                TouchPhase::Started if is_focused => {
+                    unfocus = true;
+                    true
+                }
+                TouchPhase::Moved | TouchPhase::Stationary if dragging == Some(touch.id) => {
+                    focus_at = Some((touch.id, touch.position));
+                    false
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled if dragging == Some(touch.id) => {
+                    released = true;
+                    false
+                }
+                _ => true,
+            }
+        });
+        if let Some((touch_id, pos)) = focus_at {
+            if !is_focused {
+                // the box edited before is drawn later, or not at all anymore
+                if let Some((id, editor)) = focused.take() {
+                    COMMITTED.with(|it| *it.borrow_mut() = Some((id, editor.string())));
+                }
+                // keys typed before are meant for something else
+                take_edits();
+                *focused = Some((id.to_owned(), TextEditor::new(value)));
+            }
+            let (x, _) = ui.to_local((pos.x, pos.y));
+            let editor = &mut focused.as_mut().unwrap().1;
+            let pos = position_at(ui, &editor.text, password, x - r.x - PADDING + editor.scroll);
+            let select = editor.dragging == Some(touch_id);
+            editor.dragging = Some(touch_id);
+            editor.move_to(pos, select);
+        }
+        let Some((_, editor)) = focused.as_mut().filter(|it| it.0 == id) else {
+            draw_unfocused(ui, r, value, password);
+            return;
+        };
+        editor.password = password;
+        editor.frame = FRAME.with(Cell::get);
+        let mut cancel = false;
+        for edit in take_edits() {
+            match editor.apply(edit) {
+                EditResult::Editing => {}
+                EditResult::Commit => unfocus = true,
+                EditResult::Cancel => cancel = true,
+            }
+        }
+        if cancel || unfocus {
+            if !cancel {
+                *value = editor.string();
+            }
+            *focused = None;
+            draw_unfocused(ui, r, value, password);
+            return;
+        }
+
+        let inner = r.w - PADDING * 2.;
+        let mut shown = editor.text.clone();
+        shown.splice(editor.cursor..editor.cursor, editor.preedit.iter().copied());
+        let composing = !editor.preedit.is_empty();
+        let before_preedit = width_of(ui, &display(&editor.text[..editor.cursor], password));
+        let before_cursor = width_of(ui, &display(&shown[..editor.cursor + editor.preedit.len()], password));
+        let before_anchor = width_of(ui, &display(&editor.text[..editor.anchor], password));
+        // a composition wider than the box keeps its end in sight
+        let least = (before_cursor - inner).max(0.);
+        editor.scroll = editor.scroll.clamp(least, before_preedit.max(least));
+        let x = r.x + PADDING - editor.scroll;
+
+        ui.fill_rect(r, WHITE);
+        ui.scissor(Some(r));
+        let (h, cy) = (r.h * 0.7, r.center().y);
+        if editor.cursor != editor.anchor && !composing {
+            let (from, to) = (before_cursor.min(before_anchor), before_cursor.max(before_anchor));
+            let accent = ui.accent();
+            ui.fill_rect(Rect::new(x + from, cy - h / 2., to - from, h), Color { a: 0.4, ..accent });
+        }
+        ui.text(display(&shown, password))
+            .pos(x, cy)
+            .anchor(0., 0.5)
+            .size(TEXT_SIZE)
+            .color(BLACK)
+            .no_baseline()
+            .draw();
+        if composing {
+            ui.fill_rect(Rect::new(x + before_preedit, cy + h / 2. - 0.003, before_cursor - before_preedit, 0.003), BLACK);
+        }
+        if ((miniquad::date::now() - editor.moved_at) / BLINK_PERIOD).fract() < 0.5 {
+            ui.fill_rect(Rect::new(x + before_cursor, cy - h / 2., 0.003, h), BLACK);
+        }
+        ui.scissor(None);
+        if released {
+            editor.dragging = None;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: KeyCode, shift: bool, ctrl: bool) -> Edit {
+        Edit::Key(
+            key,
+            KeyMods {
+                shift,
+                ctrl,
+                alt: false,
+                logo: false,
+            },
+        )
+    }
+
+    fn edit(text: &str, edits: impl IntoIterator<Item = Edit>) -> TextEditor {
+        let mut editor = TextEditor::new(text);
+        for edit in edits {
+            assert!(matches!(editor.apply(edit), EditResult::Editing));
+        }
+        editor
+    }
+
+    #[test]
+    fn typing_at_cursor() {
+        let editor = edit(
+            "abc",
+            [
+                Edit::Char('d'),
+                key(KeyCode::Left, false, false),
+                key(KeyCode::Left, false, false),
+                Edit::Char('X'),
+            ],
+        );
+        assert_eq!(editor.string(), "abXcd");
+        assert_eq!((editor.cursor, editor.anchor), (3, 3));
+        let editor = edit("abc", [key(KeyCode::Home, false, false), Edit::Char('\n'), Edit::Char('谱')]);
+        assert_eq!(editor.string(), "谱abc");
+    }
+
+    #[test]
+    fn selection_replaced() {
+        let editor = edit("abcdef", [key(KeyCode::Left, true, false), key(KeyCode::Left, true, false)]);
+        assert_eq!(editor.selected(), "ef");
+        let editor = edit("abcdef", [key(KeyCode::Left, true, false), key(KeyCode::Left, true, false), Edit::Char('X')]);
+        assert_eq!(editor.string(), "abcdX");
+        // moving collapses the selection to its side
+        let editor = edit("abcdef", [key(KeyCode::Home, true, false), key(KeyCode::Right, false, false)]);
+        assert_eq!((editor.cursor, editor.anchor), (6, 6));
+        let editor = edit("abcdef", [key(KeyCode::A, false, true), key(KeyCode::Backspace, false, false)]);
+        assert_eq!(editor.string(), "");
+    }
+
+    #[test]
+    fn words() {
+        let editor = edit("hello, world", [key(KeyCode::Left, false, true)]);
+        assert_eq!(editor.cursor, 7);
+        let editor = edit("hello, world", [key(KeyCode::Left, false, true), key(KeyCode::Left, false, true)]);
+        assert_eq!(editor.cursor, 0);
+        let editor = edit("hello, world", [key(KeyCode::Backspace, false, true)]);
+        assert_eq!(editor.string(), "hello, ");
+        let editor = edit("hello, world", [key(KeyCode::Home, false, false), key(KeyCode::Delete, false, true)]);
+        assert_eq!(editor.string(), ", world");
+        let editor = edit("hello, world", [key(KeyCode::Home, false, false), key(KeyCode::Right, true, true)]);
+        assert_eq!(editor.selected(), "hello");
+    }
+
+    #[test]
+    fn deleting_characters() {
+        let editor = edit("abc", [key(KeyCode::Backspace, false, false)]);
+        assert_eq!(editor.string(), "ab");
+        let editor = edit(
+            "abc",
+            [
+                key(KeyCode::Home, false, false),
+                key(KeyCode::Backspace, false, false),
+                key(KeyCode::Delete, false, false),
+            ],
+        );
+        assert_eq!(editor.string(), "bc");
+        let editor = edit("abc", [key(KeyCode::Delete, false, false)]);
+        assert_eq!(editor.string(), "abc");
+    }
+
+    #[test]
+    fn composition_kept_apart() {
+        let editor = edit("ab", [Edit::Preedit("pu\t".to_owned())]);
+        assert_eq!(editor.string(), "ab");
+        assert_eq!(editor.preedit, ['p', 'u']);
+        // committed as characters
+        let editor = edit("ab", [Edit::Preedit("pu".to_owned()), Edit::Char('谱')]);
+        assert_eq!(editor.string(), "ab谱");
+        assert!(editor.preedit.is_empty());
+        let editor = edit("ab", [Edit::Preedit("pu".to_owned()), Edit::Preedit(String::new())]);
+        assert!(editor.preedit.is_empty());
+    }
+
+    #[test]
+    fn commit_and_cancel() {
+        let mut editor = TextEditor::new("abc");
+        assert!(matches!(editor.apply(key(KeyCode::Enter, false, false)), EditResult::Commit));
+        assert!(matches!(editor.apply(key(KeyCode::KpEnter, false, false)), EditResult::Commit));
+        assert!(matches!(editor.apply(key(KeyCode::Escape, false, false)), EditResult::Cancel));
+    }
+}